@@ -0,0 +1,213 @@
+use chrono::NaiveDate;
+use eframe::egui;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::io::{Cursor, Read};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use subtle::ConstantTimeEq;
+use tiny_http::{Header, Method, Response, Server};
+use todo_core::{Priority, TodoItem};
+
+// How long a request waits for the window to handle it
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+// Request bodies are a few fields, anything longer is refused
+const MAX_BODY: u64 = 64 * 1024;
+
+// Fields a client sends when adding or changing an item, missing ones are left alone.
+// `"due_date": null` clears the due date.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct ItemFields {
+    pub description: Option<String>,
+    pub completed: Option<bool>,
+    #[serde(deserialize_with = "present")]
+    pub due_date: Option<Option<NaiveDate>>,
+    pub priority: Option<Priority>,
+    pub notes: Option<String>,
+    pub tags: Option<Vec<String>>,
+}
+
+// Tells a field that was sent as null apart from one that wasn't sent
+fn present<'de, D: Deserializer<'de>, T: Deserialize<'de>>(deserializer: D) -> Result<Option<Option<T>>, D::Error> {
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+impl ItemFields {
+    pub fn apply_to(self, item: &mut TodoItem) {
+        if let Some(description) = self.description {
+            item.description = description;
+        }
+        if let Some(completed) = self.completed {
+            item.completed = completed;
+        }
+        if let Some(due_date) = self.due_date {
+            item.due_date = due_date;
+        }
+        if let Some(priority) = self.priority {
+            item.priority = priority;
+        }
+        if let Some(notes) = self.notes {
+            item.notes = notes;
+        }
+        if let Some(tags) = self.tags {
+            item.tags.clear();
+            tags.iter().for_each(|tag| item.add_tag(tag));
+        }
+    }
+}
+
+// What a client asked for, always about the open list
+pub enum Call {
+    List,
+    Get(u32),
+    Add(ItemFields),
+    Update(u32, ItemFields),
+    Delete(u32),
+}
+
+// The window's answer, turned into a status code and a JSON body
+pub enum Reply {
+    Items(Vec<TodoItem>),
+    Item(TodoItem),
+    Created(TodoItem),
+    Deleted,
+    NotFound,
+    BadRequest(String),
+}
+
+// A call waiting for the window to answer it
+pub struct ApiCall {
+    call: Call,
+    reply: Sender<Reply>,
+}
+
+impl ApiCall {
+    // The request may have timed out meanwhile, then the answer goes nowhere
+    pub fn answer_with(self, answer: impl FnOnce(Call) -> Reply) {
+        let _ = self.reply.send(answer(self.call));
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    error: &'a str,
+}
+
+// JSON endpoints for the open list, so scripts and phones can add tasks.
+// Calls are handed to the window, which changes the list like any other edit, undo included.
+pub struct ApiServer {
+    server: Arc<Server>,
+    calls: Receiver<ApiCall>,
+}
+
+impl ApiServer {
+    // Every request must carry `Authorization: Bearer <token>`. Only this computer can connect
+    // unless `lan` is set.
+    pub fn start(ctx: &egui::Context, port: u16, lan: bool, token: String) -> Result<Self, String> {
+        let host = if lan { "0.0.0.0" } else { "127.0.0.1" };
+        let server = Arc::new(Server::http((host, port)).map_err(|e| e.to_string())?);
+        let (sender, calls) = mpsc::channel();
+        let thread_server = Arc::clone(&server);
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            // Ends once the server is unblocked on drop
+            for mut request in thread_server.incoming_requests() {
+                let response = handle(&mut request, &token, &sender, &ctx);
+                // A client that hung up doesn't need an answer
+                let _ = request.respond(response);
+            }
+        });
+        Ok(Self { server, calls })
+    }
+
+    pub fn poll(&self) -> Vec<ApiCall> {
+        self.calls.try_iter().collect()
+    }
+}
+
+impl Drop for ApiServer {
+    fn drop(&mut self) {
+        self.server.unblock();
+    }
+}
+
+// 128 bits from the OS, so another device on the network can't guess it
+pub fn new_token() -> String {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).expect("the OS has no random numbers");
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn handle(request: &mut tiny_http::Request, token: &str, sender: &Sender<ApiCall>, ctx: &egui::Context) -> Response<Cursor<Vec<u8>>> {
+    let bearer = format!("Bearer {}", token);
+    let authorized = request
+        .headers()
+        .iter()
+        // Compared in constant time, so response times don't give the token away byte by byte
+        .any(|header| header.field.equiv("Authorization") && bool::from(header.value.as_bytes().ct_eq(bearer.as_bytes())));
+    if !authorized {
+        return error(401, "Missing or wrong token");
+    }
+
+    let path = request.url().split('?').next().unwrap_or_default().trim_end_matches('/').to_string();
+    let id = path.strip_prefix("/items/").map(str::parse::<u32>);
+    let method = request.method().clone();
+    let call = match (method, path.as_str(), id) {
+        (Method::Get, "/items", _) => Call::List,
+        (Method::Post, "/items", _) => match read_fields(request) {
+            Ok(fields) => Call::Add(fields),
+            Err((status, e)) => return error(status, &e),
+        },
+        (_, _, Some(Err(_))) => return error(404, "Item IDs are numbers"),
+        (Method::Get, _, Some(Ok(id))) => Call::Get(id),
+        (Method::Patch, _, Some(Ok(id))) => match read_fields(request) {
+            Ok(fields) => Call::Update(id, fields),
+            Err((status, e)) => return error(status, &e),
+        },
+        (Method::Delete, _, Some(Ok(id))) => Call::Delete(id),
+        _ => return error(404, "Unknown endpoint"),
+    };
+
+    let (reply_sender, reply) = mpsc::channel();
+    if sender.send(ApiCall { call, reply: reply_sender }).is_err() {
+        return error(503, "The app is closing");
+    }
+    // Wake the UI, which may be hidden and not repainting
+    ctx.request_repaint();
+    match reply.recv_timeout(REPLY_TIMEOUT) {
+        Ok(Reply::Items(items)) => json(200, &items),
+        Ok(Reply::Item(item)) => json(200, &item),
+        Ok(Reply::Created(item)) => json(201, &item),
+        Ok(Reply::Deleted) => Response::from_data(Vec::new()).with_status_code(204),
+        Ok(Reply::NotFound) => error(404, "No item with this ID in the open list"),
+        Ok(Reply::BadRequest(message)) => error(400, &message),
+        Err(_) => error(503, "The app did not answer in time"),
+    }
+}
+
+// Fails with the status to answer with
+fn read_fields(request: &mut tiny_http::Request) -> Result<ItemFields, (u16, String)> {
+    if request.body_length().is_some_and(|length| length as u64 > MAX_BODY) {
+        return Err((413, "The request body is too long".to_string()));
+    }
+    // One byte more than allowed tells a body that is too long apart from one that just fits
+    let mut body = Vec::new();
+    request.as_reader().take(MAX_BODY + 1).read_to_end(&mut body).map_err(|e| (400, e.to_string()))?;
+    if body.len() as u64 > MAX_BODY {
+        return Err((413, "The request body is too long".to_string()));
+    }
+    serde_json::from_slice(&body).map_err(|e| (400, format!("Invalid JSON: {}", e)))
+}
+
+fn json<T: Serialize + ?Sized>(status: u16, value: &T) -> Response<Cursor<Vec<u8>>> {
+    let content_type = Header::from_bytes("Content-Type", "application/json").expect("valid header");
+    Response::from_data(serde_json::to_vec(value).unwrap_or_default())
+        .with_status_code(status)
+        .with_header(content_type)
+}
+
+fn error(status: u16, message: &str) -> Response<Cursor<Vec<u8>>> {
+    json(status, &ErrorBody { error: message })
+}
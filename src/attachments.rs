@@ -0,0 +1,82 @@
+use crate::dialogs::FileDialog;
+use crate::i18n::t;
+use crate::voice;
+use eframe::egui::{self, RichText};
+use todo_core::attachment::{self, Attachment};
+
+// Small button with the file name, opening the file with the app the system picks for it.
+// Fails if the file couldn't be opened.
+pub fn chip(ui: &mut egui::Ui, attachment: &Attachment) -> Result<(), String> {
+    let response = ui
+        .add(egui::Button::new(RichText::new(format!("📎 {}", attachment.name())).small()).small())
+        .on_hover_text(attachment.path.display().to_string());
+    if response.clicked() {
+        open_file(attachment)?;
+    }
+    Ok(())
+}
+
+// Chips with a button to take each one off, then buttons to attach more. Fails if a file
+// couldn't be opened, copied or played.
+pub fn editor(ui: &mut egui::Ui, attachments: &mut Vec<Attachment>) -> Result<(), String> {
+    let mut removed = None;
+    let mut result = Ok(());
+    ui.horizontal_wrapped(|ui| {
+        for (index, attachment) in attachments.iter().enumerate() {
+            if let Err(e) = chip(ui, attachment) {
+                result = Err(e);
+            }
+            if attachment.is_audio() && ui.small_button("▶").on_hover_text(t("Play")).clicked() {
+                if let Err(e) = voice::play(&attachment.path) {
+                    result = Err(e);
+                }
+            }
+            if ui.small_button("✖").on_hover_text(t("Remove attachment")).clicked() {
+                removed = Some(index);
+            }
+        }
+    });
+    // Copies stay in the attachments folder, the task only lets go of them
+    if let Some(index) = removed {
+        attachments.remove(index);
+    }
+    ui.horizontal_wrapped(|ui| {
+        for attachment in attachments.iter().filter(|attachment| attachment.is_image()) {
+            let uri = format!("file://{}", attachment.path.display());
+            let thumbnail = ui.add(egui::Image::new(uri).max_height(96.0).max_width(160.0).sense(egui::Sense::click()));
+            if thumbnail.on_hover_text(attachment.name()).clicked() {
+                if let Err(e) = open_file(attachment) {
+                    result = Err(e);
+                }
+            }
+        }
+    });
+    ui.horizontal(|ui| {
+        if ui.button(t("Attach copy…")).on_hover_text(t("Keep a copy of the file with the task")).clicked() {
+            if let Some(path) = FileDialog::new().pick_file() {
+                match attachment::folder().map(|folder| Attachment::copy_into(&path, &folder)) {
+                    Some(Ok(copy)) => attachments.push(copy),
+                    Some(Err(e)) => result = Err(format!("Failed to copy {}: {}", path.display(), e)),
+                    None => result = Err("No data directory to copy attachments to".to_string()),
+                }
+            }
+        }
+        if ui.button(t("Link file…")).on_hover_text(t("Attach the file where it is")).clicked() {
+            if let Some(path) = FileDialog::new().pick_file() {
+                attachments.push(Attachment::link(path));
+            }
+        }
+    });
+    result
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn open_file(attachment: &Attachment) -> Result<(), String> {
+    open::that_detached(&attachment.path).map_err(|e| format!("Failed to open {}: {}", attachment.path.display(), e))
+}
+
+// The browser has no files to open
+#[cfg(target_arch = "wasm32")]
+fn open_file(_attachment: &Attachment) -> Result<(), String> {
+    Ok(())
+}
@@ -0,0 +1,24 @@
+use eframe::egui::{self, Align2, Color32, FontId, Sense};
+
+const SIZE: f32 = 18.0;
+
+// Round badge with the initials of a name, in a color that stays the same for the name
+pub fn show(ui: &mut egui::Ui, name: &str) -> egui::Response {
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(SIZE, SIZE), Sense::hover());
+    let painter = ui.painter();
+    painter.circle_filled(rect.center(), SIZE / 2.0, color(name));
+    painter.text(rect.center(), Align2::CENTER_CENTER, initials(name), FontId::proportional(SIZE * 0.5), Color32::WHITE);
+    response.on_hover_text(name)
+}
+
+// First letters of the first two words, "Sam Lee" is "SL" and "sam" is "S"
+fn initials(name: &str) -> String {
+    name.split_whitespace().take(2).filter_map(|word| word.chars().next()).flat_map(char::to_uppercase).collect()
+}
+
+// A hue picked from the name, dark enough for white text
+fn color(name: &str) -> Color32 {
+    let hash = name.bytes().fold(0u32, |hash, byte| hash.wrapping_mul(31).wrapping_add(u32::from(byte)));
+    let hue = (hash % 360) as f32 / 360.0;
+    egui::ecolor::Hsva::new(hue, 0.6, 0.6, 1.0).into()
+}
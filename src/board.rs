@@ -0,0 +1,75 @@
+use crate::avatar;
+use crate::i18n::t;
+use crate::priority_color;
+use crate::theme::Palette;
+use chrono::NaiveDate;
+use eframe::egui::{self, Color32, RichText};
+use todo_core::{Status, TodoItem};
+
+// Board view with one column per status, cards are dragged between columns.
+// Returns the ID of a card dropped on another column and the status of that column.
+pub fn show(ui: &mut egui::Ui, items: &[TodoItem], order: &[usize], today: NaiveDate, palette: Palette) -> Option<(u32, Status)> {
+    let mut moved = None;
+    ui.columns(Status::ALL.len(), |columns| {
+        for (ui, status) in columns.iter_mut().zip(Status::ALL) {
+            let cards: Vec<&TodoItem> = order
+                .iter()
+                .map(|&index| &items[index])
+                .filter(|item| item.status() == status)
+                .collect();
+            ui.label(RichText::new(format!("{} ({})", t(status.label()), cards.len())).strong());
+
+            let column = ui
+                .group(|ui| {
+                    ui.set_min_size(egui::vec2(ui.available_width(), 200.0));
+                    ui.vertical(|ui| {
+                        for item in cards {
+                            show_card(ui, item, today, palette, t("Drag to another column to change its status"));
+                        }
+                    });
+                })
+                .response;
+
+            // Highlight the column under a dragged card and move the card there on release
+            if column.dnd_hover_payload::<u32>().is_some() {
+                let stroke = ui.visuals().selection.stroke;
+                ui.painter().rect_stroke(column.rect, 4.0, stroke);
+            }
+            if let Some(dragged_id) = column.dnd_release_payload::<u32>() {
+                moved = Some((*dragged_id, status));
+            }
+        }
+    });
+    moved
+}
+
+// Card that can be dragged by its ID, shared with the matrix view
+pub fn show_card(ui: &mut egui::Ui, item: &TodoItem, today: NaiveDate, palette: Palette, hint: &str) {
+    ui.dnd_drag_source(egui::Id::new(("board_card", item.id)), item.id, |ui| {
+        egui::Frame::group(ui.style()).show(ui, |ui| {
+            ui.set_width(ui.available_width());
+            ui.horizontal_wrapped(|ui| {
+                let badge = RichText::new(t(item.priority.label())).small().color(Color32::WHITE);
+                ui.add(egui::Button::new(badge).fill(priority_color(item.priority)).small());
+                if let Some(icon) = &item.icon {
+                    ui.label(icon.as_str());
+                }
+                let text = if item.completed {
+                    RichText::new(&item.description).strikethrough().color(palette.completed)
+                } else {
+                    RichText::new(&item.description)
+                };
+                ui.label(text);
+                if let Some(assignee) = &item.assignee {
+                    avatar::show(ui, assignee);
+                }
+            });
+            if let Some(due) = item.due_date {
+                let text = RichText::new(format!("📅 {}", due.format("%Y-%m-%d"))).small();
+                ui.label(if item.is_overdue(today) { text.color(palette.overdue) } else { text });
+            }
+        });
+    })
+    .response
+    .on_hover_text(hint);
+}
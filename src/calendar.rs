@@ -0,0 +1,87 @@
+use crate::i18n::t;
+use crate::priority_color;
+use chrono::{Datelike, Days, Months, NaiveDate};
+use eframe::egui::{self, RichText};
+use todo_core::TodoItem;
+
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+// Month grid with every task placed on its due date
+pub struct Calendar {
+    // First day of the month being shown
+    month: NaiveDate,
+}
+
+impl Calendar {
+    pub fn new(today: NaiveDate) -> Self {
+        Self { month: first_of_month(today) }
+    }
+
+    // Returns the ID of a task that was clicked so it can be opened for editing
+    pub fn show(&mut self, ui: &mut egui::Ui, items: &[TodoItem], order: &[usize], today: NaiveDate) -> Option<u32> {
+        ui.horizontal(|ui| {
+            if ui.button("◀").on_hover_text(t("Previous month")).clicked() {
+                self.month = self.month - Months::new(1);
+            }
+            ui.label(RichText::new(format!("{} {}", t(&self.month.format("%B").to_string()), self.month.year())).strong());
+            if ui.button("▶").on_hover_text(t("Next month")).clicked() {
+                self.month = self.month + Months::new(1);
+            }
+            if ui.button(t("Today")).clicked() {
+                self.month = first_of_month(today);
+            }
+        });
+
+        // The grid starts on the Monday on or before the first of the month
+        let offset = self.month.weekday().num_days_from_monday() as u64;
+        let start = self.month - Days::new(offset);
+        let next_month = self.month + Months::new(1);
+        let cell_width = (ui.available_width() / 7.0 - ui.spacing().item_spacing.x).max(40.0);
+
+        let mut clicked = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("calendar_grid").striped(true).min_col_width(cell_width).max_col_width(cell_width).show(ui, |ui| {
+                for name in WEEKDAYS {
+                    ui.label(RichText::new(t(name)).strong());
+                }
+                ui.end_row();
+
+                let mut day = start;
+                while day < next_month {
+                    for _ in 0..7 {
+                        ui.vertical(|ui| {
+                            ui.set_min_height(60.0);
+                            let mut number = RichText::new(day.day().to_string());
+                            if day == today {
+                                number = number.strong().underline();
+                            } else if day.month() != self.month.month() {
+                                number = number.weak();
+                            }
+                            ui.label(number);
+
+                            for item in order.iter().map(|&index| &items[index]).filter(|item| item.due_date == Some(day)) {
+                                let mut text = RichText::new(&item.description).small().color(priority_color(item.priority));
+                                if item.completed {
+                                    text = text.strikethrough();
+                                }
+                                if ui.add(egui::Label::new(text).truncate(true).sense(egui::Sense::click()))
+                                    .on_hover_text(&item.description)
+                                    .clicked()
+                                {
+                                    clicked = Some(item.id);
+                                }
+                            }
+                        });
+                        day = day + Days::new(1);
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+        clicked
+    }
+}
+
+fn first_of_month(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap_or(date)
+}
@@ -0,0 +1,151 @@
+use crate::i18n::t;
+use chrono::Local;
+use eframe::egui;
+use egui_extras::DatePickerButton;
+use std::collections::BTreeMap;
+use todo_core::fields::{FieldDef, FieldKind, FieldValue};
+
+// The field being set up at the bottom of the custom fields window
+#[derive(Default)]
+pub struct Draft {
+    name: String,
+    kind: FieldKind,
+    // Dropdown options, separated by commas
+    options: String,
+}
+
+impl Draft {
+    fn to_field(&self) -> FieldDef {
+        let kind = match self.kind {
+            FieldKind::Dropdown(_) => FieldKind::Dropdown(
+                self.options.split(',').map(str::trim).filter(|option| !option.is_empty()).map(str::to_string).collect(),
+            ),
+            ref kind => kind.clone(),
+        };
+        FieldDef { name: self.name.trim().to_string(), kind }
+    }
+}
+
+// Editor for one field's value on an item, for a row of the detail panel grid
+pub fn value_editor(ui: &mut egui::Ui, field: &FieldDef, values: &mut BTreeMap<String, FieldValue>) {
+    let value = values.get(&field.name);
+    let mut changed = None;
+    match &field.kind {
+        FieldKind::Text => {
+            let mut text = match value {
+                Some(FieldValue::Text(text)) => text.clone(),
+                _ => String::new(),
+            };
+            if ui.text_edit_singleline(&mut text).changed() {
+                changed = Some((!text.is_empty()).then_some(FieldValue::Text(text)));
+            }
+        }
+        FieldKind::Number => {
+            ui.horizontal(|ui| match value {
+                Some(&FieldValue::Number(mut number)) => {
+                    if ui.add(egui::DragValue::new(&mut number).speed(0.1)).changed() {
+                        changed = Some(Some(FieldValue::Number(number)));
+                    }
+                    if ui.small_button("✖").on_hover_text(t("Clear")).clicked() {
+                        changed = Some(None);
+                    }
+                }
+                _ => {
+                    if ui.small_button("➕").on_hover_text(t("Set")).clicked() {
+                        changed = Some(Some(FieldValue::Number(0.0)));
+                    }
+                }
+            });
+        }
+        FieldKind::Date => {
+            ui.horizontal(|ui| match value {
+                Some(&FieldValue::Date(mut date)) => {
+                    let picker = ui.add(DatePickerButton::new(&mut date).id_source(&format!("field_{}", field.name)));
+                    if picker.changed() {
+                        changed = Some(Some(FieldValue::Date(date)));
+                    }
+                    if ui.small_button("✖").on_hover_text(t("Clear")).clicked() {
+                        changed = Some(None);
+                    }
+                }
+                _ => {
+                    if ui.button("📅").on_hover_text(t("Set")).clicked() {
+                        changed = Some(Some(FieldValue::Date(Local::now().date_naive())));
+                    }
+                }
+            });
+        }
+        FieldKind::Dropdown(options) => {
+            let selected = match value {
+                Some(FieldValue::Text(text)) => Some(text.as_str()),
+                _ => None,
+            };
+            egui::ComboBox::from_id_source(("field", &field.name)).selected_text(selected.unwrap_or("—")).show_ui(ui, |ui| {
+                if ui.selectable_label(selected.is_none(), "—").clicked() {
+                    changed = Some(None);
+                }
+                for option in options {
+                    if ui.selectable_label(selected == Some(option.as_str()), option).clicked() {
+                        changed = Some(Some(FieldValue::Text(option.clone())));
+                    }
+                }
+            });
+        }
+    }
+    match changed {
+        Some(Some(value)) => {
+            values.insert(field.name.clone(), value);
+        }
+        Some(None) => {
+            values.remove(&field.name);
+        }
+        None => {}
+    }
+}
+
+// The list's fields with a button to remove each, then a form to add another. Values
+// already filled in stay on the items, so adding a removed field back brings them back.
+pub fn definitions(ui: &mut egui::Ui, fields: &mut Vec<FieldDef>, draft: &mut Draft) {
+    if fields.is_empty() {
+        ui.weak(t("No custom fields yet"));
+    }
+    let mut removed = None;
+    egui::Grid::new("custom_fields").num_columns(3).show(ui, |ui| {
+        for (index, field) in fields.iter().enumerate() {
+            ui.label(&field.name);
+            match &field.kind {
+                FieldKind::Dropdown(options) => ui.label(format!("{}: {}", t("Dropdown"), options.join(", "))),
+                kind => ui.label(t(kind.label())),
+            };
+            if ui.small_button("✖").on_hover_text(t("Remove field")).clicked() {
+                removed = Some(index);
+            }
+            ui.end_row();
+        }
+    });
+    if let Some(index) = removed {
+        fields.remove(index);
+    }
+
+    ui.separator();
+    ui.horizontal(|ui| {
+        ui.add(egui::TextEdit::singleline(&mut draft.name).hint_text(t("Name")).desired_width(120.0));
+        egui::ComboBox::from_id_source("new_field_kind").selected_text(t(draft.kind.label())).show_ui(ui, |ui| {
+            for kind in [FieldKind::Text, FieldKind::Number, FieldKind::Date, FieldKind::Dropdown(Vec::new())] {
+                let label = t(kind.label());
+                ui.selectable_value(&mut draft.kind, kind, label);
+            }
+        });
+    });
+    if matches!(draft.kind, FieldKind::Dropdown(_)) {
+        ui.add(egui::TextEdit::singleline(&mut draft.options).hint_text(t("Options, separated by commas")));
+    }
+    let field = draft.to_field();
+    let valid = !field.name.is_empty()
+        && !fields.iter().any(|existing| existing.name == field.name)
+        && !matches!(&field.kind, FieldKind::Dropdown(options) if options.is_empty());
+    if ui.add_enabled(valid, egui::Button::new(t("Add field"))).clicked() {
+        fields.push(field);
+        *draft = Draft::default();
+    }
+}
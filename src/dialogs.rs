@@ -0,0 +1,30 @@
+// Native file dialogs. The browser only offers asynchronous pickers, so until the
+// web build gets its own file handling every dialog there comes back empty.
+#[cfg(not(target_arch = "wasm32"))]
+pub use rfd::FileDialog;
+
+#[cfg(target_arch = "wasm32")]
+pub struct FileDialog;
+
+#[cfg(target_arch = "wasm32")]
+impl FileDialog {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn add_filter(self, _name: impl Into<String>, _extensions: &[impl ToString]) -> Self {
+        self
+    }
+
+    pub fn set_file_name(self, _name: impl Into<String>) -> Self {
+        self
+    }
+
+    pub fn pick_file(self) -> Option<std::path::PathBuf> {
+        None
+    }
+
+    pub fn save_file(self) -> Option<std::path::PathBuf> {
+        None
+    }
+}
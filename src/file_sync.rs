@@ -0,0 +1,146 @@
+use chrono::{DateTime, Local};
+use eframe::egui;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+use todo_core::file_sync::{self, Fetched, FileSyncError, SharedFile};
+use todo_core::{merge, storage, TodoList};
+
+// Network and disk work done off the UI thread
+enum Job {
+    Fetch(SharedFile),
+    Upload(SharedFile, String, Option<String>),
+}
+
+enum SyncEvent {
+    Fetched(Fetched),
+    Uploaded,
+    // Written by another device between fetching and uploading
+    Changed,
+    Failed(String),
+}
+
+// Keeps the lists in sync with a shared save file. The file is fetched, merged into the lists
+// on the UI thread and written back only if it lacks something, and only if nobody else wrote
+// it meanwhile. Otherwise it is fetched and merged again, so other devices' changes are never
+// overwritten.
+pub struct FileSyncer {
+    jobs: Sender<Job>,
+    events: Receiver<SyncEvent>,
+    last_started: Option<Instant>,
+    busy: bool,
+    pub last_synced: Option<DateTime<Local>>,
+    pub last_error: Option<String>,
+}
+
+impl FileSyncer {
+    pub fn start(ctx: &egui::Context) -> Self {
+        let (jobs, job_receiver) = mpsc::channel::<Job>();
+        let (event_sender, events) = mpsc::channel();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            for job in job_receiver {
+                let event = match job {
+                    Job::Fetch(shared) => match file_sync::fetch(&shared) {
+                        Ok(fetched) => SyncEvent::Fetched(fetched),
+                        Err(e) => SyncEvent::Failed(e.to_string()),
+                    },
+                    Job::Upload(shared, json, version) => match file_sync::upload(&shared, &json, version.as_deref()) {
+                        Ok(()) => SyncEvent::Uploaded,
+                        Err(FileSyncError::Changed) => SyncEvent::Changed,
+                        Err(e) => SyncEvent::Failed(e.to_string()),
+                    },
+                };
+                if event_sender.send(event).is_err() {
+                    break;
+                }
+                ctx.request_repaint();
+            }
+        });
+        Self {
+            jobs,
+            events,
+            last_started: None,
+            busy: false,
+            last_synced: None,
+            last_error: None,
+        }
+    }
+
+    // Sync on the next poll instead of waiting for the interval
+    pub fn sync_now(&mut self) {
+        self.last_started = None;
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.busy
+    }
+
+    // Merge fetched files and start a new sync once the interval is up. Returns whether the
+    // lists were changed, and why syncs failed for the caller to show.
+    pub fn poll(&mut self, ctx: &egui::Context, shared: &SharedFile, lists: &mut Vec<TodoList>) -> (bool, Vec<String>) {
+        let mut changed = false;
+        let mut errors = Vec::new();
+        while let Ok(event) = self.events.try_recv() {
+            match event {
+                SyncEvent::Fetched(fetched) => match merge_fetched(lists, &fetched) {
+                    Ok((merged, upload)) => {
+                        changed |= merged;
+                        match upload {
+                            Some(json) => {
+                                let _ = self.jobs.send(Job::Upload(shared.clone(), json, fetched.version));
+                            }
+                            None => self.finish(None),
+                        }
+                    }
+                    Err(e) => errors.push(self.fail(e.to_string())),
+                },
+                SyncEvent::Uploaded => self.finish(None),
+                SyncEvent::Changed => {
+                    self.busy = false;
+                    self.sync_now();
+                }
+                SyncEvent::Failed(error) => errors.push(self.fail(error)),
+            }
+        }
+
+        if !shared.is_configured() || self.busy {
+            return (changed, errors);
+        }
+        let interval = Duration::from_secs(u64::from(shared.interval_minutes.max(1)) * 60);
+        match self.last_started.map(|started| interval.saturating_sub(started.elapsed())) {
+            Some(remaining) if !remaining.is_zero() => ctx.request_repaint_after(remaining),
+            _ => {
+                self.last_started = Some(Instant::now());
+                self.busy = true;
+                let _ = self.jobs.send(Job::Fetch(shared.clone()));
+            }
+        }
+        (changed, errors)
+    }
+
+    fn fail(&mut self, error: String) -> String {
+        self.finish(Some(error.clone()));
+        error
+    }
+
+    fn finish(&mut self, error: Option<String>) {
+        self.busy = false;
+        if error.is_none() {
+            self.last_synced = Some(Local::now());
+        }
+        self.last_error = error;
+    }
+}
+
+// Merge the shared file into the lists. Returns whether the lists changed, and the JSON to
+// write back if the file is missing anything the lists have.
+fn merge_fetched(lists: &mut Vec<TodoList>, fetched: &Fetched) -> Result<(bool, Option<String>), storage::StorageError> {
+    let read = |json: &Option<String>| json.as_deref().map(storage::from_json).transpose().map(Option::unwrap_or_default);
+    let changed = merge::merge_lists(lists, read(&fetched.json)?);
+    let ours = storage::to_json(lists)?;
+    // IDs differ between devices, so the file is compared by merging into it rather than as text
+    let mut shared = read(&fetched.json)?;
+    let upload = merge::merge_lists(&mut shared, storage::from_json(&ours)?).then_some(ours);
+    Ok((changed, upload))
+}
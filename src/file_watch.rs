@@ -0,0 +1,47 @@
+use eframe::egui;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+
+// Notices when the open save file is written, by this app or any other program. Its folder
+// is watched rather than the file itself, since saves replace the file by renaming over it.
+pub struct FileWatch {
+    path: PathBuf,
+    changes: Receiver<()>,
+    // Stops watching when dropped
+    _watcher: RecommendedWatcher,
+}
+
+impl FileWatch {
+    // Fails if the folder can't be watched
+    pub fn start(ctx: &egui::Context, path: &Path) -> Result<Self, String> {
+        let (sender, changes) = mpsc::channel();
+        let ctx = ctx.clone();
+        let file_name = path.file_name().ok_or_else(|| format!("{} is not a file", path.display()))?.to_os_string();
+        let handler = move |result: notify::Result<notify::Event>| {
+            let Ok(event) = result else {
+                return;
+            };
+            let is_write = event.kind.is_create() || event.kind.is_modify();
+            if is_write && event.paths.iter().any(|changed| changed.file_name() == Some(&file_name)) {
+                let _ = sender.send(());
+                ctx.request_repaint();
+            }
+        };
+        let mut watcher = notify::recommended_watcher(handler).map_err(|e| e.to_string())?;
+        let folder = path.parent().filter(|folder| !folder.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        watcher
+            .watch(folder, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("{}: {}", folder.display(), e))?;
+        Ok(Self { path: path.to_path_buf(), changes, _watcher: watcher })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // True if the file was written since the last call
+    pub fn changed(&self) -> bool {
+        self.changes.try_iter().count() > 0
+    }
+}
@@ -0,0 +1,53 @@
+use crate::i18n::tf;
+use eframe::egui::{self, FontData, FontDefinitions, FontFamily};
+use todo_core::settings::FontChoice;
+
+const CUSTOM_FONT: &str = "custom";
+
+// Family names of the fonts installed on the system, sorted and without duplicates.
+// Scanning the font directories takes a moment, so this is only done when the menu is opened.
+pub fn system_families() -> Vec<String> {
+    let mut database = fontdb::Database::new();
+    database.load_system_fonts();
+    let mut families: Vec<String> = database
+        .faces()
+        .filter_map(|face| face.families.first().map(|(name, _)| name.clone()))
+        .collect();
+    families.sort_unstable_by_key(|name| name.to_lowercase());
+    families.dedup();
+    families
+}
+
+// The regular face of an installed family, as font bytes and the index of the face in them
+fn load_system_font(family: &str) -> Option<(Vec<u8>, u32)> {
+    let mut database = fontdb::Database::new();
+    database.load_system_fonts();
+    let query = fontdb::Query {
+        families: &[fontdb::Family::Name(family)],
+        ..Default::default()
+    };
+    let id = database.query(&query)?;
+    database.with_face_data(id, |data, index| (data.to_vec(), index))
+}
+
+// Use the chosen font for all proportional text. egui's own fonts stay behind it
+// as fallbacks for emoji and other characters the font doesn't have.
+pub fn apply(ctx: &egui::Context, choice: &FontChoice) -> Result<(), String> {
+    let mut fonts = FontDefinitions::default();
+    let (data, index) = match choice {
+        FontChoice::Default => {
+            ctx.set_fonts(fonts);
+            return Ok(());
+        }
+        FontChoice::System(family) => load_system_font(family).ok_or_else(|| tf("{} is not installed", &[&family]))?,
+        FontChoice::File(path) => (std::fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?, 0),
+    };
+    let mut font = FontData::from_owned(data);
+    font.index = index;
+    fonts.font_data.insert(CUSTOM_FONT.to_string(), font);
+    if let Some(family) = fonts.families.get_mut(&FontFamily::Proportional) {
+        family.insert(0, CUSTOM_FONT.to_string());
+    }
+    ctx.set_fonts(fonts);
+    Ok(())
+}
@@ -0,0 +1,73 @@
+use crate::i18n::{t, tf};
+use eframe::egui;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use todo_core::google_tasks::{self, Authorization, GoogleAccount};
+use todo_core::TodoList;
+
+// Outcome of a request to Google, errors are already formatted for display
+pub enum GoogleResult {
+    Connected(Result<String, String>),
+    Imported(Result<Vec<TodoList>, String>),
+    Exported(Result<(), String>),
+}
+
+// Runs one Google Tasks request at a time on a background thread,
+// signing in waits on the browser and must not block the UI
+#[derive(Default)]
+pub struct GoogleBridge {
+    job: Option<Receiver<GoogleResult>>,
+    pub status: Option<String>,
+}
+
+impl GoogleBridge {
+    pub fn is_busy(&self) -> bool {
+        self.job.is_some()
+    }
+
+    // Open the consent page in the browser and wait for the redirect
+    pub fn connect(&mut self, ctx: &egui::Context, account: &GoogleAccount) {
+        let authorization = match Authorization::start(account) {
+            Ok(authorization) => authorization,
+            Err(e) => {
+                self.status = Some(tf("Could not start sign-in: {}", &[&format!("{:?}", e)]));
+                return;
+            }
+        };
+        self.status = Some(match webbrowser::open(&authorization.url) {
+            Ok(()) => t("Waiting for the browser…").to_string(),
+            // Signing in still works with the page opened by hand
+            Err(e) => tf("Could not open the browser ({}), open this address in one: {}", &[&e, &authorization.url]),
+        });
+        let account = account.clone();
+        self.spawn(ctx, move || GoogleResult::Connected(authorization.finish(&account).map_err(|e| format!("{:?}", e))));
+    }
+
+    pub fn import(&mut self, ctx: &egui::Context, account: &GoogleAccount) {
+        let account = account.clone();
+        self.spawn(ctx, move || GoogleResult::Imported(google_tasks::import(&account).map_err(|e| format!("{:?}", e))));
+    }
+
+    pub fn export(&mut self, ctx: &egui::Context, account: &GoogleAccount, list: &TodoList) {
+        let account = account.clone();
+        let mut copy = TodoList::new(list.name.clone());
+        copy.items = list.items.clone();
+        self.spawn(ctx, move || GoogleResult::Exported(google_tasks::export(&account, &copy).map_err(|e| format!("{:?}", e))));
+    }
+
+    pub fn poll(&mut self) -> Option<GoogleResult> {
+        let result = self.job.as_ref()?.try_recv().ok()?;
+        self.job = None;
+        Some(result)
+    }
+
+    fn spawn(&mut self, ctx: &egui::Context, work: impl FnOnce() -> GoogleResult + Send + 'static) {
+        let (sender, receiver) = mpsc::channel();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let _ = sender.send(work());
+            ctx.request_repaint();
+        });
+        self.job = Some(receiver);
+    }
+}
@@ -0,0 +1,42 @@
+use crate::i18n::tf;
+use chrono::{Datelike, Days, NaiveDate};
+use eframe::egui::{self, Color32};
+use todo_core::habit::Habit;
+
+const CELL: f32 = 9.0;
+const GAP: f32 = 2.0;
+const DONE: Color32 = Color32::from_rgb(64, 160, 80);
+
+// Grid of the last `weeks` weeks like a contribution graph, a column per week from Monday down,
+// with the days the habit was done filled in
+pub fn heatmap(ui: &mut egui::Ui, habit: &Habit, today: NaiveDate, weeks: u64) {
+    let this_monday = today - Days::new(today.weekday().num_days_from_monday() as u64);
+    let start = this_monday - Days::new((weeks - 1) * 7);
+    let size = egui::vec2(weeks as f32 * (CELL + GAP), 7.0 * (CELL + GAP));
+    let (rect, response) = ui.allocate_exact_size(size, egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let empty = ui.visuals().faint_bg_color.linear_multiply(4.0);
+
+    let mut hovered = None;
+    for week in 0..weeks {
+        for weekday in 0..7 {
+            let day = start + Days::new(week * 7 + weekday);
+            if day > today {
+                break;
+            }
+            let min = rect.min + egui::vec2(week as f32 * (CELL + GAP), weekday as f32 * (CELL + GAP));
+            let cell = egui::Rect::from_min_size(min, egui::vec2(CELL, CELL));
+            let fill = if habit.is_done_on(day) { DONE } else { empty };
+            painter.rect_filled(cell, 2.0, fill);
+            if day == today {
+                painter.rect_stroke(cell, 2.0, ui.visuals().widgets.active.fg_stroke);
+            }
+            if response.hover_pos().is_some_and(|pos| cell.contains(pos)) {
+                hovered = Some(day);
+            }
+        }
+    }
+    if let Some(day) = hovered {
+        response.on_hover_text(tf(if habit.is_done_on(day) { "Done on {}" } else { "Not done on {}" }, &[&day.format("%Y-%m-%d")]));
+    }
+}
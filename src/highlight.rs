@@ -0,0 +1,26 @@
+use eframe::egui::{self, text::LayoutJob, Color32, Stroke, TextFormat};
+use todo_core::search::match_ranges;
+
+// Label text with every occurrence of `query` given a highlighted background
+pub fn highlight(ui: &egui::Ui, text: &str, query: &str, color: Color32, strikethrough: bool) -> LayoutJob {
+    let format = TextFormat {
+        font_id: egui::TextStyle::Body.resolve(ui.style()),
+        color,
+        strikethrough: if strikethrough { Stroke::new(1.0, color) } else { Stroke::NONE },
+        ..Default::default()
+    };
+    let highlighted = TextFormat {
+        background: ui.visuals().selection.bg_fill,
+        ..format.clone()
+    };
+
+    let mut job = LayoutJob::default();
+    let mut last = 0;
+    for range in match_ranges(text, query) {
+        job.append(&text[last..range.start], 0.0, format.clone());
+        job.append(&text[range.clone()], 0.0, highlighted.clone());
+        last = range.end;
+    }
+    job.append(&text[last..], 0.0, format);
+    job
+}
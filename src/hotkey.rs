@@ -0,0 +1,63 @@
+use eframe::egui;
+use global_hotkey::hotkey::{Code, HotKey, Modifiers};
+use global_hotkey::{GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState};
+use std::sync::mpsc::{self, Receiver};
+
+// System wide Ctrl+Alt+T that opens the quick-add window, even while another app has focus
+pub struct QuickAddHotkey {
+    manager: GlobalHotKeyManager,
+    hotkey: HotKey,
+    registered: bool,
+    // What the setting last asked for, so a failed registration isn't retried every frame
+    wanted: bool,
+    events: Receiver<GlobalHotKeyEvent>,
+}
+
+impl QuickAddHotkey {
+    // Fails if global shortcuts aren't available, e.g. on Wayland
+    pub fn new(ctx: &egui::Context) -> Result<Self, String> {
+        let manager = GlobalHotKeyManager::new().map_err(|e| e.to_string())?;
+
+        // Wake the UI, which may be hidden and not repainting
+        let (sender, events) = mpsc::channel();
+        let ctx = ctx.clone();
+        GlobalHotKeyEvent::set_event_handler(Some(move |event| {
+            let _ = sender.send(event);
+            ctx.request_repaint();
+        }));
+
+        Ok(Self {
+            manager,
+            hotkey: HotKey::new(Some(Modifiers::CONTROL | Modifiers::ALT), Code::KeyT),
+            registered: false,
+            wanted: false,
+            events,
+        })
+    }
+
+    // Follow the setting, registering fails if another app already uses the shortcut.
+    // A failure is returned once, and tried again when the setting changes.
+    pub fn set_enabled(&mut self, enabled: bool) -> Result<(), String> {
+        if enabled == self.wanted || enabled == self.registered {
+            self.wanted = enabled;
+            return Ok(());
+        }
+        self.wanted = enabled;
+        let result = if enabled {
+            self.manager.register(self.hotkey)
+        } else {
+            self.manager.unregister(self.hotkey)
+        };
+        result.map(|()| self.registered = enabled).map_err(|e| e.to_string())
+    }
+
+    // Whether the shortcut was pressed since the last frame
+    pub fn pressed(&self) -> bool {
+        let hotkey_id = self.hotkey.id();
+        self.events
+            .try_iter()
+            .filter(|event| event.id == hotkey_id && event.state == HotKeyState::Pressed)
+            .count()
+            > 0
+    }
+}
@@ -0,0 +1,51 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
+use todo_core::settings::Language;
+
+// Language every `t` call translates to. Global so background threads and
+// plain functions can translate without being handed the settings.
+static LANGUAGE: AtomicU8 = AtomicU8::new(Language::English as u8);
+
+// One "English = Translation" pair per line, the English text is the key
+const GERMAN: &str = include_str!("../assets/locales/de.txt");
+
+pub fn set_language(language: Language) {
+    LANGUAGE.store(language as u8, Ordering::Relaxed);
+}
+
+fn parse(file: &'static str) -> HashMap<&'static str, &'static str> {
+    file.lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once(" = "))
+        .collect()
+}
+
+fn translations() -> Option<&'static HashMap<&'static str, &'static str>> {
+    static GERMAN_TABLE: OnceLock<HashMap<&'static str, &'static str>> = OnceLock::new();
+    match Language::from_index(LANGUAGE.load(Ordering::Relaxed)) {
+        Language::English => None,
+        Language::German => Some(GERMAN_TABLE.get_or_init(|| parse(GERMAN))),
+    }
+}
+
+// Text in the current language, or as given if it has no translation
+pub fn t(text: &str) -> &str {
+    translations().and_then(|table| table.get(text).copied()).unwrap_or(text)
+}
+
+// Like `t` for text with `{}` placeholders, filled in order with `args`
+pub fn tf(text: &str, args: &[&dyn Display]) -> String {
+    let mut args = args.iter();
+    let mut out = String::new();
+    for (index, part) in t(text).split("{}").enumerate() {
+        if index > 0 {
+            if let Some(arg) = args.next() {
+                out.push_str(&arg.to_string());
+            }
+        }
+        out.push_str(part);
+    }
+    out
+}
@@ -0,0 +1,44 @@
+use crate::i18n::t;
+use eframe::egui::{self, RichText};
+
+// Emoji offered in the picker, all of them in egui's built-in emoji font.
+// Any other emoji can be typed or pasted into the field below them.
+const CHOICES: [&str; 32] = [
+    "🏠", "💼", "🛒", "📚", "💡", "🎯", "⭐", "❤", "🔥", "✈", "🚗", "🍎", "💰", "📞", "📧", "📅",
+    "🎉", "🎵", "🏃", "🐶", "🌱", "🔧", "💻", "📝", "🎁", "⚠", "✅", "❓", "🧹", "💊", "🎮", "🐛",
+];
+const COLUMNS: usize = 8;
+
+// A button showing the icon, or a faint placeholder without one, that opens the picker
+pub fn picker(ui: &mut egui::Ui, icon: &mut Option<String>) {
+    let button = match icon {
+        Some(icon) => RichText::new(icon.as_str()),
+        None => RichText::new("☺").weak(),
+    };
+    ui.menu_button(button, |ui| {
+        egui::Grid::new("icon_choices").spacing([2.0, 2.0]).show(ui, |ui| {
+            for (index, choice) in CHOICES.iter().enumerate() {
+                if ui.selectable_label(icon.as_deref() == Some(*choice), RichText::new(*choice).size(18.0)).clicked() {
+                    *icon = Some(choice.to_string());
+                    ui.close_menu();
+                }
+                if index % COLUMNS == COLUMNS - 1 {
+                    ui.end_row();
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            let mut text = icon.clone().unwrap_or_default();
+            let response = ui.add(egui::TextEdit::singleline(&mut text).hint_text(t("Other")).desired_width(60.0));
+            if response.changed() {
+                *icon = Some(text.trim().to_string()).filter(|text| !text.is_empty());
+            }
+            if ui.button(t("No icon")).clicked() {
+                *icon = None;
+                ui.close_menu();
+            }
+        });
+    })
+    .response
+    .on_hover_text(t("Icon"));
+}
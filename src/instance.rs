@@ -0,0 +1,169 @@
+use directories::ProjectDirs;
+use eframe::egui;
+use std::fs::OpenOptions;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+use subtle::ConstantTimeEq;
+
+// A request is one line with a path or a task description, anything longer is cut off
+const MAX_REQUEST_LENGTH: u64 = 64 * 1024;
+
+// What a second launch asks the running instance to do
+pub enum Request {
+    Show,
+    Open(PathBuf),
+    Add(String),
+}
+
+impl Request {
+    // `rust_todo_list [file]` or `rust_todo_list --add <description>`
+    pub fn from_args(mut args: impl Iterator<Item = std::ffi::OsString>) -> Self {
+        match args.next() {
+            Some(flag) if flag == "--add" => Request::Add(args.map(|arg| arg.to_string_lossy().into_owned()).collect::<Vec<_>>().join(" ")),
+            Some(path) => Request::Open(PathBuf::from(path)),
+            None => Request::Show,
+        }
+    }
+
+    // `--add` without any text just brings up the window
+    pub fn filter_empty(self) -> Self {
+        match self {
+            Request::Add(description) if description.trim().is_empty() => Request::Show,
+            request => request,
+        }
+    }
+
+    fn encode(&self) -> String {
+        match self {
+            Request::Show => "show\t".to_string(),
+            Request::Open(path) => format!("open\t{}", path.display()),
+            // One request per line, so new lines are sent escaped
+            Request::Add(description) => format!("add\t{}", description.replace('\\', "\\\\").replace('\n', "\\n")),
+        }
+    }
+
+    fn decode(line: &str) -> Option<Self> {
+        match line.split_once('\t')? {
+            ("show", _) => Some(Request::Show),
+            ("open", path) => Some(Request::Open(PathBuf::from(path))),
+            ("add", description) => Some(Request::Add(unescape(description))),
+            _ => None,
+        }
+    }
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+// Port and secret of the running instance, so stray programs on the port aren't mistaken for it
+fn lock_path() -> Option<PathBuf> {
+    ProjectDirs::from("", "", "rust_todo_list").map(|dirs| dirs.data_local_dir().join("instance"))
+}
+
+// Only readable by the user, so other users of the computer can't send requests to the instance
+fn write_lock(path: &Path, contents: &str) -> io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path)?;
+    // A lock file left by an older version may be readable by others
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+    file.write_all(contents.as_bytes())
+}
+
+// Hand the request to an instance that is already running.
+// Returns false if there is none, then this launch should become the instance.
+pub fn forward(request: &Request) -> bool {
+    let Some(lock) = lock_path().and_then(|path| std::fs::read_to_string(path).ok()) else {
+        return false;
+    };
+    let Some((port, secret)) = lock.trim().split_once(' ') else {
+        return false;
+    };
+    let Ok(port) = port.parse::<u16>() else {
+        return false;
+    };
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let Ok(mut stream) = TcpStream::connect_timeout(&address, Duration::from_millis(500)) else {
+        return false;
+    };
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(2)));
+    if writeln!(stream, "{}\t{}", secret, request.encode()).is_err() {
+        return false;
+    }
+    // The instance answers once it has checked the secret
+    let mut reply = String::new();
+    BufReader::new(&stream).read_line(&mut reply).is_ok() && reply.trim() == "ok"
+}
+
+// Accepts requests from later launches on a local port
+pub struct Instance {
+    requests: Receiver<Request>,
+}
+
+impl Instance {
+    // Fails if the port or lock file can't be set up, the app then simply runs without
+    // handing off
+    pub fn start(ctx: &egui::Context) -> Result<Self, String> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).map_err(|e| e.to_string())?;
+        let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+        let mut bytes = [0u8; 16];
+        getrandom::getrandom(&mut bytes).map_err(|e| e.to_string())?;
+        let secret: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+        let path = lock_path().ok_or("No data directory for the lock file")?;
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        write_lock(&path, &format!("{} {}", port, secret)).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        let (sender, requests) = mpsc::channel();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let mut line = String::new();
+                let _ = stream.set_read_timeout(Some(Duration::from_secs(2)));
+                if BufReader::new((&stream).take(MAX_REQUEST_LENGTH)).read_line(&mut line).is_err() {
+                    continue;
+                }
+                let line = line.trim_end_matches(['\r', '\n']);
+                let request = line
+                    .split_once('\t')
+                    // Compared in constant time, so response times don't give the secret away
+                    .filter(|(sent_secret, _)| bool::from(sent_secret.as_bytes().ct_eq(secret.as_bytes())))
+                    .and_then(|(_, request)| Request::decode(request));
+                if let Some(request) = request {
+                    let _ = (&stream).write_all(b"ok\n");
+                    if sender.send(request).is_err() {
+                        break;
+                    }
+                    ctx.request_repaint();
+                }
+            }
+        });
+        Ok(Self { requests })
+    }
+
+    pub fn poll(&self) -> Vec<Request> {
+        self.requests.try_iter().collect()
+    }
+}
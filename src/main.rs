@@ -1,223 +1,4320 @@
 // Disable terminal
 #![windows_subsystem = "windows"]
 
-use eframe::egui::{self, Color32, RichText, Slider, TextStyle::Body, Visuals};
-use rfd::FileDialog;
-use serde::{Deserialize, Serialize};
-use std::sync::atomic::{AtomicU32, Ordering};
-use std::{fs::File, io::BufReader, io::BufWriter};
+#[cfg(feature = "api")]
+mod api;
+mod attachments;
+mod avatar;
+mod board;
+mod calendar;
+mod custom_fields;
+mod dialogs;
+mod file_sync;
+#[cfg(not(target_arch = "wasm32"))]
+mod file_watch;
+mod fonts;
+mod google;
+mod habits;
+mod highlight;
+#[cfg(not(target_arch = "wasm32"))]
+mod hotkey;
+mod i18n;
+mod icons;
+mod instance;
+mod markdown;
+mod matrix;
+mod my_day;
+mod notifications;
+mod paste_image;
+#[cfg(not(target_arch = "wasm32"))]
+mod peers;
+mod pomodoro;
+mod saver;
+mod shortcuts;
+mod smart_lists;
+mod sound;
+mod stats;
+mod sync;
+mod theme;
+mod timeline;
+mod toasts;
+#[cfg(not(target_arch = "wasm32"))]
+mod tray;
+mod voice;
 
-// Generate unique IDs for each todo item to ensure each item's uniqueness
-static NEXT_ID: AtomicU32 = AtomicU32::new(1);
+#[cfg(feature = "api")]
+use api::{ApiServer, Call, Reply};
+use calendar::Calendar;
+use timeline::Timeline;
+use chrono::{Local, Timelike, Utc};
+use eframe::egui::{self, Color32, RichText, Slider, TextStyle::Body};
+use egui_extras::DatePickerButton;
+use google::{GoogleBridge, GoogleResult};
+#[cfg(not(target_arch = "wasm32"))]
+use hotkey::QuickAddHotkey;
+use i18n::{t, tf};
+use instance::{Instance, Request};
+use notifications::Notifier;
+#[cfg(not(target_arch = "wasm32"))]
+use peers::PeerSync;
+use pomodoro::FocusTimer;
+use saver::Saver;
+use dialogs::FileDialog;
+use file_sync::FileSyncer;
+#[cfg(not(target_arch = "wasm32"))]
+use file_watch::FileWatch;
+use shortcuts::Shortcut;
+use sync::Syncer;
+use toasts::Toasts;
+#[cfg(not(target_arch = "wasm32"))]
+use tray::{Tray, TrayAction};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use todo_core::autosave::{self, Autosave};
+use todo_core::csv_io::{self, CsvImport};
+use todo_core::history::{self, Command};
+use todo_core::query::{self, Query};
+use todo_core::quick_add;
+use todo_core::habit::Habit;
+use todo_core::recurrence::Recurrence;
+use todo_core::reminder::{self, Snooze};
+use todo_core::settings::{DeleteConfirmation, FontChoice, Language, Settings, WindowGeometry, Wrapping};
+use todo_core::theme::Theme;
+use todo_core::tracking::{format_duration, seconds_until_next_change};
+use todo_core::filter::{Completion, DueBucket, Group, Grouping, Sections, SmartList};
+#[cfg(not(target_arch = "wasm32"))]
+use todo_core::database;
+#[cfg(not(target_arch = "wasm32"))]
+use todo_core::pdf;
+use todo_core::{checklist, encryption, export, filter, html, ical, load_lists, merge, save_lists, todoist};
+use todo_core::{ColorLabel, Filter, Priority, SortOrder, TodoItem, TodoList, TrashedItem, TRASH_RETENTION_DAYS};
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct TodoItem {
-    id: u32,
-    description: String,
-    completed: bool,
-    edit: bool,
+// Why the passphrase window is asking
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PassphrasePurpose {
+    // Decrypt an existing file and open it
+    Open,
+    // Start saving to the file encrypted, the passphrase is typed twice
+    Encrypt,
+}
+
+struct PassphrasePrompt {
+    path: PathBuf,
+    purpose: PassphrasePurpose,
+    passphrase: String,
+    confirmation: String,
+    error: Option<String>,
+}
+
+impl PassphrasePrompt {
+    fn new(path: PathBuf, purpose: PassphrasePurpose) -> Self {
+        Self {
+            path,
+            purpose,
+            passphrase: String::new(),
+            confirmation: String::new(),
+            error: None,
+        }
+    }
+}
+
+// How the open list is laid out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ViewMode {
+    List,
+    Board,
+    Calendar,
+    // Bars from start to due date
+    Timeline,
+    // Urgent/important quadrants
+    Matrix,
+    // Today's plan across all lists
+    MyDay,
+}
+
+// Something done to every item picked with Ctrl+click at once
+enum BulkAction {
+    Complete,
+    Delete,
+    MoveTo(usize),
+    AddTag(String),
+    ClearSelection,
+}
+
+// Context menu entries that change the list rather than just the row
+enum RowAction {
+    // Due dates of the copy moved by one interval, if any
+    Duplicate(Option<Recurrence>),
+    MoveTo(usize),
+    // Open the task window with its activity log
+    Details,
+}
+
+// What the rows of the list view share in a frame, and what was done in them
+struct Rows {
+    today: chrono::NaiveDate,
+    now: chrono::DateTime<Local>,
+    palette: theme::Palette,
+    selection_step: i32,
+    can_reorder: bool,
+    auto_complete_parents: bool,
+    completion_animation: bool,
+    completion_sound: bool,
+    wrapping: Wrapping,
+    clamp_lines: usize,
+    query: String,
+    list_names: Vec<String>,
+    // Applied once every row is drawn
+    clicked_row: Option<u32>,
+    commands: Vec<Command>,
+    // Dragged item ID and the index it was dropped on
+    moved: Option<(u32, usize)>,
+    row_action: Option<(u32, RowAction)>,
+    start_focus: Option<u32>,
+    to_remove: Vec<usize>,
+}
+
+const APP_NAME: &str = "Todo List App";
+
+// Widget ID of the search box so Ctrl+F can focus it
+const SEARCH_ID: &str = "search_box";
+
+// Badge color for each priority level
+fn priority_color(priority: Priority) -> Color32 {
+    match priority {
+        Priority::Low => Color32::GRAY,
+        Priority::Medium => Color32::from_rgb(70, 130, 180),
+        Priority::High => Color32::from_rgb(230, 140, 0),
+        Priority::Urgent => Color32::RED,
+    }
+}
+
+// Stripe color for each color label
+fn label_color(color: ColorLabel) -> Color32 {
+    match color {
+        ColorLabel::Red => Color32::from_rgb(220, 50, 50),
+        ColorLabel::Orange => Color32::from_rgb(240, 140, 30),
+        ColorLabel::Yellow => Color32::from_rgb(230, 200, 40),
+        ColorLabel::Green => Color32::from_rgb(60, 170, 80),
+        ColorLabel::Blue => Color32::from_rgb(60, 120, 220),
+        ColorLabel::Purple => Color32::from_rgb(150, 80, 200),
+    }
+}
+
+// Heading of a group in the list view, in the interface language
+fn group_heading(group: &Group) -> String {
+    match group {
+        Group::Due(bucket) => t(bucket.label()).to_string(),
+        Group::Context(Some(context)) => format!("@{}", context),
+        Group::Context(None) => t("No context").to_string(),
+    }
+}
+
+// Recurrence::label in the interface language
+fn recurrence_label(recurrence: Recurrence) -> String {
+    match recurrence {
+        Recurrence::EveryNDays(days) => tf("Every {} days", &[&days]),
+        _ => t(&recurrence.label()).to_string(),
+    }
+}
+
+// Title of the error shown when an autosave didn't reach the last used file
+fn autosave_failed(last_file: Option<&Path>) -> &'static str {
+    if autosave::keeps_recovery(last_file) {
+        t("Autosave failed, changes were written to the recovery file")
+    } else {
+        t("Autosave of the encrypted file failed, the changes are not saved yet")
+    }
+}
+
+// "Created 3 days ago", and when it was completed, for the tooltip of a description.
+// None for items from saves without creation times.
+fn age_text(item: &TodoItem, now: chrono::DateTime<Utc>) -> Option<String> {
+    let mut text = tf("Created {} ago", &[&todo_core::stats::format_age(now - item.created_at?)]);
+    if let Some(completed_at) = item.completed_at.filter(|_| item.completed) {
+        text.push('\n');
+        text.push_str(&tf("Completed {} ago", &[&todo_core::stats::format_age(now - completed_at)]));
+    }
+    Some(text)
 }
 
 struct TodoApp {
-    items: Vec<TodoItem>,
+    lists: Vec<TodoList>,
+    // Index of the list shown in the open tab
+    current_list: usize,
+    // Name being typed while a list is renamed
+    rename_input: String,
     input: String,
-    text_size: f32,    
-    dark_mode: bool,  
+    settings: Settings,
     showing_add_item_input: bool,
+    // IDs of items waiting for the user to confirm their deletion
+    pending_delete: Option<Vec<u32>>,
+    // Save file dropped onto the window or passed to a later launch, waiting for the
+    // unsaved changes to be dealt with
+    pending_drop: Option<PathBuf>,
+    showing_close_confirmation: bool,
+    // Kept out of `settings` while the window moves so the file is only written on exit
+    window: WindowGeometry,
+    // The user chose to quit anyway, so the next close request goes through
+    close_confirmed: bool,
+    // Item picked with the mouse or arrow keys, target of keyboard actions
+    selected: Option<u32>,
+    // Items picked for bulk actions, separate from completion and the keyboard selection
+    bulk_selection: HashSet<u32>,
+    bulk_tag_input: String,
+    showing_trash: bool,
+    showing_archive: bool,
+    showing_stats: bool,
+    // Custom fields of the open list
+    showing_fields: bool,
+    // Name typed for the current filters while saving them as a smart list
+    smart_list_name: Option<String>,
+    field_draft: custom_fields::Draft,
+    archive_search: String,
+    // Day the lists were last checked for tasks to archive
+    archive_checked: Option<chrono::NaiveDate>,
+    // Small window for adding a task from the tray without the full UI
+    showing_quick_add: bool,
+    #[cfg(not(target_arch = "wasm32"))]
+    hotkey: Option<QuickAddHotkey>,
+    // Receives files and tasks from later launches, None if handing off isn't available
+    instance: Option<Instance>,
+    // Serves the open list to the network while enabled in the settings
+    #[cfg(feature = "api")]
+    api: Option<ApiServer>,
+    quick_add_input: String,
+    #[cfg(not(target_arch = "wasm32"))]
+    tray: Option<Tray>,
+    window_visible: bool,
+    // CSV file waiting for its columns to be mapped before import
+    csv_import: Option<CsvImport>,
+    // Text of a checklist being imported, pasted in or read from a file
+    checklist_import: Option<ChecklistImport>,
+    sort_order: SortOrder,
+    // Headings the open items are shown under, by due date or context
+    grouping: Grouping,
+    view_mode: ViewMode,
+    calendar: Calendar,
+    timeline: Timeline,
+    // Item shown in the detail panel, opened by clicking it in the list or calendar
+    detail_item: Option<u32>,
+    focus: Option<FocusTimer>,
+    // Only one task is shown, the selected one or else the top open one
+    focus_mode: bool,
+    // Voice memo being recorded for the item with this ID
+    recording: Option<(u32, voice::Recorder)>,
+    syncer: Option<Syncer>,
+    file_syncer: Option<FileSyncer>,
+    // Writes autosaves off the UI thread, the web build saves in place
+    saver: Option<Saver>,
+    // Watches the last used file for writes by other programs
+    #[cfg(not(target_arch = "wasm32"))]
+    file_watch: Option<FileWatch>,
+    // The last used file if watching it failed
+    #[cfg(not(target_arch = "wasm32"))]
+    unwatchable_file: Option<PathBuf>,
+    // Lists from the last used file after another program changed it, while asking what to do.
+    // Autosave waits meanwhile, so it doesn't overwrite them.
+    external_change: Option<Vec<TodoList>>,
+    // Swaps lists with other computers on the network while LAN sync is set up
+    #[cfg(not(target_arch = "wasm32"))]
+    peers: Option<PeerSync>,
+    // LAN sync key being typed, applied on Enter or when the field loses focus so a half-typed
+    // key doesn't restart sync
+    #[cfg(not(target_arch = "wasm32"))]
+    lan_sync_key: String,
+    // API token being typed while the Todoist import window is open
+    todoist_token: Option<String>,
+    google: GoogleBridge,
+    showing_google: bool,
+    passphrase_prompt: Option<PassphrasePrompt>,
+    toasts: Toasts,
+    showing_sync_settings: bool,
+    showing_theme_editor: bool,
+    // Installed font families, listed the first time the font menu is opened
+    system_fonts: Option<Vec<String>>,
+    filter: Filter,
+    autosave: Autosave,
+    notifier: Notifier,
+    // Items as they were when their edit mode was entered, so the whole edit undoes at once
+    edit_originals: HashMap<u32, TodoItem>,
+    // The same for the activity log, which sees an edit only once it is finished
+    log_originals: HashMap<u32, TodoItem>,
+    // Height of each row when it was last drawn, used for rows scrolled out of view
+    row_heights: HashMap<u32, f32>,
+    // Items of the open list as they were before this frame first changed it, for the end of
+    // the frame to save and stamp what changed. None while nothing has changed.
+    unchanged_items: Option<Vec<TodoItem>>,
+}
+
+impl Default for TodoApp {
+    fn default() -> Self {
+        Self {
+            lists: vec![TodoList::new("Todo".to_string())],
+            current_list: 0,
+            rename_input: String::new(),
+            input: String::new(),
+            settings: Settings::load(),
+            showing_add_item_input: false,
+            selected: None,
+            bulk_selection: HashSet::new(),
+            bulk_tag_input: String::new(),
+            pending_delete: None,
+            pending_drop: None,
+            showing_close_confirmation: false,
+            window: WindowGeometry::default(),
+            close_confirmed: false,
+            showing_trash: false,
+            showing_archive: false,
+            showing_fields: false,
+            smart_list_name: None,
+            field_draft: custom_fields::Draft::default(),
+            showing_stats: false,
+            archive_search: String::new(),
+            archive_checked: None,
+            showing_quick_add: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            hotkey: None,
+            instance: None,
+            #[cfg(feature = "api")]
+            api: None,
+            quick_add_input: String::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            tray: None,
+            window_visible: true,
+            csv_import: None,
+            checklist_import: None,
+            sort_order: SortOrder::Manual,
+            grouping: Grouping::None,
+            view_mode: ViewMode::List,
+            calendar: Calendar::new(Local::now().date_naive()),
+            timeline: Timeline::new(Local::now().date_naive()),
+            detail_item: None,
+            focus: None,
+            focus_mode: false,
+            recording: None,
+            syncer: None,
+            file_syncer: None,
+            saver: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            file_watch: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            unwatchable_file: None,
+            external_change: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            peers: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            lan_sync_key: String::new(),
+            todoist_token: None,
+            google: GoogleBridge::default(),
+            showing_google: false,
+            passphrase_prompt: None,
+            toasts: Toasts::default(),
+            showing_sync_settings: false,
+            showing_theme_editor: false,
+            system_fonts: None,
+            filter: Filter::default(),
+            autosave: Autosave::default(),
+            notifier: Notifier::start(),
+            edit_originals: HashMap::new(),
+            log_originals: HashMap::new(),
+            row_heights: HashMap::new(),
+            unchanged_items: None,
+        }
+    }
 }
 
-impl Default for TodoApp {
-    fn default() -> Self {
-        Self {
-            items: Vec::new(),
-            input: String::new(),
-            text_size: 14.0,   
-            dark_mode: false,
-            showing_add_item_input: false,
+impl TodoApp {
+    // Restore the list from the recovery file, or else open the file passed on the
+    // command line or the last used file
+    fn new(cc: &eframe::CreationContext, request: Request) -> Self {
+        let mut app = Self::default();
+        // Thumbnails of attached pictures are loaded from their files
+        egui_extras::install_image_loaders(&cc.egui_ctx);
+        i18n::set_language(app.settings.language);
+        // The web build has no tray, global shortcuts, other instances or background threads
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            match Tray::new(&cc.egui_ctx) {
+                Ok(tray) => app.tray = Some(tray),
+                Err(e) => app.toasts.error(t("Could not create the tray icon"), e),
+            }
+            match QuickAddHotkey::new(&cc.egui_ctx) {
+                Ok(hotkey) => app.hotkey = Some(hotkey),
+                Err(e) => app.toasts.error(t("Could not set up global shortcuts"), e),
+            }
+            match Instance::start(&cc.egui_ctx) {
+                Ok(instance) => app.instance = Some(instance),
+                Err(e) => app.toasts.error(t("Could not listen for other launches of the app"), e),
+            }
+            app.syncer = Some(Syncer::start(&cc.egui_ctx));
+            app.file_syncer = Some(FileSyncer::start(&cc.egui_ctx));
+            app.saver = Some(Saver::start(&cc.egui_ctx));
+            app.lan_sync_key = app.settings.lan_sync.key.clone();
+            app.restart_peer_sync(&cc.egui_ctx);
+        }
+        #[cfg(feature = "api")]
+        app.restart_api(&cc.egui_ctx);
+        let opened_file = match &request {
+            Request::Open(path) => Some(path.clone()),
+            _ => None,
+        };
+        app.window = app.settings.window;
+        app.apply_font(&cc.egui_ctx);
+        if let Some(lists) = autosave::load_recovery() {
+            app.lists = lists;
+            // The recovered changes haven't reached the last used file yet
+            app.autosave.mark_changed();
+            // Opening another file now would let the next save discard the recovered changes
+            if let Some(path) = opened_file {
+                app.toasts.error(
+                    tf("Did not open {}", &[&path.display()]),
+                    t("Unsaved changes from the last session were restored instead. Save them, then load the file."),
+                );
+            }
+        } else if let Some(path) = opened_file.or_else(|| app.settings.last_file.clone()) {
+            app.open_file(path);
+        }
+        // Added before the first frame, so change detection wouldn't see it
+        if let Request::Add(description) = request.filter_empty() {
+            app.add_item(description.trim().to_string());
+            app.autosave.mark_changed();
+        }
+        app.notifier.update(&app.lists, app.settings.notifications);
+        app
+    }
+
+    // Falls back to the default font if the chosen one can't be loaded
+    fn apply_font(&mut self, ctx: &egui::Context) {
+        if let Err(e) = fonts::apply(ctx, &self.settings.font) {
+            self.toasts.error(t("Failed to load the font"), e);
+            self.settings.font = FontChoice::Default;
+        }
+    }
+
+    // Files and tasks handed over by later launches, which exit right after
+    fn handle_instance_requests(&mut self, ctx: &egui::Context) {
+        let Some(instance) = &self.instance else {
+            return;
+        };
+        for request in instance.poll().into_iter().map(Request::filter_empty) {
+            self.window_visible = true;
+            ctx.send_viewport_cmd(egui::ViewportCommand::Visible(true));
+            ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+            match request {
+                Request::Show => {}
+                Request::Open(path) if self.autosave.has_unsaved_changes() => self.pending_drop = Some(path),
+                Request::Open(path) => self.open_file(path),
+                Request::Add(description) => {
+                    self.add_item(description.trim().to_string());
+                    self.toasts.info(tf("Added to {}", &[&self.list().name]));
+                }
+            }
+        }
+    }
+
+    // Leave the network and join it again with the current key, if LAN sync is set up
+    #[cfg(not(target_arch = "wasm32"))]
+    fn restart_peer_sync(&mut self, ctx: &egui::Context) {
+        self.peers = None;
+        if self.settings.lan_sync.is_configured() {
+            match PeerSync::start(ctx, self.settings.lan_sync.key.clone()) {
+                Ok(peers) => self.peers = Some(peers),
+                Err(e) => self.toasts.error(t("Could not start LAN sync"), e),
+            }
+        }
+    }
+
+    // Stop the API server and start it again with the current port and token, if it is enabled
+    #[cfg(feature = "api")]
+    fn restart_api(&mut self, ctx: &egui::Context) {
+        // Drop the old server first so its port is free again
+        self.api = None;
+        if !self.settings.api.enabled {
+            return;
+        }
+        if self.settings.api.token.is_empty() {
+            self.settings.api.token = api::new_token();
+            self.settings.save();
+        }
+        match ApiServer::start(ctx, self.settings.api.port, self.settings.api.lan, self.settings.api.token.clone()) {
+            Ok(server) => self.api = Some(server),
+            Err(e) => self.toasts.error(tf("Could not start the API server on port {}", &[&self.settings.api.port]), e),
+        }
+    }
+
+    // Requests from the API server, answered from and applied to the open list like edits in the window
+    #[cfg(feature = "api")]
+    fn handle_api_calls(&mut self) {
+        let Some(server) = &self.api else {
+            return;
+        };
+        for call in server.poll() {
+            call.answer_with(|call| self.answer_api_call(call));
+        }
+    }
+
+    #[cfg(feature = "api")]
+    fn answer_api_call(&mut self, call: Call) -> Reply {
+        let today = Local::now().date_naive();
+        let index_of = |list: &TodoList, id: u32| list.items.iter().position(|item| item.id == id);
+        match call {
+            Call::List => Reply::Items(self.list().items.clone()),
+            Call::Get(id) => match index_of(self.list(), id) {
+                Some(index) => Reply::Item(self.list().items[index].clone()),
+                None => Reply::NotFound,
+            },
+            Call::Add(fields) => match fields.description.as_deref().map(str::trim) {
+                Some(description) if !description.is_empty() => {
+                    let mut item = quick_add::parse(description, today).into_item();
+                    api::ItemFields { description: None, ..fields }.apply_to(&mut item);
+                    item.stamp_completion(Utc::now());
+                    let list = self.list_mut();
+                    list.history.push(Command::Add { index: list.items.len(), item: item.clone() });
+                    list.items.push(item.clone());
+                    Reply::Created(item)
+                }
+                _ => Reply::BadRequest("A description is required".to_string()),
+            },
+            Call::Update(id, fields) => match index_of(self.list(), id) {
+                Some(index) => {
+                    let list = self.list_mut();
+                    let before = list.items[index].clone();
+                    let item = &mut list.items[index];
+                    fields.apply_to(item);
+                    item.reschedule_if_recurring(today);
+                    item.stamp_completion(Utc::now());
+                    item.log_changes(&before, Utc::now());
+                    let after = item.clone();
+                    if after != before {
+                        list.history.push(Command::Edit { before: Box::new(before), after: Box::new(after.clone()) });
+                    }
+                    Reply::Item(after)
+                }
+                None => Reply::NotFound,
+            },
+            Call::Delete(id) => match index_of(self.list(), id) {
+                Some(index) => {
+                    self.delete_items(vec![index], today);
+                    Reply::Deleted
+                }
+                None => Reply::NotFound,
+            },
+        }
+    }
+
+    // Settings row for the API server, with the token clients need to send
+    #[cfg(feature = "api")]
+    fn show_api_settings(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(t("API server:"));
+            ui.checkbox(&mut self.settings.api.enabled, t("Serve the open list on port"));
+            ui.add(egui::DragValue::new(&mut self.settings.api.port).clamp_range(1024..=65535));
+            ui.checkbox(&mut self.settings.api.lan, t("Reachable from other devices"));
+        });
+        if self.settings.api.lan {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                t("The API uses plain HTTP, so anyone on the network can read the token and the tasks sent with it"),
+            );
+        }
+        if self.api.is_some() {
+            ui.horizontal(|ui| {
+                ui.label(t("Token:"));
+                ui.monospace(&self.settings.api.token);
+                if ui.button(t("Copy")).clicked() {
+                    ui.output_mut(|o| o.copied_text = self.settings.api.token.clone());
+                }
+                if ui.button(t("New token")).on_hover_text(t("Clients using the old token are locked out")).clicked() {
+                    self.settings.api.token = api::new_token();
+                }
+            });
+        }
+    }
+
+    // Turn the changes made to one row this frame into an undoable command
+    fn record_row_change(&mut self, before: TodoItem, index: usize, commands: &mut Vec<Command>) {
+        if self.list().items[index] != before {
+            self.begin_item_change(index, &before);
+        }
+        // Edits to subtasks count as edits to the top level item
+        let item = &self.lists[self.current_list].items[index];
+        if !before.is_editing() && item.is_editing() {
+            self.edit_originals.insert(item.id, before);
+        } else if before.is_editing() && !item.is_editing() {
+            if let Some(original) = self.edit_originals.remove(&item.id) {
+                if original != *item {
+                    commands.push(Command::Edit { before: Box::new(original), after: Box::new(item.clone()) });
+                }
+            }
+        } else if !item.is_editing() && *item != before {
+            let mut toggled = before.clone();
+            toggled.completed = !toggled.completed;
+            if toggled == *item {
+                commands.push(Command::ToggleComplete { id: item.id });
+            } else {
+                commands.push(Command::Edit { before: Box::new(before), after: Box::new(item.clone()) });
+            }
+        }
+    }
+
+    // Bar above the list while items are picked for bulk actions
+    fn show_bulk_bar(&mut self, ui: &mut egui::Ui) -> Option<BulkAction> {
+        let mut action = None;
+        ui.horizontal_wrapped(|ui| {
+            ui.label(RichText::new(tf("{} selected", &[&self.bulk_selection.len()])).strong());
+            if ui.button(t("✔ Complete")).clicked() {
+                action = Some(BulkAction::Complete);
+            }
+            if ui.button(RichText::new(t("❌ Delete")).color(Color32::RED)).clicked() {
+                action = Some(BulkAction::Delete);
+            }
+            if self.lists.len() > 1 {
+                ui.menu_button(t("Move to"), |ui| {
+                    for (index, list) in self.lists.iter().enumerate() {
+                        if index != self.current_list && ui.button(&list.name).clicked() {
+                            action = Some(BulkAction::MoveTo(index));
+                            ui.close_menu();
+                        }
+                    }
+                });
+            }
+            let response = ui.add(egui::TextEdit::singleline(&mut self.bulk_tag_input).hint_text(t("Add tag")).desired_width(80.0));
+            if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) && !self.bulk_tag_input.trim().is_empty() {
+                action = Some(BulkAction::AddTag(std::mem::take(&mut self.bulk_tag_input)));
+            }
+            // Picked items in list order
+            let picked = self.list().items.iter().filter(|item| self.bulk_selection.contains(&item.id));
+            if ui.button(t("Copy as text")).clicked() {
+                ui.output_mut(|o| o.copied_text = export::items_to_text(picked.clone()));
+            }
+            if ui.button(t("Copy as Markdown")).clicked() {
+                ui.output_mut(|o| o.copied_text = export::items_to_markdown(picked));
+            }
+            if ui.button(t("Clear selection")).clicked() {
+                action = Some(BulkAction::ClearSelection);
+            }
+        });
+        ui.separator();
+        action
+    }
+
+    // Complete and tag are undoable as one step. Deleting goes through the usual confirmation.
+    fn apply_bulk_action(&mut self, action: BulkAction, to_remove: &mut Vec<usize>, now: chrono::DateTime<Local>) {
+        let today = now.date_naive();
+        let indices: Vec<usize> = (0..self.list().items.len())
+            .filter(|&index| self.bulk_selection.contains(&self.list().items[index].id))
+            .collect();
+        match action {
+            BulkAction::Complete | BulkAction::AddTag(_) => {
+                let mut commands = Vec::new();
+                for &index in &indices {
+                    let item = &mut self.list_mut().items[index];
+                    let before = item.clone();
+                    match &action {
+                        BulkAction::AddTag(tag) => item.add_tag(tag),
+                        _ if !item.completed => {
+                            item.completed = true;
+                            item.stop_tracking(now);
+                            item.reschedule_if_recurring(today);
+                        }
+                        _ => {}
+                    }
+                    if *item != before {
+                        commands.push(Command::Edit { before: Box::new(before), after: Box::new(item.clone()) });
+                    }
+                }
+                if !commands.is_empty() {
+                    self.list_mut().history.push(Command::Batch(commands));
+                }
+            }
+            BulkAction::Delete => {
+                to_remove.extend(indices);
+                self.bulk_selection.clear();
+            }
+            BulkAction::MoveTo(target) => {
+                self.move_to_list(&indices, target);
+                self.bulk_selection.clear();
+            }
+            BulkAction::ClearSelection => self.bulk_selection.clear(),
+        }
+    }
+
+    // Not undoable, like restoring from the trash. The items go to the end of the other list in the
+    // order they had, stamped so syncing takes them for changed. `indices` must be ascending.
+    fn move_to_list(&mut self, indices: &[usize], target: usize) {
+        let mut moved = Vec::with_capacity(indices.len());
+        for &index in indices.iter().rev() {
+            let item = self.list_mut().items.remove(index);
+            self.edit_originals.remove(&item.id);
+            if self.selected == Some(item.id) {
+                self.selected = None;
+            }
+            moved.push(item);
+        }
+        let now = Utc::now();
+        moved.reverse();
+        moved.iter_mut().for_each(|item| item.modified = Some(now));
+        self.lists[target].items.extend(moved);
+    }
+
+    // The copy goes right below the original
+    fn duplicate_item(&mut self, index: usize, shift: Option<Recurrence>) {
+        let copy = self.list().items[index].duplicate(shift);
+        let list = self.list_mut();
+        list.items.insert(index + 1, copy.clone());
+        list.history.push(Command::Add { index: index + 1, item: copy });
+    }
+
+    fn list(&self) -> &TodoList {
+        &self.lists[self.current_list]
+    }
+
+    fn list_mut(&mut self) -> &mut TodoList {
+        self.begin_change();
+        &mut self.lists[self.current_list]
+    }
+
+    // Called before changing the open list, only the first change of a frame copies the items
+    fn begin_change(&mut self) {
+        if self.unchanged_items.is_none() {
+            self.unchanged_items = Some(self.list().items.clone());
+        }
+    }
+
+    // The same for an item that widgets already changed in place, `before` being how it was
+    fn begin_item_change(&mut self, index: usize, before: &TodoItem) {
+        if self.unchanged_items.is_none() {
+            let mut items = self.list().items.clone();
+            items[index] = before.clone();
+            self.unchanged_items = Some(items);
+        }
+    }
+
+    fn undo(&mut self) {
+        self.list_mut().undo();
+    }
+
+    fn redo(&mut self) {
+        self.list_mut().redo();
+    }
+
+    fn add_list(&mut self) {
+        let name = tf("List {}", &[&(self.lists.len() + 1)]);
+        self.lists.push(TodoList::new(name));
+        self.current_list = self.lists.len() - 1;
+    }
+
+    // The last remaining list can't be deleted
+    fn delete_list(&mut self, index: usize) {
+        if self.lists.len() > 1 {
+            self.lists.remove(index);
+            if self.current_list >= index && self.current_list > 0 {
+                self.current_list -= 1;
+            }
+        }
+    }
+
+    // Add a task to the open list so it can be undone like any other addition
+    // Dates, #tags and !priorities typed along with the description fill in those fields
+    fn add_item(&mut self, description: String) {
+        let item = quick_add::parse(&description, Local::now().date_naive()).into_item();
+        let list = self.list_mut();
+        list.history.push(Command::Add { index: list.items.len(), item: item.clone() });
+        list.items.push(item);
+    }
+
+    // Write to the last used file, or ask where to save if there is none yet
+    // Returns false if nothing was written
+    fn save(&mut self) -> bool {
+        // A background save of the same file could otherwise finish after this one and undo it
+        self.wait_for_saves();
+        match self.settings.last_file.clone() {
+            Some(path) => match save_lists(&path, &self.lists) {
+                Ok(()) => {
+                    autosave::clear_recovery();
+                    self.autosave.mark_saved();
+                    self.toasts.info(t("Saved"));
+                    true
+                }
+                Err(e) => {
+                    self.toasts.error(tf("Could not save {}", &[&path.display()]), e);
+                    false
+                }
+            },
+            #[cfg(not(target_arch = "wasm32"))]
+            None => self.save_to_file_dialog(),
+            // The browser's local storage is the only place the web build saves to
+            #[cfg(target_arch = "wasm32")]
+            None => {
+                let _ = self.autosave.flush(&self.lists, None);
+                self.toasts.info(t("Saved"));
+                true
+            }
+        }
+    }
+
+    // Move the items at these indices of the open list to the trash, undoable as one step
+    fn delete_items(&mut self, mut indices: Vec<usize>, today: chrono::NaiveDate) {
+        indices.sort_unstable(); // Display order may differ from storage order
+        indices.dedup();
+        let mut deletions = Vec::new();
+        for &index in indices.iter().rev() { // Reverse iterate to avoid index shift
+            let item = self.list_mut().items.remove(index);
+            self.edit_originals.remove(&item.id);
+            if self.selected == Some(item.id) {
+                self.selected = None;
+            }
+            self.list_mut().trash.push(TrashedItem::new(item.clone(), today));
+            deletions.push(Command::Delete { index, item });
+        }
+        match deletions.len() {
+            0 => {}
+            1 => self.list_mut().history.push(deletions.remove(0)),
+            _ => self.list_mut().history.push(Command::Batch(deletions)),
+        }
+    }
+
+    // Modal asking whether the pending items should really be deleted
+    fn show_delete_confirmation(&mut self, ctx: &egui::Context) {
+        let Some(ids) = &self.pending_delete else {
+            return;
+        };
+        let items = &self.list().items;
+        let names: Vec<&str> = ids
+            .iter()
+            .filter_map(|id| items.iter().find(|item| item.id == *id))
+            .map(|item| item.description.as_str())
+            .collect();
+        let subtask_count: usize = ids
+            .iter()
+            .filter_map(|id| items.iter().find(|item| item.id == *id))
+            .map(|item| item.children.len())
+            .sum();
+
+        let mut confirmed = false;
+        let mut cancelled = false;
+        egui::Window::new(t("Delete task?"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                for name in &names {
+                    ui.label(egui::RichText::new(*name).strong());
+                }
+                if subtask_count > 0 {
+                    ui.label(tf("This also deletes {} subtask(s).", &[&subtask_count]));
+                }
+                ui.label(t("Deleted tasks can be restored from the trash."));
+                ui.horizontal(|ui| {
+                    if ui.button(t("Cancel")).clicked() {
+                        cancelled = true;
+                    }
+                    if ui.button(egui::RichText::new(t("Delete")).color(egui::Color32::RED)).clicked() {
+                        confirmed = true;
+                    }
+                });
+            });
+
+        if confirmed {
+            let ids = self.pending_delete.take().unwrap_or_default();
+            let indices = ids
+                .iter()
+                .filter_map(|id| self.list().items.iter().position(|item| item.id == *id))
+                .collect();
+            self.delete_items(indices, Local::now().date_naive());
+        } else if cancelled || ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            self.pending_delete = None;
+        }
+    }
+
+    // Step the selection through the visible rows, starting at the first or last one
+    fn move_selection(&mut self, order: &[usize], step: i32) {
+        let items = &self.list().items;
+        let ids: Vec<u32> = order.iter().map(|&index| items[index].id).collect();
+        if ids.is_empty() {
+            self.selected = None;
+            return;
+        }
+        let position = self.selected.and_then(|id| ids.iter().position(|&other| other == id));
+        let next = match position {
+            Some(position) => (position as i32 + step).clamp(0, ids.len() as i32 - 1) as usize,
+            None if step > 0 => 0,
+            None => ids.len() - 1,
+        };
+        self.selected = Some(ids[next]);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn handle_tray(&mut self, ctx: &egui::Context) {
+        let Some(tray) = self.tray.as_mut() else {
+            return;
+        };
+        let open_count = self.lists.iter().flat_map(|list| &list.items).filter(|item| !item.completed).count();
+        if let Err(e) = tray.set_open_count(open_count) {
+            self.toasts.error(t("Could not update the tray icon"), e);
+        }
+
+        for action in tray.poll() {
+            match action {
+                TrayAction::ToggleWindow => {
+                    self.window_visible = !self.window_visible;
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Visible(self.window_visible));
+                    if self.window_visible {
+                        ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+                    }
+                }
+                TrayAction::QuickAdd => self.showing_quick_add = true,
+                TrayAction::Quit => ctx.send_viewport_cmd(egui::ViewportCommand::Close),
+            }
+        }
+    }
+
+    // Popup listing reminders that came up, with snooze buttons that push them back.
+    // Dismissing clears the reminder, the desktop notification comes from the notifier.
+    fn show_reminders(&mut self, ctx: &egui::Context) {
+        let now = Local::now();
+        if let Some(next) = reminder::next(&self.lists, now) {
+            ctx.request_repaint_after((next - now).to_std().unwrap_or_default());
+        }
+        let due = reminder::due(&self.lists, now);
+        if due.is_empty() {
+            return;
+        }
+
+        let mut action: Option<(usize, u32, Option<Snooze>)> = None;
+        egui::Window::new(t("Reminders"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::RIGHT_BOTTOM, [-16.0, -16.0])
+            .show(ctx, |ui| {
+                for &(list_index, id) in &due {
+                    let list = &self.lists[list_index];
+                    let Some(item) = list.items.iter().find(|item| item.id == id) else {
+                        continue;
+                    };
+                    ui.horizontal(|ui| {
+                        ui.label(RichText::new(&item.description).strong());
+                        if self.lists.len() > 1 {
+                            ui.label(RichText::new(&list.name).small().weak());
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label(t("Snooze:"));
+                        for snooze in Snooze::ALL {
+                            if ui.button(t(snooze.label())).clicked() {
+                                action = Some((list_index, id, Some(snooze)));
+                            }
+                        }
+                        if ui.button(t("Dismiss")).clicked() {
+                            action = Some((list_index, id, None));
+                        }
+                    });
+                    ui.separator();
+                }
+            });
+
+        if let Some((list_index, id, snooze)) = action {
+            self.edit_item_in(list_index, id, |item| item.reminder = snooze.map(|snooze| snooze.until(now)));
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn handle_hotkey(&mut self, ctx: &egui::Context) {
+        let Some(hotkey) = self.hotkey.as_mut() else {
+            return;
+        };
+        if let Err(e) = hotkey.set_enabled(self.settings.quick_add_hotkey) {
+            self.toasts.error(t("Could not change the quick-add shortcut"), e);
+        }
+        if hotkey.pressed() {
+            self.showing_quick_add = true;
+            // Bring an already open window back to the front
+            ctx.send_viewport_cmd_to(egui::ViewportId::from_hash_of("quick_add"), egui::ViewportCommand::Focus);
+        }
+    }
+
+    // Separate always-on-top window with just a text field, opened from the tray or Ctrl+Alt+T
+    fn show_quick_add(&mut self, ctx: &egui::Context) {
+        if !self.showing_quick_add {
+            return;
+        }
+        let mut add = false;
+        let mut close = false;
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("quick_add"),
+            egui::ViewportBuilder::default()
+                .with_title(t("Quick add"))
+                .with_inner_size([320.0, 60.0])
+                .with_always_on_top(),
+            |ctx, _class| {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        let response = ui.add(egui::TextEdit::singleline(&mut self.quick_add_input).hint_text(t("New task")));
+                        response.request_focus();
+                        let enter = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                        if ui.button(egui::RichText::new("✔").color(egui::Color32::DARK_GREEN)).clicked() || enter {
+                            add = true;
+                        }
+                    });
+                });
+                if ctx.input(|i| i.viewport().close_requested() || i.key_pressed(egui::Key::Escape)) {
+                    close = true;
+                }
+            },
+        );
+
+        if add && !self.quick_add_input.trim().is_empty() {
+            let description = self.quick_add_input.trim().to_string();
+            self.add_item(description);
+            self.quick_add_input.clear();
+            close = true;
+        }
+        if close {
+            self.showing_quick_add = false;
+        }
+    }
+
+    // Remember the item as it was so everything changed in the window undoes at once
+    fn open_detail(&mut self, id: u32) {
+        self.close_detail();
+        if let Some(item) = self.list().items.iter().find(|item| item.id == id) {
+            self.edit_originals.insert(id, item.clone());
+            self.detail_item = Some(id);
+        }
+    }
+
+    fn close_detail(&mut self) {
+        let Some(id) = self.detail_item.take() else {
+            return;
+        };
+        let original = self.edit_originals.remove(&id);
+        if let (Some(before), Some(item)) = (&original, self.list_mut().items.iter_mut().find(|item| item.id == id)) {
+            item.log_changes(before, Utc::now());
+        }
+        let item = self.list().items.iter().find(|item| item.id == id).cloned();
+        if let (Some(before), Some(after)) = (original, item) {
+            if before != after {
+                self.list_mut().history.push(Command::Edit { before: Box::new(before), after: Box::new(after) });
+            }
+        }
+    }
+
+    // Sidebar of saved smart lists, only shown once one has been saved
+    fn show_smart_lists(&mut self, ctx: &egui::Context) {
+        if self.settings.smart_lists.is_empty() {
+            return;
+        }
+        let action = egui::SidePanel::left("smart_lists")
+            .resizable(true)
+            .default_width(180.0)
+            .show(ctx, |ui| {
+                smart_lists::show(ui, &self.settings.smart_lists, &self.lists, self.current_list, &self.filter, Local::now().date_naive())
+            })
+            .inner;
+        match action {
+            Some(smart_lists::Action::Apply(index)) => self.settings.smart_lists[index].apply(&mut self.filter),
+            Some(smart_lists::Action::Open(index)) => self.current_list = index,
+            Some(smart_lists::Action::Update(index)) => {
+                let name = self.settings.smart_lists[index].name.clone();
+                self.settings.smart_lists[index] = SmartList::from_filter(name, &self.filter);
+            }
+            Some(smart_lists::Action::Delete(index)) => {
+                self.settings.smart_lists.remove(index);
+            }
+            None => {}
+        }
+    }
+
+    fn show_save_smart_list_window(&mut self, ctx: &egui::Context) {
+        let Some(name) = self.smart_list_name.as_mut() else {
+            return;
+        };
+        let mut open = true;
+        let mut saved = false;
+        egui::Window::new(t("Save as smart list")).open(&mut open).collapsible(false).show(ctx, |ui| {
+            ui.label(t("Keeps the search text, tags, priority and status filters."));
+            let field = ui.add(egui::TextEdit::singleline(name).hint_text(t("Name")));
+            let entered = field.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if (ui.add_enabled(!name.trim().is_empty(), egui::Button::new(t("Save"))).clicked() || entered) && !name.trim().is_empty() {
+                saved = true;
+            }
+        });
+        if saved {
+            let name = name.trim().to_string();
+            self.settings.smart_lists.push(SmartList::from_filter(name, &self.filter));
+        }
+        if saved || !open {
+            self.smart_list_name = None;
+        }
+    }
+
+    // Everything about the clicked item in one place, laid out as a form on the right
+    fn show_detail_panel(&mut self, ctx: &egui::Context) {
+        let Some(id) = self.detail_item else {
+            return;
+        };
+        let auto_complete_parents = self.settings.auto_complete_parents;
+        let fields = self.lists[self.current_list].fields.clone();
+        let contexts = filter::all_contexts(self.lists.iter().flat_map(|list| &list.items));
+        let assignees = filter::all_assignees(self.lists.iter().flat_map(|list| &list.items));
+        let Some(index) = self.list().items.iter().position(|item| item.id == id) else {
+            // The item was deleted or the list was switched while the panel was open
+            self.close_detail();
+            return;
+        };
+        let before = self.list().items[index].clone();
+        let item = &mut self.lists[self.current_list].items[index];
+
+        let mut closed = false;
+        let mut pasted = false;
+        let mut start_recording = false;
+        let mut stop_recording = false;
+        egui::SidePanel::right("detail_panel").resizable(true).default_width(280.0).show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.heading(t("Details"));
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.button("✖").on_hover_text(t("Close")).clicked() {
+                        closed = true;
+                    }
+                });
+            });
+            ui.separator();
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                ui.add(egui::TextEdit::multiline(&mut item.description).desired_rows(1).desired_width(f32::INFINITY));
+                egui::Grid::new("detail_fields").num_columns(2).spacing([8.0, 6.0]).show(ui, |ui| {
+                    ui.label(t("Done"));
+                    ui.checkbox(&mut item.completed, "");
+                    ui.end_row();
+
+                    ui.label(t("Icon:"));
+                    icons::picker(ui, &mut item.icon);
+                    ui.end_row();
+
+                    ui.label(t("Priority:"));
+                    egui::ComboBox::from_id_source("detail_priority")
+                        .selected_text(t(item.priority.label()))
+                        .show_ui(ui, |ui| {
+                            for priority in Priority::ALL {
+                                ui.selectable_value(&mut item.priority, priority, t(priority.label()));
+                            }
+                        });
+                    ui.end_row();
+
+                    ui.label(t("Due:"));
+                    ui.horizontal(|ui| match item.due_date.as_mut() {
+                        Some(due) => {
+                            ui.add(DatePickerButton::new(due).id_source("detail_due"));
+                            if ui.small_button("✖").on_hover_text(t("Clear due date")).clicked() {
+                                item.due_date = None;
+                            }
+                        }
+                        None => {
+                            if ui.button("📅").on_hover_text(t("Set due date")).clicked() {
+                                item.due_date = Some(Local::now().date_naive());
+                            }
+                        }
+                    });
+                    ui.end_row();
+
+                    ui.label(t("Context:"));
+                    show_name_editor(ui, &mut item.context, &contexts, "@", "@home", t("No context"));
+                    ui.end_row();
+
+                    ui.label(t("Assignee:"));
+                    show_name_editor(ui, &mut item.assignee, &assignees, "", t("Name"), t("Nobody"));
+                    ui.end_row();
+
+                    ui.label(t("Estimate:"));
+                    ui.horizontal(|ui| match item.estimate.as_mut() {
+                        Some(minutes) => {
+                            ui.add(egui::DragValue::new(minutes).clamp_range(1..=10_000).speed(5.0).suffix(format!(" {}", t("min"))));
+                            if ui.small_button("✖").on_hover_text(t("Clear estimate")).clicked() {
+                                item.estimate = None;
+                            }
+                        }
+                        None => {
+                            if ui.button("⌛").on_hover_text(t("Estimate the effort")).clicked() {
+                                item.estimate = Some(30);
+                            }
+                        }
+                    });
+                    ui.end_row();
+
+                    ui.label(t("Start:"));
+                    ui.horizontal(|ui| match item.start_date.as_mut() {
+                        Some(start) => {
+                            ui.add(DatePickerButton::new(start).id_source("detail_start"));
+                            if ui.small_button("✖").on_hover_text(t("Clear start date")).clicked() {
+                                item.start_date = None;
+                            }
+                        }
+                        None => {
+                            if ui.button("📅").on_hover_text(t("Set start date")).clicked() {
+                                item.start_date = Some(Local::now().date_naive());
+                            }
+                        }
+                    });
+                    ui.end_row();
+
+                    for field in &fields {
+                        ui.label(format!("{}:", field.name));
+                        custom_fields::value_editor(ui, field, &mut item.fields);
+                        ui.end_row();
+                    }
+                });
+                show_recurrence_editor(ui, item);
+                if let (Some(habit), Some(streak)) = (&item.habit, item.habit_streak(Local::now().date_naive())) {
+                    ui.label(tf("🔥 {} in a row", &[&streak]));
+                    habits::heatmap(ui, habit, Local::now().date_naive(), 12);
+                }
+                show_reminder_editor(ui, item);
+
+                ui.separator();
+                ui.label(t("Tags:"));
+                show_tag_editor(ui, item);
+
+                ui.separator();
+                ui.label(t("Attachments:"));
+                if let Err(e) = attachments::editor(ui, &mut item.attachments) {
+                    self.toasts.error(t("Attachment failed"), e);
+                }
+                ui.horizontal(|ui| match &self.recording {
+                    Some((recording_id, recorder)) => {
+                        let seconds = recorder.elapsed().as_secs();
+                        ui.label(RichText::new(format!("⏺ {}:{:02}", seconds / 60, seconds % 60)).color(Color32::RED));
+                        if *recording_id != id {
+                            ui.weak(t("for another task"));
+                        }
+                        stop_recording = ui.button(t("⏹ Stop")).clicked();
+                    }
+                    None => {
+                        start_recording = ui.button(t("🎤 Record memo")).on_hover_text(t("Record a short voice memo")).clicked();
+                    }
+                });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label(t("Notes:"));
+                    pasted |= ui.small_button("🖼").on_hover_text(t("Paste an image from the clipboard")).clicked();
+                });
+                let notes = ui.add(egui::TextEdit::multiline(&mut item.notes).desired_rows(4).desired_width(f32::INFINITY));
+                // Ctrl+V in the notes takes an image too, text is pasted by the field itself
+                pasted |= notes.has_focus() && ui.input(|i| i.modifiers.command && i.key_pressed(egui::Key::V));
+                if pasted {
+                    match paste_image::paste() {
+                        Ok(Some(image)) => {
+                            if !item.notes.is_empty() && !item.notes.ends_with('\n') {
+                                item.notes.push('\n');
+                            }
+                            item.notes.push_str(&format!("![{}]({})", image.name(), image.path.display()));
+                            item.attachments.push(image);
+                        }
+                        Ok(None) => {}
+                        Err(e) => self.toasts.error(t("Could not paste the image"), e),
+                    }
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label(t("Subtasks:"));
+                    if ui.small_button("➕").on_hover_text(t("Add subtask")).clicked() {
+                        add_subtask(item);
+                    }
+                });
+                let children_done_before = item.all_children_completed();
+                show_subtasks(ui, &mut item.children, auto_complete_parents);
+                if auto_complete_parents && !children_done_before && item.all_children_completed() {
+                    item.completed = true;
+                }
+
+                ui.separator();
+                egui::CollapsingHeader::new(t("Activity")).id_source("detail_activity").default_open(true).show(ui, |ui| {
+                    if item.log.is_empty() {
+                        ui.weak(t("Nothing logged yet"));
+                    }
+                    for entry in item.log.iter().rev() {
+                        ui.horizontal(|ui| {
+                            ui.weak(entry.at.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string());
+                            ui.label(t(entry.activity.label()));
+                        });
+                    }
+                });
+            });
+        });
+        item.reschedule_if_recurring(Local::now().date_naive());
+        if *item != before {
+            self.begin_item_change(index, &before);
+        }
+        if closed {
+            self.close_detail();
+        }
+        if start_recording {
+            match voice::Recorder::start() {
+                Ok(recorder) => self.recording = Some((id, recorder)),
+                Err(e) => self.toasts.error(t("Could not start recording"), e),
+            }
+        }
+        if stop_recording {
+            self.finish_recording();
+        }
+    }
+
+    // The memo goes to the task it was started for, even if another one is open by now
+    fn finish_recording(&mut self) {
+        let Some((id, recorder)) = self.recording.take() else {
+            return;
+        };
+        match recorder.finish() {
+            Ok(memo) => {
+                if let Some(list) = self.lists.iter().position(|list| list.items.iter().any(|item| item.id == id)) {
+                    self.edit_item_in(list, id, |item| item.attachments.push(memo));
+                }
+            }
+            Err(e) => self.toasts.error(t("Could not save the voice memo"), e),
+        }
+    }
+
+    fn update_recording(&mut self, ctx: &egui::Context) {
+        let Some((_, recorder)) = &self.recording else {
+            return;
+        };
+        if let Some(e) = recorder.error() {
+            self.recording = None;
+            self.toasts.error(t("Recording failed"), e);
+        } else if recorder.elapsed() >= voice::MAX_LENGTH {
+            self.finish_recording();
+        } else {
+            // Keep the elapsed time ticking
+            ctx.request_repaint_after(Duration::from_millis(500));
+        }
+    }
+
+    fn start_focus(&mut self, id: u32) {
+        self.stop_focus();
+        if let Some(item) = self.list().items.iter().find(|item| item.id == id) {
+            self.focus = Some(FocusTimer::start(item));
+        }
+    }
+
+    fn stop_focus(&mut self) {
+        if let Some(focus) = self.focus.take() {
+            focus.stop();
+        }
+    }
+
+    // Apply an edit to an item of any list the way edits in the window are: stamped, logged,
+    // undoable in its own list and saved
+    fn edit_item_in(&mut self, list: usize, id: u32, edit: impl FnOnce(&mut TodoItem)) {
+        let Some(item) = self.lists[list].items.iter_mut().find(|item| item.id == id) else {
+            return;
+        };
+        let before = item.clone();
+        edit(item);
+        if *item == before {
+            return;
+        }
+        let now = Utc::now();
+        item.modified = Some(now);
+        item.stamp_completion(now);
+        item.log_changes(&before, now);
+        let after = item.clone();
+        self.lists[list].history.push(Command::Edit { before: Box::new(before), after: Box::new(after) });
+        self.autosave.mark_changed();
+        self.notifier.update(&self.lists, self.settings.notifications);
+    }
+
+    // Check an item off or open it again, stopping its timer and moving a recurring one on
+    fn toggle_completed(&mut self, list: usize, id: u32) {
+        let sound = self.settings.completion_sound;
+        let now = Local::now();
+        self.edit_item_in(list, id, |item| {
+            item.completed = !item.completed;
+            if item.completed {
+                item.stop_tracking(now);
+                if sound {
+                    sound::play_completion();
+                }
+            }
+            item.reschedule_if_recurring(now.date_naive());
+        });
+    }
+
+    // Open items of the open list in the order the list view shows them
+    fn open_items_in_order(&self, today: chrono::NaiveDate) -> Vec<usize> {
+        let items = &self.list().items;
+        let order = filter::display_order(items, &self.filter, self.sort_order, today);
+        Sections::new(items, order, self.grouping, today).rows().filter(|&index| !items[index].completed).collect()
+    }
+
+    // The lists, toolbar and detail panel, everything but focus mode
+    fn show_main_view(
+        &mut self,
+        ctx: &egui::Context,
+        palette: theme::Palette,
+        system_theme: Option<eframe::Theme>,
+        selection_step: i32,
+        delete_selected: bool,
+        focus_new_item: bool,
+    ) {
+        // Side panels have to be laid out before the central panel
+        self.show_detail_panel(ctx);
+        self.show_smart_lists(ctx);
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.heading(t("Todo List"));
+
+                self.show_list_tabs(ui);
+
+                // Countdown of the running focus session
+                let mut stop_focus = false;
+                if let Some(focus) = &self.focus {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("🍅 {} – {}", focus.countdown(), focus.description));
+                        stop_focus = ui.button(t("Stop")).clicked();
+                    });
+                }
+                if stop_focus {
+                    self.stop_focus();
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Title(APP_NAME.to_string()));
+                }
+
+                // Progress of the open list, with a breakdown per list when there are several
+                let (done, total) = self.list().progress();
+                let fraction = if total == 0 { 0.0 } else { done as f32 / total as f32 };
+                ui.add(egui::ProgressBar::new(fraction).text(tf("{} of {} done", &[&done, &total])));
+                if self.lists.len() > 1 {
+                    ui.horizontal_wrapped(|ui| {
+                        for list in &self.lists {
+                            let (done, total) = list.progress();
+                            ui.label(format!("{}: {}/{}", list.name, done, total));
+                        }
+                    });
+                }
+
+                self.show_toolbar(ui);
+                self.show_display_settings(ui, system_theme);
+                self.show_filters(ui);
+                ui.separator();
+                self.show_search(ui);
+                self.show_items(ui, palette, selection_step, delete_selected);
+                self.show_add_item_input(ui, focus_new_item);
+            });
+        });
+    }
+
+    // Load and Save buttons, and the menus and windows next to them
+    fn show_toolbar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.button(t("Load")).clicked() {
+                self.load_from_file_dialog();
+            }
+            ui.add_space(20.0);
+            if ui.button(t("Save")).clicked() {
+                self.save_to_file_dialog();
+            }
+            let encrypted = self.settings.last_file.as_deref().is_some_and(|path| encryption::passphrase_for(path).is_some());
+            ui.menu_button(if encrypted { "🔒" } else { "🔓" }, |ui| {
+                if ui.button(t("Save encrypted…")).clicked() {
+                    ui.close_menu();
+                    self.save_encrypted_dialog();
+                }
+                if ui.add_enabled(encrypted, egui::Button::new(t("Remove encryption"))).clicked() {
+                    ui.close_menu();
+                    self.remove_encryption();
+                }
+            });
+            ui.menu_button(t("Import/Export"), |ui| {
+                if ui.button(t("Merge from file…")).clicked() {
+                    ui.close_menu();
+                    self.merge_from_file_dialog();
+                }
+                if ui.button(t("Import CSV…")).clicked() {
+                    ui.close_menu();
+                    self.import_csv_dialog();
+                }
+                if ui.button(t("Import checklist…")).clicked() {
+                    ui.close_menu();
+                    self.checklist_import = Some(ChecklistImport::new(String::new()));
+                }
+                if ui.button(t("Import Todoist…")).clicked() {
+                    ui.close_menu();
+                    self.import_todoist_dialog();
+                }
+                if ui.button(t("Import from Todoist API…")).clicked() {
+                    ui.close_menu();
+                    self.todoist_token = Some(String::new());
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                if ui.button(t("Google Tasks…")).clicked() {
+                    ui.close_menu();
+                    self.showing_google = true;
+                }
+                ui.separator();
+                if ui.button(t("Export CSV…")).clicked() {
+                    ui.close_menu();
+                    self.export_csv_dialog();
+                }
+                if ui.button(t("Export Markdown…")).clicked() {
+                    ui.close_menu();
+                    self.export_markdown_dialog();
+                }
+                if ui.button(t("Export iCalendar…")).clicked() {
+                    ui.close_menu();
+                    self.export_ical_dialog();
+                }
+                if ui.button(t("Export HTML…")).on_hover_text(t("The list as shown, as a web page in the current theme")).clicked() {
+                    ui.close_menu();
+                    self.export_html_dialog(ui.visuals().dark_mode);
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                if ui.button(t("Export PDF…")).on_hover_text(t("The list as shown, for printing")).clicked() {
+                    ui.close_menu();
+                    self.export_pdf_dialog();
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    ui.separator();
+                    if ui.button(t("Migrate JSON to SQLite…")).clicked() {
+                        ui.close_menu();
+                        self.migrate_to_database_dialog();
+                    }
+                }
+            });
+            ui.add_space(20.0);
+            if ui.add_enabled(self.list().history.can_undo(), egui::Button::new(t("⟲ Undo"))).on_hover_text(t("Ctrl+Z")).clicked() {
+                self.undo();
+            }
+            if ui.add_enabled(self.list().history.can_redo(), egui::Button::new(t("⟳ Redo"))).on_hover_text(t("Ctrl+Y")).clicked() {
+                self.redo();
+            }
+            ui.add_space(20.0);
+            if ui.button(tf("🗑 Trash ({})", &[&self.list().trash.len()])).clicked() {
+                self.showing_trash = !self.showing_trash;
+                if self.showing_trash {
+                    self.list_mut().purge_old_trash(Local::now().date_naive());
+                }
+            }
+            if ui.button(tf("📦 Archive ({})", &[&self.list().archive.len()])).clicked() {
+                self.showing_archive = !self.showing_archive;
+            }
+            if ui.button(t("📊 Stats")).clicked() {
+                self.showing_stats = !self.showing_stats;
+            }
+            if ui.button(t("☁ Sync…")).clicked() {
+                self.showing_sync_settings = !self.showing_sync_settings;
+            }
+        });
+    }
+
+    // Look, sound and behavior settings shown above the list
+    fn show_display_settings(&mut self, ui: &mut egui::Ui, system_theme: Option<eframe::Theme>) {
+        ui.horizontal(|ui| {
+            ui.label(t("UI size:"));
+            ui.add(Slider::new(&mut self.settings.text_size, 6.0..=32.0).text(""));
+            // Measured row heights no longer fit the new layout
+            if ui.checkbox(&mut self.settings.compact, t("Compact rows")).on_hover_text(t("Less spacing and one line per task")).changed() {
+                self.row_heights.clear();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.settings.completion_animation, t("Animate checking off"));
+            ui.checkbox(&mut self.settings.completion_sound, t("Sound when done"));
+        });
+
+        ui.add_enabled_ui(!self.settings.compact, |ui| {
+            ui.horizontal(|ui| {
+                let before = (self.settings.wrapping, self.settings.clamp_lines);
+                ui.label(t("Long descriptions:"));
+                egui::ComboBox::from_id_source("wrapping")
+                    .selected_text(t(self.settings.wrapping.label()))
+                    .show_ui(ui, |ui| {
+                        for wrapping in Wrapping::ALL {
+                            ui.selectable_value(&mut self.settings.wrapping, wrapping, t(wrapping.label()));
+                        }
+                    });
+                if self.settings.wrapping == Wrapping::Clamp {
+                    ui.add(egui::DragValue::new(&mut self.settings.clamp_lines).clamp_range(1..=20).suffix(format!(" {}", t("lines"))));
+                }
+                if (self.settings.wrapping, self.settings.clamp_lines) != before {
+                    self.row_heights.clear();
+                }
+            });
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(t("Language:"));
+            egui::ComboBox::from_id_source("language")
+                .selected_text(self.settings.language.label())
+                .show_ui(ui, |ui| {
+                    for language in Language::ALL {
+                        ui.selectable_value(&mut self.settings.language, language, language.label());
+                    }
+                });
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(t("Font:"));
+            let system_fonts = &mut self.system_fonts;
+            let font = &mut self.settings.font;
+            egui::ComboBox::from_id_source("font").selected_text(t(&font.label())).show_ui(ui, |ui| {
+                ui.selectable_value(font, FontChoice::Default, t("Default"));
+                for family in system_fonts.get_or_insert_with(fonts::system_families).iter() {
+                    ui.selectable_value(font, FontChoice::System(family.clone()), family);
+                }
+            });
+            if ui.button(t("Load font file…")).clicked() {
+                if let Some(path) = FileDialog::new().add_filter(t("Font"), &["ttf", "otf"]).pick_file() {
+                    self.settings.font = FontChoice::File(path);
+                }
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(t("Theme:"));
+            ui.add_enabled_ui(self.settings.theme.is_none(), |ui| {
+                ui.checkbox(&mut self.settings.follow_system_theme, t("Auto"))
+                    .on_hover_text(t("Follow the system's dark or light mode"));
+                let follows_system = self.settings.follow_system_theme && system_theme.is_some();
+                ui.add_enabled(!follows_system, egui::Checkbox::new(&mut self.settings.dark_mode, t("Dark")));
+            });
+            if ui.button(t("🎨 Customize…")).clicked() {
+                self.showing_theme_editor = !self.showing_theme_editor;
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(t("Confirm delete:"));
+            egui::ComboBox::from_id_source("confirm_delete")
+                .selected_text(t(self.settings.confirm_delete.label()))
+                .show_ui(ui, |ui| {
+                    for option in DeleteConfirmation::ALL {
+                        ui.selectable_value(&mut self.settings.confirm_delete, option, t(option.label()));
+                    }
+                });
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(t("Reminders:"));
+            ui.checkbox(&mut self.settings.notifications, t("Notify when tasks are due"));
+        });
+
+        #[cfg(not(target_arch = "wasm32"))]
+        ui.horizontal(|ui| {
+            ui.label(t("Quick add:"));
+            ui.add_enabled(self.hotkey.is_some(), egui::Checkbox::new(&mut self.settings.quick_add_hotkey, t("Open with Ctrl+Alt+T from anywhere")));
+        });
+
+        #[cfg(feature = "api")]
+        self.show_api_settings(ui);
+
+        ui.horizontal(|ui| {
+            ui.label(t("Subtasks:"));
+            ui.checkbox(&mut self.settings.auto_complete_parents, t("Complete parent when all are done"));
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(t("Archive:"));
+            ui.checkbox(&mut self.settings.auto_archive, t("Archive tasks completed more than"));
+            ui.add_enabled(self.settings.auto_archive, egui::DragValue::new(&mut self.settings.auto_archive_days).clamp_range(1..=365));
+            ui.label(t("days ago"));
+        });
+    }
+
+    // Sorting, layout, grouping and the filter menus
+    fn show_filters(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(t("Sort by:"));
+            egui::ComboBox::from_id_source("sort_order")
+                .selected_text(t(self.sort_order.label()))
+                .show_ui(ui, |ui| {
+                    for order in SortOrder::ALL {
+                        ui.selectable_value(&mut self.sort_order, order, t(order.label()));
+                    }
+                });
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(t("Layout:"));
+            ui.selectable_value(&mut self.view_mode, ViewMode::List, t("☰ List"));
+            ui.selectable_value(&mut self.view_mode, ViewMode::Board, t("▦ Board"));
+            ui.selectable_value(&mut self.view_mode, ViewMode::Calendar, t("📅 Calendar"));
+            ui.selectable_value(&mut self.view_mode, ViewMode::Timeline, t("📊 Timeline"));
+            ui.selectable_value(&mut self.view_mode, ViewMode::Matrix, t("⊞ Matrix"));
+            ui.selectable_value(&mut self.view_mode, ViewMode::MyDay, t("☀ My Day"));
+            if ui.button(t("🎯 Focus")).on_hover_text(t("Show only the selected or top task")).clicked() {
+                self.focus_mode = true;
+            }
+        });
+
+        // Smart views narrow the list down to one due date bucket
+        ui.horizontal(|ui| {
+            ui.label(t("View:"));
+            egui::ComboBox::from_id_source("due_view")
+                .selected_text(self.filter.due.map_or(t("All Tasks"), |due| t(due.label())))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.filter.due, None, t("All Tasks"));
+                    for bucket in DueBucket::ALL {
+                        ui.selectable_value(&mut self.filter.due, Some(bucket), t(bucket.label()));
+                    }
+                });
+            ui.label(t("Group by:"));
+            egui::ComboBox::from_id_source("grouping")
+                .selected_text(t(self.grouping.label()))
+                .show_ui(ui, |ui| {
+                    for grouping in Grouping::ALL {
+                        ui.selectable_value(&mut self.grouping, grouping, t(grouping.label()));
+                    }
+                });
+            ui.checkbox(&mut self.filter.show_deferred, t("Show not started"))
+                .on_hover_text(t("Also show tasks whose start date is still to come"));
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(t("Color:"));
+            let selected = match self.filter.color {
+                Some(color) => egui::RichText::new(format!("● {}", t(color.label()))).color(label_color(color)),
+                None => egui::RichText::new(t("Any")),
+            };
+            egui::ComboBox::from_id_source("color_filter").selected_text(selected).show_ui(ui, |ui| {
+                ui.selectable_value(&mut self.filter.color, None, t("Any"));
+                for color in ColorLabel::ALL {
+                    let text = egui::RichText::new(format!("● {}", t(color.label()))).color(label_color(color));
+                    ui.selectable_value(&mut self.filter.color, Some(color), text);
+                }
+            });
+
+            ui.label(t("Priority:"));
+            egui::ComboBox::from_id_source("priority_filter")
+                .selected_text(self.filter.priority.map_or(t("Any").to_string(), |priority| format!("≥ {}", t(priority.label()))))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.filter.priority, None, t("Any"));
+                    for priority in Priority::ALL {
+                        ui.selectable_value(&mut self.filter.priority, Some(priority), format!("≥ {}", t(priority.label())));
+                    }
+                });
+
+            ui.label(t("Status:"));
+            egui::ComboBox::from_id_source("completion_filter")
+                .selected_text(self.filter.completion.map_or(t("Any"), |completion| t(completion.label())))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.filter.completion, None, t("Any"));
+                    for completion in Completion::ALL {
+                        ui.selectable_value(&mut self.filter.completion, Some(completion), t(completion.label()));
+                    }
+                });
+
+            // Only offered once someone has tasks assigned to them
+            let assignees = filter::all_assignees(&self.list().items);
+            if !assignees.is_empty() || self.filter.assignee.is_some() {
+                ui.label(t("Assignee:"));
+                egui::ComboBox::from_id_source("assignee_filter")
+                    .selected_text(self.filter.assignee.clone().unwrap_or_else(|| t("Anyone").to_string()))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.filter.assignee, None, t("Anyone"));
+                        for assignee in assignees {
+                            ui.selectable_value(&mut self.filter.assignee, Some(assignee.clone()), assignee);
+                        }
+                    });
+            }
+        });
+    }
+
+    // Search box, filters as you type, and the tag chips below it
+    fn show_search(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("🔍");
+            ui.add(egui::TextEdit::singleline(&mut self.filter.search).id(egui::Id::new(SEARCH_ID)).hint_text("Search"))
+                .on_hover_text(t("Ctrl+F. Queries such as tag:work AND due<2025-01-01 AND !done work too."));
+            if !self.filter.search.is_empty() && ui.small_button("✖").on_hover_text(t("Clear search")).clicked() {
+                self.filter.search.clear();
+            }
+            if ui.small_button("⭐").on_hover_text(t("Save the filters as a smart list")).clicked() {
+                self.smart_list_name = Some(String::new());
+            }
+        });
+        // Searches that look like queries but aren't are searched for as text
+        if let Err(e) = query::parse_search(&self.filter.search) {
+            ui.weak(tf("Not a query, {}. Searching for the text instead.", &[&e]));
+        }
+
+        // Tag filter chips, selected tags stay active until clicked again
+        let mut chips = filter::all_tags(&self.list().items);
+        chips.extend(self.filter.active_tags.iter().cloned());
+        if !chips.is_empty() {
+            ui.horizontal_wrapped(|ui| {
+                ui.label(t("Tags:"));
+                for tag in chips {
+                    let selected = self.filter.active_tags.contains(&tag);
+                    if ui.selectable_label(selected, format!("#{}", tag)).clicked() {
+                        if selected {
+                            self.filter.active_tags.remove(&tag);
+                        } else {
+                            self.filter.active_tags.insert(tag);
+                        }
+                    }
+                }
+                if !self.filter.active_tags.is_empty() && ui.small_button(t("Clear")).clicked() {
+                    self.filter.active_tags.clear();
+                }
+            });
+            ui.separator();
+        }
+    }
+
+    // The items of the open list in the chosen layout, and what was done to them
+    fn show_items(&mut self, ui: &mut egui::Ui, palette: theme::Palette, selection_step: i32, delete_selected: bool) {
+        let mut to_remove: Vec<usize> = Vec::new(); // Prepare a list to track items to remove
+        let now = Local::now();
+        let today = now.date_naive();
+
+        // Items of other lists drop out when switching tabs, IDs are unique across lists
+        let items = &self.lists[self.current_list].items;
+        self.bulk_selection.retain(|id| items.iter().any(|item| item.id == *id));
+        if !self.bulk_selection.is_empty() && self.view_mode == ViewMode::List {
+            if let Some(action) = self.show_bulk_bar(ui) {
+                self.apply_bulk_action(action, &mut to_remove, now);
+            }
+        }
+        let order = filter::display_order(&self.list().items, &self.filter, self.sort_order, today);
+        // Open items first, completed ones in their own collapsible section below
+        let sections = Sections::new(&self.list().items, order, self.grouping, today);
+        let visual_order: Vec<usize> = sections.rows().collect();
+        if selection_step != 0 {
+            self.move_selection(&visual_order, selection_step);
+        }
+        // Work left in what is shown, e.g. today's tasks with the Today view
+        let workload: u32 = visual_order.iter().map(|&index| self.list().items[index].remaining_estimate()).sum();
+        if workload > 0 && self.view_mode != ViewMode::MyDay {
+            ui.label(tf("⌛ {} of estimated work left in the tasks shown", &[&format_duration(u64::from(workload) * 60)]));
+        }
+
+        let mut row_action = None;
+        match self.view_mode {
+            ViewMode::List => row_action = self.show_list(ui, &sections, now, palette, selection_step, &mut to_remove),
+            ViewMode::Board => self.show_board(ui, &visual_order, today, palette),
+            ViewMode::Calendar => self.show_calendar(ui, &visual_order, today),
+            ViewMode::Timeline => self.show_timeline(ui, &visual_order, today, palette),
+            ViewMode::Matrix => self.show_matrix(ui, &visual_order, today, palette),
+            ViewMode::MyDay => self.show_my_day(ui, today, palette),
+        }
+
+        // Keep running timers ticking, redrawing only when the shown time changes
+        if let Some(wait) = self
+            .list()
+            .items
+            .iter()
+            .filter(|item| item.is_tracking())
+            .map(|item| seconds_until_next_change(item.tracked_seconds(now)))
+            .min()
+        {
+            ui.ctx().request_repaint_after(Duration::from_secs(wait));
+        }
+
+        if delete_selected {
+            if let Some(index) = self.selected.and_then(|id| self.list().items.iter().position(|item| item.id == id)) {
+                to_remove.push(index);
+            }
+        }
+        // Ask first if any of the items is covered by the confirmation setting
+        let confirm = self.settings.confirm_delete;
+        if to_remove.iter().any(|&index| confirm.applies_to(&self.list().items[index])) {
+            self.pending_delete = Some(to_remove.iter().map(|&index| self.list().items[index].id).collect());
+        } else {
+            self.delete_items(to_remove, today);
+        }
+
+        // Looked up again by ID, since deleting may have shifted the rows
+        if let Some((id, action)) = row_action {
+            if let Some(index) = self.list().items.iter().position(|item| item.id == id) {
+                match action {
+                    RowAction::Duplicate(shift) => self.duplicate_item(index, shift),
+                    RowAction::MoveTo(target) => self.move_to_list(&[index], target),
+                    RowAction::Details => self.open_detail(id),
+                }
+            }
+        }
+    }
+
+    // Columns by status, a card dropped on another column changes its status
+    fn show_board(&mut self, ui: &mut egui::Ui, visual_order: &[usize], today: chrono::NaiveDate, palette: theme::Palette) {
+        let items = &self.lists[self.current_list].items;
+        if let Some((id, status)) = board::show(ui, items, visual_order, today, palette) {
+            if let Some(index) = items.iter().position(|item| item.id == id) {
+                let item = &mut self.list_mut().items[index];
+                if item.status() != status {
+                    let before = item.clone();
+                    item.set_status(status);
+                    item.reschedule_if_recurring(today);
+                    let command = Command::Edit { before: Box::new(before), after: Box::new(item.clone()) };
+                    self.list_mut().history.push(command);
+                }
+            }
+        }
+    }
+
+    // Tasks by due date on a month grid, clicking one opens its details
+    fn show_calendar(&mut self, ui: &mut egui::Ui, visual_order: &[usize], today: chrono::NaiveDate) {
+        let items = &self.lists[self.current_list].items;
+        if let Some(id) = self.calendar.show(ui, items, visual_order, today) {
+            self.open_detail(id);
+        }
+    }
+
+    // Bars from start to due date, dragging a bar or its ends moves the dates
+    fn show_timeline(&mut self, ui: &mut egui::Ui, visual_order: &[usize], today: chrono::NaiveDate, palette: theme::Palette) {
+        let items = &self.lists[self.current_list].items;
+        match self.timeline.show(ui, items, visual_order, today, palette) {
+            Some(timeline::Action::Open(id)) => self.open_detail(id),
+            Some(timeline::Action::Reschedule(id, start_days, due_days)) => {
+                if let Some(item) = self.list_mut().items.iter_mut().find(|item| item.id == id) {
+                    let before = item.clone();
+                    item.shift_dates(start_days, due_days);
+                    let command = Command::Edit { before: Box::new(before), after: Box::new(item.clone()) };
+                    self.list_mut().history.push(command);
+                }
+            }
+            None => {}
+        }
+    }
+
+    // Urgent/important quadrants, a card dropped on another quadrant takes its priority
+    fn show_matrix(&mut self, ui: &mut egui::Ui, visual_order: &[usize], today: chrono::NaiveDate, palette: theme::Palette) {
+        let items = &self.lists[self.current_list].items;
+        if let Some((id, quadrant)) = matrix::show(ui, items, visual_order, today, palette) {
+            if let Some(index) = items.iter().position(|item| item.id == id) {
+                let item = &mut self.list_mut().items[index];
+                if item.quadrant() != quadrant {
+                    let before = item.clone();
+                    item.set_quadrant(quadrant);
+                    let command = Command::Edit { before: Box::new(before), after: Box::new(item.clone()) };
+                    self.list_mut().history.push(command);
+                }
+            }
+        }
+    }
+
+    // Today's plan across all lists
+    fn show_my_day(&mut self, ui: &mut egui::Ui, today: chrono::NaiveDate, palette: theme::Palette) {
+        match my_day::show(ui, &self.lists, today, palette) {
+            Some(my_day::Action::Toggle(list, id)) => self.toggle_completed(list, id),
+            Some(my_day::Action::Add(list, id)) => self.edit_item_in(list, id, |item| item.my_day = Some(today)),
+            Some(my_day::Action::Remove(list, id)) => self.edit_item_in(list, id, |item| item.my_day = None),
+            None => {}
+        }
+    }
+
+    // Rows of the open list, pinned ones first and completed ones in a section of their own
+    fn show_list(
+        &mut self,
+        ui: &mut egui::Ui,
+        sections: &Sections,
+        now: chrono::DateTime<Local>,
+        palette: theme::Palette,
+        selection_step: i32,
+        to_remove: &mut Vec<usize>,
+    ) -> Option<(u32, RowAction)> {
+        let compact = self.settings.compact;
+        let mut rows = Rows {
+            today: now.date_naive(),
+            now,
+            palette,
+            selection_step,
+            // Rows can only be dragged when the list is shown in its stored order
+            can_reorder: self.sort_order == SortOrder::Manual,
+            auto_complete_parents: self.settings.auto_complete_parents,
+            completion_animation: self.settings.completion_animation,
+            completion_sound: self.settings.completion_sound,
+            // Compact rows always cut descriptions to one line
+            wrapping: if compact { Wrapping::Truncate } else { self.settings.wrapping },
+            clamp_lines: usize::from(self.settings.clamp_lines.max(1)),
+            // Only plain text searches are highlighted, not the parts of a query
+            query: match &*self.filter.query.get(&self.filter.search) {
+                Query::Text(text) => text.clone(),
+                _ => String::new(),
+            },
+            list_names: self.lists.iter().map(|list| list.name.clone()).collect(),
+            clicked_row: None,
+            commands: Vec::new(),
+            moved: None,
+            row_action: None,
+            start_focus: None,
+            to_remove: Vec::new(),
+        };
+        let mut clear_completed = false;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            if compact {
+                let spacing = ui.spacing_mut();
+                spacing.item_spacing.y = 1.0;
+                spacing.button_padding = egui::vec2(2.0, 0.0);
+            }
+
+            if !sections.pinned.is_empty() {
+                ui.label(RichText::new(tf("📌 Pinned ({})", &[&sections.pinned.len()])).strong());
+                for &index in &sections.pinned {
+                    self.show_row(ui, index, &mut rows);
+                }
+                ui.separator();
+            }
+
+            for (group, indices) in &sections.groups {
+                if let Some(group) = group {
+                    ui.label(RichText::new(format!("{} ({})", group_heading(group), indices.len())).strong());
+                }
+                for &index in indices {
+                    self.show_row(ui, index, &mut rows);
+                }
+            }
+
+            if !sections.completed.is_empty() {
+                egui::CollapsingHeader::new(tf("Completed ({})", &[&sections.completed.len()]))
+                    .id_source("completed_section")
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        for &index in &sections.completed {
+                            self.show_row(ui, index, &mut rows);
+                        }
+                        if ui.button(t("Clear completed")).on_hover_text(t("Move completed tasks to the archive")).clicked() {
+                            clear_completed = true;
+                        }
+                    });
+            }
+        });
+
+        // Move a dropped row to the position of the row it was dropped on
+        if let Some((dragged_id, to)) = rows.moved {
+            if let Some(from) = self.list().items.iter().position(|item| item.id == dragged_id) {
+                if from != to {
+                    history::move_item(&mut self.list_mut().items, from, to);
+                    rows.commands.push(Command::Move { from, to });
+                }
+            }
+        }
+
+        for command in rows.commands {
+            self.list_mut().history.push(command);
+        }
+        if let Some(id) = rows.clicked_row {
+            self.selected = rows.clicked_row;
+            if self.detail_item != Some(id) {
+                self.open_detail(id);
+            }
+        }
+        if clear_completed {
+            self.list_mut().archive_completed(rows.today);
+        }
+        if let Some(id) = rows.start_focus {
+            self.start_focus(id);
+        }
+        to_remove.extend(rows.to_remove);
+        rows.row_action
+    }
+
+    // One row of the list view with its notes and subtasks below it
+    fn show_row(&mut self, ui: &mut egui::Ui, index: usize, rows: &mut Rows) {
+        // Rows out of view only take up their last measured height, which keeps
+        // long lists fast. The selected row is drawn so it can be scrolled to.
+        let id = self.lists[self.current_list].items[index].id;
+        let scrolling_to = self.selected == Some(id) && rows.selection_step != 0;
+        if let Some(&height) = self.row_heights.get(&id) {
+            let size = egui::vec2(ui.available_width(), height);
+            if !scrolling_to && !ui.is_rect_visible(egui::Rect::from_min_size(ui.cursor().min, size)) {
+                ui.allocate_space(size);
+                return;
+            }
+        }
+        let top = ui.cursor().top();
+        let before = self.lists[self.current_list].items[index].clone();
+        let item = &mut self.lists[self.current_list].items[index];
+        let open_id = egui::Id::new(("subtasks_open", item.id));
+        let mut open = ui.data_mut(|d| *d.get_persisted_mut_or(open_id, true));
+        let mut description = None;
+        let mut cancelled = false;
+        let row = ui.horizontal(|ui| {
+
+            // Drag handle for manual reordering
+            if rows.can_reorder {
+                ui.dnd_drag_source(egui::Id::new(("drag_item", item.id)), item.id, |ui| {
+                    ui.label("☰");
+                })
+                .response
+                .on_hover_text(t("Drag to reorder"));
+            }
+
+            show_collapse_toggle(ui, item, &mut open);
+
+            // Checkbox for completion status
+            ui.checkbox(&mut item.completed, "");
+
+            let star = if item.pinned { egui::RichText::new("★").color(Color32::GOLD) } else { egui::RichText::new("☆").weak() };
+            if ui.add(egui::Button::new(star).frame(false))
+                .on_hover_text(if item.pinned { t("Unpin") } else { t("Pin to the top") })
+                .clicked()
+            {
+                item.pinned = !item.pinned;
+            }
+
+            // Color label picker, a hollow dot while the item has none
+            let dot = match item.color {
+                Some(color) => egui::RichText::new("●").color(label_color(color)),
+                None => egui::RichText::new("○").weak(),
+            };
+            ui.menu_button(dot, |ui| {
+                if ui.selectable_label(item.color.is_none(), t("No color")).clicked() {
+                    item.color = None;
+                    ui.close_menu();
+                }
+                for color in ColorLabel::ALL {
+                    let text = egui::RichText::new(format!("● {}", t(color.label()))).color(label_color(color));
+                    if ui.selectable_label(item.color == Some(color), text).clicked() {
+                        item.color = Some(color);
+                        ui.close_menu();
+                    }
+                }
+            });
+
+            // Colored priority badge, click to cycle the level
+            let badge = egui::RichText::new(t(item.priority.label())).small().color(Color32::WHITE);
+            if ui.add(egui::Button::new(badge).fill(priority_color(item.priority)).small())
+                .on_hover_text(t("Click to change priority"))
+                .clicked()
+            {
+                item.priority = item.priority.next();
+            }
+
+            if !item.edit {
+                // If not in edit mode, show the description with its Markdown rendered.
+                // Overdue items are highlighted, completed ones struck through, in the theme's colors.
+                // Checking an item off fades it and draws the strike along. The first
+                // time a row is seen it starts out done, so loading a list doesn't animate.
+                let done = if rows.completion_animation {
+                    ui.ctx().animate_bool_with_time(egui::Id::new(("completion", item.id)), item.completed, 0.35)
+                } else if item.completed {
+                    1.0
+                } else {
+                    0.0
+                };
+                let color = if item.is_overdue(rows.today) {
+                    rows.palette.overdue
+                } else if item.is_deferred(rows.today) {
+                    ui.visuals().weak_text_color()
+                } else {
+                    let faded = egui::Rgba::from(ui.visuals().text_color()) * (1.0 - done) + egui::Rgba::from(rows.palette.completed) * done;
+                    Color32::from(faded)
+                };
+
+                if item.is_in_my_day(rows.today) {
+                    ui.label("☀").on_hover_text(t("In My Day"));
+                }
+                if let Some(icon) = &item.icon {
+                    ui.label(icon.as_str());
+                }
+
+                // Clicking the description selects the row for keyboard actions,
+                // Ctrl+click picks it for bulk actions instead
+                let expanded_id = egui::Id::new(("description_expanded", item.id));
+                let expanded = ui.data(|d| d.get_temp(expanded_id).unwrap_or(false));
+                let max_rows = match rows.wrapping {
+                    Wrapping::Wrap => usize::MAX,
+                    Wrapping::Clamp if expanded => usize::MAX,
+                    Wrapping::Clamp => rows.clamp_lines,
+                    Wrapping::Truncate => 1,
+                };
+                let (mut response, elided) = markdown::show(ui, &item.description, &rows.query, color, done, max_rows);
+                if elided && rows.wrapping == Wrapping::Truncate {
+                    response = response.on_hover_text(&item.description);
+                }
+                if rows.wrapping == Wrapping::Clamp && (elided || expanded) {
+                    let (arrow, hint) = if expanded { ("⏶", t("Show less")) } else { ("⏷", t("Show all")) };
+                    if ui.small_button(arrow).on_hover_text(hint).clicked() {
+                        ui.data_mut(|d| d.insert_temp(expanded_id, !expanded));
+                    }
+                }
+                if let Some(age) = age_text(item, Utc::now()) {
+                    response = response.on_hover_text(age);
+                }
+                if response.double_clicked() {
+                    item.edit = true;
+                    ui.memory_mut(|m| m.request_focus(description_edit_id(item.id)));
+                } else if response.clicked() {
+                    if ui.input(|i| i.modifiers.command) {
+                        if !self.bulk_selection.remove(&item.id) {
+                            self.bulk_selection.insert(item.id);
+                        }
+                    } else {
+                        rows.clicked_row = Some(item.id);
+                    }
+                }
+                description = Some(response);
+
+                for tag in &item.tags {
+                    ui.label(egui::RichText::new(format!("#{}", tag)).small().weak());
+                }
+                if let Some(context) = &item.context {
+                    ui.label(egui::RichText::new(format!("@{}", context)).small().italics().weak());
+                }
+                if let Some(assignee) = &item.assignee {
+                    avatar::show(ui, assignee);
+                }
+                for attachment in &item.attachments {
+                    if let Err(e) = attachments::chip(ui, attachment) {
+                        self.toasts.error(t("Attachment failed"), e);
+                    }
+                }
+
+                if let (Some(habit), Some(streak)) = (&item.habit, item.habit_streak(rows.today)) {
+                    ui.label(format!("🔥{}", streak)).on_hover_ui(|ui| {
+                        ui.label(tf("{} in a row", &[&streak]));
+                        habits::heatmap(ui, habit, rows.today, 12);
+                    });
+                } else if let Some(recurrence) = item.recurrence {
+                    ui.label("🔁").on_hover_text(recurrence_label(recurrence));
+                }
+
+                if let (true, Some(start)) = (item.is_deferred(rows.today), item.start_date) {
+                    ui.label("⏳").on_hover_text(tf("Starts {}", &[&start.format("%Y-%m-%d")]));
+                }
+
+                if let Some(at) = item.reminder {
+                    ui.label("🔔").on_hover_text(tf("Reminder {}", &[&at.format("%Y-%m-%d %H:%M")]));
+                }
+
+                if item.pomodoros > 0 {
+                    ui.label(format!("🍅{}", item.pomodoros)).on_hover_text(t("Focus sessions finished"));
+                }
+
+                let tracked = item.tracked_seconds(rows.now);
+                if tracked > 0 || item.is_tracking() {
+                    ui.label(format!("⏱ {}", format_duration(tracked))).on_hover_text(t("Time spent"));
+                }
+
+                if let Some(minutes) = item.estimate {
+                    ui.label(format!("⌛ {}", format_duration(u64::from(minutes) * 60))).on_hover_text(t("Estimated effort"));
+                }
+            } else {
+                // If in edit mode, show a text edit field
+                ui.vertical(|ui| {
+                    ui.horizontal(|ui| {
+                        icons::picker(ui, &mut item.icon);
+                        // Enter finishes the edit, Shift+Enter starts a new line
+                        let edit_id = description_edit_id(item.id);
+                        if ui.memory(|m| m.has_focus(edit_id))
+                            && ui.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Enter))
+                        {
+                            item.edit = false;
+                        }
+                        let response = ui.add(egui::TextEdit::multiline(&mut item.description).id(edit_id));
+                        cancelled |= escaped(ui, &response);
+                    });
+
+                    show_tag_editor(ui, item);
+
+                    let response = ui.add(egui::TextEdit::multiline(&mut item.notes).hint_text(t("Notes")).desired_rows(2));
+                    cancelled |= escaped(ui, &response);
+
+                    show_recurrence_editor(ui, item);
+                    show_reminder_editor(ui, item);
+                });
+            }
+
+            // Right-align the edit and delete buttons
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
+                // Editing starts with a double-click on the description, or from the context menu
+                if item.edit {
+                    if ui.button("↶").on_hover_text(t("Cancel, Esc")).clicked() {
+                        cancelled = true;
+                    }
+                    if ui.button(egui::RichText::new("✔").color(egui::Color32::DARK_GREEN)).on_hover_text(t("Enter")).clicked() {
+                        item.edit = false;
+                    }
+                }
+
+                // Button for deletion
+                if ui.add(egui::Button::new(egui::RichText::new("❌").color(egui::Color32::RED))).clicked() {
+                    rows.to_remove.push(index); // Mark this index for removal
+                }
+
+                if item.is_tracking() && ui.button("⏹").on_hover_text(t("Stop tracking time")).clicked() {
+                    item.stop_tracking(rows.now);
+                }
+
+                // Due date picker, or a button to schedule the item
+                match item.due_date.as_mut() {
+                    Some(due) => {
+                        if ui.small_button("✖").on_hover_text(t("Clear due date")).clicked() {
+                            item.due_date = None;
+                        } else {
+                            ui.add(DatePickerButton::new(due).id_source(&format!("due_{}", item.id)));
+                        }
+                    }
+                    None => {
+                        if ui.button("📅").on_hover_text(t("Set due date")).clicked() {
+                            item.due_date = Some(rows.today);
+                        }
+                    }
+                }
+            });
+        }).response;
+
+        // Right-click anywhere on the row, including its description, for less common actions
+        let mut menu_area = ui.interact(row.rect, egui::Id::new(("row_menu", item.id)), egui::Sense::click());
+        if let Some(description) = description {
+            menu_area = menu_area.union(description);
+        }
+        menu_area.context_menu(|ui| {
+            if !item.edit && ui.button(t("Edit")).clicked() {
+                item.edit = true;
+                ui.close_menu();
+            }
+            // Dated tasks can have the copy's due dates moved along
+            if item.due_date.is_none() {
+                if ui.button(t("Duplicate")).on_hover_text(t("Ctrl+D")).clicked() {
+                    rows.row_action = Some((item.id, RowAction::Duplicate(None)));
+                    ui.close_menu();
+                }
+            } else {
+                ui.menu_button(t("Duplicate"), |ui| {
+                    let shifts = [
+                        (t("Same due date"), None),
+                        (t("A day later"), Some(Recurrence::Daily)),
+                        (t("A week later"), Some(Recurrence::Weekly)),
+                        (t("A month later"), Some(Recurrence::Monthly)),
+                    ];
+                    for (label, shift) in shifts {
+                        if ui.button(label).clicked() {
+                            rows.row_action = Some((item.id, RowAction::Duplicate(shift)));
+                            ui.close_menu();
+                        }
+                    }
+                });
+            }
+            if ui.button(t("Add subtask")).clicked() {
+                add_subtask(item);
+                open = true;
+                ui.close_menu();
+            }
+            if ui.button(t("Copy as text")).clicked() {
+                ui.output_mut(|o| o.copied_text = export::items_to_text([&*item]));
+                ui.close_menu();
+            }
+            if ui.button(t("Copy as Markdown")).clicked() {
+                ui.output_mut(|o| o.copied_text = export::items_to_markdown([&*item]));
+                ui.close_menu();
+            }
+            if ui.button(t("Details…")).clicked() {
+                rows.row_action = Some((item.id, RowAction::Details));
+                ui.close_menu();
+            }
+            if item.is_in_my_day(rows.today) {
+                if ui.button(t("Remove from My Day")).clicked() {
+                    item.my_day = None;
+                    ui.close_menu();
+                }
+            } else if !item.completed && ui.button(t("Add to My Day")).clicked() {
+                item.my_day = Some(rows.today);
+                ui.close_menu();
+            }
+            ui.menu_button(t("Set priority"), |ui| {
+                for priority in Priority::ALL {
+                    if ui.selectable_label(item.priority == priority, t(priority.label())).clicked() {
+                        item.priority = priority;
+                        ui.close_menu();
+                    }
+                }
+            });
+            if rows.list_names.len() > 1 {
+                ui.menu_button(t("Move to"), |ui| {
+                    for (target, name) in rows.list_names.iter().enumerate() {
+                        if target != self.current_list && ui.button(name).clicked() {
+                            rows.row_action = Some((item.id, RowAction::MoveTo(target)));
+                            ui.close_menu();
+                        }
+                    }
+                });
+            }
+            ui.separator();
+            if !item.completed && ui.button(t("Start a focus session")).clicked() {
+                rows.start_focus = Some(item.id);
+                ui.close_menu();
+            }
+            if !item.completed && !item.is_tracking() && ui.button(t("Start tracking time")).clicked() {
+                item.start_tracking(rows.now);
+                ui.close_menu();
+            }
+            ui.separator();
+            if ui.button(RichText::new(t("Delete")).color(Color32::RED)).clicked() {
+                rows.to_remove.push(index);
+                ui.close_menu();
+            }
+        });
+
+        if let Some(color) = item.color {
+            let stripe = egui::Rect::from_min_max(
+                egui::pos2(row.rect.left() - 6.0, row.rect.top()),
+                egui::pos2(row.rect.left() - 3.0, row.rect.bottom()),
+            );
+            ui.painter().rect_filled(stripe, 1.0, label_color(color));
+        }
+        if self.bulk_selection.contains(&item.id) {
+            ui.painter().rect_filled(row.rect.expand(2.0), 2.0, ui.visuals().selection.bg_fill.gamma_multiply(0.3));
+        }
+        if self.selected == Some(item.id) {
+            ui.painter().rect_stroke(row.rect.expand(2.0), 2.0, ui.visuals().selection.stroke);
+            if rows.selection_step != 0 {
+                row.scroll_to_me(None);
+            }
+        }
+
+        // Notes live in a collapsed section below the row
+        if !item.edit && !item.notes.trim().is_empty() {
+            egui::CollapsingHeader::new(egui::RichText::new(t("Notes")).small())
+                .id_source(("notes", item.id))
+                .default_open(!rows.query.is_empty() && todo_core::search::contains_ignore_case(&item.notes, &rows.query))
+                .show(ui, |ui| {
+                    let color = ui.visuals().text_color();
+                    ui.label(highlight::highlight(ui, &item.notes, &rows.query, color, false));
+                });
+        }
+
+        if open && !item.children.is_empty() {
+            ui.indent(open_id, |ui| {
+                cancelled |= show_subtasks(ui, &mut item.children, rows.auto_complete_parents);
+            });
+        }
+        ui.data_mut(|d| d.insert_persisted(open_id, open));
+
+        // Esc puts the item back as it was when editing began
+        if cancelled {
+            if let Some(original) = self.edit_originals.get(&id) {
+                *item = original.clone();
+            }
+        }
+
+        // Complete the parent once its last open subtask gets checked
+        if rows.auto_complete_parents && !before.all_children_completed() && item.all_children_completed() {
+            item.completed = true;
+        }
+
+        if !before.completed {
+            // Finishing a task stops its timer
+            if item.completed {
+                item.stop_tracking(rows.now);
+                if rows.completion_sound {
+                    sound::play_completion();
+                }
+            }
+            item.reschedule_if_recurring(rows.today);
+        }
+
+        // Show where a dragged row will land and move it there on release
+        if rows.can_reorder {
+            if row.dnd_hover_payload::<u32>().is_some() {
+                let stroke = ui.visuals().selection.stroke;
+                ui.painter().hline(row.rect.x_range(), row.rect.top(), stroke);
+            }
+            if let Some(dragged_id) = row.dnd_release_payload::<u32>() {
+                rows.moved = Some((*dragged_id, index));
+            }
+        }
+
+        self.record_row_change(before, index, &mut rows.commands);
+        self.row_heights.insert(id, ui.cursor().top() - top - ui.spacing().item_spacing.y);
+    }
+
+    // The ➕ button, and the input it opens to add an item
+    fn show_add_item_input(&mut self, ui: &mut egui::Ui, focus_new_item: bool) {
+        // Toggle the visibility of the add item input
+        if !self.showing_add_item_input && ui.button("➕").on_hover_text(t("Ctrl+N")).clicked() {
+            self.showing_add_item_input = true;
+        }
+
+        // Conditionally show the add item input and button
+        if self.showing_add_item_input {
+            ui.horizontal(|ui| {
+                let response = ui.text_edit_multiline(&mut self.input);
+                if focus_new_item {
+                    response.request_focus();
+                }
+                // Enter submits, Shift+Enter starts a new line
+                let enter = response.has_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter) && !i.modifiers.shift);
+                if (ui.button(egui::RichText::new("✔").color(egui::Color32::DARK_GREEN)).clicked() || enter) && !self.input.trim().is_empty() {
+                    self.add_item(self.input.trim().to_string());
+                    self.input.clear();
+                    self.showing_add_item_input = false;
+                }
+            });
+        }
+    }
+    // The selected task in large type with nothing else around it
+    fn show_focus_mode(&mut self, ctx: &egui::Context) {
+        let now = Local::now();
+        let open = self.open_items_in_order(now.date_naive());
+        let position = self
+            .selected
+            .and_then(|id| open.iter().position(|&index| self.list().items[index].id == id))
+            .unwrap_or(0);
+        let current = open.get(position).copied();
+        let mut done = false;
+        let mut skip = false;
+        let mut start_focus = false;
+        let mut stop_focus = false;
+        let mut exit = ctx.input(|i| i.key_pressed(egui::Key::Escape));
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
+                exit |= ui.button(t("Exit focus mode")).on_hover_text(t("Esc")).clicked();
+            });
+            ui.vertical_centered(|ui| {
+                ui.add_space(ui.available_height() / 5.0);
+                let Some(index) = current else {
+                    ui.heading(t("Nothing left to do 🎉"));
+                    return;
+                };
+                let item = &self.list().items[index];
+                let size = self.settings.text_size * 2.2;
+                let text = match &item.icon {
+                    Some(icon) => format!("{} {}", icon, item.description),
+                    None => item.description.clone(),
+                };
+                ui.add(egui::Label::new(RichText::new(text).size(size).strong()).wrap(true));
+                if let Some(due) = item.due_date {
+                    let text = RichText::new(tf("Due {}", &[&due.format("%Y-%m-%d")]));
+                    ui.label(if item.is_overdue(now.date_naive()) { text.color(Color32::RED) } else { text.weak() });
+                }
+                if !item.notes.is_empty() {
+                    ui.add_space(8.0);
+                    ui.label(RichText::new(&item.notes).weak());
+                }
+                ui.add_space(24.0);
+
+                // The focus session countdown, if one runs on this task
+                let running = self.focus.as_ref().filter(|focus| focus.item_id == item.id);
+                if let Some(focus) = running {
+                    ui.label(RichText::new(format!("🍅 {}", focus.countdown())).size(size).monospace());
+                }
+                ui.horizontal(|ui| {
+                    // Centre the buttons under the task
+                    ui.add_space((ui.available_width() - 320.0).max(0.0) / 2.0);
+                    done = ui.add(egui::Button::new(RichText::new(t("✔ Done")).size(size * 0.6)).fill(Color32::DARK_GREEN)).clicked();
+                    if open.len() > 1 {
+                        skip = ui.button(t("Skip")).on_hover_text(t("Show the next task")).clicked();
+                    }
+                    if running.is_some() {
+                        stop_focus = ui.button(t("Stop")).clicked();
+                    } else {
+                        start_focus = ui.button(t("Start a focus session")).clicked();
+                    }
+                });
+                ui.add_space(8.0);
+                ui.label(RichText::new(tf("{} open tasks", &[&open.len()])).weak());
+            });
+        });
+
+        if let (true, Some(index)) = (done, current) {
+            let id = self.list().items[index].id;
+            self.toggle_completed(self.current_list, id);
+            if self.focus.as_ref().is_some_and(|focus| focus.item_id == id) {
+                self.stop_focus();
+                ctx.send_viewport_cmd(egui::ViewportCommand::Title(APP_NAME.to_string()));
+            }
+            // On to the next task, a recurring one stays open and moves on as well
+            self.selected = open.get(position + 1).map(|&index| self.list().items[index].id);
+        }
+        if skip {
+            self.selected = open.get((position + 1) % open.len()).map(|&index| self.list().items[index].id);
+        }
+        if let (true, Some(index)) = (start_focus, current) {
+            self.start_focus(self.list().items[index].id);
+        }
+        if stop_focus {
+            self.stop_focus();
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(APP_NAME.to_string()));
+        }
+        if exit {
+            self.focus_mode = false;
+        }
+    }
+
+    // Log a finished session on its item, otherwise keep the countdown in the title bar ticking
+    fn update_focus(&mut self, ctx: &egui::Context) {
+        let Some(focus) = &self.focus else {
+            return;
+        };
+        if focus.is_finished() {
+            let id = focus.item_id;
+            // The item may be in another list than the open one
+            if let Some(list) = self.lists.iter().position(|list| list.items.iter().any(|item| item.id == id)) {
+                self.edit_item_in(list, id, |item| item.pomodoros += 1);
+            }
+            self.focus = None;
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(APP_NAME.to_string()));
+        } else {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(format!("🍅 {} - {}", focus.countdown(), APP_NAME)));
+            ctx.request_repaint_after(Duration::from_secs(1));
+        }
+    }
+
+    fn show_sync_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.showing_sync_settings;
+        let account = &mut self.settings.caldav;
+        egui::Window::new(t("Sync")).open(&mut open).show(ctx, |ui| {
+            ui.checkbox(&mut account.enabled, t("Sync with a CalDAV server"));
+            egui::Grid::new("caldav_account").num_columns(2).show(ui, |ui| {
+                ui.label(t("Calendar URL:"));
+                ui.add(egui::TextEdit::singleline(&mut account.url).hint_text("https://example.com/dav/calendars/me/tasks/"));
+                ui.end_row();
+                ui.label(t("Username:"));
+                ui.text_edit_singleline(&mut account.username);
+                ui.end_row();
+                ui.label(t("Password:"));
+                ui.add(egui::TextEdit::singleline(&mut account.password).password(true));
+                ui.end_row();
+                if account.sends_password_in_cleartext() {
+                    ui.label("");
+                    ui.colored_label(Color32::RED, t("Plain http:// would send the password unencrypted, use https://"));
+                    ui.end_row();
+                }
+                ui.label(t("Sync every:"));
+                ui.add(egui::DragValue::new(&mut account.interval_minutes).clamp_range(1..=1440).suffix(format!(" {}", t("min"))));
+                ui.end_row();
+                ui.label(t("List:"));
+                let selected = self.lists[account.list_index(&self.lists)].name.clone();
+                egui::ComboBox::from_id_source("caldav_list").selected_text(selected).show_ui(ui, |ui| {
+                    for list in &self.lists {
+                        ui.selectable_value(&mut account.list, list.name.clone(), &list.name);
+                    }
+                });
+                ui.end_row();
+            });
+
+            if let Some(syncer) = self.syncer.as_mut() {
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(account.is_configured() && !syncer.is_busy(), egui::Button::new(t("Sync now"))).clicked() {
+                        syncer.sync_now();
+                    }
+                    if syncer.is_busy() {
+                        ui.spinner();
+                    } else if let Some(error) = &syncer.last_error {
+                        ui.colored_label(Color32::RED, t("Sync failed")).on_hover_text(error);
+                    } else if let Some(time) = syncer.last_synced {
+                        ui.label(format!("Last synced {}", time.format("%H:%M")));
+                    }
+                });
+            }
+
+            if let Some(file_syncer) = self.file_syncer.as_mut() {
+                let shared = &mut self.settings.shared_file;
+                ui.separator();
+                ui.checkbox(&mut shared.enabled, t("Sync with a shared file"));
+                egui::Grid::new("shared_file").num_columns(2).show(ui, |ui| {
+                    ui.label(t("File:"));
+                    ui.horizontal(|ui| {
+                        ui.add(egui::TextEdit::singleline(&mut shared.location).hint_text("https://example.com/dav/todo.json"))
+                            .on_hover_text(t("A WebDAV address, or a file in a folder synced by Dropbox or a similar app"));
+                        if ui.button(t("Choose…")).clicked() {
+                            if let Some(path) = FileDialog::new().add_filter(t("JSON files"), &["json"]).set_file_name("todo.json").save_file() {
+                                shared.location = path.display().to_string();
+                            }
+                        }
+                    });
+                    ui.end_row();
+                    if shared.is_webdav() {
+                        ui.label(t("Username:"));
+                        ui.text_edit_singleline(&mut shared.username);
+                        ui.end_row();
+                        ui.label(t("Password:"));
+                        ui.add(egui::TextEdit::singleline(&mut shared.password).password(true));
+                        ui.end_row();
+                        if shared.sends_password_in_cleartext() {
+                            ui.label("");
+                            ui.colored_label(Color32::RED, t("Plain http:// would send the password unencrypted, use https://"));
+                            ui.end_row();
+                        }
+                    }
+                    ui.label(t("Sync every:"));
+                    ui.add(egui::DragValue::new(&mut shared.interval_minutes).clamp_range(1..=1440).suffix(format!(" {}", t("min"))));
+                    ui.end_row();
+                });
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(shared.is_configured() && !file_syncer.is_busy(), egui::Button::new(t("Sync now"))).clicked() {
+                        file_syncer.sync_now();
+                    }
+                    if file_syncer.is_busy() {
+                        ui.spinner();
+                    } else if let Some(error) = &file_syncer.last_error {
+                        ui.colored_label(Color32::RED, t("Sync failed")).on_hover_text(error);
+                    } else if let Some(time) = file_syncer.last_synced {
+                        ui.label(format!("Last synced {}", time.format("%H:%M")));
+                    }
+                });
+            }
+
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                let lan_sync = &mut self.settings.lan_sync;
+                ui.separator();
+                ui.checkbox(&mut lan_sync.enabled, t("Sync with other computers on this network"));
+                egui::Grid::new("lan_sync").num_columns(2).show(ui, |ui| {
+                    ui.label(t("Sync key:"));
+                    let key = ui
+                        .add(egui::TextEdit::singleline(&mut self.lan_sync_key).password(true))
+                        .on_hover_text(t("Enter the same key on each computer that should sync, it is used once you press Enter"));
+                    if key.lost_focus() {
+                        lan_sync.key = self.lan_sync_key.clone();
+                    }
+                    ui.end_row();
+                    ui.label(t("Sync every:"));
+                    ui.add(egui::DragValue::new(&mut lan_sync.interval_minutes).clamp_range(1..=1440).suffix(format!(" {}", t("min"))));
+                    ui.end_row();
+                });
+                if let Some(peers) = self.peers.as_mut() {
+                    ui.horizontal(|ui| {
+                        ui.label(tf("Computers found: {}", &[&peers.peer_count()]));
+                        if ui.add_enabled(peers.peer_count() > 0, egui::Button::new(t("Sync now"))).clicked() {
+                            peers.sync_now();
+                        }
+                        if let Some(error) = &peers.last_error {
+                            ui.colored_label(Color32::RED, t("Sync failed")).on_hover_text(error);
+                        } else if let Some(time) = peers.last_synced {
+                            ui.label(format!("Last synced {}", time.format("%H:%M")));
+                        }
+                    });
+                }
+            }
+        });
+        self.showing_sync_settings = open;
+    }
+
+    // Deleted items of the open list, each can be restored or purged for good
+    fn show_trash_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.showing_trash;
+        let list = &self.lists[self.current_list];
+        let mut to_restore = None;
+        let mut to_purge = None;
+        let mut empty = false;
+
+        egui::Window::new(t("Trash")).open(&mut open).show(ctx, |ui| {
+            if list.trash.is_empty() {
+                ui.label(t("The trash is empty."));
+                return;
+            }
+            ui.label(tf("Deleted items are purged after {} days.", &[&TRASH_RETENTION_DAYS]));
+
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for (index, trashed) in list.trash.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(&trashed.item.description);
+                        ui.label(egui::RichText::new(tf("deleted {}", &[&trashed.deleted_on])).small().weak());
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
+                            if ui.button(t("Purge")).clicked() {
+                                to_purge = Some(index);
+                            }
+                            if ui.button(t("Restore")).clicked() {
+                                to_restore = Some(index);
+                            }
+                        });
+                    });
+                }
+            });
+
+            ui.separator();
+            empty = ui.button(t("Empty trash")).clicked();
+        });
+        self.showing_trash = open;
+        if let Some(index) = to_restore {
+            self.list_mut().restore(index);
+        } else if let Some(index) = to_purge {
+            self.list_mut().purge(index);
+        } else if empty {
+            self.list_mut().empty_trash();
+        }
+    }
+
+    // Completed tasks moved out of the open list, newest first
+    fn show_archive_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.showing_archive;
+        let list = &self.lists[self.current_list];
+        let search = &mut self.archive_search;
+        let search_index = &self.filter.index;
+        let auto_archive = self.settings.auto_archive.then_some(self.settings.auto_archive_days);
+
+        let mut to_restore = None;
+        let mut to_delete = None;
+
+        egui::Window::new(t("Archive")).open(&mut open).show(ctx, |ui| {
+            if list.archive.is_empty() {
+                ui.label(t("The archive is empty."));
+                return;
+            }
+            if let Some(days) = auto_archive {
+                ui.label(tf("Tasks completed more than {} days ago are archived automatically.", &[&days]));
+            }
+            ui.add(egui::TextEdit::singleline(search).hint_text(t("Search the archive")));
+
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for (index, archived) in list.archive.iter().enumerate().rev() {
+                    if !search_index.matches(&archived.item, search) {
+                        continue;
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label(egui::RichText::new(&archived.item.description).strikethrough());
+                        ui.label(egui::RichText::new(tf("archived {}", &[&archived.archived_on])).small().weak());
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
+                            if ui.button(t("Delete")).clicked() {
+                                to_delete = Some(index);
+                            }
+                            if ui.button(t("Restore")).clicked() {
+                                to_restore = Some(index);
+                            }
+                        });
+                    });
+                }
+            });
+        });
+        self.showing_archive = open;
+        if let Some(index) = to_restore {
+            self.list_mut().unarchive(index);
+        } else if let Some(index) = to_delete {
+            self.list_mut().archive.remove(index);
+        }
+    }
+
+    // Tab strip for switching lists, right-click a tab to rename or delete it
+    fn show_list_tabs(&mut self, ui: &mut egui::Ui) {
+        let mut to_delete = None;
+        ui.horizontal_wrapped(|ui| {
+            let can_delete = self.lists.len() > 1;
+            for index in 0..self.lists.len() {
+                let selected = index == self.current_list;
+                let list = &self.lists[index];
+                let name = match &list.icon {
+                    Some(icon) => format!("{} {}", icon, list.name),
+                    None => list.name.clone(),
+                };
+                let tab = ui.selectable_label(selected, name);
+                if tab.clicked() {
+                    self.current_list = index;
+                }
+                if tab.secondary_clicked() {
+                    self.rename_input = self.lists[index].name.clone();
+                }
+                tab.context_menu(|ui| {
+                    ui.horizontal(|ui| {
+                        ui.text_edit_singleline(&mut self.rename_input);
+                        if ui.button(t("Rename")).clicked() && !self.rename_input.trim().is_empty() {
+                            self.lists[index].name = self.rename_input.trim().to_string();
+                            ui.close_menu();
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label(t("Icon:"));
+                        icons::picker(ui, &mut self.lists[index].icon);
+                    });
+                    if ui.button(t("Custom fields…")).clicked() {
+                        self.current_list = index;
+                        self.showing_fields = true;
+                        ui.close_menu();
+                    }
+                    if ui.add_enabled(can_delete, egui::Button::new(t("Delete list"))).clicked() {
+                        to_delete = Some(index);
+                        ui.close_menu();
+                    }
+                });
+            }
+            if ui.button("➕").on_hover_text(t("New list")).clicked() {
+                self.add_list();
+            }
+        });
+        if let Some(index) = to_delete {
+            self.delete_list(index);
+        }
+    }
+
+    fn set_last_file(&mut self, path: PathBuf) {
+        self.settings.last_file = Some(path);
+        self.settings.save();
+    }
+
+    // Returns false if the dialog was cancelled or nothing was written
+    fn save_to_file_dialog(&mut self) -> bool {
+        // Filter for .json files, or a SQLite database
+        let Some(path) = FileDialog::new()
+            .add_filter(t("JSON files"), &["json"])
+            .add_filter(t("SQLite database"), &["db"])
+            .set_file_name("todo_list_save.json")
+            .save_file() 
+        else {
+            return false;
+        };
+        self.wait_for_saves();
+        match save_lists(&path, &self.lists) {
+            Ok(()) => {
+                autosave::clear_recovery();
+                self.autosave.mark_saved();
+                self.set_last_file(path);
+                true
+            }
+            Err(e) => {
+                self.toasts.error(tf("Could not save {}", &[&path.display()]), e);
+                false
+            }
+        }
+    }
+
+    fn export_markdown_dialog(&mut self) {
+        if let Some(path) = FileDialog::new()
+            .add_filter(t("Markdown files"), &["md"])
+            .set_file_name("todo_list.md")
+            .save_file()
+        {
+            if let Err(e) = std::fs::write(path, export::to_markdown(self.list())) {
+                self.toasts.error(t("Could not export Markdown"), e);
+            }
+        }
+    }
+
+    // The open list as the list view shows it: filtered, sorted and grouped
+    fn shown_sections(&self, today: chrono::NaiveDate) -> Vec<export::Section<'_>> {
+        let items = &self.list().items;
+        let order = filter::display_order(items, &self.filter, self.sort_order, today);
+        let sections = Sections::new(items, order, self.grouping, today);
+        let section = |heading: Option<String>, rows: &[usize]| export::Section {
+            heading,
+            items: rows.iter().map(|&index| &items[index]).collect(),
+        };
+        let mut shown = vec![section(Some(tf("Pinned ({})", &[&sections.pinned.len()])), &sections.pinned)];
+        for (group, rows) in &sections.groups {
+            let heading = group.as_ref().map(|group| format!("{} ({})", group_heading(group), rows.len()));
+            shown.push(section(heading, rows));
+        }
+        shown.push(section(Some(tf("Completed ({})", &[&sections.completed.len()])), &sections.completed));
+        shown
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn export_pdf_dialog(&mut self) {
+        if let Some(path) = FileDialog::new()
+            .add_filter(t("PDF files"), &["pdf"])
+            .set_file_name("todo_list.pdf")
+            .save_file()
+        {
+            let written = pdf::to_pdf(&self.list().name, &self.shown_sections(Local::now().date_naive()))
+                .map_err(|e| e.to_string())
+                .and_then(|bytes| std::fs::write(path, bytes).map_err(|e| e.to_string()));
+            if let Err(e) = written {
+                self.toasts.error(t("Could not export PDF"), e);
+            }
+        }
+    }
+
+    // `dark` is whether the window is dark, for when no custom theme is picked
+    fn export_html_dialog(&mut self, dark: bool) {
+        if let Some(path) = FileDialog::new()
+            .add_filter(t("HTML files"), &["html"])
+            .set_file_name("todo_list.html")
+            .save_file()
+        {
+            let theme = match self.settings.active_theme() {
+                Some(theme) => theme.clone(),
+                None if dark => Theme::dark(String::new()),
+                None => Theme::light(String::new()),
+            };
+            let today = Local::now().date_naive();
+            let page = html::to_html(&self.list().name, &self.shown_sections(today), &theme, today);
+            if let Err(e) = std::fs::write(path, page) {
+                self.toasts.error(t("Could not export HTML"), e);
+            }
+        }
+    }
+
+    fn export_ical_dialog(&mut self) {
+        if let Some(path) = FileDialog::new()
+            .add_filter(t("iCalendar files"), &["ics"])
+            .set_file_name("todo_list.ics")
+            .save_file()
+        {
+            if let Err(e) = std::fs::write(path, ical::to_ical(self.list())) {
+                self.toasts.error(t("Could not export iCalendar"), e);
+            }
+        }
+    }
+
+    fn export_csv_dialog(&mut self) {
+        if let Some(path) = FileDialog::new()
+            .add_filter(t("CSV files"), &["csv"])
+            .set_file_name("todo_list.csv")
+            .save_file()
+        {
+            if let Err(e) = csv_io::write_csv(&path, self.list()) {
+                self.toasts.error(t("Could not export CSV"), e);
+            }
+        }
+    }
+
+    // Todoist projects become new lists, the first of them is opened
+    fn add_imported_lists(&mut self, lists: Vec<TodoList>) {
+        if !lists.is_empty() {
+            self.current_list = self.lists.len();
+            self.lists.extend(lists);
+        }
+    }
+
+    fn import_todoist_dialog(&mut self) {
+        if let Some(path) = FileDialog::new()
+            .add_filter(t("Todoist export"), &["csv", "json"])
+            .pick_file()
+        {
+            let is_json = path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+            let result = if is_json {
+                todoist::read_json(&path)
+            } else {
+                todoist::read_csv(&path).map(|list| vec![list])
+            };
+            match result {
+                Ok(lists) => self.add_imported_lists(lists),
+                Err(e) => self.toasts.error(t("Could not import the Todoist file"), format!("{:?}", e)),
+            }
+        }
+    }
+
+    fn show_todoist_window(&mut self, ctx: &egui::Context) {
+        let Some(token) = self.todoist_token.as_mut() else {
+            return;
+        };
+        let mut open = true;
+        let mut import = false;
+        egui::Window::new(t("Import from Todoist")).open(&mut open).show(ctx, |ui| {
+            ui.label(t("Paste the API token from Todoist's Settings → Integrations → Developer."));
+            ui.add(egui::TextEdit::singleline(token).password(true).hint_text(t("API token")));
+            import = ui.add_enabled(!token.trim().is_empty(), egui::Button::new(t("Import"))).clicked();
+        });
+
+        if import {
+            match todoist::fetch(token) {
+                Ok(lists) => {
+                    self.add_imported_lists(lists);
+                    open = false;
+                }
+                Err(e) => self.toasts.error(t("Could not import from Todoist"), format!("{:?}", e)),
+            }
+        }
+        if !open {
+            self.todoist_token = None;
+        }
+    }
+
+    fn handle_google_result(&mut self) {
+        let Some(result) = self.google.poll() else {
+            return;
+        };
+        self.google.status = match result {
+            GoogleResult::Connected(Ok(refresh_token)) => {
+                self.settings.google.refresh_token = Some(refresh_token);
+                Some(t("Connected.").to_string())
+            }
+            GoogleResult::Imported(Ok(lists)) => {
+                let count = lists.len();
+                self.add_imported_lists(lists);
+                Some(tf("Imported {} lists.", &[&count]))
+            }
+            GoogleResult::Exported(Ok(())) => Some(tf("Exported '{}'.", &[&self.list().name])),
+            GoogleResult::Connected(Err(e)) | GoogleResult::Imported(Err(e)) | GoogleResult::Exported(Err(e)) => {
+                self.toasts.error(t("Google Tasks request failed"), e);
+                Some(t("Request failed.").to_string())
+            }
+        };
+    }
+
+    fn show_google_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.showing_google;
+        let account = &mut self.settings.google;
+        let google = &mut self.google;
+        let list = &self.lists[self.current_list];
+        egui::Window::new(t("Google Tasks")).open(&mut open).show(ctx, |ui| {
+            ui.label(t("Create an OAuth client of type \"Desktop app\" with the Tasks API enabled in the Google Cloud console and enter its details."));
+            egui::Grid::new("google_account").num_columns(2).show(ui, |ui| {
+                ui.label(t("Client ID:"));
+                ui.text_edit_singleline(&mut account.client_id);
+                ui.end_row();
+                ui.label(t("Client secret:"));
+                ui.add(egui::TextEdit::singleline(&mut account.client_secret).password(true));
+                ui.end_row();
+            });
+
+            ui.add_enabled_ui(!google.is_busy(), |ui| {
+                ui.horizontal(|ui| {
+                    if account.is_connected() {
+                        if ui.button(t("Disconnect")).clicked() {
+                            account.refresh_token = None;
+                        }
+                    } else if ui.add_enabled(!account.client_id.trim().is_empty(), egui::Button::new(t("Connect…"))).clicked() {
+                        google.connect(ctx, account);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.set_enabled(account.is_connected());
+                    if ui.button(t("Import all lists")).clicked() {
+                        google.import(ctx, account);
+                    }
+                    if ui.button(tf("Export '{}'", &[&list.name])).clicked() {
+                        google.export(ctx, account, list);
+                    }
+                });
+            });
+            if google.is_busy() {
+                ui.spinner();
+            }
+            if let Some(status) = &google.status {
+                ui.label(status);
+            }
+        });
+        self.showing_google = open;
+    }
+
+    // Read the file and open the column mapping window, items are added from there
+    fn import_csv_dialog(&mut self) {
+        if let Some(path) = FileDialog::new()
+            .add_filter(t("CSV files"), &["csv"])
+            .pick_file()
+        {
+            match CsvImport::read(&path) {
+                Ok(import) => self.csv_import = Some(import),
+                Err(e) => self.toasts.error(t("Could not read the CSV file"), e),
+            }
+        }
+    }
+
+    // Column mapping step of a CSV import
+    fn show_csv_import_window(&mut self, ctx: &egui::Context) {
+        let Some(import) = self.csv_import.as_mut() else {
+            return;
+        };
+        let mut open = true;
+        let mut confirmed = false;
+
+        egui::Window::new(t("Import CSV")).open(&mut open).show(ctx, |ui| {
+            ui.label(tf("{} rows found. Choose which columns to use:", &[&import.rows.len()]));
+            let headers = &import.headers;
+            let column_name = |column: Option<usize>| column.map_or(t("(none)").to_string(), |c| headers[c].clone());
+
+            egui::Grid::new("csv_mapping").num_columns(2).show(ui, |ui| {
+                ui.label(t("Description"));
+                egui::ComboBox::from_id_source("csv_description")
+                    .selected_text(column_name(Some(import.mapping.description)))
+                    .show_ui(ui, |ui| {
+                        for (column, header) in headers.iter().enumerate() {
+                            ui.selectable_value(&mut import.mapping.description, column, header);
+                        }
+                    });
+                ui.end_row();
+
+                let optional_columns = [
+                    (t("Completed"), &mut import.mapping.completed),
+                    (t("Due date"), &mut import.mapping.due_date),
+                    (t("Notes"), &mut import.mapping.notes),
+                ];
+                for (label, mapping) in optional_columns {
+                    ui.label(label);
+                    egui::ComboBox::from_id_source(("csv", label))
+                        .selected_text(column_name(*mapping))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(mapping, None, t("(none)"));
+                            for (column, header) in headers.iter().enumerate() {
+                                ui.selectable_value(mapping, Some(column), header);
+                            }
+                        });
+                    ui.end_row();
+                }
+            });
+
+            ui.separator();
+            if ui.button(t("Import")).clicked() {
+                confirmed = true;
+            }
+        });
+
+        if confirmed {
+            let items = import.to_items();
+            self.add_items(items);
+        }
+        if confirmed || !open {
+            self.csv_import = None;
+        }
+    }
+
+    // Add imported items to the end of the open list, undone as one step
+    fn add_items(&mut self, items: Vec<TodoItem>) {
+        let list = self.list_mut();
+        let commands = items
+            .iter()
+            .enumerate()
+            .map(|(offset, item)| Command::Add { index: list.items.len() + offset, item: item.clone() })
+            .collect();
+        list.history.push(Command::Batch(commands));
+        list.items.extend(items);
+    }
+
+    fn import_checklist_dialog(&mut self) {
+        if let Some(path) = FileDialog::new()
+            .add_filter(t("Text and Markdown files"), &["txt", "md", "markdown"])
+            .pick_file()
+        {
+            self.read_checklist(&path);
+        }
+    }
+
+    fn read_checklist(&mut self, path: &std::path::Path) {
+        match std::fs::read_to_string(path) {
+            Ok(text) => self.checklist_import = Some(ChecklistImport::new(text)),
+            Err(e) => self.toasts.error(tf("Could not open {}", &[&path.display()]), e),
+        }
+    }
+
+    // Text box to paste a checklist into, or to check one read from a file, before adding its tasks
+    fn show_checklist_import_window(&mut self, ctx: &egui::Context) {
+        let Some(import) = self.checklist_import.as_mut() else {
+            return;
+        };
+        let mut open = true;
+        let mut confirmed = false;
+        let mut open_file = false;
+        egui::Window::new(t("Import checklist")).open(&mut open).show(ctx, |ui| {
+            ui.label(t("One task per line. `- [ ]` and `- [x]` boxes are read and indented lines become subtasks."));
+            #[cfg(not(target_arch = "wasm32"))]
+            if ui.button(t("Open file…")).clicked() {
+                open_file = true;
+            }
+            egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                let response = ui.add(egui::TextEdit::multiline(&mut import.text).code_editor().desired_rows(10).desired_width(f32::INFINITY));
+                if response.changed() {
+                    import.found = checklist::parse(&import.text).len();
+                }
+            });
+            ui.separator();
+            ui.horizontal(|ui| {
+                confirmed = ui.add_enabled(import.found > 0, egui::Button::new(t("Import"))).clicked();
+                ui.label(tf("{} tasks found", &[&import.found]));
+            });
+        });
+
+        if open_file {
+            self.import_checklist_dialog();
+            return;
+        }
+        if confirmed {
+            let items = checklist::parse(&import.text);
+            self.add_items(items);
+        }
+        if confirmed || !open {
+            self.checklist_import = None;
+        }
+    }
+
+    fn load_from_file_dialog(&mut self) {
+        // Filter for .json files, or a SQLite database
+        if let Some(path) = FileDialog::new()
+            .add_filter(t("JSON files"), &["json"])
+            .add_filter(t("SQLite database"), &["db", "sqlite", "sqlite3"])
+            .pick_file() 
+        {
+            self.open_file(path);
+        }
+    }
+
+    // Merge the matching list of another save into the open list, e.g. a conflicted copy
+    // left by Dropbox. Its list with the same name is used, or else its first list.
+    fn merge_from_file_dialog(&mut self) {
+        let Some(path) = FileDialog::new()
+            .add_filter(t("JSON files"), &["json"])
+            .add_filter(t("SQLite database"), &["db", "sqlite", "sqlite3"])
+            .pick_file()
+        else {
+            return;
+        };
+        let mut lists = match load_lists(&path) {
+            Ok(lists) => lists,
+            Err(e) => {
+                self.toasts.error(tf("Could not open {}", &[&path.display()]), e);
+                return;
+            }
+        };
+        let index = lists.iter().position(|list| list.name == self.list().name).unwrap_or(0);
+        let added = merge::merge_from_file(self.list_mut(), lists.swap_remove(index));
+        self.toasts.info(tf("Merged {}, {} new tasks", &[&path.display(), &added]));
+    }
+
+    // Replace the lists with a save file, asking for the passphrase first if it is encrypted
+    fn open_file(&mut self, path: PathBuf) {
+        if encryption::is_encrypted(&path) && encryption::passphrase_for(&path).is_none() {
+            self.passphrase_prompt = Some(PassphrasePrompt::new(path, PassphrasePurpose::Open));
+        } else {
+            match load_lists(&path) {
+                Ok(lists) => self.open_lists(lists, path),
+                Err(e) => self.toasts.error(tf("Could not open {}", &[&path.display()]), e),
+            }
+        }
+    }
+
+    // CSV files and text checklists are imported into the open list, anything else is opened as a save file
+    fn handle_dropped_files(&mut self, ctx: &egui::Context) {
+        let Some(path) = ctx.input(|i| i.raw.dropped_files.iter().find_map(|file| file.path.clone())) else {
+            return;
+        };
+        let has_extension = |extensions: &[&str]| {
+            path.extension().is_some_and(|extension| extensions.iter().any(|known| extension.eq_ignore_ascii_case(known)))
+        };
+        if has_extension(&["csv"]) {
+            match CsvImport::read(&path) {
+                Ok(import) => self.csv_import = Some(import),
+                Err(e) => self.toasts.error(t("Could not read the CSV file"), e),
+            }
+        } else if has_extension(&["txt", "md", "markdown"]) {
+            self.read_checklist(&path);
+        } else if self.autosave.has_unsaved_changes() {
+            self.pending_drop = Some(path);
+        } else {
+            self.open_file(path);
+        }
+    }
+
+    // Finish background saves before their outcome is needed, e.g. when closing
+    fn wait_for_saves(&mut self) {
+        if let Some(saver) = self.saver.as_mut() {
+            for e in saver.wait(&mut self.autosave) {
+                self.toasts.error(autosave_failed(self.settings.last_file.as_deref()), e);
+            }
         }
     }
-}
 
-impl TodoApp {
-    fn save_to_file_dialog(&self) {
-        // Filter for .json files
+    // Small note in the corner while a save is being written in the background
+    fn show_saving_indicator(&self, ctx: &egui::Context) {
+        if !self.saver.as_ref().is_some_and(Saver::is_saving) {
+            return;
+        }
+        egui::Area::new("saving_indicator")
+            .anchor(egui::Align2::LEFT_BOTTOM, [10.0, -10.0])
+            .interactable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.weak(t("Saving…"));
+                });
+            });
+    }
+
+    // Dim the window while a file is dragged over it
+    fn show_drop_hint(&self, ctx: &egui::Context) {
+        if ctx.input(|i| i.raw.hovered_files.is_empty()) {
+            return;
+        }
+        let painter = ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("drop_hint")));
+        let screen = ctx.screen_rect();
+        painter.rect_filled(screen, 0.0, Color32::from_black_alpha(160));
+        painter.text(
+            screen.center(),
+            egui::Align2::CENTER_CENTER,
+            t("Drop a save file to open it, or a CSV or checklist file to import it"),
+            egui::TextStyle::Heading.resolve(&ctx.style()),
+            Color32::WHITE,
+        );
+    }
+
+    fn show_drop_confirmation(&mut self, ctx: &egui::Context) {
+        let Some(path) = &self.pending_drop else {
+            return;
+        };
+        let (mut cancelled, mut discard, mut save_first) = (false, false, false);
+        egui::Window::new(t("Save changes?"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(tf("Opening {} replaces the current lists, which have unsaved changes.", &[&path.display()]));
+                ui.horizontal(|ui| {
+                    cancelled = ui.button(t("Cancel")).clicked();
+                    discard = ui.button(egui::RichText::new(t("Discard changes")).color(Color32::RED)).clicked();
+                    save_first = ui.button(t("Save and open")).clicked();
+                });
+            });
+
+        if !(cancelled || discard || save_first) {
+            return;
+        }
+        let Some(path) = self.pending_drop.take() else {
+            return;
+        };
+        if discard {
+            autosave::clear_recovery();
+            self.autosave.mark_saved();
+            self.open_file(path);
+        } else if save_first && self.save() {
+            self.open_file(path);
+        }
+    }
+
+    // Reload the last used file when another program changed it, or ask first if that would
+    // throw away unsaved changes
+    #[cfg(not(target_arch = "wasm32"))]
+    fn handle_file_changes(&mut self, ctx: &egui::Context) {
+        let Some(path) = self.settings.last_file.clone() else {
+            self.file_watch = None;
+            return;
+        };
+        if self.file_watch.as_ref().is_none_or(|watch| watch.path() != path) {
+            // Tried once per file, not on every frame
+            if self.unwatchable_file.as_ref() == Some(&path) {
+                return;
+            }
+            match FileWatch::start(ctx, &path) {
+                Ok(watch) => self.file_watch = Some(watch),
+                Err(e) => {
+                    self.file_watch = None;
+                    self.unwatchable_file = Some(path);
+                    self.toasts.error(t("Could not watch the file for changes by other programs"), e);
+                }
+            }
+            return;
+        }
+        if !self.file_watch.as_ref().is_some_and(FileWatch::changed) {
+            return;
+        }
+        // Not readable yet if the other program is still writing, there will be another change then
+        let Ok(lists) = load_lists(&path) else {
+            return;
+        };
+        // Written by this app, possibly by a background save from before the latest edits
+        let json = todo_core::storage::to_json(&lists).ok();
+        let ours = |written: &[TodoList]| todo_core::storage::to_json(written).ok() == json;
+        if ours(&self.lists) || self.saver.as_ref().and_then(Saver::last_sent).is_some_and(ours) {
+            return;
+        }
+        if self.autosave.has_unsaved_changes() {
+            self.external_change = Some(lists);
+        } else {
+            self.reload_lists(lists, path.clone());
+            self.toasts.info(tf("Reloaded {}, it was changed by another program", &[&path.display()]));
+        }
+    }
+
+    // Replace the lists with a new version of the same file, staying on the same tab
+    fn reload_lists(&mut self, lists: Vec<TodoList>, path: PathBuf) {
+        let current_list = self.current_list;
+        self.open_lists(lists, path);
+        self.current_list = current_list.min(self.lists.len() - 1);
+    }
+
+    fn show_external_change_prompt(&mut self, ctx: &egui::Context) {
+        let Some(path) = self.settings.last_file.clone() else {
+            self.external_change = None;
+            return;
+        };
+        if self.external_change.is_none() {
+            return;
+        }
+        let (mut reload, mut merge_in, mut keep) = (false, false, false);
+        egui::Window::new(t("File changed"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(tf("{} was changed by another program, and the lists here have unsaved changes.", &[&path.display()]));
+                ui.horizontal(|ui| {
+                    reload = ui.button(egui::RichText::new(t("Reload")).color(Color32::RED))
+                        .on_hover_text(t("Discard the changes made here"))
+                        .clicked();
+                    merge_in = ui.button(t("Merge"))
+                        .on_hover_text(t("Keep both, where a task was changed on both sides the later change wins"))
+                        .clicked();
+                    keep = ui.button(t("Keep mine"))
+                        .on_hover_text(t("Overwrite the file with the lists here"))
+                        .clicked();
+                });
+            });
+
+        if !(reload || merge_in || keep) {
+            return;
+        }
+        let Some(lists) = self.external_change.take() else {
+            return;
+        };
+        if reload {
+            autosave::clear_recovery();
+            self.reload_lists(lists, path);
+        } else if merge_in {
+            merge::merge_lists(&mut self.lists, lists);
+            self.autosave.mark_changed();
+        } else {
+            self.autosave.mark_changed();
+        }
+    }
+
+    // Remember where the window is. The size and position from before it was maximized
+    // are kept, so un-maximizing after the next launch restores them.
+    fn track_window_geometry(&mut self, ctx: &egui::Context) {
+        ctx.input(|i| {
+            let viewport = i.viewport();
+            if viewport.minimized == Some(true) {
+                return;
+            }
+            self.window.maximized = viewport.maximized == Some(true);
+            if self.window.maximized {
+                return;
+            }
+            if let Some(rect) = viewport.inner_rect {
+                self.window.size = Some([rect.width(), rect.height()]);
+            }
+            if let Some(rect) = viewport.outer_rect {
+                self.window.position = Some([rect.min.x, rect.min.y]);
+            }
+        });
+    }
+
+    // Keep the window open while there are changes that only exist in memory or the recovery file
+    fn handle_close_request(&mut self, ctx: &egui::Context) {
+        if self.close_confirmed || !ctx.input(|i| i.viewport().close_requested()) {
+            return;
+        }
+        self.wait_for_saves();
+        // Edits within the debounce window just need the autosave to run early
+        if self.autosave.is_pending() && self.settings.last_file.is_some() {
+            if let Err(e) = self.autosave.flush(&self.lists, self.settings.last_file.as_deref()) {
+                self.toasts.error(autosave_failed(self.settings.last_file.as_deref()), e);
+            }
+        }
+        if self.autosave.has_unsaved_changes() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.showing_close_confirmation = true;
+        }
+    }
+
+    fn show_close_confirmation(&mut self, ctx: &egui::Context) {
+        if !self.showing_close_confirmation {
+            return;
+        }
+        let (mut cancelled, mut discard, mut save_first) = (false, false, false);
+        egui::Window::new(t("Save changes before closing?"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                match &self.settings.last_file {
+                    Some(path) => ui.label(tf("Some changes haven't been written to {} yet.", &[&path.display()])),
+                    None => ui.label(t("The lists haven't been saved to a file yet.")),
+                };
+                ui.horizontal(|ui| {
+                    cancelled = ui.button(t("Cancel")).clicked();
+                    discard = ui.button(egui::RichText::new(t("Don't save")).color(Color32::RED)).clicked();
+                    save_first = ui.button(t("Save")).clicked();
+                });
+            });
+
+        if cancelled {
+            self.showing_close_confirmation = false;
+        } else if discard || (save_first && self.save()) {
+            if discard {
+                autosave::clear_recovery();
+                self.autosave.mark_saved();
+            }
+            self.showing_close_confirmation = false;
+            self.close_confirmed = true;
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+    }
+
+    fn open_lists(&mut self, lists: Vec<TodoList>, path: PathBuf) {
+        self.autosave.mark_saved();
+        self.archive_checked = None;
+        self.lists = lists;
+        self.current_list = 0;
+        self.unchanged_items = None;
+        self.edit_originals.clear();
+        self.log_originals.clear();
+        self.filter.index.clear();
+        self.set_last_file(path);
+    }
+
+    fn save_encrypted_dialog(&mut self) {
         if let Some(path) = FileDialog::new()
-            .add_filter("JSON files", &["json"])
+            .add_filter(t("JSON files"), &["json"])
             .set_file_name("todo_list_save.json")
-            .save_file() 
+            .save_file()
         {
-            match File::create(path) {
-                Ok(file) => {
-                    let writer = BufWriter::new(file);
-                    // Use to_writer_pretty for indented JSON
-                    if serde_json::to_writer_pretty(writer, &self.items).is_err() {
-                        eprintln!("Failed to write items to file.");
+            self.passphrase_prompt = Some(PassphrasePrompt::new(path, PassphrasePurpose::Encrypt));
+        }
+    }
+
+    // Save the last used file as plain JSON from now on
+    fn remove_encryption(&mut self) {
+        if let Some(path) = self.settings.last_file.clone() {
+            self.wait_for_saves();
+            // The passphrase is kept if this fails, so autosaves go on encrypting the file
+            match todo_core::storage::save_unencrypted(&path, &self.lists) {
+                Ok(()) => self.autosave.mark_saved(),
+                Err(e) => self.toasts.error(tf("Could not save {}", &[&path.display()]), e),
+            }
+        }
+    }
+
+    fn show_passphrase_window(&mut self, ctx: &egui::Context) {
+        let Some(prompt) = self.passphrase_prompt.as_mut() else {
+            return;
+        };
+        let mut open = true;
+        let mut submitted = false;
+        let mut encrypt = None;
+        let title = match prompt.purpose {
+            PassphrasePurpose::Open => t("Encrypted file"),
+            PassphrasePurpose::Encrypt => t("Encrypt save"),
+        };
+        egui::Window::new(title).open(&mut open).collapsible(false).show(ctx, |ui| {
+            ui.label(prompt.path.display().to_string());
+            let response = ui.add(egui::TextEdit::singleline(&mut prompt.passphrase).password(true).hint_text(t("Passphrase")));
+            let mut enter = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if prompt.purpose == PassphrasePurpose::Encrypt {
+                let response = ui.add(egui::TextEdit::singleline(&mut prompt.confirmation).password(true).hint_text(t("Repeat passphrase")));
+                enter = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                ui.label(egui::RichText::new(t("There is no way to recover the tasks if the passphrase is lost.")).small().weak());
+            }
+            if let Some(error) = &prompt.error {
+                ui.colored_label(Color32::RED, error);
+            }
+            let label = match prompt.purpose {
+                PassphrasePurpose::Open => t("Open"),
+                PassphrasePurpose::Encrypt => t("Save"),
+            };
+            submitted = (ui.add_enabled(!prompt.passphrase.is_empty(), egui::Button::new(label)).clicked() || enter)
+                && !prompt.passphrase.is_empty();
+        });
+
+        if submitted {
+            let path = prompt.path.clone();
+            match prompt.purpose {
+                PassphrasePurpose::Open => {
+                    encryption::remember_passphrase(&path, &prompt.passphrase);
+                    match load_lists(&path) {
+                        Ok(lists) => {
+                            self.open_lists(lists, path);
+                            open = false;
+                        }
+                        Err(e) => {
+                            encryption::forget_passphrase(&path);
+                            prompt.error = Some(format!("{}.", e));
+                        }
+                    }
+                }
+                PassphrasePurpose::Encrypt if prompt.passphrase != prompt.confirmation => {
+                    prompt.error = Some(t("The passphrases don't match.").to_string());
+                }
+                PassphrasePurpose::Encrypt => encrypt = Some((path, prompt.passphrase.clone())),
+            }
+        }
+        // Written once the prompt is let go of, after any background save of the same file
+        if let Some((path, passphrase)) = encrypt {
+            self.wait_for_saves();
+            encryption::remember_passphrase(&path, &passphrase);
+            match save_lists(&path, &self.lists) {
+                Ok(()) => {
+                    autosave::clear_recovery();
+                    self.autosave.mark_saved();
+                    self.set_last_file(path);
+                    open = false;
+                }
+                Err(e) => {
+                    encryption::forget_passphrase(&path);
+                    if let Some(prompt) = self.passphrase_prompt.as_mut() {
+                        prompt.error = Some(tf("Could not write the file: {}", &[&e]));
                     }
                 }
-                Err(e) => eprintln!("Failed to create file: {:?}", e),
             }
         }
+        if !open {
+            self.passphrase_prompt = None;
+        }
     }
 
-    fn load_from_file_dialog(&mut self) {
-        // Filter for .json files
-        if let Some(path) = FileDialog::new()
-            .add_filter("JSON files", &["json"])
-            .pick_file() 
-        {
-            match File::open(path) {
-                Ok(file) => {
-                    let reader = BufReader::new(file);
-                    match serde_json::from_reader(reader) {
-                        Ok(items) => {
-                            self.items = items;
-                            let max_id = self.items.iter().max_by_key(|item| item.id).map_or(0, |item| item.id);
-                            NEXT_ID.store(max_id + 1, Ordering::SeqCst);
-                        }
-                        Err(e) => eprintln!("Failed to deserialize items: {:?}", e),
+    // Copy a JSON save into a new database next to it and keep working from the database
+    #[cfg(not(target_arch = "wasm32"))]
+    fn migrate_to_database_dialog(&mut self) {
+        let Some(json_path) = FileDialog::new()
+            .add_filter(t("JSON files"), &["json"])
+            .pick_file()
+        else {
+            return;
+        };
+        let lists = match load_lists(&json_path) {
+            Ok(lists) => lists,
+            Err(e) => {
+                self.toasts.error(tf("Could not open {}", &[&json_path.display()]), e);
+                return;
+            }
+        };
+
+        let database_path = json_path.with_extension("db");
+        match database::has_data(&database_path) {
+            Ok(true) => {
+                self.toasts.error(t("Not migrating"), tf("{} already contains data.", &[&database_path.display()]));
+                return;
+            }
+            Ok(false) => {}
+            Err(e) => {
+                self.toasts.error(t("Could not open the database"), format!("{:?}", e));
+                return;
+            }
+        }
+
+        match save_lists(&database_path, &lists) {
+            Ok(()) => self.open_lists(lists, database_path),
+            Err(e) => self.toasts.error(t("Could not write the database"), e),
+        }
+    }
+}
+
+// ID of the description field of an item in edit mode, to focus it when editing starts
+fn description_edit_id(id: u32) -> egui::Id {
+    egui::Id::new(("edit_description", id))
+}
+
+// True if Esc was pressed in the field, text fields give up focus on Esc
+fn escaped(ui: &egui::Ui, response: &egui::Response) -> bool {
+    response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Escape))
+}
+
+// Render subtasks as an indented tree below their parent.
+// Returns true if an edit was cancelled with Esc.
+fn show_subtasks(ui: &mut egui::Ui, children: &mut Vec<TodoItem>, auto_complete_parents: bool) -> bool {
+    let mut cancelled = false;
+    let mut to_remove: Vec<usize> = Vec::new();
+    for (index, child) in children.iter_mut().enumerate() {
+        let children_done_before = child.all_children_completed();
+        let open_id = egui::Id::new(("subtasks_open", child.id));
+        let mut open = ui.data_mut(|d| *d.get_persisted_mut_or(open_id, true));
+
+        ui.horizontal(|ui| {
+            show_collapse_toggle(ui, child, &mut open);
+            ui.checkbox(&mut child.completed, "");
+
+            if child.edit {
+                let response = ui.add(egui::TextEdit::singleline(&mut child.description).id(description_edit_id(child.id)));
+                if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    child.edit = false;
+                }
+                cancelled |= escaped(ui, &response);
+            } else {
+                // Double-click to edit
+                let text = egui::RichText::new(&child.description);
+                let text = if child.completed { text.strikethrough() } else { text };
+                if ui.add(egui::Label::new(text).sense(egui::Sense::click())).double_clicked() {
+                    child.edit = true;
+                    ui.memory_mut(|m| m.request_focus(description_edit_id(child.id)));
+                }
+            }
+
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
+                if child.edit {
+                    if ui.button("↶").on_hover_text(t("Cancel, Esc")).clicked() {
+                        cancelled = true;
+                    }
+                    if ui.button(egui::RichText::new("✔").color(egui::Color32::DARK_GREEN)).clicked() {
+                        child.edit = false;
+                    }
+                }
+                if ui.add(egui::Button::new(egui::RichText::new("❌").color(egui::Color32::RED))).clicked() {
+                    to_remove.push(index);
+                }
+                if ui.button("↳").on_hover_text(t("Add subtask")).clicked() {
+                    add_subtask(child);
+                    open = true;
+                }
+            });
+        });
+
+        if open && !child.children.is_empty() {
+            ui.indent(open_id, |ui| {
+                cancelled |= show_subtasks(ui, &mut child.children, auto_complete_parents);
+            });
+        }
+        ui.data_mut(|d| d.insert_persisted(open_id, open));
+
+        // Complete the parent once its last open subtask gets checked
+        if auto_complete_parents && !children_done_before && child.all_children_completed() {
+            child.completed = true;
+        }
+    }
+
+    for &index in to_remove.iter().rev() {
+        children.remove(index);
+    }
+    cancelled
+}
+
+// Repeat menu shown while editing, with an interval field for custom rules
+fn show_recurrence_editor(ui: &mut egui::Ui, item: &mut TodoItem) {
+    ui.horizontal(|ui| {
+        ui.label(t("Repeat:"));
+        let selected_text = item.recurrence.map_or(t("Never").to_string(), recurrence_label);
+        egui::ComboBox::from_id_source(("recurrence", item.id))
+            .selected_text(selected_text)
+            .show_ui(ui, |ui| {
+                if ui.selectable_label(item.recurrence.is_none(), t("Never")).clicked() {
+                    item.recurrence = None;
+                }
+                for option in Recurrence::ALL {
+                    let selected = item.recurrence.is_some_and(|current| current.same_kind(option));
+                    let label = match option {
+                        Recurrence::EveryNDays(_) => t("Custom").to_string(),
+                        _ => recurrence_label(option),
+                    };
+                    if ui.selectable_label(selected, label).clicked() && !selected {
+                        item.recurrence = Some(option);
                     }
                 }
-                Err(e) => eprintln!("Failed to open file: {:?}", e),
+            });
+
+        if let Some(Recurrence::EveryNDays(days)) = item.recurrence.as_mut() {
+            ui.add(egui::DragValue::new(days).clamp_range(1..=365).suffix(format!(" {}", t("days"))));
+        }
+
+        // A habit needs a schedule to come back on, daily unless one is picked
+        let mut habit = item.habit.is_some();
+        if ui.checkbox(&mut habit, t("Habit")).on_hover_text(t("Track a streak of completions")).changed() {
+            if habit {
+                item.habit = Some(Habit::default());
+                item.recurrence.get_or_insert(Recurrence::Daily);
+            } else {
+                item.habit = None;
+            }
+        }
+    });
+}
+
+// A name typed in without its `prefix`, like the @ of contexts, with the names already in use
+// to pick from. `none` is the label for clearing it.
+fn show_name_editor(ui: &mut egui::Ui, value: &mut Option<String>, names: &BTreeSet<String>, prefix: &str, hint: &str, none: &str) {
+    ui.horizontal(|ui| {
+        let mut text = value.clone().unwrap_or_default();
+        let field = egui::TextEdit::singleline(&mut text).hint_text(hint).desired_width(120.0);
+        if ui.add(field).changed() {
+            let name = text.trim().trim_start_matches(prefix);
+            *value = (!name.is_empty()).then(|| name.to_string());
+        }
+        if !names.is_empty() {
+            ui.menu_button("⏷", |ui| {
+                for name in names {
+                    if ui.selectable_label(value.as_ref() == Some(name), format!("{}{}", prefix, name)).clicked() {
+                        *value = Some(name.clone());
+                        ui.close_menu();
+                    }
+                }
+                if ui.button(none).clicked() {
+                    *value = None;
+                    ui.close_menu();
+                }
+            });
+        }
+    });
+}
+
+// Reminder date and time shown while editing, or a button to add one an hour from now
+fn show_reminder_editor(ui: &mut egui::Ui, item: &mut TodoItem) {
+    ui.horizontal(|ui| {
+        ui.label(t("Remind:"));
+        let Some(at) = item.reminder else {
+            if ui.button("🔔").on_hover_text(t("Set reminder")).clicked() {
+                item.reminder = Some(Snooze::OneHour.until(Local::now()));
             }
+            return;
+        };
+        let mut day = at.date_naive();
+        let mut hour = at.hour();
+        let mut minute = at.minute();
+        ui.add(DatePickerButton::new(&mut day).id_source(&format!("reminder_{}", item.id)));
+        ui.add(egui::DragValue::new(&mut hour).clamp_range(0..=23).custom_formatter(|n, _| format!("{:02}", n)));
+        ui.label(":");
+        ui.add(egui::DragValue::new(&mut minute).clamp_range(0..=59).custom_formatter(|n, _| format!("{:02}", n)));
+        // Times skipped when clocks go forward keep the previous value
+        if let Some(changed) = reminder::at(day, hour, minute) {
+            item.reminder = Some(changed);
+        }
+        if ui.small_button("✖").on_hover_text(t("Clear reminder")).clicked() {
+            item.reminder = None;
+        }
+    });
+}
+
+// Existing tags can be removed, new ones typed in and added with Enter
+fn show_tag_editor(ui: &mut egui::Ui, item: &mut TodoItem) {
+    ui.horizontal_wrapped(|ui| {
+        let mut removed_tag = None;
+        for (tag_index, tag) in item.tags.iter().enumerate() {
+            if ui.small_button(format!("#{} ✖", tag)).clicked() {
+                removed_tag = Some(tag_index);
+            }
+        }
+        if let Some(tag_index) = removed_tag {
+            item.tags.remove(tag_index);
+        }
+
+        let response = ui.add(egui::TextEdit::singleline(&mut item.tag_input).hint_text(t("Add tag")).desired_width(80.0));
+        if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+            let tag = std::mem::take(&mut item.tag_input);
+            item.add_tag(&tag);
         }
+    });
+}
+
+// Arrow button that expands or collapses an item's subtasks
+fn show_collapse_toggle(ui: &mut egui::Ui, item: &TodoItem, open: &mut bool) {
+    if item.children.is_empty() {
+        return;
+    }
+    let icon = if *open { "⏷" } else { "⏵" };
+    if ui.small_button(icon).on_hover_text(tf("{} subtasks", &[&item.children.len()])).clicked() {
+        *open = !*open;
+    }
+}
+
+// Checklist text waiting to be imported, with the number of tasks found in it so far
+struct ChecklistImport {
+    text: String,
+    found: usize,
+}
+
+impl ChecklistImport {
+    fn new(text: String) -> Self {
+        let found = checklist::parse(&text).len();
+        Self { text, found }
     }
 }
 
+// New subtasks start empty and in edit mode so they can be typed in straight away
+fn add_subtask(parent: &mut TodoItem) {
+    let mut child = TodoItem::new(String::new());
+    child.edit = true;
+    parent.children.push(child);
+}
 
 impl eframe::App for TodoApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
         // Apply the selected theme
-        ctx.set_visuals(if self.dark_mode { Visuals::dark() } else { Visuals::light() });
+        // eframe keeps the system theme up to date when the OS appearance changes
+        let system_theme = frame.info().system_theme;
+        let visuals = theme::visuals(&self.settings, system_theme);
+        let palette = theme::palette(&self.settings, &visuals);
+        ctx.set_visuals(visuals);
 
         // Set text style based on the chosen text size
         let mut style: egui::Style = (*ctx.style()).clone();
         // Calculate scale factor based on default text size and chosen text size to scale the UI too
-        let scale_factor = self.text_size / style.text_styles.get(&Body).unwrap().size;
+        let scale_factor = self.settings.text_size / style.text_styles.get(&Body).unwrap().size;
         style.text_styles.iter_mut().for_each(|(_style, data)| {
             data.size *= scale_factor;
         });
         ctx.set_style(style);
 
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.vertical_centered(|ui| {
-                ui.heading("Todo List");
+        // Remember the settings as they were before this frame's widgets change them
+        let previous_settings = self.settings.clone();
+        let previous_list = self.current_list;
+        let previous_names: Vec<(String, Option<String>)> = self.lists.iter().map(|list| (list.name.clone(), list.icon.clone())).collect();
 
-                // Load and Save buttons
-                ui.horizontal(|ui| {
-                    if ui.button("Load").clicked() {
-                        self.load_from_file_dialog();
-                    }
-                    ui.add_space(20.0);
-                    if ui.button("Save").clicked() {
-                        self.save_to_file_dialog();
+        // Selection movement needs the display order, so it is applied once that is known
+        let mut selection_step = 0;
+        let mut delete_selected = false;
+        let mut focus_new_item = false;
+        for shortcut in shortcuts::pressed(ctx) {
+            match shortcut {
+                Shortcut::NewItem => {
+                    self.showing_add_item_input = true;
+                    focus_new_item = true;
+                }
+                Shortcut::FocusSearch => ctx.memory_mut(|m| m.request_focus(egui::Id::new(SEARCH_ID))),
+                Shortcut::Save => {
+                    self.save();
+                }
+                Shortcut::Undo => self.undo(),
+                Shortcut::Redo => self.redo(),
+                Shortcut::DeleteSelected => delete_selected = true,
+                Shortcut::DuplicateSelected => {
+                    if let Some(index) = self.selected.and_then(|id| self.list().items.iter().position(|item| item.id == id)) {
+                        self.duplicate_item(index, None);
                     }
-                });
-
-                ui.horizontal(|ui| {
-                    ui.label("UI size:");
-                    ui.add(Slider::new(&mut self.text_size, 6.0..=32.0).text(""));
-                });
+                }
+                Shortcut::SelectPrevious => selection_step -= 1,
+                Shortcut::SelectNext => selection_step += 1,
+            }
+        }
 
-                ui.horizontal(|ui| {
-                    ui.label("Theme:");
-                    ui.checkbox(&mut self.dark_mode, "Dark");
-                });
+        // Lines copied from e.g. meeting notes and pasted onto the list are offered as tasks
+        if let Some(text) = shortcuts::pasted_lines(ctx) {
+            if self.view_mode == ViewMode::List && self.checklist_import.is_none() {
+                self.checklist_import = Some(ChecklistImport::new(text));
+            }
+        }
 
-                ui.separator();
+        self.update_focus(ctx);
+        self.update_recording(ctx);
+        // Focus mode takes over the window, the detail panel waits until it is left
+        if self.focus_mode {
+            self.show_focus_mode(ctx);
+        } else {
+            self.show_main_view(ctx, palette, system_theme, selection_step, delete_selected, focus_new_item);
+        }
 
-                let mut to_remove: Vec<usize> = Vec::new(); // Prepare a list to track items to remove
-                egui::ScrollArea::vertical().show(ui, |ui| {
-                    for (index, item) in self.items.iter_mut().enumerate() {
-                        ui.horizontal(|ui| {
-                            
-                            // Checkbox for completion status
-                            ui.checkbox(&mut item.completed, "");
-
-                            if !item.edit {
-                                // If not in edit mode, show the description and the edit button
-                                let text = if item.completed {
-                                    // Apply strikethrough style if item is completed
-                                    egui::RichText::new(&item.description).strikethrough()
-                                } else {
-                                    egui::RichText::new(&item.description)
-                                };
-                
-                                ui.label(text);
-                            } else {
-                                // If in edit mode, show a text edit field
-                                ui.text_edit_multiline(&mut item.description);
-                            }
-                            
-                            // Right-align the edit and delete buttons
-                            ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
-                                if item.edit {
-                                    // If in edit mode, show a save button
-                                    if ui.button(egui::RichText::new("✔").color(egui::Color32::DARK_GREEN)).clicked() {
-                                        item.edit = false; // Disable edit mode after saving
-                                    }
-                                } else {
-                                    // Show the edit button if not in edit mode
-                                    if ui.button("Edit").clicked() {
-                                        item.edit = true; // Enable edit mode
-                                    }
-                                }
-                                
-                                // Button for deletion
-                                if ui.add(egui::Button::new(egui::RichText::new("❌").color(egui::Color32::RED))).clicked() {
-                                    to_remove.push(index); // Mark this index for removal
-                                }
-                            });
-                        });
-                    }
-                });
+        if self.showing_trash {
+            self.show_trash_window(ctx);
+        }
+        if self.showing_archive {
+            self.show_archive_window(ctx);
+        }
+        if self.showing_fields {
+            let mut fields = self.list().fields.clone();
+            egui::Window::new(tf("Custom fields of {}", &[&self.list().name])).open(&mut self.showing_fields).show(ctx, |ui| {
+                custom_fields::definitions(ui, &mut fields, &mut self.field_draft);
+            });
+            if fields != self.list().fields {
+                self.list_mut().fields = fields;
+            }
+        }
+        if self.showing_stats {
+            stats::show(ctx, &mut self.showing_stats, &self.lists, Local::now().date_naive());
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.handle_tray(ctx);
+            self.handle_hotkey(ctx);
+            for e in notifications::take_failures() {
+                self.toasts.error(t("Could not show a notification"), e);
+            }
+        }
+        self.handle_instance_requests(ctx);
+        #[cfg(feature = "api")]
+        self.handle_api_calls();
+        self.show_quick_add(ctx);
+        self.show_csv_import_window(ctx);
+        self.show_save_smart_list_window(ctx);
+        self.show_checklist_import_window(ctx);
+        self.show_delete_confirmation(ctx);
+        self.handle_dropped_files(ctx);
+        self.show_drop_confirmation(ctx);
+        #[cfg(not(target_arch = "wasm32"))]
+        self.handle_file_changes(ctx);
+        self.show_external_change_prompt(ctx);
+        self.handle_close_request(ctx);
+        self.track_window_geometry(ctx);
+        self.show_close_confirmation(ctx);
+        self.show_reminders(ctx);
+        if self.showing_sync_settings {
+            self.show_sync_window(ctx);
+        }
+        if self.showing_theme_editor {
+            theme::show_editor(ctx, &mut self.showing_theme_editor, &mut self.settings, &mut self.toasts);
+        }
+        self.show_todoist_window(ctx);
+        self.show_passphrase_window(ctx);
+        self.handle_google_result();
+        if self.showing_google {
+            self.show_google_window(ctx);
+        }
+        self.toasts.show(ctx);
+        self.show_saving_indicator(ctx);
+        self.show_drop_hint(ctx);
 
-                // Remove items that were marked for deletion
-                for &index in to_remove.iter().rev() { // Reverse iterate to avoid index shift
-                    self.items.remove(index);
+        // Persist settings whenever they were changed this frame
+        if self.settings != previous_settings {
+            if self.settings.font != previous_settings.font {
+                self.apply_font(ctx);
+            }
+            if self.settings.language != previous_settings.language {
+                i18n::set_language(self.settings.language);
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(tray) = self.tray.as_mut() {
+                    tray.retranslate();
                 }
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            if self.settings.lan_sync.is_configured() != previous_settings.lan_sync.is_configured()
+                || self.settings.lan_sync.key != previous_settings.lan_sync.key
+            {
+                self.restart_peer_sync(ctx);
+            }
+            #[cfg(feature = "api")]
+            if self.settings.api != previous_settings.api {
+                self.restart_api(ctx);
+            }
+            self.archive_checked = None;
+            self.settings.save();
+            self.notifier.update(&self.lists, self.settings.notifications);
+        }
 
-                // Toggle the visibility of the add item input
-                if !self.showing_add_item_input {
-                    if ui.button("➕").clicked() {
-                        self.showing_add_item_input = true;
+        // Restart the autosave timer on every edit and write once it runs out
+        let names_changed = self.lists.len() != previous_names.len()
+            || self.lists.iter().zip(&previous_names).any(|(list, (name, icon))| list.name != *name || list.icon != *icon);
+        let unchanged_items = self.unchanged_items.take();
+        if self.current_list != previous_list || names_changed || unchanged_items.is_some() {
+            self.autosave.mark_changed();
+            self.notifier.update(&self.lists, self.settings.notifications);
+        }
+        // Stamp edited items so syncing knows which side is newer, and log finished changes
+        if let (true, Some(previous_items)) = (self.current_list == previous_list, &unchanged_items) {
+            let previous_items: HashMap<u32, &TodoItem> = previous_items.iter().map(|item| (item.id, item)).collect();
+            let now = Utc::now();
+            let detail_item = self.detail_item;
+            let current_list = self.current_list;
+            let log_originals = &mut self.log_originals;
+            for item in self.lists[current_list].items.iter_mut() {
+                let previous = previous_items.get(&item.id).copied();
+                if previous != Some(&*item) {
+                    item.modified = Some(now);
+                    item.stamp_completion(now);
+                    // Items added this frame come with their own entry
+                    let Some(previous) = previous else {
+                        continue;
+                    };
+                    if item.is_editing() {
+                        log_originals.entry(item.id).or_insert_with(|| previous.clone());
+                    } else if detail_item != Some(item.id) {
+                        // The detail panel logs its changes when it is closed
+                        let before = log_originals.remove(&item.id).unwrap_or_else(|| previous.clone());
+                        item.log_changes(&before, now);
                     }
                 }
+            }
+        }
 
-                // Conditionally show the add item input and button
-                if self.showing_add_item_input {
-                    ui.horizontal(|ui| {
-                        ui.text_edit_multiline(&mut self.input);
-                        if ui.button(egui::RichText::new("✔").color(egui::Color32::DARK_GREEN)).clicked() && !self.input.trim().is_empty() {
-                            self.items.push(TodoItem {
-                                id: NEXT_ID.fetch_add(1, Ordering::SeqCst),
-                                description: self.input.trim().to_string(),
-                                completed: false,
-                                edit: false,
-                            });
-                            self.input.clear();
-                            self.showing_add_item_input = false;
+        // Merged after the change detection above, so synced changes aren't stamped as local edits
+        if let Some(syncer) = self.syncer.as_mut() {
+            let (changed, errors) = syncer.poll(ctx, &self.settings.caldav, &mut self.lists);
+            if changed {
+                self.autosave.mark_changed();
+                self.notifier.update(&self.lists, self.settings.notifications);
+            }
+            for e in errors {
+                self.toasts.error(t("CalDAV sync failed"), e);
+            }
+        }
+        if let Some(file_syncer) = self.file_syncer.as_mut() {
+            let (changed, errors) = file_syncer.poll(ctx, &self.settings.shared_file, &mut self.lists);
+            if changed {
+                self.autosave.mark_changed();
+                self.notifier.update(&self.lists, self.settings.notifications);
+            }
+            for e in errors {
+                self.toasts.error(t("Shared file sync failed"), e);
+            }
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(peers) = self.peers.as_mut() {
+            let (changed, errors) = peers.poll(ctx, &self.settings.lan_sync, &mut self.lists);
+            if changed {
+                self.autosave.mark_changed();
+                self.notifier.update(&self.lists, self.settings.notifications);
+            }
+            for e in errors {
+                self.toasts.error(t("LAN sync failed"), e);
+            }
+        }
+        // Checked once a day, and again after the settings change or another file is opened
+        let today = Local::now().date_naive();
+        if self.settings.auto_archive && self.archive_checked != Some(today) {
+            self.archive_checked = Some(today);
+            let now = Utc::now();
+            let days = self.settings.auto_archive_days;
+            if self.lists.iter_mut().fold(false, |changed, list| list.archive_stale_completed(now, days) | changed) {
+                self.autosave.mark_changed();
+            }
+        }
+        // The window is only redrawn on input or when something asks for it, so wake up when the
+        // day changes for overdue highlights, due date groups and the archive to catch up
+        let midnight = today.succ_opt().and_then(|day| day.and_hms_opt(0, 0, 0)).and_then(|at| at.and_local_timezone(Local).earliest());
+        if let Some(midnight) = midnight {
+            ctx.request_repaint_after((midnight - Local::now()).to_std().unwrap_or_default());
+        }
+
+        if let Some(saver) = self.saver.as_mut() {
+            for e in saver.poll(&mut self.autosave) {
+                self.toasts.error(autosave_failed(self.settings.last_file.as_deref()), e);
+            }
+        }
+        if let Some(remaining) = self.autosave.time_until_due().filter(|_| self.external_change.is_none()) {
+            if remaining.is_zero() {
+                match self.saver.as_mut() {
+                    Some(saver) => saver.save(&mut self.autosave, &self.lists, self.settings.last_file.clone()),
+                    None => {
+                        if let Err(e) = self.autosave.flush(&self.lists, self.settings.last_file.as_deref()) {
+                            self.toasts.error(autosave_failed(self.settings.last_file.as_deref()), e);
                         }
-                    });
+                    }
                 }
-            });
-        });
+            } else {
+                ctx.request_repaint_after(remaining);
+            }
+        }
+    }
+
+    // Don't lose edits made within the debounce window before closing
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        if self.settings.window != self.window {
+            self.settings.window = self.window;
+            self.settings.save();
+        }
+        self.wait_for_saves();
+        if self.autosave.is_pending() {
+            // The window is already gone, so the recovery file is all that can be done
+            let _ = self.autosave.flush(&self.lists, self.settings.last_file.as_deref());
+        }
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 fn main() {
+    // `rust_todo_list mylist.json`, also how file managers pass a double-clicked file.
+    // If the app is already running, the file or task goes to that window instead, so
+    // two instances never write the same save file.
+    let request = Request::from_args(std::env::args_os().skip(1));
+    if instance::forward(&request) {
+        return;
+    }
+
+    let window = Settings::load().window;
+    let mut viewport = egui::ViewportBuilder::default()
+        .with_inner_size(window.size.unwrap_or([400.0, 600.0]))
+        .with_maximized(window.maximized)
+        .with_drag_and_drop(true);
+    if let Some(position) = window.position {
+        viewport = viewport.with_position(position);
+    }
 
     let options = eframe::NativeOptions {
-            viewport: egui::ViewportBuilder::default()
-                .with_inner_size([400.0, 600.0])
-                .with_drag_and_drop(true),
+            viewport,
+            // The geometry is kept in the settings file instead
+            persist_window: false,
              ..Default::default()
         };
 
     if let Err(e) = eframe::run_native(
-        "Todo List App",
+        APP_NAME,
         options,
-        Box::new(|_cc| Box::new(TodoApp::default())),
+        Box::new(|cc| Box::new(TodoApp::new(cc, request))),
     ) {
         eprintln!("Application error: {}", e);
     }
 }
+
+// Served by `trunk serve`, the app draws into the canvas of index.html
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    wasm_bindgen_futures::spawn_local(async {
+        if let Err(e) = eframe::WebRunner::new()
+            .start(
+                "todo_canvas",
+                eframe::WebOptions::default(),
+                Box::new(|cc| Box::new(TodoApp::new(cc, Request::Show))),
+            )
+            .await
+        {
+            web_sys::console::error_1(&e);
+        }
+    });
+}
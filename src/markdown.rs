@@ -0,0 +1,216 @@
+use eframe::egui::{self, text::LayoutJob, Color32, Stroke, TextFormat};
+use std::ops::Range;
+use todo_core::search::match_ranges;
+
+// Inline Markdown for descriptions: **bold**, *italic*, `code`, ~~strikethrough~~ and
+// [links](https://example.com). Block syntax like headings isn't useful in a task row.
+// Bare http(s) addresses become links as well.
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Style {
+    strong: bool,
+    italics: bool,
+    strikethrough: bool,
+    code: bool,
+}
+
+#[derive(Debug)]
+struct Span {
+    text: String,
+    style: Style,
+    link: Option<String>,
+}
+
+fn flush(spans: &mut Vec<Span>, current: &mut String, style: Style) {
+    if !current.is_empty() {
+        spans.push(Span { text: std::mem::take(current), style, link: None });
+    }
+}
+
+// `[label](url)` at the start of `text`, with the number of bytes it takes up
+fn link_at(text: &str) -> Option<(&str, &str, usize)> {
+    let label_end = text.find("](")?;
+    let url_end = label_end + 2 + text[label_end + 2..].find(')')?;
+    let (label, url) = (&text[1..label_end], &text[label_end + 2..url_end]);
+    if label.is_empty() || url.is_empty() || label.contains('\n') || url.contains(char::is_whitespace) {
+        return None;
+    }
+    Some((label, url, url_end + 1))
+}
+
+// Length of a bare URL at the start of `text`. Punctuation at the end, like the period
+// of a sentence, is left out unless the parentheses are part of the URL.
+fn url_len(text: &str) -> Option<usize> {
+    if !(text.starts_with("https://") || text.starts_with("http://")) {
+        return None;
+    }
+    let mut url = &text[..text.find(char::is_whitespace).unwrap_or(text.len())];
+    while let Some(last) = url.chars().last() {
+        let unbalanced_paren = last == ')' && url.matches('(').count() < url.matches(')').count();
+        if ".,;:!?'\"".contains(last) || unbalanced_paren {
+            url = &url[..url.len() - 1];
+        } else {
+            break;
+        }
+    }
+    let host = url.trim_start_matches("https://").trim_start_matches("http://");
+    Some(url.len()).filter(|_| !host.is_empty())
+}
+
+fn parse(text: &str) -> Vec<Span> {
+    let mut spans = Vec::new();
+    let mut current = String::new();
+    let mut style = Style::default();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let after = &rest[c.len_utf8()..];
+
+        // A backslash keeps the next marker character as it is
+        if let Some(escaped) = after.chars().next().filter(|next| c == '\\' && "\\`*~[]".contains(*next)) {
+            current.push(escaped);
+            rest = &after[escaped.len_utf8()..];
+            continue;
+        }
+
+        // Nothing inside backticks is formatted
+        if c == '`' {
+            if let Some(end) = after.find('`') {
+                flush(&mut spans, &mut current, style);
+                spans.push(Span { text: after[..end].to_string(), style: Style { code: true, ..style }, link: None });
+                rest = &after[end + 1..];
+                continue;
+            }
+        }
+
+        if let Some(marker) = ["**", "~~", "*"].into_iter().find(|marker| rest.starts_with(marker)) {
+            let is_open = match marker {
+                "**" => style.strong,
+                "~~" => style.strikethrough,
+                _ => style.italics,
+            };
+            let inner = &rest[marker.len()..];
+            // Opening markers need text right after them and a closing marker later on
+            if is_open || (inner.starts_with(|next: char| !next.is_whitespace()) && inner.contains(marker)) {
+                flush(&mut spans, &mut current, style);
+                match marker {
+                    "**" => style.strong = !is_open,
+                    "~~" => style.strikethrough = !is_open,
+                    _ => style.italics = !is_open,
+                }
+                rest = inner;
+                continue;
+            }
+        }
+
+        // Only at the start of a word, so "xhttp://" isn't picked up
+        if !current.ends_with(char::is_alphanumeric) {
+            if let Some(len) = url_len(rest) {
+                flush(&mut spans, &mut current, style);
+                spans.push(Span { text: rest[..len].to_string(), style, link: Some(rest[..len].to_string()) });
+                rest = &rest[len..];
+                continue;
+            }
+        }
+
+        if c == '[' {
+            if let Some((label, url, len)) = link_at(rest) {
+                flush(&mut spans, &mut current, style);
+                spans.push(Span { text: label.to_string(), style, link: Some(url.to_string()) });
+                rest = &rest[len..];
+                continue;
+            }
+        }
+
+        current.push(c);
+        rest = after;
+    }
+    flush(&mut spans, &mut current, style);
+    spans
+}
+
+// Append `range` of `text` with `format`, giving the parts that match the search a background
+fn append_highlighted(job: &mut LayoutJob, text: &str, range: Range<usize>, matches: &[Range<usize>], format: &TextFormat, background: Color32) {
+    let mut position = range.start;
+    for found in matches.iter().filter(|found| found.start < range.end && found.end > range.start) {
+        let (start, end) = (found.start.max(range.start), found.end.min(range.end));
+        job.append(&text[position..start], 0.0, format.clone());
+        job.append(&text[start..end], 0.0, TextFormat { background, ..format.clone() });
+        position = end;
+    }
+    job.append(&text[position..range.end], 0.0, format.clone());
+}
+
+// Show a description with its Markdown rendered and search matches highlighted.
+// Clicking a link opens it in the browser. The response is returned so callers can
+// react to clicks on the text, a click on a link included, and attach a context menu.
+// Text past `max_rows` rows is cut off with an ellipsis. Returns whether any was.
+// `strike` is how far across the text is struck through, from 0 to 1, so checking an item
+// off can draw the line along.
+pub fn show(ui: &mut egui::Ui, text: &str, query: &str, color: Color32, strike: f32, max_rows: usize) -> (egui::Response, bool) {
+    let spans = parse(text);
+    let display: String = spans.iter().map(|span| span.text.as_str()).collect();
+    let matches = match_ranges(&display, query);
+    let visuals = ui.visuals().clone();
+
+    let mut job = LayoutJob::default();
+    job.wrap.max_width = ui.available_width();
+    job.wrap.max_rows = max_rows;
+    job.wrap.break_anywhere = max_rows == 1;
+    // Character ranges of the links, to find the one under the pointer
+    let mut links: Vec<(Range<usize>, &str)> = Vec::new();
+    let (mut offset, mut char_offset) = (0, 0);
+    for span in &spans {
+        let mut format = TextFormat {
+            font_id: egui::TextStyle::Body.resolve(ui.style()),
+            color,
+            italics: span.style.italics,
+            ..Default::default()
+        };
+        // Layout jobs have no bold face, so strong text is drawn brighter like `RichText::strong`
+        if span.style.strong && color == visuals.text_color() {
+            format.color = visuals.strong_text_color();
+        }
+        if span.style.code {
+            format.font_id = egui::TextStyle::Monospace.resolve(ui.style());
+            format.background = visuals.code_bg_color;
+        }
+        if let Some(url) = &span.link {
+            format.color = visuals.hyperlink_color;
+            format.underline = Stroke::new(1.0, visuals.hyperlink_color);
+            let chars = span.text.chars().count();
+            links.push((char_offset..char_offset + chars, url.as_str()));
+        }
+        if strike >= 1.0 || span.style.strikethrough {
+            format.strikethrough = Stroke::new(1.0, format.color);
+        }
+        append_highlighted(&mut job, &display, offset..offset + span.text.len(), &matches, &format, visuals.selection.bg_fill);
+        offset += span.text.len();
+        char_offset += span.text.chars().count();
+    }
+
+    let galley = ui.fonts(|fonts| fonts.layout_job(job));
+    let elided = galley.elided;
+    let (rect, response) = ui.allocate_exact_size(galley.size(), egui::Sense::click());
+    let hovered_link = response.hover_pos().and_then(|pos| {
+        let index = galley.cursor_from_pos(pos - rect.min).ccursor.index;
+        links.iter().find(|(range, _)| range.contains(&index)).map(|(_, url)| *url)
+    });
+    if let Some(url) = hovered_link {
+        ui.ctx().set_cursor_icon(egui::CursorIcon::PointingHand);
+        response.clone().on_hover_text(url);
+    }
+    // A line part of the way across every row while the strike is being drawn
+    if strike > 0.0 && strike < 1.0 {
+        for row in &galley.rows {
+            let row_rect = row.rect.translate(rect.min.to_vec2());
+            let x = row_rect.left()..=row_rect.left() + row_rect.width() * strike;
+            ui.painter().hline(x, row_rect.center().y, Stroke::new(1.0, color));
+        }
+    }
+    ui.painter().galley(rect.min, galley, color);
+
+    if let (true, Some(url)) = (response.clicked(), hovered_link) {
+        ui.ctx().open_url(egui::OpenUrl::new_tab(url));
+    }
+    (response, elided)
+}
@@ -0,0 +1,46 @@
+use crate::board::show_card;
+use crate::i18n::t;
+use crate::theme::Palette;
+use chrono::NaiveDate;
+use eframe::egui::{self, RichText};
+use todo_core::{Quadrant, TodoItem};
+
+// Urgent/important matrix of the open items, two rows of two quadrants. Returns the ID of a
+// card dropped on another quadrant and that quadrant.
+pub fn show(ui: &mut egui::Ui, items: &[TodoItem], order: &[usize], today: NaiveDate, palette: Palette) -> Option<(u32, Quadrant)> {
+    let mut moved = None;
+    let height = (ui.available_height() / 2.0 - 40.0).max(150.0);
+    for row in Quadrant::ALL.chunks(2) {
+        ui.columns(2, |columns| {
+            for (ui, &quadrant) in columns.iter_mut().zip(row) {
+                let cards: Vec<&TodoItem> = order
+                    .iter()
+                    .map(|&index| &items[index])
+                    .filter(|item| !item.completed && item.quadrant() == quadrant)
+                    .collect();
+                ui.label(RichText::new(format!("{} ({})", t(quadrant.label()), cards.len())).strong())
+                    .on_hover_text(t(quadrant.description()));
+
+                let square = ui
+                    .group(|ui| {
+                        ui.set_min_size(egui::vec2(ui.available_width(), height));
+                        egui::ScrollArea::vertical().id_source(("matrix", quadrant.label())).max_height(height).show(ui, |ui| {
+                            for item in cards {
+                                show_card(ui, item, today, palette, t("Drag to another quadrant to change how urgent and important it is"));
+                            }
+                        });
+                    })
+                    .response;
+
+                if square.dnd_hover_payload::<u32>().is_some() {
+                    let stroke = ui.visuals().selection.stroke;
+                    ui.painter().rect_stroke(square.rect, 4.0, stroke);
+                }
+                if let Some(dragged_id) = square.dnd_release_payload::<u32>() {
+                    moved = Some((*dragged_id, quadrant));
+                }
+            }
+        });
+    }
+    moved
+}
@@ -0,0 +1,89 @@
+use crate::i18n::{t, tf};
+use crate::theme::Palette;
+use chrono::NaiveDate;
+use eframe::egui::{self, Color32, RichText};
+use std::cmp::Reverse;
+use todo_core::tracking::format_duration;
+use todo_core::{TodoItem, TodoList};
+
+// Something done to a task in the My Day view, by the index of its list and its ID
+pub enum Action {
+    Toggle(usize, u32),
+    Add(usize, u32),
+    Remove(usize, u32),
+}
+
+// Today's plan gathered from every list, with the open tasks to pick from below it
+pub fn show(ui: &mut egui::Ui, lists: &[TodoList], today: NaiveDate, palette: Palette) -> Option<Action> {
+    let items = || lists.iter().enumerate().flat_map(|(list, items)| items.items.iter().map(move |item| (list, item)));
+    let planned: Vec<(usize, &TodoItem)> = items().filter(|(_, item)| item.is_in_my_day(today)).collect();
+    // Overdue and due tasks come first, then the rest by due date and priority
+    let mut suggestions: Vec<(usize, &TodoItem)> =
+        items().filter(|(_, item)| !item.completed && !item.is_in_my_day(today)).collect();
+    suggestions.sort_by_key(|(_, item)| (item.due_date.is_none(), item.due_date, Reverse(item.priority)));
+
+    let mut action = None;
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        ui.heading(t("☀ My Day"));
+        let done = planned.iter().filter(|(_, item)| item.completed).count();
+        if planned.is_empty() {
+            ui.label(RichText::new(t("Nothing planned yet, pick tasks for today below")).weak());
+        } else {
+            ui.add(egui::ProgressBar::new(done as f32 / planned.len() as f32).text(tf("{} of {} done", &[&done, &planned.len()])));
+            // Enough to tell whether the plan fits in the day
+            let workload: u32 = planned.iter().map(|(_, item)| item.remaining_estimate()).sum();
+            if workload > 0 {
+                ui.label(tf("⌛ {} of estimated work left", &[&format_duration(u64::from(workload) * 60)]));
+            }
+        }
+        ui.add_space(4.0);
+        for &(list, item) in &planned {
+            ui.horizontal(|ui| {
+                let mut completed = item.completed;
+                if ui.checkbox(&mut completed, "").changed() {
+                    action = Some(Action::Toggle(list, item.id));
+                }
+                show_description(ui, lists, list, item, today, palette);
+                if item.is_carried_over(today) {
+                    let badge = RichText::new(t("Carried over")).small().color(Color32::WHITE);
+                    ui.add(egui::Button::new(badge).fill(palette.overdue).small())
+                        .on_hover_text(tf("Planned for {}", &[&item.my_day.unwrap_or(today).format("%Y-%m-%d")]));
+                }
+                if ui.small_button("✖").on_hover_text(t("Remove from My Day")).clicked() {
+                    action = Some(Action::Remove(list, item.id));
+                }
+            });
+        }
+
+        ui.separator();
+        egui::CollapsingHeader::new(tf("Suggestions ({})", &[&suggestions.len()])).id_source("my_day_suggestions").default_open(true).show(ui, |ui| {
+            for &(list, item) in &suggestions {
+                ui.horizontal(|ui| {
+                    if ui.small_button("➕").on_hover_text(t("Add to My Day")).clicked() {
+                        action = Some(Action::Add(list, item.id));
+                    }
+                    show_description(ui, lists, list, item, today, palette);
+                });
+            }
+        });
+    });
+    action
+}
+
+// Description with its icon, due date and the list it is in
+fn show_description(ui: &mut egui::Ui, lists: &[TodoList], list: usize, item: &TodoItem, today: NaiveDate, palette: Palette) {
+    if let Some(icon) = &item.icon {
+        ui.label(icon.as_str());
+    }
+    let text = if item.completed {
+        RichText::new(&item.description).strikethrough().color(palette.completed)
+    } else {
+        RichText::new(&item.description)
+    };
+    ui.label(text);
+    if let Some(due) = item.due_date {
+        let text = RichText::new(format!("📅 {}", due.format("%Y-%m-%d"))).small();
+        ui.label(if item.is_overdue(today) { text.color(palette.overdue) } else { text });
+    }
+    ui.label(RichText::new(&lists[list].name).small().weak());
+}
@@ -0,0 +1,149 @@
+#[cfg(not(target_arch = "wasm32"))]
+use crate::i18n::t;
+use chrono::{DateTime, Local, NaiveDate};
+#[cfg(not(target_arch = "wasm32"))]
+use notify_rust::Notification;
+#[cfg(not(target_arch = "wasm32"))]
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::OnceLock;
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Duration;
+use todo_core::TodoList;
+
+// How often the background thread looks for tasks that became due
+#[cfg(not(target_arch = "wasm32"))]
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+// The parts of an open task the notification thread needs
+struct DueItem {
+    id: u32,
+    description: String,
+    due_date: NaiveDate,
+}
+
+// An open task with a reminder set
+struct ReminderItem {
+    id: u32,
+    description: String,
+    at: DateTime<Local>,
+}
+
+#[derive(Default)]
+struct Watched {
+    due_items: Vec<DueItem>,
+    reminders: Vec<ReminderItem>,
+}
+
+// Fires desktop notifications for due and overdue tasks and for reminders from a
+// background thread, so they still arrive while the window is minimized and not repainting
+pub struct Notifier {
+    watched: Arc<Mutex<Watched>>,
+}
+
+impl Notifier {
+    pub fn start() -> Self {
+        let watched: Arc<Mutex<Watched>> = Arc::default();
+        // Browsers can't block a thread, the web build only shows reminders inside the page
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let thread_watched = Arc::clone(&watched);
+            thread::spawn(move || watch(&thread_watched));
+        }
+        Self { watched }
+    }
+
+    // Replace the watched tasks with the open, dated or reminded tasks of every list
+    pub fn update(&self, lists: &[TodoList], enabled: bool) {
+        let mut watched = Watched::default();
+        if enabled {
+            for item in lists.iter().flat_map(|list| list.items.iter()).filter(|item| !item.completed) {
+                if let Some(due_date) = item.due_date {
+                    watched.due_items.push(DueItem {
+                        id: item.id,
+                        description: item.description.clone(),
+                        due_date,
+                    });
+                }
+                if let Some(at) = item.reminder {
+                    watched.reminders.push(ReminderItem {
+                        id: item.id,
+                        description: item.description.clone(),
+                        at,
+                    });
+                }
+            }
+        }
+        if let Ok(mut current) = self.watched.lock() {
+            *current = watched;
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn watch(watched: &Mutex<Watched>) {
+    // Each task is announced once per day at most
+    let mut notified: HashSet<(u32, NaiveDate)> = HashSet::new();
+    // Each reminder once, a snoozed reminder has a new time and fires again
+    let mut reminded: HashSet<(u32, DateTime<Local>)> = HashSet::new();
+    loop {
+        let now = Local::now();
+        let today = now.date_naive();
+        notified.retain(|&(_, day)| day == today);
+        reminded.retain(|&(_, at)| now - at < chrono::Duration::days(1));
+        if let Ok(watched) = watched.lock() {
+            for item in watched.due_items.iter().filter(|item| item.due_date <= today) {
+                if notified.insert((item.id, today)) {
+                    notify(item, today);
+                }
+            }
+            for reminder in watched.reminders.iter().filter(|reminder| reminder.at <= now) {
+                if reminded.insert((reminder.id, reminder.at)) {
+                    notify_reminder(&reminder.description);
+                }
+            }
+        }
+        thread::sleep(CHECK_INTERVAL);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn notify(item: &DueItem, today: NaiveDate) {
+    let summary = if item.due_date < today { t("Task overdue") } else { t("Task due today") };
+    show(summary, &item.description);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn notify_reminder(description: &str) {
+    show(t("Reminder"), description);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn notify_focus_finished(description: &str) {
+    show(t("Focus session finished"), description);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn show(summary: &str, body: &str) {
+    if let Err(e) = Notification::new().appname("Todo List App").summary(summary).body(body).show() {
+        if let Ok(mut failures) = failures().lock() {
+            failures.push(e.to_string());
+        }
+    }
+}
+
+// Why notifications couldn't be shown. They are sent from background threads, so the window
+// picks these up to report them.
+#[cfg(not(target_arch = "wasm32"))]
+fn failures() -> &'static Mutex<Vec<String>> {
+    static FAILURES: OnceLock<Mutex<Vec<String>>> = OnceLock::new();
+    FAILURES.get_or_init(Mutex::default)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn take_failures() -> Vec<String> {
+    failures().lock().map(|mut failures| std::mem::take(&mut *failures)).unwrap_or_default()
+}
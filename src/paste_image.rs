@@ -0,0 +1,32 @@
+use todo_core::attachment::Attachment;
+
+#[cfg(not(target_arch = "wasm32"))]
+use chrono::Local;
+#[cfg(not(target_arch = "wasm32"))]
+use todo_core::attachment;
+
+// The image on the clipboard, if there is one, saved as a PNG in the attachments folder.
+// Fails if it couldn't be saved.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn paste() -> Result<Option<Attachment>, String> {
+    let Ok(image) = arboard::Clipboard::new().and_then(|mut clipboard| clipboard.get_image()) else {
+        return Ok(None);
+    };
+    let folder = attachment::folder().ok_or("No data directory to save pasted images to")?;
+    let Some(buffer) = image::RgbaImage::from_raw(image.width as u32, image.height as u32, image.bytes.into_owned()) else {
+        return Ok(None);
+    };
+    std::fs::create_dir_all(&folder).map_err(|e| format!("Failed to create {}: {}", folder.display(), e))?;
+    let name = Local::now().format("Pasted image %Y-%m-%d %H%M%S.png").to_string();
+    let path = attachment::free_name(&folder, name.into());
+    match buffer.save(&path) {
+        Ok(()) => Ok(Some(Attachment { path, copied: true })),
+        Err(e) => Err(format!("Failed to save pasted image to {}: {}", path.display(), e)),
+    }
+}
+
+// The browser keeps its clipboard images to itself
+#[cfg(target_arch = "wasm32")]
+pub fn paste() -> Result<Option<Attachment>, String> {
+    Ok(None)
+}
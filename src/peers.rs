@@ -0,0 +1,287 @@
+use chrono::{DateTime, Local};
+use eframe::egui;
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use todo_core::settings::LanSync;
+use todo_core::{lan, merge, storage, TodoList};
+
+// Name the app announces itself under on the network
+const SERVICE_TYPE: &str = "_rust-todo-list._tcp.local.";
+// A peer that sends or answers nothing for this long is given up on
+const TIMEOUT: Duration = Duration::from_secs(10);
+// Peers served at once, more are hung up on until one is done
+const MAX_CONNECTIONS: usize = 8;
+
+enum PeerEvent {
+    Found(String, SocketAddr),
+    Lost(String),
+    // Lists a peer sent, answered with ours once they are merged
+    Received(Vec<TodoList>, Sender<String>),
+    // A peer's lists in answer to ours
+    Answered(Vec<TodoList>),
+    Failed(String),
+}
+
+// Finds other running instances on the network with mDNS and swaps lists with them, both sides
+// merging what they get. As with CalDAV sync, network work runs on background threads and merging
+// on the UI thread. Only instances with the same key talk to each other, proving it to each other
+// without sending the key, and the lists go over the network encrypted.
+pub struct PeerSync {
+    daemon: ServiceDaemon,
+    exchanges: Sender<(SocketAddr, String)>,
+    events: Receiver<PeerEvent>,
+    port: u16,
+    stopped: Arc<AtomicBool>,
+    peers: HashMap<String, SocketAddr>,
+    last_started: Option<Instant>,
+    pub last_synced: Option<DateTime<Local>>,
+    pub last_error: Option<String>,
+}
+
+impl PeerSync {
+    // Fails if the port or the announcement can't be set up
+    pub fn start(ctx: &egui::Context, key: String) -> Result<Self, String> {
+        let listening = TcpListener::bind((Ipv4Addr::UNSPECIFIED, 0)).and_then(|listener| Ok((listener.local_addr()?.port(), listener)));
+        let (port, listener) = listening.map_err(|e| format!("Failed to listen for other computers: {}", e))?;
+        let daemon = ServiceDaemon::new().map_err(|e| format!("Failed to start mDNS: {}", e))?;
+        let name = format!("{:016x}", RandomState::new().build_hasher().finish());
+        let announced = ServiceInfo::new(SERVICE_TYPE, &name, &format!("{}.local.", name), "", port, None::<HashMap<String, String>>)
+            .map(ServiceInfo::enable_addr_auto)
+            .and_then(|service| {
+                let own_name = service.get_fullname().to_string();
+                daemon.register(service).map(|()| own_name)
+            })
+            .and_then(|own_name| daemon.browse(SERVICE_TYPE).map(|browser| (own_name, browser)));
+        let (own_name, browser) = match announced {
+            Ok(announced) => announced,
+            Err(e) => {
+                let _ = daemon.shutdown();
+                return Err(format!("Failed to announce on the network: {}", e));
+            }
+        };
+
+        let (event_sender, events) = mpsc::channel();
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        // Other instances coming and going, ends when the daemon shuts down
+        let sender = event_sender.clone();
+        let browser_ctx = ctx.clone();
+        thread::spawn(move || {
+            while let Ok(event) = browser.recv() {
+                let event = match event {
+                    ServiceEvent::ServiceResolved(info) if info.get_fullname() != own_name => {
+                        let addresses = info.get_addresses();
+                        let Some(ip) = addresses.iter().find(|ip| ip.is_ipv4()).or_else(|| addresses.iter().next()) else {
+                            continue;
+                        };
+                        PeerEvent::Found(info.get_fullname().to_string(), SocketAddr::new(*ip, info.get_port()))
+                    }
+                    ServiceEvent::ServiceRemoved(_, full_name) => PeerEvent::Lost(full_name),
+                    _ => continue,
+                };
+                if sender.send(event).is_err() {
+                    break;
+                }
+                browser_ctx.request_repaint();
+            }
+        });
+
+        // Peers sending their lists, each on its own thread so a slow or idle one holds up no other
+        let sender = event_sender.clone();
+        let listener_ctx = ctx.clone();
+        let listener_key = key.clone();
+        let listener_stopped = Arc::clone(&stopped);
+        thread::spawn(move || {
+            let open = Arc::new(AtomicUsize::new(0));
+            for stream in listener.incoming().flatten() {
+                if listener_stopped.load(Ordering::SeqCst) {
+                    break;
+                }
+                if open.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+                    open.fetch_sub(1, Ordering::SeqCst);
+                    continue;
+                }
+                let open = Arc::clone(&open);
+                let sender = sender.clone();
+                let ctx = listener_ctx.clone();
+                let key = listener_key.clone();
+                thread::spawn(move || {
+                    serve(stream, &key, &sender, &ctx);
+                    open.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+
+        // Sending our lists to peers
+        let (exchanges, jobs) = mpsc::channel::<(SocketAddr, String)>();
+        let sender = event_sender;
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            for (address, json) in jobs {
+                let event = match exchange(address, &key, &json) {
+                    Ok(Some(lists)) => PeerEvent::Answered(lists),
+                    // Hung up on, so another key is set over there
+                    Ok(None) => continue,
+                    Err(e) => PeerEvent::Failed(format!("{}: {}", address, e)),
+                };
+                if sender.send(event).is_err() {
+                    break;
+                }
+                ctx.request_repaint();
+            }
+        });
+
+        Ok(Self {
+            daemon,
+            exchanges,
+            events,
+            port,
+            stopped,
+            peers: HashMap::new(),
+            last_started: None,
+            last_synced: None,
+            last_error: None,
+        })
+    }
+
+    pub fn peer_count(&self) -> usize {
+        self.peers.len()
+    }
+
+    // Sync on the next poll instead of waiting for the interval
+    pub fn sync_now(&mut self) {
+        self.last_started = None;
+    }
+
+    // Merge lists from peers and send ours to every peer once the interval is up. Returns
+    // whether the lists were changed, and why exchanges failed for the caller to show.
+    pub fn poll(&mut self, ctx: &egui::Context, settings: &LanSync, lists: &mut Vec<TodoList>) -> (bool, Vec<String>) {
+        let mut changed = false;
+        let mut errors = Vec::new();
+        while let Ok(event) = self.events.try_recv() {
+            match event {
+                PeerEvent::Found(name, address) => {
+                    self.peers.insert(name, address);
+                    errors.extend(self.send_lists(address, lists));
+                }
+                PeerEvent::Lost(name) => {
+                    self.peers.remove(&name);
+                }
+                PeerEvent::Received(theirs, reply) => {
+                    changed |= merge::merge_lists(lists, theirs);
+                    if let Ok(json) = storage::to_json(lists) {
+                        let _ = reply.send(json);
+                    }
+                    self.finish(None);
+                }
+                PeerEvent::Answered(theirs) => {
+                    changed |= merge::merge_lists(lists, theirs);
+                    self.finish(None);
+                }
+                PeerEvent::Failed(error) => {
+                    self.finish(Some(error.clone()));
+                    errors.push(error);
+                }
+            }
+        }
+
+        let interval = Duration::from_secs(u64::from(settings.interval_minutes.max(1)) * 60);
+        match self.last_started.map(|started| interval.saturating_sub(started.elapsed())) {
+            Some(remaining) if !remaining.is_zero() => ctx.request_repaint_after(remaining),
+            _ => {
+                self.last_started = Some(Instant::now());
+                for &address in self.peers.values() {
+                    errors.extend(self.send_lists(address, lists));
+                }
+            }
+        }
+        (changed, errors)
+    }
+
+    // The error if the lists can't be turned into JSON
+    fn send_lists(&self, address: SocketAddr, lists: &[TodoList]) -> Option<String> {
+        match storage::to_json(lists) {
+            Ok(json) => {
+                let _ = self.exchanges.send((address, json));
+                None
+            }
+            Err(e) => Some(format!("Failed to serialize the lists: {}", e)),
+        }
+    }
+
+    fn finish(&mut self, error: Option<String>) {
+        if error.is_none() {
+            self.last_synced = Some(Local::now());
+        }
+        self.last_error = error;
+    }
+}
+
+impl Drop for PeerSync {
+    fn drop(&mut self) {
+        let _ = self.daemon.shutdown();
+        // Wake the listener so it sees it should stop
+        self.stopped.store(true, Ordering::SeqCst);
+        let _ = TcpStream::connect_timeout(&SocketAddr::from((Ipv4Addr::LOCALHOST, self.port)), TIMEOUT);
+    }
+}
+
+// Answer a peer that sends its lists with ours once they are merged. Computers that don't
+// prove they know the key are hung up on without a word.
+fn serve(mut stream: TcpStream, key: &str, events: &Sender<PeerEvent>, ctx: &egui::Context) {
+    let _ = stream.set_read_timeout(Some(TIMEOUT));
+    let _ = stream.set_write_timeout(Some(TIMEOUT));
+    let Ok(Some(mut session)) = lan::accept(&mut stream, key) else {
+        return;
+    };
+    let received = session.receive(&mut stream).and_then(|json| read_lists(&json));
+    let lists = match received {
+        Ok(lists) => lists,
+        Err(e) => {
+            let _ = events.send(PeerEvent::Failed(format!("Failed to read lists from another computer: {}", e)));
+            ctx.request_repaint();
+            return;
+        }
+    };
+    let (reply_sender, reply) = mpsc::channel();
+    if events.send(PeerEvent::Received(lists, reply_sender)).is_err() {
+        return;
+    }
+    ctx.request_repaint();
+    if let Ok(json) = reply.recv_timeout(TIMEOUT) {
+        let _ = session.send(&mut stream, json.as_bytes());
+    }
+}
+
+// Send our lists and read the peer's in answer. None if the peer hung up on our key, or didn't
+// answer because it was closing.
+fn exchange(address: SocketAddr, key: &str, json: &str) -> io::Result<Option<Vec<TodoList>>> {
+    let mut stream = TcpStream::connect_timeout(&address, TIMEOUT)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let Some(mut session) = lan::connect(&mut stream, key)? else {
+        return Ok(None);
+    };
+    session.send(&mut stream, json.as_bytes())?;
+    match session.receive(&mut stream) {
+        Ok(reply) => read_lists(&reply).map(Some),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn read_lists(json: &[u8]) -> io::Result<Vec<TodoList>> {
+    std::str::from_utf8(json)
+        .map_err(|e| e.to_string())
+        .and_then(|json| storage::from_json(json).map_err(|e| e.to_string()))
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
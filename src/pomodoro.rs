@@ -0,0 +1,65 @@
+#[cfg(not(target_arch = "wasm32"))]
+use crate::notifications;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+use std::time::Duration;
+use todo_core::TodoItem;
+use web_time::Instant;
+
+// Length of one focus session
+pub const SESSION_LENGTH: Duration = Duration::from_secs(25 * 60);
+
+// A focus session running on one task
+pub struct FocusTimer {
+    pub item_id: u32,
+    pub description: String,
+    ends_at: Instant,
+    // Tells the notification thread the session was stopped early
+    cancelled: Arc<AtomicBool>,
+}
+
+impl FocusTimer {
+    // The end of the session is announced from a background thread,
+    // so the notification arrives even while the window is not repainting.
+    // In the web build only the countdown in the page shows it.
+    pub fn start(item: &TodoItem) -> Self {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let thread_cancelled = Arc::clone(&cancelled);
+            let description = item.description.clone();
+            thread::spawn(move || {
+                thread::sleep(SESSION_LENGTH);
+                if !thread_cancelled.load(Ordering::SeqCst) {
+                    notifications::notify_focus_finished(&description);
+                }
+            });
+        }
+        Self {
+            item_id: item.id,
+            description: item.description.clone(),
+            ends_at: Instant::now() + SESSION_LENGTH,
+            cancelled,
+        }
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.ends_at.saturating_duration_since(Instant::now())
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.remaining().is_zero()
+    }
+
+    // Time left as minutes and seconds, e.g. "24:59"
+    pub fn countdown(&self) -> String {
+        let seconds = self.remaining().as_secs();
+        format!("{:02}:{:02}", seconds / 60, seconds % 60)
+    }
+
+    pub fn stop(self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+}
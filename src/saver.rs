@@ -0,0 +1,100 @@
+use eframe::egui;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use todo_core::autosave::{self, Autosave};
+use todo_core::{StorageError, TodoList};
+
+// A copy of the lists to write, with the change count from `Autosave::start_flush`
+struct Job {
+    lists: Arc<Vec<TodoList>>,
+    last_file: Option<PathBuf>,
+    started: u64,
+}
+
+// Writes autosaves on a background thread, so serializing and writing a large save doesn't
+// stall the window. What is saved of the lists is copied when the save starts, without the undo
+// history, and edits carry on meanwhile.
+pub struct Saver {
+    jobs: Sender<Job>,
+    results: Receiver<(u64, Result<bool, StorageError>)>,
+    // Saves sent to the thread that haven't reported back yet
+    in_flight: usize,
+    // What was last sent to be written, edits may have moved the lists on since
+    last_sent: Option<Arc<Vec<TodoList>>>,
+}
+
+impl Saver {
+    pub fn start(ctx: &egui::Context) -> Self {
+        let (jobs, job_receiver) = mpsc::channel::<Job>();
+        let (result_sender, results) = mpsc::channel();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            // Saves are written one after the other, so the last one started ends up on disk
+            for job in job_receiver {
+                let written = autosave::write(&job.lists, job.last_file.as_deref());
+                if result_sender.send((job.started, written)).is_err() {
+                    break;
+                }
+                ctx.request_repaint();
+            }
+        });
+        Self {
+            jobs,
+            results,
+            in_flight: 0,
+            last_sent: None,
+        }
+    }
+
+    pub fn save(&mut self, autosave: &mut Autosave, lists: &[TodoList], last_file: Option<PathBuf>) {
+        let started = autosave.start_flush();
+        let job = Job {
+            lists: Arc::new(lists.iter().map(TodoList::saved_copy).collect()),
+            last_file,
+            started,
+        };
+        self.last_sent = Some(Arc::clone(&job.lists));
+        if self.jobs.send(job).is_ok() {
+            self.in_flight += 1;
+        }
+    }
+
+    // Lists this app had written, so the write isn't taken for another program's
+    pub fn last_sent(&self) -> Option<&[TodoList]> {
+        self.last_sent.as_deref().map(Vec::as_slice)
+    }
+
+    pub fn is_saving(&self) -> bool {
+        self.in_flight > 0
+    }
+
+    // Hand finished saves to `autosave`. Returns the errors of the ones that failed.
+    pub fn poll(&mut self, autosave: &mut Autosave) -> Vec<StorageError> {
+        let mut errors = Vec::new();
+        while let Ok((started, written)) = self.results.try_recv() {
+            self.finish(autosave, started, written, &mut errors);
+        }
+        errors
+    }
+
+    // Block until every save has been written, e.g. before the window closes
+    pub fn wait(&mut self, autosave: &mut Autosave) -> Vec<StorageError> {
+        let mut errors = Vec::new();
+        while self.in_flight > 0 {
+            match self.results.recv() {
+                Ok((started, written)) => self.finish(autosave, started, written, &mut errors),
+                Err(_) => break,
+            }
+        }
+        errors
+    }
+
+    fn finish(&mut self, autosave: &mut Autosave, started: u64, written: Result<bool, StorageError>, errors: &mut Vec<StorageError>) {
+        self.in_flight -= 1;
+        if let Err(e) = autosave.finish_flush(started, written) {
+            errors.push(e);
+        }
+    }
+}
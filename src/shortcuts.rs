@@ -0,0 +1,54 @@
+use eframe::egui::{Context, Event, Key, KeyboardShortcut, Modifiers};
+
+// Actions that can be triggered from the keyboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shortcut {
+    NewItem,
+    FocusSearch,
+    Save,
+    Undo,
+    Redo,
+    DeleteSelected,
+    DuplicateSelected,
+    SelectPrevious,
+    SelectNext,
+}
+
+// Key bindings, the last column marks shortcuts that would clash with typing into a text field
+const BINDINGS: [(Shortcut, Modifiers, Key, bool); 9] = [
+    (Shortcut::NewItem, Modifiers::COMMAND, Key::N, false),
+    (Shortcut::FocusSearch, Modifiers::COMMAND, Key::F, false),
+    (Shortcut::Save, Modifiers::COMMAND, Key::S, false),
+    (Shortcut::Undo, Modifiers::COMMAND, Key::Z, true),
+    (Shortcut::Redo, Modifiers::COMMAND, Key::Y, true),
+    (Shortcut::DeleteSelected, Modifiers::NONE, Key::Delete, true),
+    (Shortcut::DuplicateSelected, Modifiers::COMMAND, Key::D, true),
+    (Shortcut::SelectPrevious, Modifiers::NONE, Key::ArrowUp, true),
+    (Shortcut::SelectNext, Modifiers::NONE, Key::ArrowDown, true),
+];
+
+// Consume and return the shortcuts pressed this frame
+pub fn pressed(ctx: &Context) -> Vec<Shortcut> {
+    let typing = ctx.wants_keyboard_input();
+    BINDINGS
+        .iter()
+        .filter(|(_, _, _, text_conflict)| !(typing && *text_conflict))
+        .filter(|(_, modifiers, key, _)| {
+            ctx.input_mut(|i| i.consume_shortcut(&KeyboardShortcut::new(*modifiers, *key)))
+        })
+        .map(|(shortcut, _, _, _)| *shortcut)
+        .collect()
+}
+
+// Text pasted with Ctrl+V while no text field has focus, if it has more than one non-empty line
+pub fn pasted_lines(ctx: &Context) -> Option<String> {
+    if ctx.wants_keyboard_input() {
+        return None;
+    }
+    ctx.input(|i| {
+        i.events.iter().find_map(|event| match event {
+            Event::Paste(text) if text.lines().filter(|line| !line.trim().is_empty()).count() > 1 => Some(text.clone()),
+            _ => None,
+        })
+    })
+}
@@ -0,0 +1,66 @@
+use crate::i18n::t;
+use chrono::NaiveDate;
+use eframe::egui::{self, RichText};
+use todo_core::filter::SmartList;
+use todo_core::{Filter, TodoList};
+
+pub enum Action {
+    // Put the smart list's filters in place
+    Apply(usize),
+    // Switch to the list with this index
+    Open(usize),
+    // Save the current filters under the smart list's name
+    Update(usize),
+    Delete(usize),
+}
+
+// Saved filters with how many tasks of all lists they match right now. The one in place
+// shows the lists its tasks are in underneath, to jump between them.
+pub fn show(
+    ui: &mut egui::Ui,
+    smart_lists: &[SmartList],
+    lists: &[TodoList],
+    current_list: usize,
+    filter: &Filter,
+    today: NaiveDate,
+) -> Option<Action> {
+    let mut action = None;
+    ui.heading(t("Smart lists"));
+    ui.separator();
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        for (index, smart) in smart_lists.iter().enumerate() {
+            let query = smart.query();
+            let counts: Vec<usize> = lists
+                .iter()
+                .map(|list| list.items.iter().filter(|item| smart.matches(item, &query, &filter.index, today)).count())
+                .collect();
+            let applied = smart.is_applied(filter);
+            let entry = ui.selectable_label(applied, format!("{} ({})", smart.name, counts.iter().sum::<usize>()));
+            if entry.clicked() {
+                action = Some(Action::Apply(index));
+            }
+            entry.context_menu(|ui| {
+                if ui.button(t("Save current filters here")).clicked() {
+                    action = Some(Action::Update(index));
+                    ui.close_menu();
+                }
+                if ui.button(t("Delete smart list")).clicked() {
+                    action = Some(Action::Delete(index));
+                    ui.close_menu();
+                }
+            });
+            if !applied {
+                continue;
+            }
+            ui.indent(("smart_list_lists", index), |ui| {
+                for (list_index, &count) in counts.iter().enumerate().filter(|(_, &count)| count > 0) {
+                    let name = RichText::new(format!("{} ({})", lists[list_index].name, count)).small();
+                    if ui.selectable_label(list_index == current_list, name).clicked() {
+                        action = Some(Action::Open(list_index));
+                    }
+                }
+            });
+        }
+    });
+    action
+}
@@ -0,0 +1,29 @@
+#[cfg(not(target_arch = "wasm32"))]
+use rodio::{source::SineWave, OutputStream, Sink, Source};
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Duration;
+
+// Two short rising notes, made up on the spot so no sound files need to ship with the app.
+// Played on its own thread since the output stream has to stay open until they end.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn play_completion() {
+    thread::spawn(|| {
+        let Ok((_stream, handle)) = OutputStream::try_default() else {
+            eprintln!("No audio output for the completion sound");
+            return;
+        };
+        let Ok(sink) = Sink::try_new(&handle) else {
+            return;
+        };
+        for frequency in [880.0, 1320.0] {
+            sink.append(SineWave::new(frequency).take_duration(Duration::from_millis(90)).fade_in(Duration::from_millis(5)).amplify(0.15));
+        }
+        sink.sleep_until_end();
+    });
+}
+
+// The web build has no sound yet
+#[cfg(target_arch = "wasm32")]
+pub fn play_completion() {}
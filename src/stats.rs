@@ -0,0 +1,65 @@
+use crate::i18n::{t, tf};
+use chrono::NaiveDate;
+use eframe::egui::{self, RichText};
+use egui_plot::{Bar, BarChart, Plot};
+use todo_core::stats::{self, Stats};
+use todo_core::TodoList;
+
+// How far back the charts go
+const DAYS: u32 = 30;
+const WEEKS: u32 = 12;
+
+// Statistics window, `open` is cleared when it is closed
+pub fn show(ctx: &egui::Context, open: &mut bool, lists: &[TodoList], today: NaiveDate) {
+    let stats = Stats::collect(lists, today, DAYS, WEEKS);
+    egui::Window::new(t("Statistics")).open(open).default_width(360.0).show(ctx, |ui| {
+        egui::Grid::new("stats_summary").num_columns(2).show(ui, |ui| {
+            ui.label(t("Current streak:"));
+            ui.label(RichText::new(tf("{} days", &[&stats.streak])).strong());
+            ui.end_row();
+            ui.label(t("Average time to complete:"));
+            ui.label(RichText::new(stats.average_time_to_complete.map_or("-".to_string(), stats::format_age)).strong());
+            ui.end_row();
+            ui.label(t("Oldest open task:"));
+            ui.label(RichText::new(stats.oldest_open.map_or("-".to_string(), stats::format_age)).strong());
+            ui.end_row();
+        });
+
+        ui.separator();
+        ui.label(tf("Completed per day, last {} days", &[&DAYS]));
+        let bars = stats.per_day.iter().enumerate().map(|(index, (day, count))| {
+            Bar::new(index as f64, *count as f64).name(day.format("%a %d %b").to_string())
+        });
+        chart(ui, "stats_per_day", BarChart::new(bars.collect()));
+
+        ui.label(tf("Completed per week, last {} weeks", &[&WEEKS]));
+        let bars = stats.per_week.iter().enumerate().map(|(index, (monday, count))| {
+            Bar::new(index as f64, *count as f64).name(format!("Week of {}", monday.format("%d %b")))
+        });
+        chart(ui, "stats_per_week", BarChart::new(bars.collect()));
+
+        ui.separator();
+        ui.label(t("Tasks per tag"));
+        if stats.tag_counts.is_empty() {
+            ui.label(RichText::new(t("No tags yet.")).weak());
+        }
+        egui::Grid::new("stats_tags").num_columns(2).show(ui, |ui| {
+            for (tag, count) in &stats.tag_counts {
+                ui.label(format!("#{}", tag));
+                ui.label(count.to_string());
+                ui.end_row();
+            }
+        });
+    });
+}
+
+fn chart(ui: &mut egui::Ui, id: &str, chart: BarChart) {
+    Plot::new(id)
+        .height(120.0)
+        .allow_drag(false)
+        .allow_zoom(false)
+        .allow_scroll(false)
+        .show_x(false)
+        .include_y(0.0)
+        .show(ui, |plot| plot.bar_chart(chart));
+}
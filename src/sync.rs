@@ -0,0 +1,123 @@
+use chrono::{DateTime, Local};
+use eframe::egui;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+use todo_core::caldav::{self, CaldavAccount, Change, RemoteTodo};
+use todo_core::TodoList;
+
+// Network work done off the UI thread
+enum Job {
+    Fetch(CaldavAccount),
+    Upload(CaldavAccount, Vec<Change>),
+}
+
+enum SyncEvent {
+    Fetched(Vec<RemoteTodo>),
+    Uploaded,
+    Failed(String),
+}
+
+// Keeps one list in sync with a CalDAV calendar. Requests run on a background
+// thread, while merging happens on the UI thread so it never races with edits.
+pub struct Syncer {
+    jobs: Sender<Job>,
+    events: Receiver<SyncEvent>,
+    last_started: Option<Instant>,
+    busy: bool,
+    pub last_synced: Option<DateTime<Local>>,
+    pub last_error: Option<String>,
+}
+
+impl Syncer {
+    pub fn start(ctx: &egui::Context) -> Self {
+        let (jobs, job_receiver) = mpsc::channel::<Job>();
+        let (event_sender, events) = mpsc::channel();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            for job in job_receiver {
+                let event = match job {
+                    Job::Fetch(account) => match caldav::fetch(&account) {
+                        Ok(remote) => SyncEvent::Fetched(remote),
+                        Err(e) => SyncEvent::Failed(e.to_string()),
+                    },
+                    Job::Upload(account, changes) => match caldav::upload(&account, &changes) {
+                        Ok(()) => SyncEvent::Uploaded,
+                        Err(e) => SyncEvent::Failed(e.to_string()),
+                    },
+                };
+                if event_sender.send(event).is_err() {
+                    break;
+                }
+                ctx.request_repaint();
+            }
+        });
+        Self {
+            jobs,
+            events,
+            last_started: None,
+            busy: false,
+            last_synced: None,
+            last_error: None,
+        }
+    }
+
+    // Sync on the next poll instead of waiting for the interval
+    pub fn sync_now(&mut self) {
+        self.last_started = None;
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.busy
+    }
+
+    // Merge finished downloads and start a new sync once the interval is up. Returns whether
+    // the lists were changed, and why syncs failed for the caller to show.
+    pub fn poll(&mut self, ctx: &egui::Context, account: &CaldavAccount, lists: &mut [TodoList]) -> (bool, Vec<String>) {
+        let mut changed = false;
+        let mut errors = Vec::new();
+        while let Ok(event) = self.events.try_recv() {
+            match event {
+                SyncEvent::Fetched(remote) => {
+                    let index = account.list_index(lists);
+                    let list = &mut lists[index];
+                    let (items_before, trash_before) = (list.items.clone(), list.trash.clone());
+                    let changes = caldav::merge(list, account, remote, Local::now().date_naive());
+                    changed |= list.items != items_before || list.trash != trash_before;
+                    if changes.is_empty() {
+                        self.finish(None);
+                    } else {
+                        let _ = self.jobs.send(Job::Upload(account.clone(), changes));
+                    }
+                }
+                SyncEvent::Uploaded => self.finish(None),
+                SyncEvent::Failed(error) => {
+                    self.finish(Some(error.clone()));
+                    errors.push(error);
+                }
+            }
+        }
+
+        if !account.is_configured() || self.busy {
+            return (changed, errors);
+        }
+        let interval = Duration::from_secs(u64::from(account.interval_minutes.max(1)) * 60);
+        match self.last_started.map(|started| interval.saturating_sub(started.elapsed())) {
+            Some(remaining) if !remaining.is_zero() => ctx.request_repaint_after(remaining),
+            _ => {
+                self.last_started = Some(Instant::now());
+                self.busy = true;
+                let _ = self.jobs.send(Job::Fetch(account.clone()));
+            }
+        }
+        (changed, errors)
+    }
+
+    fn finish(&mut self, error: Option<String>) {
+        self.busy = false;
+        if error.is_none() {
+            self.last_synced = Some(Local::now());
+        }
+        self.last_error = error;
+    }
+}
@@ -0,0 +1,147 @@
+use crate::dialogs::FileDialog;
+use crate::i18n::{t, tf};
+use crate::toasts::Toasts;
+use eframe::egui::{self, Color32, Visuals};
+use todo_core::settings::Settings;
+use todo_core::theme::{self, Theme};
+
+// Colors the rows need besides the ones egui's visuals already cover
+#[derive(Clone, Copy)]
+pub struct Palette {
+    pub completed: Color32,
+    pub overdue: Color32,
+}
+
+fn color([r, g, b]: [u8; 3]) -> Color32 {
+    Color32::from_rgb(r, g, b)
+}
+
+// The custom theme if one is picked, otherwise the built-in dark or light look.
+// `system` is the operating system's appearance, if it could be detected.
+pub fn visuals(settings: &Settings, system: Option<eframe::Theme>) -> Visuals {
+    let Some(theme) = settings.active_theme() else {
+        let dark = match system {
+            Some(system) if settings.follow_system_theme => system == eframe::Theme::Dark,
+            _ => settings.dark_mode,
+        };
+        return if dark { Visuals::dark() } else { Visuals::light() };
+    };
+    let mut visuals = if theme.dark { Visuals::dark() } else { Visuals::light() };
+    let accent = color(theme.accent);
+    let background = color(theme.background);
+    visuals.selection.bg_fill = accent;
+    visuals.hyperlink_color = accent;
+    visuals.widgets.hovered.bg_stroke.color = accent;
+    visuals.panel_fill = background;
+    visuals.window_fill = background;
+    visuals
+}
+
+pub fn palette(settings: &Settings, visuals: &Visuals) -> Palette {
+    match settings.active_theme() {
+        Some(theme) => Palette {
+            completed: color(theme.completed_text),
+            overdue: color(theme.overdue),
+        },
+        None => Palette {
+            completed: visuals.text_color(),
+            overdue: Color32::RED,
+        },
+    }
+}
+
+// A name that isn't taken yet, e.g. "Theme 2"
+fn new_name(themes: &[Theme]) -> String {
+    (1..)
+        .map(|number| tf("Theme {}", &[&number]))
+        .find(|name| themes.iter().all(|theme| &theme.name != name))
+        .unwrap_or_default()
+}
+
+// Pick, create, edit, import and export custom themes. Changes apply right away.
+pub fn show_editor(ctx: &egui::Context, open: &mut bool, settings: &mut Settings, toasts: &mut Toasts) {
+    egui::Window::new(t("Theme editor")).open(open).resizable(false).show(ctx, |ui| {
+        ui.horizontal(|ui| {
+            ui.label(t("Theme:"));
+            let selected_text = settings.theme.clone().unwrap_or_else(|| t("Built-in").to_string());
+            egui::ComboBox::from_id_source("custom_theme").selected_text(selected_text).show_ui(ui, |ui| {
+                ui.selectable_value(&mut settings.theme, None, t("Built-in"));
+                for theme in &settings.themes {
+                    ui.selectable_value(&mut settings.theme, Some(theme.name.clone()), &theme.name);
+                }
+            });
+        });
+
+        ui.horizontal(|ui| {
+            let mut created = None;
+            if ui.button(t("New light")).clicked() {
+                created = Some(Theme::light(new_name(&settings.themes)));
+            }
+            if ui.button(t("New dark")).clicked() {
+                created = Some(Theme::dark(new_name(&settings.themes)));
+            }
+            if ui.button(t("Import…")).clicked() {
+                if let Some(path) = FileDialog::new().add_filter(t("Theme"), &["json"]).pick_file() {
+                    match theme::import(&path) {
+                        Ok(imported) => created = Some(imported),
+                        Err(e) => toasts.error(t("Failed to import the theme"), e),
+                    }
+                }
+            }
+            // An imported theme replaces one with the same name
+            if let Some(created) = created {
+                settings.themes.retain(|theme| theme.name != created.name);
+                settings.theme = Some(created.name.clone());
+                settings.themes.push(created);
+            }
+        });
+
+        let Some(index) = settings.theme.as_ref().and_then(|name| settings.themes.iter().position(|theme| &theme.name == name)) else {
+            return;
+        };
+        ui.separator();
+
+        let theme = &mut settings.themes[index];
+        let mut deleted = false;
+        egui::Grid::new("theme_colors").num_columns(2).show(ui, |ui| {
+            ui.label(t("Name:"));
+            ui.text_edit_singleline(&mut theme.name);
+            ui.end_row();
+            ui.label(t("Base:"));
+            ui.checkbox(&mut theme.dark, t("Dark"));
+            ui.end_row();
+            for (label, rgb) in [
+                (t("Accent:"), &mut theme.accent),
+                (t("Background:"), &mut theme.background),
+                (t("Completed text:"), &mut theme.completed_text),
+                (t("Overdue:"), &mut theme.overdue),
+            ] {
+                ui.label(label);
+                ui.color_edit_button_srgb(rgb);
+                ui.end_row();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            if ui.button(t("Export…")).clicked() {
+                let file_name = format!("{}.json", theme.name);
+                if let Some(path) = FileDialog::new().add_filter(t("Theme"), &["json"]).set_file_name(&file_name).save_file() {
+                    match theme::export(&path, theme) {
+                        Ok(()) => toasts.info(tf("Exported {}", &[&path.display()])),
+                        Err(e) => toasts.error(t("Failed to export the theme"), e),
+                    }
+                }
+            }
+            if ui.button(egui::RichText::new(t("Delete")).color(Color32::RED)).clicked() {
+                deleted = true;
+            }
+        });
+
+        // Follow renames so the theme stays selected
+        settings.theme = Some(theme.name.clone());
+        if deleted {
+            settings.themes.remove(index);
+            settings.theme = None;
+        }
+    });
+}
@@ -0,0 +1,149 @@
+use crate::i18n::t;
+use crate::priority_color;
+use crate::theme::Palette;
+use chrono::{Datelike, Days, NaiveDate, Weekday};
+use eframe::egui::{self, pos2, vec2, Align2, CursorIcon, FontId, Rect, RichText, Sense};
+use todo_core::TodoItem;
+
+const LABEL_WIDTH: f32 = 160.0;
+const DAY_WIDTH: f32 = 24.0;
+const ROW_HEIGHT: f32 = 22.0;
+// Width of the ends of a bar that move only one of its dates
+const HANDLE: f32 = 6.0;
+
+pub enum Action {
+    Open(u32),
+    // Days to move the start and the due date of an item by
+    Reschedule(u32, i64, i64),
+}
+
+// A bar per task from its start to its due date, dragged to reschedule it
+pub struct Timeline {
+    // Day in the first column
+    first: NaiveDate,
+}
+
+impl Timeline {
+    pub fn new(today: NaiveDate) -> Self {
+        Self { first: today - Days::new(7) }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, items: &[TodoItem], order: &[usize], today: NaiveDate, palette: Palette) -> Option<Action> {
+        ui.horizontal(|ui| {
+            if ui.button("◀").on_hover_text(t("A week earlier")).clicked() {
+                self.first = self.first - Days::new(7);
+            }
+            if ui.button(t("Today")).clicked() {
+                self.first = today - Days::new(7);
+            }
+            if ui.button("▶").on_hover_text(t("A week later")).clicked() {
+                self.first = self.first + Days::new(7);
+            }
+        });
+
+        let rows: Vec<&TodoItem> = order.iter().map(|&index| &items[index]).filter(|item| item.due_date.is_some()).collect();
+        if rows.is_empty() {
+            ui.label(RichText::new(t("Tasks with a due date show up here")).weak());
+            return None;
+        }
+
+        let first = self.first;
+        let days = ((ui.available_width() - LABEL_WIDTH) / DAY_WIDTH).floor().max(7.0) as u64;
+        let mut action = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            // Two header rows, months above day numbers
+            let size = vec2(LABEL_WIDTH + days as f32 * DAY_WIDTH, (rows.len() + 2) as f32 * ROW_HEIGHT);
+            let (rect, _) = ui.allocate_exact_size(size, Sense::hover());
+            let painter = ui.painter_at(rect);
+            let x_of = |day: NaiveDate| rect.left() + LABEL_WIDTH + (day - first).num_days() as f32 * DAY_WIDTH;
+            let text_color = ui.visuals().text_color();
+            let weak_color = ui.visuals().weak_text_color();
+            let font = FontId::proportional(11.0);
+
+            for offset in 0..days {
+                let day = first + Days::new(offset);
+                let x = x_of(day);
+                if matches!(day.weekday(), Weekday::Sat | Weekday::Sun) {
+                    let weekend = Rect::from_min_max(pos2(x, rect.top() + ROW_HEIGHT), pos2(x + DAY_WIDTH, rect.bottom()));
+                    painter.rect_filled(weekend, 0.0, ui.visuals().faint_bg_color);
+                }
+                if offset == 0 || day.day() == 1 {
+                    let month = format!("{} {}", t(&day.format("%B").to_string()), day.year());
+                    painter.text(pos2(x + 2.0, rect.top() + ROW_HEIGHT / 2.0), Align2::LEFT_CENTER, month, font.clone(), text_color);
+                }
+                let color = if day == today { text_color } else { weak_color };
+                painter.text(pos2(x + DAY_WIDTH / 2.0, rect.top() + ROW_HEIGHT * 1.5), Align2::CENTER_CENTER, day.day().to_string(), font.clone(), color);
+            }
+            if today >= first && today < first + Days::new(days) {
+                let x = x_of(today) + DAY_WIDTH / 2.0;
+                painter.vline(x, (rect.top() + ROW_HEIGHT)..=rect.bottom(), egui::Stroke::new(1.0, palette.overdue));
+            }
+
+            for (row, item) in rows.iter().enumerate() {
+                let top = rect.top() + (row + 2) as f32 * ROW_HEIGHT;
+                let label_rect = Rect::from_min_size(pos2(rect.left(), top), vec2(LABEL_WIDTH - 4.0, ROW_HEIGHT));
+                let mut text = RichText::new(&item.description);
+                if item.completed {
+                    text = text.strikethrough().color(palette.completed);
+                }
+                let label = ui.put(label_rect, egui::Label::new(text).truncate(true).sense(Sense::click()));
+                if label.on_hover_text(t("Click to open the details")).clicked() {
+                    action = Some(Action::Open(item.id));
+                }
+
+                let Some(due) = item.due_date else {
+                    continue;
+                };
+                let start = item.start_date.unwrap_or(due).min(due);
+                let bar = Rect::from_min_max(pos2(x_of(start) + 1.0, top + 3.0), pos2(x_of(due) + DAY_WIDTH - 1.0, top + ROW_HEIGHT - 3.0));
+
+                // The ends move one date each, the middle moves both. The distance dragged so far
+                // is kept until the mouse is released and then rounded to whole days.
+                let zones = [
+                    (Rect::from_min_max(bar.min, pos2(bar.left() + HANDLE, bar.bottom())), 1, 0, CursorIcon::ResizeWest),
+                    (Rect::from_min_max(pos2(bar.right() - HANDLE, bar.top()), bar.max), 0, 1, CursorIcon::ResizeEast),
+                    (bar.shrink2(vec2(HANDLE, 0.0)), 1, 1, CursorIcon::Grab),
+                ];
+                let drag_id = egui::Id::new(("timeline_drag", item.id));
+                let mut dragging: Option<(i64, i64, f32)> = ui.data(|d| d.get_temp(drag_id));
+                for (index, (zone, moves_start, moves_due, cursor)) in zones.into_iter().enumerate() {
+                    let response = ui
+                        .interact(zone, drag_id.with(index), Sense::drag())
+                        .on_hover_cursor(cursor)
+                        .on_hover_text(format!("{} – {}", start.format("%Y-%m-%d"), due.format("%Y-%m-%d")));
+                    if response.dragged() {
+                        let dx = dragging.map_or(0.0, |(_, _, dx)| dx) + response.drag_delta().x;
+                        dragging = Some((moves_start, moves_due, dx));
+                    }
+                    if response.drag_released() {
+                        if let Some((moves_start, moves_due, dx)) = dragging.take() {
+                            let days = (dx / DAY_WIDTH).round() as i64;
+                            if days != 0 {
+                                action = Some(Action::Reschedule(item.id, moves_start * days, moves_due * days));
+                            }
+                        }
+                    }
+                }
+                match dragging {
+                    Some(state) => ui.data_mut(|d| d.insert_temp(drag_id, state)),
+                    None => ui.data_mut(|d| d.remove::<(i64, i64, f32)>(drag_id)),
+                }
+
+                let mut shown = bar;
+                if let Some((moves_start, moves_due, dx)) = dragging {
+                    shown.min.x += moves_start as f32 * dx;
+                    shown.max.x = (shown.max.x + moves_due as f32 * dx).max(shown.min.x + HANDLE);
+                }
+                let color = if item.completed {
+                    palette.completed
+                } else if item.is_overdue(today) {
+                    palette.overdue
+                } else {
+                    priority_color(item.priority)
+                };
+                painter.rect_filled(shown, 3.0, color);
+            }
+        });
+        action
+    }
+}
@@ -0,0 +1,92 @@
+use crate::i18n::t;
+use eframe::egui::{self, Color32, RichText};
+use std::time::Duration;
+use web_time::Instant;
+
+// Confirmations disappear on their own, errors stay until dismissed
+const INFO_TIMEOUT: Duration = Duration::from_secs(4);
+
+#[derive(PartialEq)]
+enum Level {
+    Info,
+    Error,
+}
+
+struct Toast {
+    level: Level,
+    title: String,
+    details: Option<String>,
+    shown_at: Instant,
+}
+
+// Notifications stacked in the bottom right corner of the window.
+// The console is hidden on Windows, so failures have to be shown in the app.
+#[derive(Default)]
+pub struct Toasts {
+    toasts: Vec<Toast>,
+}
+
+impl Toasts {
+    // Errors are logged too, so there is a trace when running from a terminal
+    pub fn error(&mut self, title: impl Into<String>, details: impl ToString) {
+        let title = title.into();
+        let details = details.to_string();
+        eprintln!("{}: {}", title, details);
+        self.push(Level::Error, title, Some(details));
+    }
+
+    pub fn info(&mut self, title: impl Into<String>) {
+        self.push(Level::Info, title.into(), None);
+    }
+
+    // A repeated failure, e.g. autosave, replaces the earlier toast instead of stacking up
+    fn push(&mut self, level: Level, title: String, details: Option<String>) {
+        self.toasts.retain(|toast| toast.title != title);
+        self.toasts.push(Toast { level, title, details, shown_at: Instant::now() });
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        self.toasts.retain(|toast| toast.level == Level::Error || toast.shown_at.elapsed() < INFO_TIMEOUT);
+        if self.toasts.is_empty() {
+            return;
+        }
+
+        let mut dismissed = None;
+        egui::Area::new("toasts")
+            .anchor(egui::Align2::RIGHT_BOTTOM, [-10.0, -10.0])
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                ui.set_max_width(320.0);
+                for (index, toast) in self.toasts.iter().enumerate() {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            let title = RichText::new(&toast.title).strong();
+                            ui.label(if toast.level == Level::Error { title.color(Color32::RED) } else { title });
+                            if ui.small_button("✖").on_hover_text(t("Dismiss")).clicked() {
+                                dismissed = Some(index);
+                            }
+                        });
+                        if let Some(details) = &toast.details {
+                            egui::CollapsingHeader::new(t("Details")).id_source(("toast", toast.shown_at)).show(ui, |ui| {
+                                ui.label(RichText::new(details).small());
+                            });
+                        }
+                    });
+                }
+            });
+        if let Some(index) = dismissed {
+            self.toasts.remove(index);
+        }
+
+        // Wake up again to fade out confirmations
+        if let Some(remaining) = self
+            .toasts
+            .iter()
+            .filter(|toast| toast.level == Level::Info)
+            .map(|toast| INFO_TIMEOUT.saturating_sub(toast.shown_at.elapsed()))
+            .min()
+        {
+            ctx.request_repaint_after(remaining);
+        }
+    }
+}
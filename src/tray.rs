@@ -0,0 +1,133 @@
+use crate::i18n::{t, tf};
+use eframe::egui;
+use std::sync::mpsc::{self, Receiver};
+use tray_icon::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use tray_icon::{ClickType, Icon, TrayIcon, TrayIconBuilder, TrayIconEvent};
+
+// Size of the generated tray icon in pixels
+const ICON_SIZE: u32 = 32;
+
+// What the user asked for through the tray icon
+pub enum TrayAction {
+    ToggleWindow,
+    QuickAdd,
+    Quit,
+}
+
+pub struct Tray {
+    icon: TrayIcon,
+    // Kept to match events against and to relabel when the language changes
+    show_hide: MenuItem,
+    quick_add: MenuItem,
+    quit: MenuItem,
+    menu_events: Receiver<MenuEvent>,
+    icon_events: Receiver<TrayIconEvent>,
+    open_count: Option<usize>,
+}
+
+impl Tray {
+    // Fails if the platform has no tray available
+    pub fn new(ctx: &egui::Context) -> Result<Self, String> {
+        let show_hide = MenuItem::new(t("Show/Hide"), true, None);
+        let quick_add = MenuItem::new(t("Quick add…"), true, None);
+        let quit = MenuItem::new(t("Quit"), true, None);
+        let menu = Menu::new();
+        menu.append_items(&[&show_hide, &quick_add, &PredefinedMenuItem::separator(), &quit])
+            .map_err(|e| e.to_string())?;
+
+        let icon = TrayIconBuilder::new()
+            .with_menu(Box::new(menu))
+            .with_tooltip(t("Todo List"))
+            .with_icon(build_icon())
+            .build()
+            .map_err(|e| e.to_string())?;
+
+        // Forward events and wake the UI, which may be hidden and not repainting
+        let (menu_sender, menu_events) = mpsc::channel();
+        let menu_ctx = ctx.clone();
+        MenuEvent::set_event_handler(Some(move |event| {
+            let _ = menu_sender.send(event);
+            menu_ctx.request_repaint();
+        }));
+        let (icon_sender, icon_events) = mpsc::channel();
+        let icon_ctx = ctx.clone();
+        TrayIconEvent::set_event_handler(Some(move |event| {
+            let _ = icon_sender.send(event);
+            icon_ctx.request_repaint();
+        }));
+
+        Ok(Self {
+            icon,
+            show_hide,
+            quick_add,
+            quit,
+            menu_events,
+            icon_events,
+            open_count: None,
+        })
+    }
+
+    // Actions requested since the last frame, a left click on the icon toggles the window
+    pub fn poll(&self) -> Vec<TrayAction> {
+        let mut actions = Vec::new();
+        for event in self.icon_events.try_iter() {
+            if event.click_type == ClickType::Left {
+                actions.push(TrayAction::ToggleWindow);
+            }
+        }
+        for event in self.menu_events.try_iter() {
+            if event.id == *self.show_hide.id() {
+                actions.push(TrayAction::ToggleWindow);
+            } else if event.id == *self.quick_add.id() {
+                actions.push(TrayAction::QuickAdd);
+            } else if event.id == *self.quit.id() {
+                actions.push(TrayAction::Quit);
+            }
+        }
+        actions
+    }
+
+    // Menu and tooltip are native, so they don't pick up a new language by themselves
+    pub fn retranslate(&mut self) {
+        self.show_hide.set_text(t("Show/Hide"));
+        self.quick_add.set_text(t("Quick add…"));
+        self.quit.set_text(t("Quit"));
+        self.open_count = None;
+    }
+
+    // A failure is returned once, and not tried again until the count changes
+    pub fn set_open_count(&mut self, count: usize) -> Result<(), String> {
+        if self.open_count == Some(count) {
+            return Ok(());
+        }
+        self.open_count = Some(count);
+        let tooltip = match count {
+            1 => t("Todo List: 1 open task").to_string(),
+            _ => tf("Todo List: {} open tasks", &[&count]),
+        };
+        self.icon.set_tooltip(Some(tooltip)).map_err(|e| e.to_string())
+    }
+}
+
+// A green rounded check box drawn in code so no image file has to ship with the app
+fn build_icon() -> Icon {
+    let size = ICON_SIZE as i32;
+    let mut rgba = Vec::with_capacity((ICON_SIZE * ICON_SIZE * 4) as usize);
+    for y in 0..size {
+        for x in 0..size {
+            let border = x < 3 || y < 3 || x >= size - 3 || y >= size - 3;
+            // Two strokes of the check mark
+            let short_stroke = (8..=14).contains(&x) && (y - (x + 8)).abs() <= 2;
+            let long_stroke = (14..=25).contains(&x) && (y - (36 - x)).abs() <= 2;
+            let pixel = if short_stroke || long_stroke {
+                [255, 255, 255, 255]
+            } else if border {
+                [30, 110, 50, 255]
+            } else {
+                [50, 160, 80, 255]
+            };
+            rgba.extend_from_slice(&pixel);
+        }
+    }
+    Icon::from_rgba(rgba, ICON_SIZE, ICON_SIZE).expect("icon buffer matches its size")
+}
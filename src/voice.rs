@@ -0,0 +1,174 @@
+use std::path::Path;
+use std::time::Duration;
+use todo_core::attachment::Attachment;
+
+#[cfg(not(target_arch = "wasm32"))]
+use chrono::Local;
+#[cfg(not(target_arch = "wasm32"))]
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::{mpsc, Arc, Mutex};
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(not(target_arch = "wasm32"))]
+use todo_core::attachment;
+
+// Memos stop by themselves after this long
+pub const MAX_LENGTH: Duration = Duration::from_secs(120);
+
+// An audio memo being recorded from the default microphone. Samples are collected by
+// the audio thread and written to a WAV file once the recording is finished.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct Recorder {
+    // Recording stops when the stream is dropped
+    _stream: cpal::Stream,
+    samples: Arc<Mutex<Vec<i16>>>,
+    // Set by the audio thread if the microphone stops working
+    failed: Arc<Mutex<Option<String>>>,
+    sample_rate: u32,
+    channels: u16,
+    started: Instant,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Recorder {
+    pub fn start() -> Result<Self, String> {
+        let device = cpal::default_host().default_input_device().ok_or("No microphone found")?;
+        let config = device.default_input_config().map_err(|e| e.to_string())?;
+        let samples = Arc::new(Mutex::new(Vec::new()));
+        let failed = Arc::new(Mutex::new(None));
+        let on_error = {
+            let failed = failed.clone();
+            move |e: cpal::StreamError| *failed.lock().unwrap() = Some(format!("Audio input failed: {}", e))
+        };
+        let stream = match config.sample_format() {
+            cpal::SampleFormat::F32 => {
+                let samples = samples.clone();
+                device.build_input_stream(
+                    &config.config(),
+                    move |data: &[f32], _: &_| {
+                        let converted = data.iter().map(|sample| (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16);
+                        samples.lock().unwrap().extend(converted);
+                    },
+                    on_error,
+                    None,
+                )
+            }
+            cpal::SampleFormat::I16 => {
+                let samples = samples.clone();
+                device.build_input_stream(
+                    &config.config(),
+                    move |data: &[i16], _: &_| samples.lock().unwrap().extend_from_slice(data),
+                    on_error,
+                    None,
+                )
+            }
+            cpal::SampleFormat::U16 => {
+                let samples = samples.clone();
+                device.build_input_stream(
+                    &config.config(),
+                    move |data: &[u16], _: &_| samples.lock().unwrap().extend(data.iter().map(|&sample| (sample as i32 - 32768) as i16)),
+                    on_error,
+                    None,
+                )
+            }
+            format => return Err(format!("Unsupported sample format {:?}", format)),
+        }
+        .map_err(|e| e.to_string())?;
+        stream.play().map_err(|e| e.to_string())?;
+        Ok(Self {
+            _stream: stream,
+            samples,
+            failed,
+            sample_rate: config.sample_rate().0,
+            channels: config.channels(),
+            started: Instant::now(),
+        })
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    // Why the recording broke off, if it did
+    pub fn error(&self) -> Option<String> {
+        self.failed.lock().unwrap().clone()
+    }
+
+    // Stop recording and save the memo to the attachments folder
+    pub fn finish(self) -> Result<Attachment, String> {
+        let folder = attachment::folder().ok_or("No data directory to save memos to")?;
+        std::fs::create_dir_all(&folder).map_err(|e| e.to_string())?;
+        let name = Local::now().format("Voice memo %Y-%m-%d %H%M%S.wav").to_string();
+        let path = attachment::free_name(&folder, name.into());
+        let spec = hound::WavSpec {
+            channels: self.channels,
+            sample_rate: self.sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).map_err(|e| e.to_string())?;
+        for &sample in self.samples.lock().unwrap().iter() {
+            writer.write_sample(sample).map_err(|e| e.to_string())?;
+        }
+        writer.finalize().map_err(|e| e.to_string())?;
+        Ok(Attachment { path, copied: true })
+    }
+}
+
+// Play a memo on its own thread, the output stream has to stay open until it ends.
+// Fails if playback couldn't start.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn play(path: &Path) -> Result<(), String> {
+    let path = path.to_path_buf();
+    let (started_sender, started) = mpsc::channel();
+    std::thread::spawn(move || {
+        let Ok((_stream, handle)) = rodio::OutputStream::try_default() else {
+            let _ = started_sender.send(Err(format!("No audio output to play {}", path.display())));
+            return;
+        };
+        let played = std::fs::File::open(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|file| rodio::Decoder::new(std::io::BufReader::new(file)).map_err(|e| e.to_string()))
+            .and_then(|source| rodio::Sink::try_new(&handle).map(|sink| (sink, source)).map_err(|e| e.to_string()));
+        match played {
+            Ok((sink, source)) => {
+                let _ = started_sender.send(Ok(()));
+                sink.append(source);
+                sink.sleep_until_end();
+            }
+            Err(e) => {
+                let _ = started_sender.send(Err(format!("Failed to play {}: {}", path.display(), e)));
+            }
+        }
+    });
+    started.recv().unwrap_or(Ok(()))
+}
+
+// No microphone or speakers in the web build yet
+#[cfg(target_arch = "wasm32")]
+pub struct Recorder;
+
+#[cfg(target_arch = "wasm32")]
+impl Recorder {
+    pub fn start() -> Result<Self, String> {
+        Err("Recording needs the desktop version".to_string())
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        Duration::ZERO
+    }
+
+    pub fn error(&self) -> Option<String> {
+        None
+    }
+
+    pub fn finish(self) -> Result<Attachment, String> {
+        Err("Recording needs the desktop version".to_string())
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn play(_path: &Path) -> Result<(), String> {
+    Ok(())
+}
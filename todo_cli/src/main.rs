@@ -0,0 +1,172 @@
+use chrono::{Local, Utc};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use todo_core::settings::Settings;
+use todo_core::{load_lists, quick_add, save_lists, TodoItem, TodoList, TrashedItem};
+
+const USAGE: &str = "Usage: todo [--file <path>] [--list <name>] <command>
+
+Commands:
+  add <description>   Add a task, e.g. \"pay rent friday #home !high\"
+  list [--all]        Show open tasks, or every task with --all
+  done <id>           Mark a task as completed
+  remove <id>         Move a task to the trash
+
+Without --file the file last opened or saved in the GUI is used.
+Without --list the first list in the file is used.";
+
+enum Action {
+    Add(String),
+    List { all: bool },
+    Done(u32),
+    Remove(u32),
+}
+
+struct Args {
+    file: Option<PathBuf>,
+    list: Option<String>,
+    action: Action,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut file = None;
+    let mut list = None;
+    let mut rest = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--file" => file = Some(PathBuf::from(args.next().ok_or("--file needs a path")?)),
+            "--list" => list = Some(args.next().ok_or("--list needs a name")?),
+            _ => rest.push(arg),
+        }
+    }
+
+    let parse_id = |value: Option<&String>| -> Result<u32, String> {
+        let value = value.ok_or("Missing task ID")?;
+        value.parse().map_err(|_| format!("'{}' is not a task ID", value))
+    };
+
+    let action = match rest.first().map(String::as_str) {
+        Some("add") => {
+            let description = rest[1..].join(" ");
+            if description.trim().is_empty() {
+                return Err("Nothing to add".to_string());
+            }
+            Action::Add(description.trim().to_string())
+        }
+        Some("list") => Action::List { all: rest.iter().any(|arg| arg == "--all") },
+        Some("done") => Action::Done(parse_id(rest.get(1))?),
+        Some("remove") => Action::Remove(parse_id(rest.get(1))?),
+        Some(command) => return Err(format!("Unknown command '{}'", command)),
+        None => return Err("Missing command".to_string()),
+    };
+    Ok(Args { file, list, action })
+}
+
+// Print a task and its subtasks, indented by depth
+fn print_item(item: &TodoItem, depth: usize, all: bool) {
+    if item.completed && !all {
+        return;
+    }
+    let checkbox = if item.completed { "[x]" } else { "[ ]" };
+    let mut line = format!("{:>4} {}{} {}", item.id, "  ".repeat(depth), checkbox, item.description);
+    if let Some(due_date) = item.due_date {
+        line.push_str(&format!("  (due {})", due_date));
+    }
+    for tag in &item.tags {
+        line.push_str(&format!(" #{}", tag));
+    }
+    println!("{}", line);
+    for child in &item.children {
+        print_item(child, depth + 1, all);
+    }
+}
+
+// Find a task by ID anywhere in the list, including subtasks
+fn find_item(items: &mut [TodoItem], id: u32) -> Option<&mut TodoItem> {
+    for item in items {
+        if item.id == id {
+            return Some(item);
+        }
+        if let Some(found) = find_item(&mut item.children, id) {
+            return Some(found);
+        }
+    }
+    None
+}
+
+fn run(args: Args) -> Result<(), String> {
+    let path = args
+        .file
+        .or_else(|| Settings::load().last_file)
+        .ok_or("No save file yet. Save a list in the GUI first, or pass --file <path>.")?;
+
+    // A missing file starts a new save, anything else that fails to load is an error
+    let mut lists = if path.exists() {
+        load_lists(&path).map_err(|e| format!("Could not read {}: {}", path.display(), e))?
+    } else {
+        vec![TodoList::new("Todo".to_string())]
+    };
+
+    let list_index = match &args.list {
+        Some(name) => lists
+            .iter()
+            .position(|list| list.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("No list named '{}'", name))?,
+        None => 0,
+    };
+    let list = &mut lists[list_index];
+    let today = Local::now().date_naive();
+
+    match args.action {
+        Action::List { all } => {
+            println!("{}", list.name);
+            for item in &list.items {
+                print_item(item, 0, all);
+            }
+            return Ok(());
+        }
+        Action::Add(description) => {
+            let item = quick_add::parse(&description, today).into_item();
+            println!("Added {}: {}", item.id, item.description);
+            list.items.push(item);
+        }
+        Action::Done(id) => {
+            let item = find_item(&mut list.items, id).ok_or_else(|| format!("No task with ID {}", id))?;
+            let before = item.clone();
+            item.completed = true;
+            println!("Completed {}: {}", item.id, item.description);
+            item.reschedule_if_recurring(today);
+            item.stamp_completion(Utc::now());
+            item.log_changes(&before, Utc::now());
+        }
+        Action::Remove(id) => {
+            let index = list
+                .items
+                .iter()
+                .position(|item| item.id == id)
+                .ok_or_else(|| format!("No top level task with ID {}", id))?;
+            let item = list.items.remove(index);
+            println!("Moved {} to the trash: {}", item.id, item.description);
+            list.trash.push(TrashedItem::new(item, today));
+        }
+    }
+
+    save_lists(&path, &lists).map_err(|e| format!("Could not write {}: {}", path.display(), e))
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[cfg(not(target_arch = "wasm32"))]
+use directories::ProjectDirs;
+
+// A file attached to an item. Linked files stay where they are, copies live in the
+// attachments folder so they are kept even if the original is moved or deleted.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Attachment {
+    pub path: PathBuf,
+    #[serde(default)]
+    pub copied: bool,
+}
+
+impl Attachment {
+    pub fn link(path: PathBuf) -> Self {
+        Self { path, copied: false }
+    }
+
+    // Copy `path` into `folder`, numbering the name if a file of that name is there already
+    pub fn copy_into(path: &Path, folder: &Path) -> io::Result<Self> {
+        std::fs::create_dir_all(folder)?;
+        let target = free_name(folder, path.file_name().map(PathBuf::from).unwrap_or_else(|| "attachment".into()));
+        std::fs::copy(path, &target)?;
+        Ok(Self { path: target, copied: true })
+    }
+
+    // File name shown on the chip
+    pub fn name(&self) -> String {
+        self.path.file_name().map_or_else(|| self.path.display().to_string(), |name| name.to_string_lossy().into_owned())
+    }
+
+    // Pictures get a thumbnail in the detail panel
+    pub fn is_image(&self) -> bool {
+        let extension = self.path.extension().map(|extension| extension.to_string_lossy().to_lowercase());
+        matches!(extension.as_deref(), Some("png" | "jpg" | "jpeg" | "gif"))
+    }
+
+    // Voice memos and other sound files get a play button
+    pub fn is_audio(&self) -> bool {
+        let extension = self.path.extension().map(|extension| extension.to_string_lossy().to_lowercase());
+        matches!(extension.as_deref(), Some("wav" | "ogg" | "mp3" | "flac"))
+    }
+}
+
+// "notes.txt", then "notes (2).txt", "notes (3).txt" and so on
+pub fn free_name(folder: &Path, name: PathBuf) -> PathBuf {
+    let stem = name.file_stem().map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+    let extension = name.extension().map(|extension| extension.to_string_lossy().into_owned());
+    let mut candidate = folder.join(&name);
+    let mut number = 2;
+    while candidate.exists() {
+        let numbered = match &extension {
+            Some(extension) => format!("{} ({}).{}", stem, number, extension),
+            None => format!("{} ({})", stem, number),
+        };
+        candidate = folder.join(numbered);
+        number += 1;
+    }
+    candidate
+}
+
+// Copies of attached files, in the platform data directory, e.g. ~/.local/share/rust_todo_list/attachments
+#[cfg(not(target_arch = "wasm32"))]
+pub fn folder() -> Option<PathBuf> {
+    ProjectDirs::from("", "", "rust_todo_list").map(|dirs| dirs.data_dir().join("attachments"))
+}
+
+// The browser has no folder to copy to
+#[cfg(target_arch = "wasm32")]
+pub fn folder() -> Option<PathBuf> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copies_get_names_of_their_own() {
+        let folder = std::env::temp_dir().join(format!("rust_todo_list_attachments_{}", std::process::id()));
+        let source = std::env::temp_dir().join(format!("rust_todo_list_source_{}.txt", std::process::id()));
+        std::fs::write(&source, "hello").unwrap();
+
+        let first = Attachment::copy_into(&source, &folder).unwrap();
+        let second = Attachment::copy_into(&source, &folder).unwrap();
+        assert!(first.copied);
+        assert_ne!(first.path, second.path);
+        assert!(second.name().ends_with("(2).txt"));
+        assert_eq!(std::fs::read_to_string(&second.path).unwrap(), "hello");
+
+        std::fs::remove_dir_all(&folder).unwrap();
+        std::fs::remove_file(&source).unwrap();
+    }
+}
@@ -0,0 +1,216 @@
+#[cfg(target_arch = "wasm32")]
+use crate::browser::{self, BrowserStorage};
+#[cfg(target_arch = "wasm32")]
+use crate::storage::Storage;
+use crate::{encryption, save_lists, StorageError, TodoList};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{read_lists, write_lists};
+#[cfg(not(target_arch = "wasm32"))]
+use directories::ProjectDirs;
+use std::path::Path;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+use std::time::Duration;
+use web_time::Instant;
+
+// How long to wait after the last edit before writing to disk
+const DEBOUNCE: Duration = Duration::from_secs(2);
+
+// Tracks pending changes and writes them out once edits settle down
+#[derive(Default)]
+pub struct Autosave {
+    // Time of the most recent change that hasn't been written yet
+    last_edit: Option<Instant>,
+    // Changes that haven't reached a save file, the recovery file doesn't count
+    unsaved: bool,
+    // Counts changes, so a write that finishes later can tell whether it covered the latest ones
+    changes: u64,
+}
+
+impl Autosave {
+    pub fn mark_changed(&mut self) {
+        self.last_edit = Some(Instant::now());
+        self.unsaved = true;
+        self.changes += 1;
+    }
+
+    // The lists were just written to or read from a save file
+    pub fn mark_saved(&mut self) {
+        self.last_edit = None;
+        self.unsaved = false;
+    }
+
+    pub fn has_unsaved_changes(&self) -> bool {
+        self.unsaved
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.last_edit.is_some()
+    }
+
+    // Time left until the debounce expires, None if nothing is pending
+    pub fn time_until_due(&self) -> Option<Duration> {
+        self.last_edit.map(|last_edit| DEBOUNCE.saturating_sub(last_edit.elapsed()))
+    }
+
+    // Write the lists to the last used file, falling back to the recovery file.
+    // Returns why the last used file couldn't be written, if it couldn't.
+    pub fn flush(&mut self, lists: &[TodoList], last_file: Option<&Path>) -> Result<(), StorageError> {
+        let started = self.start_flush();
+        self.finish_flush(started, write(lists, last_file))
+    }
+
+    // For writes done elsewhere, e.g. on another thread: the pending changes count as
+    // being written. Returns what to hand to `finish_flush` along with the outcome.
+    pub fn start_flush(&mut self) -> u64 {
+        self.last_edit = None;
+        self.changes
+    }
+
+    // Take the outcome of a write begun with `start_flush`. Changes made in the meantime
+    // stay unsaved.
+    pub fn finish_flush(&mut self, started: u64, written: Result<bool, StorageError>) -> Result<(), StorageError> {
+        if matches!(written, Ok(true)) && started == self.changes {
+            self.unsaved = false;
+        }
+        written.map(|_| ())
+    }
+}
+
+// Write the lists to the last used file, falling back to the recovery file. Returns whether
+// they reached a save file, or why the last used file couldn't be written.
+pub fn write(lists: &[TodoList], last_file: Option<&Path>) -> Result<bool, StorageError> {
+    let mut result = Ok(false);
+    if let Some(path) = last_file {
+        match save_lists(path, lists) {
+            Ok(()) => {
+                clear_recovery();
+                return Ok(true);
+            }
+            Err(e) => result = Err(e),
+        }
+    }
+    // There are no files in the browser, its storage is where the lists are saved
+    #[cfg(target_arch = "wasm32")]
+    if browser_lists().save(lists).is_ok() {
+        return result.map(|_| true);
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(path) = recovery_path().filter(|_| keeps_recovery(last_file)) {
+        if let Some(dir) = path.parent() {
+            if let Err(e) = std::fs::create_dir_all(dir) {
+                eprintln!("Failed to create recovery directory: {:?}", e);
+                return result;
+            }
+        }
+        write_lists(&path, lists);
+    }
+    result
+}
+
+// Whether a failed write of this file falls back to the recovery file. Encrypted files don't,
+// the recovery file would hold their lists in plain text.
+pub fn keeps_recovery(last_file: Option<&Path>) -> bool {
+    !last_file.is_some_and(|path| encryption::passphrase_for(path).is_some() || encryption::is_encrypted(path))
+}
+
+// Local storage key of the lists in the web build
+#[cfg(target_arch = "wasm32")]
+const BROWSER_KEY: &str = "rust_todo_list.lists";
+
+#[cfg(target_arch = "wasm32")]
+fn browser_lists() -> BrowserStorage {
+    BrowserStorage { key: BROWSER_KEY.to_string() }
+}
+
+// Recovery file in the platform data directory, e.g. ~/.local/share/rust_todo_list
+#[cfg(not(target_arch = "wasm32"))]
+fn recovery_path() -> Option<PathBuf> {
+    ProjectDirs::from("", "", "rust_todo_list").map(|dirs| dirs.data_dir().join("recovery.json"))
+}
+
+// Lists left behind by a session that never reached its last used file
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load_recovery() -> Option<Vec<TodoList>> {
+    let path = recovery_path()?;
+    if path.exists() {
+        read_lists(&path)
+    } else {
+        None
+    }
+}
+
+// In the web build these are simply the saved lists
+#[cfg(target_arch = "wasm32")]
+pub fn load_recovery() -> Option<Vec<TodoList>> {
+    match browser_lists().load() {
+        Ok(lists) => Some(lists),
+        // Nothing was saved yet
+        Err(StorageError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => {
+            eprintln!("Failed to read the lists from local storage: {}", e);
+            None
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn clear_recovery() {
+    if let Some(path) = recovery_path() {
+        if path.exists() {
+            if let Err(e) = std::fs::remove_file(path) {
+                eprintln!("Failed to remove recovery file: {:?}", e);
+            }
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn clear_recovery() {
+    browser::remove(BROWSER_KEY);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_finished_write_saves_the_changes_it_covered() {
+        let mut autosave = Autosave::default();
+        autosave.mark_changed();
+        let started = autosave.start_flush();
+        assert!(!autosave.is_pending());
+        assert!(autosave.finish_flush(started, Ok(true)).is_ok());
+        assert!(!autosave.has_unsaved_changes());
+    }
+
+    #[test]
+    fn changes_made_during_a_write_stay_unsaved() {
+        let mut autosave = Autosave::default();
+        autosave.mark_changed();
+        let started = autosave.start_flush();
+        autosave.mark_changed();
+        assert!(autosave.finish_flush(started, Ok(true)).is_ok());
+        assert!(autosave.has_unsaved_changes());
+        assert!(autosave.is_pending());
+    }
+
+    #[test]
+    fn encrypted_files_are_not_recovered_in_plain_text() {
+        let path = std::env::temp_dir().join(format!("rust_todo_list_{}_recovery.json", std::process::id()));
+        assert!(keeps_recovery(None));
+        assert!(keeps_recovery(Some(&path)));
+        encryption::remember_passphrase(&path, "correct horse");
+        assert!(!keeps_recovery(Some(&path)));
+        encryption::forget_passphrase(&path);
+    }
+
+    #[test]
+    fn the_recovery_file_does_not_count_as_saved() {
+        let mut autosave = Autosave::default();
+        autosave.mark_changed();
+        let started = autosave.start_flush();
+        assert!(autosave.finish_flush(started, Ok(false)).is_ok());
+        assert!(autosave.has_unsaved_changes());
+    }
+}
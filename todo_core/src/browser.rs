@@ -0,0 +1,55 @@
+use crate::storage::{self, SaveDataRef, Storage, StorageError};
+use crate::TodoList;
+use std::io;
+
+// The page's local storage, which stands in for the config and data directories in the web build.
+// None if the browser blocks it, e.g. for a file:// page or with cookies disabled.
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+pub fn read(key: &str) -> Option<String> {
+    local_storage()?.get_item(key).ok()?
+}
+
+// Returns false and logs on failure, e.g. when the storage quota is used up
+pub fn write(key: &str, value: &str) -> bool {
+    let Some(storage) = local_storage() else {
+        eprintln!("Local storage is not available.");
+        return false;
+    };
+    match storage.set_item(key, value) {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("Failed to write {} to local storage: {:?}", key, e);
+            false
+        }
+    }
+}
+
+pub fn remove(key: &str) {
+    if let Some(storage) = local_storage() {
+        let _ = storage.remove_item(key);
+    }
+}
+
+// Lists saved as JSON under a local storage key, what a save file is to the desktop app
+pub struct BrowserStorage {
+    pub key: String,
+}
+
+impl Storage for BrowserStorage {
+    fn load(&self) -> Result<Vec<TodoList>, StorageError> {
+        let json = read(&self.key).ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+        Ok(storage::prepare(storage::parse_lists(json.as_bytes())?))
+    }
+
+    fn save(&mut self, lists: &[TodoList]) -> Result<(), StorageError> {
+        let json = serde_json::to_string(&SaveDataRef { lists })?;
+        if write(&self.key, &json) {
+            Ok(())
+        } else {
+            Err(io::Error::other("Local storage is not available or full").into())
+        }
+    }
+}
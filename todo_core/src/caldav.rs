@@ -0,0 +1,291 @@
+use crate::ical::{self, VTodo};
+use crate::{TodoItem, TodoList, TrashedItem};
+use base64::Engine;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+
+// Asks the server for every VTODO in the calendar collection
+const CALENDAR_QUERY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<c:calendar-query xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop><d:getetag/><c:calendar-data/></d:prop>
+  <c:filter><c:comp-filter name="VCALENDAR"><c:comp-filter name="VTODO"/></c:comp-filter></c:filter>
+</c:calendar-query>"#;
+
+// Connection details of the calendar a list is synced with
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct CaldavAccount {
+    pub enabled: bool,
+    // Address of the calendar collection, e.g. https://cloud.example.com/remote.php/dav/calendars/me/tasks/
+    pub url: String,
+    pub username: String,
+    pub password: String,
+    pub interval_minutes: u32,
+    // Name of the list kept in sync, the first list if empty or not found
+    pub list: String,
+}
+
+impl Default for CaldavAccount {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: String::new(),
+            username: String::new(),
+            password: String::new(),
+            interval_minutes: 15,
+            list: String::new(),
+        }
+    }
+}
+
+impl CaldavAccount {
+    pub fn is_configured(&self) -> bool {
+        self.enabled && !self.url.trim().is_empty()
+    }
+
+    // Index of the synced list in `lists`
+    pub fn list_index(&self, lists: &[TodoList]) -> usize {
+        lists.iter().position(|list| list.name == self.list).unwrap_or(0)
+    }
+
+    fn authorization(&self) -> String {
+        let credentials = format!("{}:{}", self.username, self.password);
+        format!("Basic {}", base64::engine::general_purpose::STANDARD.encode(credentials))
+    }
+
+    // Basic auth over plain HTTP hands the password to anyone on the way, so such addresses are refused
+    pub fn sends_password_in_cleartext(&self) -> bool {
+        self.url.trim().starts_with("http://") && !self.password.is_empty()
+    }
+
+    // Server hrefs are usually absolute paths, so they are resolved against the host of the URL.
+    // Full URLs are only followed to the same scheme and host, since the password goes along.
+    fn resolve(&self, href: &str) -> Option<String> {
+        let url = self.url.trim();
+        if href.starts_with("http://") || href.starts_with("https://") {
+            return origin(href).eq_ignore_ascii_case(origin(url)).then(|| href.to_string());
+        }
+        Some(format!("{}{}", origin(url).trim_end_matches('/'), href))
+    }
+
+    // Where a newly uploaded item is stored
+    fn item_url(&self, uid: &str) -> String {
+        format!("{}/{}.ics", self.url.trim().trim_end_matches('/'), uid)
+    }
+}
+
+// Scheme and host of a URL with the port if any, the whole URL if it has no path
+fn origin(url: &str) -> &str {
+    let host_end = url.find("://").and_then(|scheme_end| {
+        url[scheme_end + 3..].find('/').map(|path_start| scheme_end + 3 + path_start)
+    });
+    host_end.map_or(url, |host_end| &url[..host_end])
+}
+
+#[derive(Debug)]
+pub enum CaldavError {
+    Http(Box<ureq::Error>),
+    Io(std::io::Error),
+    // The URL is http:// and a password is set
+    Cleartext,
+}
+
+impl fmt::Display for CaldavError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CaldavError::Http(e) => write!(f, "{}", e),
+            CaldavError::Io(e) => write!(f, "{}", e),
+            CaldavError::Cleartext => write!(f, "The password would be sent unencrypted, use an https:// address"),
+        }
+    }
+}
+
+impl From<ureq::Error> for CaldavError {
+    fn from(e: ureq::Error) -> Self {
+        CaldavError::Http(Box::new(e))
+    }
+}
+
+impl From<std::io::Error> for CaldavError {
+    fn from(e: std::io::Error) -> Self {
+        CaldavError::Io(e)
+    }
+}
+
+// A VTODO on the server and the address it is stored at
+#[derive(Debug, Clone)]
+pub struct RemoteTodo {
+    pub href: String,
+    pub todo: VTodo,
+}
+
+// A request that brings the server up to date with the local list
+#[derive(Debug, Clone)]
+pub enum Change {
+    Put { url: String, body: String },
+    Delete { url: String },
+}
+
+// Download every VTODO in the calendar
+pub fn fetch(account: &CaldavAccount) -> Result<Vec<RemoteTodo>, CaldavError> {
+    if account.sends_password_in_cleartext() {
+        return Err(CaldavError::Cleartext);
+    }
+    let response = ureq::request("REPORT", account.url.trim())
+        .set("Authorization", &account.authorization())
+        .set("Depth", "1")
+        .set("Content-Type", "application/xml; charset=utf-8")
+        .send_string(CALENDAR_QUERY)?;
+    let body = response.into_string()?;
+
+    let mut remote = Vec::new();
+    for response in elements(&body, "response") {
+        let Some(href) = elements(response, "href").first().map(|href| unescape_xml(href.trim())) else {
+            continue;
+        };
+        for data in elements(response, "calendar-data") {
+            for todo in ical::parse_vtodos(&unescape_xml(data)) {
+                remote.push(RemoteTodo { href: href.clone(), todo });
+            }
+        }
+    }
+    Ok(remote)
+}
+
+// Send the changes worked out by `merge`, stopping at the first failure
+pub fn upload(account: &CaldavAccount, changes: &[Change]) -> Result<(), CaldavError> {
+    if account.sends_password_in_cleartext() {
+        return Err(CaldavError::Cleartext);
+    }
+    for change in changes {
+        match change {
+            Change::Put { url, body } => {
+                ureq::put(url)
+                    .set("Authorization", &account.authorization())
+                    .set("Content-Type", "text/calendar; charset=utf-8")
+                    .send_string(body)?;
+            }
+            Change::Delete { url } => {
+                ureq::delete(url).set("Authorization", &account.authorization()).call()?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Merge the server's VTODOs into the list, the newer side of each item wins.
+// Returns the requests needed to bring the server up to date in turn.
+pub fn merge(list: &mut TodoList, account: &CaldavAccount, remote: Vec<RemoteTodo>, today: NaiveDate) -> Vec<Change> {
+    let mut changes = Vec::new();
+    let mut on_server = HashSet::new();
+
+    for RemoteTodo { href, todo } in remote {
+        on_server.insert(todo.uid.clone());
+        if let Some(item) = list.items.iter_mut().find(|item| ical::uid(item) == todo.uid) {
+            let remote_is_newer = match (todo.last_modified, item.modified) {
+                (Some(remote), Some(local)) => remote > local,
+                (remote, local) => remote.is_some() && local.is_none(),
+            };
+            let local_is_newer = match (todo.last_modified, item.modified) {
+                (Some(remote), Some(local)) => local > remote,
+                (remote, local) => local.is_some() && remote.is_none(),
+            };
+            if remote_is_newer {
+                todo.apply_to(item);
+            } else if local_is_newer {
+                if let Some(url) = account.resolve(&href) {
+                    changes.push(Change::Put { url, body: ical::to_single_ical(item) });
+                }
+            }
+            item.sync_uid = Some(todo.uid);
+        } else if list.trash.iter().any(|trashed| ical::uid(&trashed.item) == todo.uid) {
+            // Deleted here since the last sync
+            if let Some(url) = account.resolve(&href) {
+                changes.push(Change::Delete { url });
+            }
+        } else {
+            let mut item = TodoItem::new(todo.summary.clone());
+            todo.apply_to(&mut item);
+            list.items.push(item);
+        }
+    }
+
+    // Items that were on the server before but are gone now were deleted there
+    let (deleted, kept): (Vec<TodoItem>, Vec<TodoItem>) = std::mem::take(&mut list.items)
+        .into_iter()
+        .partition(|item| item.sync_uid.as_ref().is_some_and(|uid| !on_server.contains(uid)));
+    list.items = kept;
+    list.trash.extend(deleted.into_iter().map(|item| TrashedItem::new(item, today)));
+
+    // New items are uploaded until they show up on the server
+    for item in list.items.iter().filter(|item| item.sync_uid.is_none()) {
+        changes.push(Change::Put { url: account.item_url(&ical::uid(item)), body: ical::to_single_ical(item) });
+    }
+    changes
+}
+
+// Contents of every element with this local name, whatever namespace prefix the server uses
+fn elements<'a>(xml: &'a str, name: &str) -> Vec<&'a str> {
+    let mut found = Vec::new();
+    let mut position = 0;
+    while let Some(offset) = xml[position..].find('<') {
+        let tag_start = position + offset + 1;
+        let Some(tag_length) = xml[tag_start..].find('>') else {
+            break;
+        };
+        let tag = &xml[tag_start..tag_start + tag_length];
+        position = tag_start + tag_length + 1;
+
+        let full_name = tag.split_whitespace().next().unwrap_or_default();
+        let local_name = full_name.rsplit(':').next().unwrap_or_default();
+        if tag.starts_with('/') || tag.ends_with('/') || local_name != name {
+            continue;
+        }
+        let closing = format!("</{}>", full_name);
+        if let Some(end) = xml[position..].find(&closing) {
+            found.push(&xml[position..position + end]);
+            position += end + closing.len();
+        }
+    }
+    found
+}
+
+fn unescape_xml(text: &str) -> String {
+    let text = text.trim();
+    if let Some(data) = text.strip_prefix("<![CDATA[").and_then(|data| data.strip_suffix("]]>")) {
+        return data.to_string();
+    }
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#13;", "\r")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(url: &str) -> CaldavAccount {
+        CaldavAccount { url: url.to_string(), password: "secret".to_string(), ..Default::default() }
+    }
+
+    #[test]
+    fn hrefs_are_resolved_on_the_calendar_host_only() {
+        let account = account("https://cloud.example.com/dav/calendars/me/tasks/");
+        assert_eq!(account.resolve("/dav/calendars/me/tasks/a.ics").as_deref(), Some("https://cloud.example.com/dav/calendars/me/tasks/a.ics"));
+        assert_eq!(account.resolve("https://cloud.example.com/dav/b.ics").as_deref(), Some("https://cloud.example.com/dav/b.ics"));
+        assert_eq!(account.resolve("https://evil.example.net/steal.ics"), None);
+        assert_eq!(account.resolve("http://cloud.example.com/dav/b.ics"), None);
+    }
+
+    #[test]
+    fn passwords_are_not_sent_over_plain_http() {
+        assert!(account("http://cloud.example.com/dav/").sends_password_in_cleartext());
+        assert!(!account("https://cloud.example.com/dav/").sends_password_in_cleartext());
+        assert!(matches!(fetch(&account("http://cloud.example.com/dav/")), Err(CaldavError::Cleartext)));
+    }
+}
@@ -0,0 +1,178 @@
+use crate::csv_io::parse_date;
+use crate::TodoItem;
+
+// Bullets that start a list item, followed by a space
+const BULLETS: [&str; 3] = ["- ", "* ", "+ "];
+
+// Width of leading whitespace, tabs count as four spaces
+fn indent_of(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum()
+}
+
+// The text of a list item without its bullet or number, None if the line isn't one
+fn strip_bullet(text: &str) -> Option<&str> {
+    if let Some(rest) = BULLETS.iter().find_map(|bullet| text.strip_prefix(bullet)) {
+        return Some(rest);
+    }
+    // Numbered items like `1.` or `2)`
+    let digits = text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let rest = &text[digits..];
+    (digits > 0).then(|| rest.strip_prefix(". ").or_else(|| rest.strip_prefix(") "))).flatten()
+}
+
+// Trailing `#tags` and `(due 2024-03-13)` as written by the Markdown export are taken off the
+// text and added to the item, the tracked time it writes is dropped. Returns the rest of the text.
+fn take_fields(text: &str, item: &mut TodoItem) -> String {
+    let mut words: Vec<&str> = text.split_whitespace().collect();
+    let mut tags = Vec::new();
+    loop {
+        match words.as_slice() {
+            [.., tag] if tag.len() > 1 && tag.starts_with('#') => {
+                tags.insert(0, &tag[1..]);
+                words.pop();
+            }
+            [.., "(due", date] if date.ends_with(')') && parse_date(date.trim_end_matches(')')).is_some() => {
+                item.due_date = parse_date(date.trim_end_matches(')'));
+                words.truncate(words.len() - 2);
+            }
+            [.., "(time", duration] if duration.ends_with(')') => words.truncate(words.len() - 2),
+            [.., "(time", hours, minutes] if hours.ends_with('h') && minutes.ends_with(')') => words.truncate(words.len() - 3),
+            _ => break,
+        }
+    }
+    tags.iter().for_each(|tag| item.add_tag(tag));
+    words.join(" ")
+}
+
+// An item from the text of one line, a leading `[ ]` or `[x]` sets whether it is done
+fn parse_item(text: &str) -> TodoItem {
+    let (completed, text) = match text.get(..3) {
+        Some("[x]" | "[X]") => (true, &text[3..]),
+        Some("[ ]") => (false, &text[3..]),
+        _ => (false, text),
+    };
+    let mut item = TodoItem::new(String::new());
+    item.completed = completed;
+    item.description = take_fields(text, &mut item);
+    item
+}
+
+// Markdown headings, `#` marks followed by a space
+fn is_heading(text: &str) -> bool {
+    text.starts_with('#') && text.trim_start_matches('#').starts_with(' ')
+}
+
+// Put `item` below the open items it is indented under, closing those it isn't
+fn close_deeper(open: &mut Vec<(usize, TodoItem)>, items: &mut Vec<TodoItem>, indent: usize) {
+    while open.last().is_some_and(|(open_indent, _)| *open_indent >= indent) {
+        let (_, item) = open.pop().expect("checked above");
+        match open.last_mut() {
+            Some((_, parent)) => parent.children.push(item),
+            None => items.push(item),
+        }
+    }
+}
+
+// Tasks from text with one task per line, e.g. pasted meeting notes or a Markdown checklist.
+// Bullets, numbers and `[ ]`/`[x]` boxes are optional and indented lines become subtasks of
+// the line above. In a Markdown list, `> ` lines become notes and other indented lines without
+// a bullet continue the item above. Blank lines and headings are skipped.
+pub fn parse(text: &str) -> Vec<TodoItem> {
+    let is_markdown_list = text.lines().any(|line| strip_bullet(line.trim_start()).is_some());
+    let mut items = Vec::new();
+    let mut open: Vec<(usize, TodoItem)> = Vec::new();
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || is_heading(trimmed) {
+            continue;
+        }
+        let indent = indent_of(line);
+        let bullet = strip_bullet(trimmed);
+        if let (true, None, Some((open_indent, item))) = (is_markdown_list, bullet, open.last_mut()) {
+            if indent > *open_indent {
+                match trimmed.strip_prefix('>') {
+                    Some(note) => {
+                        if !item.notes.is_empty() {
+                            item.notes.push('\n');
+                        }
+                        item.notes.push_str(note.strip_prefix(' ').unwrap_or(note));
+                    }
+                    None => {
+                        let text = take_fields(trimmed, item);
+                        if !text.is_empty() {
+                            item.description.push('\n');
+                            item.description.push_str(&text);
+                        }
+                    }
+                }
+                continue;
+            }
+        }
+        let item = parse_item(bullet.unwrap_or(trimmed).trim());
+        if item.description.is_empty() {
+            continue;
+        }
+        close_deeper(&mut open, &mut items, indent);
+        open.push((indent, item));
+    }
+    close_deeper(&mut open, &mut items, 0);
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export;
+    use crate::TodoList;
+    use chrono::NaiveDate;
+
+    fn descriptions(items: &[TodoItem]) -> Vec<&str> {
+        items.iter().map(|item| item.description.as_str()).collect()
+    }
+
+    #[test]
+    fn plain_lines_become_tasks() {
+        let items = parse("buy milk\n\n  call bank\nwater plants\n");
+        assert_eq!(descriptions(&items), ["buy milk", "water plants"]);
+        assert_eq!(descriptions(&items[0].children), ["call bank"]);
+    }
+
+    #[test]
+    fn checkboxes_and_bullets_are_read() {
+        let items = parse("# Meeting\n- [ ] send notes\n- [x] book room\n* ask Sam\n1. review budget\n");
+        assert_eq!(descriptions(&items), ["send notes", "book room", "ask Sam", "review budget"]);
+        let completed: Vec<bool> = items.iter().map(|item| item.completed).collect();
+        assert_eq!(completed, [false, true, false, false]);
+    }
+
+    #[test]
+    fn indentation_nests_subtasks() {
+        let items = parse("- [ ] trip\n  - [ ] book flights\n    - [x] compare prices\n  - [ ] pack\n- [ ] taxes\n");
+        assert_eq!(descriptions(&items), ["trip", "taxes"]);
+        assert_eq!(descriptions(&items[0].children), ["book flights", "pack"]);
+        assert_eq!(descriptions(&items[0].children[0].children), ["compare prices"]);
+        assert!(items[0].children[0].children[0].completed);
+    }
+
+    #[test]
+    fn the_markdown_export_reads_back() {
+        let mut item = TodoItem::new("report\nwith details".to_string());
+        item.add_tag("work");
+        item.due_date = NaiveDate::from_ymd_opt(2024, 3, 13);
+        item.notes = "ask Sam first".to_string();
+        item.children.push(TodoItem { completed: true, ..TodoItem::new("draft".to_string()) });
+        let list = TodoList { items: vec![item.clone()], ..TodoList::new("Work".to_string()) };
+
+        let items = parse(&export::to_markdown(&list));
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].description, item.description);
+        assert_eq!(items[0].tags, item.tags);
+        assert_eq!(items[0].due_date, item.due_date);
+        assert_eq!(items[0].notes, item.notes);
+        assert_eq!(descriptions(&items[0].children), ["draft"]);
+        assert!(items[0].children[0].completed);
+    }
+}
@@ -0,0 +1,107 @@
+use crate::{TodoItem, TodoList};
+use chrono::{Local, NaiveDate};
+use std::path::Path;
+
+// Date layouts accepted when importing, tried in order
+const DATE_FORMATS: [&str; 3] = ["%Y-%m-%d", "%m/%d/%Y", "%d.%m.%Y"];
+
+// Which CSV column feeds each item field, by column index
+pub struct ColumnMapping {
+    pub description: usize,
+    pub completed: Option<usize>,
+    pub due_date: Option<usize>,
+    pub notes: Option<usize>,
+}
+
+// A CSV file that has been read and is waiting for the user to map its columns
+pub struct CsvImport {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    pub mapping: ColumnMapping,
+}
+
+impl CsvImport {
+    pub fn read(path: &Path) -> Result<Self, csv::Error> {
+        let mut reader = csv::ReaderBuilder::new().flexible(true).from_path(path)?;
+        let headers: Vec<String> = reader.headers()?.iter().map(str::to_string).collect();
+        let rows = reader
+            .records()
+            .map(|record| record.map(|record| record.iter().map(str::to_string).collect()))
+            .collect::<Result<Vec<Vec<String>>, _>>()?;
+
+        let mapping = ColumnMapping {
+            description: find_column(&headers, &["description", "task", "title", "name"]).unwrap_or(0),
+            completed: find_column(&headers, &["completed", "done", "status"]),
+            due_date: find_column(&headers, &["due_date", "due date", "due"]),
+            notes: find_column(&headers, &["notes", "note", "details"]),
+        };
+        Ok(Self { headers, rows, mapping })
+    }
+
+    // Build items from the rows using the current mapping, rows without a description are skipped
+    pub fn to_items(&self) -> Vec<TodoItem> {
+        let cell = |row: &Vec<String>, column: Option<usize>| -> String {
+            column.and_then(|column| row.get(column)).map_or(String::new(), |value| value.trim().to_string())
+        };
+
+        self.rows
+            .iter()
+            .filter_map(|row| {
+                let description = cell(row, Some(self.mapping.description));
+                if description.is_empty() {
+                    return None;
+                }
+                let mut item = TodoItem::new(description);
+                item.completed = parse_completed(&cell(row, self.mapping.completed));
+                item.due_date = parse_date(&cell(row, self.mapping.due_date));
+                item.notes = cell(row, self.mapping.notes);
+                Some(item)
+            })
+            .collect()
+    }
+}
+
+// Index of the first header matching one of the names, ignoring case
+fn find_column(headers: &[String], names: &[&str]) -> Option<usize> {
+    headers.iter().position(|header| names.iter().any(|name| header.trim().eq_ignore_ascii_case(name)))
+}
+
+fn parse_completed(value: &str) -> bool {
+    ["true", "yes", "y", "1", "x", "done", "completed"]
+        .iter()
+        .any(|truthy| value.eq_ignore_ascii_case(truthy))
+}
+
+pub(crate) fn parse_date(value: &str) -> Option<NaiveDate> {
+    DATE_FORMATS.iter().find_map(|format| NaiveDate::parse_from_str(value, format).ok())
+}
+
+// One row per top level item, tags are joined with semicolons. The list's custom fields
+// follow as one column each, named after the field.
+pub fn write_csv(path: &Path, list: &TodoList) -> Result<(), csv::Error> {
+    let mut writer = csv::Writer::from_path(path)?;
+    let columns = ["description", "completed", "due_date", "priority", "tags", "notes", "time_spent_seconds"];
+    writer.write_record(columns.into_iter().chain(list.fields.iter().map(|field| field.name.as_str())))?;
+    let now = Local::now();
+    for item in &list.items {
+        let due_date = item.due_date.map_or(String::new(), |date| date.format("%Y-%m-%d").to_string());
+        let tags = item.tags.join(";");
+        let time_spent = item.tracked_seconds(now).to_string();
+        let fields = list.fields.iter().map(|field| item.fields.get(&field.name).map_or(String::new(), |value| value.display()));
+        writer.write_record(
+            [
+                item.description.clone(),
+                if item.completed { "true" } else { "false" }.to_string(),
+                due_date,
+                item.priority.label().to_string(),
+                tags,
+                item.notes.clone(),
+                time_spent,
+            ]
+            .into_iter()
+            .chain(fields),
+        )?;
+    }
+    writer.flush()?;
+    Ok(())
+}
@@ -0,0 +1,234 @@
+use crate::{ArchivedItem, TodoItem, TodoList, TrashedItem};
+use chrono::NaiveDate;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashMap;
+use std::path::Path;
+
+// File extensions that are opened as SQLite databases instead of JSON
+const EXTENSIONS: [&str; 3] = ["db", "sqlite", "sqlite3"];
+
+// Items keep their full JSON in `data`, the other columns are copies for querying
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS lists (
+        position INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        icon TEXT,
+        fields TEXT,
+        deleted TEXT
+    );
+    CREATE TABLE IF NOT EXISTS items (
+        id INTEGER PRIMARY KEY,
+        list_position INTEGER NOT NULL,
+        position INTEGER NOT NULL,
+        description TEXT NOT NULL,
+        completed INTEGER NOT NULL,
+        due_date TEXT,
+        priority TEXT NOT NULL,
+        deleted_on TEXT,
+        archived_on TEXT,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS items_by_list ON items (list_position, position);
+";
+
+#[derive(Debug)]
+pub enum DatabaseError {
+    Sqlite(rusqlite::Error),
+    Json(serde_json::Error),
+}
+
+impl From<rusqlite::Error> for DatabaseError {
+    fn from(e: rusqlite::Error) -> Self {
+        DatabaseError::Sqlite(e)
+    }
+}
+
+impl From<serde_json::Error> for DatabaseError {
+    fn from(e: serde_json::Error) -> Self {
+        DatabaseError::Json(e)
+    }
+}
+
+pub fn is_database(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| EXTENSIONS.iter().any(|e| extension.eq_ignore_ascii_case(e)))
+}
+
+fn open(path: &Path) -> Result<Connection, DatabaseError> {
+    let connection = Connection::open(path)?;
+    connection.execute_batch(SCHEMA)?;
+    add_missing_column(&connection, "items", "archived_on", "TEXT")?;
+    add_missing_column(&connection, "lists", "icon", "TEXT")?;
+    add_missing_column(&connection, "lists", "fields", "TEXT")?;
+    add_missing_column(&connection, "lists", "deleted", "TEXT")?;
+    Ok(connection)
+}
+
+// Bring tables created by older versions up to date
+fn add_missing_column(connection: &Connection, table: &str, column: &str, column_type: &str) -> Result<(), DatabaseError> {
+    let exists = connection
+        .prepare("SELECT 1 FROM pragma_table_info(?1) WHERE name = ?2")?
+        .exists(params![table, column])?;
+    if !exists {
+        connection.execute_batch(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, column_type))?;
+    }
+    Ok(())
+}
+
+fn format_date(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+fn parse_date(date: Option<String>) -> Option<NaiveDate> {
+    date.and_then(|date| NaiveDate::parse_from_str(&date, "%Y-%m-%d").ok())
+}
+
+// Name, icon, custom fields and deleted items of a row of the lists table
+type ListRow = (String, Option<String>, Option<String>, Option<String>);
+
+// One row of the items table as it would be written
+#[derive(PartialEq)]
+struct ItemRow {
+    list_position: i64,
+    position: i64,
+    deleted_on: Option<String>,
+    archived_on: Option<String>,
+    data: String,
+}
+
+pub fn read(path: &Path) -> Result<Vec<TodoList>, DatabaseError> {
+    let connection = open(path)?;
+
+    let rows: Vec<ListRow> = connection
+        .prepare("SELECT name, icon, fields, deleted FROM lists ORDER BY position")?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))?
+        .collect::<Result<_, _>>()?;
+    // Custom fields and items deleted for good are kept as JSON like the items
+    let mut lists = Vec::with_capacity(rows.len());
+    for (name, icon, fields, deleted) in rows {
+        let fields = match fields {
+            Some(fields) => serde_json::from_str(&fields)?,
+            None => Vec::new(),
+        };
+        let deleted = match deleted {
+            Some(deleted) => serde_json::from_str(&deleted)?,
+            None => Vec::new(),
+        };
+        lists.push(TodoList { icon, fields, deleted, ..TodoList::new(name) });
+    }
+
+    let mut statement = connection
+        .prepare("SELECT list_position, deleted_on, archived_on, data FROM items ORDER BY list_position, position")?;
+    let rows = statement.query_map([], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, Option<String>>(1)?,
+            row.get::<_, Option<String>>(2)?,
+            row.get::<_, String>(3)?,
+        ))
+    })?;
+    for row in rows {
+        let (list_position, deleted_on, archived_on, data) = row?;
+        let item: TodoItem = serde_json::from_str(&data)?;
+        let Some(list) = usize::try_from(list_position).ok().and_then(|index| lists.get_mut(index)) else {
+            continue;
+        };
+        match (parse_date(deleted_on), parse_date(archived_on)) {
+            (Some(deleted_on), _) => list.trash.push(TrashedItem::new(item, deleted_on)),
+            (None, Some(archived_on)) => list.archive.push(ArchivedItem::new(item, archived_on)),
+            (None, None) => list.items.push(item),
+        }
+    }
+    Ok(lists)
+}
+
+// Only rows that changed since the last write are touched
+pub fn write(path: &Path, lists: &[TodoList]) -> Result<(), DatabaseError> {
+    let mut connection = open(path)?;
+    let transaction = connection.transaction()?;
+
+    transaction.execute("DELETE FROM lists", [])?;
+    for (position, list) in lists.iter().enumerate() {
+        let fields = serde_json::to_string(&list.fields)?;
+        let deleted = serde_json::to_string(&list.deleted)?;
+        transaction.execute(
+            "INSERT INTO lists (position, name, icon, fields, deleted) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![position as i64, list.name, list.icon, fields, deleted],
+        )?;
+    }
+
+    let mut stored: HashMap<u32, ItemRow> = HashMap::new();
+    {
+        let mut statement =
+            transaction.prepare("SELECT id, list_position, position, deleted_on, archived_on, data FROM items")?;
+        let rows = statement.query_map([], |row| {
+            Ok((
+                row.get::<_, u32>(0)?,
+                ItemRow {
+                    list_position: row.get(1)?,
+                    position: row.get(2)?,
+                    deleted_on: row.get(3)?,
+                    archived_on: row.get(4)?,
+                    data: row.get(5)?,
+                },
+            ))
+        })?;
+        for row in rows {
+            let (id, item_row) = row?;
+            stored.insert(id, item_row);
+        }
+    }
+
+    for (list_position, list) in lists.iter().enumerate() {
+        let trashed = list.trash.iter().map(|trashed| (&trashed.item, Some(trashed.deleted_on), None));
+        let archived = list.archive.iter().map(|archived| (&archived.item, None, Some(archived.archived_on)));
+        let entries = list.items.iter().map(|item| (item, None, None)).chain(trashed).chain(archived);
+        for (position, (item, deleted_on, archived_on)) in entries.enumerate() {
+            let row = ItemRow {
+                list_position: list_position as i64,
+                position: position as i64,
+                deleted_on: deleted_on.map(format_date),
+                archived_on: archived_on.map(format_date),
+                data: serde_json::to_string(item)?,
+            };
+            if stored.remove(&item.id).as_ref() == Some(&row) {
+                continue;
+            }
+            transaction.execute(
+                "INSERT OR REPLACE INTO items
+                    (id, list_position, position, description, completed, due_date, priority, deleted_on, archived_on, data)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![
+                    item.id,
+                    row.list_position,
+                    row.position,
+                    item.description,
+                    item.completed,
+                    item.due_date.map(format_date),
+                    item.priority.label(),
+                    row.deleted_on,
+                    row.archived_on,
+                    row.data,
+                ],
+            )?;
+        }
+    }
+
+    // Whatever is left was removed from every list, trash and archive
+    for id in stored.keys() {
+        transaction.execute("DELETE FROM items WHERE id = ?1", params![id])?;
+    }
+
+    transaction.commit()?;
+    Ok(())
+}
+
+// True if the database already holds data, used to avoid overwriting it during migration
+pub fn has_data(path: &Path) -> Result<bool, DatabaseError> {
+    let connection = open(path)?;
+    let found = connection
+        .query_row("SELECT 1 FROM lists LIMIT 1", [], |_| Ok(()))
+        .optional()?;
+    Ok(found.is_some())
+}
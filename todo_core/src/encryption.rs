@@ -0,0 +1,92 @@
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use argon2::Argon2;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+// Encrypted saves start with this header, followed by the salt, the nonce and the ciphertext
+const MAGIC: &[u8; 8] = b"TODOENC1";
+const SALT_LENGTH: usize = 16;
+const NONCE_LENGTH: usize = 12;
+
+#[derive(Debug)]
+pub enum EncryptionError {
+    KeyDerivation(argon2::Error),
+    // Wrong passphrase, or the file was changed or damaged
+    Cipher,
+    NotEncrypted,
+}
+
+// Passphrases entered this session, by file, so saves and autosaves stay encrypted
+fn passphrases() -> &'static Mutex<HashMap<PathBuf, String>> {
+    static PASSPHRASES: OnceLock<Mutex<HashMap<PathBuf, String>>> = OnceLock::new();
+    PASSPHRASES.get_or_init(Mutex::default)
+}
+
+pub fn remember_passphrase(path: &Path, passphrase: &str) {
+    if let Ok(mut passphrases) = passphrases().lock() {
+        passphrases.insert(path.to_path_buf(), passphrase.to_string());
+    }
+}
+
+pub fn forget_passphrase(path: &Path) {
+    if let Ok(mut passphrases) = passphrases().lock() {
+        passphrases.remove(path);
+    }
+}
+
+pub fn passphrase_for(path: &Path) -> Option<String> {
+    passphrases().lock().ok()?.get(path).cloned()
+}
+
+pub fn is_encrypted(path: &Path) -> bool {
+    let mut header = [0; MAGIC.len()];
+    std::fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .is_ok_and(|()| &header == MAGIC)
+}
+
+pub fn is_encrypted_data(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+// AES-256-GCM with a key derived from the passphrase by Argon2id
+fn cipher(passphrase: &str, salt: &[u8]) -> Result<Aes256Gcm, EncryptionError> {
+    let mut key = [0; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(EncryptionError::KeyDerivation)?;
+    Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)))
+}
+
+pub fn encrypt(plaintext: &[u8], passphrase: &str) -> Result<Vec<u8>, EncryptionError> {
+    let mut salt = [0; SALT_LENGTH];
+    let mut nonce = [0; NONCE_LENGTH];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+    let ciphertext = cipher(passphrase, &salt)?
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|_| EncryptionError::Cipher)?;
+
+    let mut data = Vec::with_capacity(MAGIC.len() + SALT_LENGTH + NONCE_LENGTH + ciphertext.len());
+    data.extend_from_slice(MAGIC);
+    data.extend_from_slice(&salt);
+    data.extend_from_slice(&nonce);
+    data.extend_from_slice(&ciphertext);
+    Ok(data)
+}
+
+pub fn decrypt(data: &[u8], passphrase: &str) -> Result<Vec<u8>, EncryptionError> {
+    let rest = data.strip_prefix(MAGIC.as_slice()).ok_or(EncryptionError::NotEncrypted)?;
+    if rest.len() < SALT_LENGTH + NONCE_LENGTH {
+        return Err(EncryptionError::Cipher);
+    }
+    let (salt, rest) = rest.split_at(SALT_LENGTH);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LENGTH);
+    cipher(passphrase, salt)?
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| EncryptionError::Cipher)
+}
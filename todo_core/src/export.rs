@@ -0,0 +1,82 @@
+use crate::tracking::format_duration;
+use crate::{TodoItem, TodoList};
+use chrono::Local;
+use std::fmt::Write;
+
+// Part of a list as shown, e.g. the tasks due today, for exports that keep the list view's grouping
+pub struct Section<'a> {
+    pub heading: Option<String>,
+    pub items: Vec<&'a TodoItem>,
+}
+
+// GitHub-style task list with the list name as the heading
+pub fn to_markdown(list: &TodoList) -> String {
+    let mut out = format!("# {}\n\n", list.name);
+    for item in &list.items {
+        write_markdown_item(&mut out, item, 0);
+    }
+    out
+}
+
+// Items as a Markdown task list without a heading, e.g. for the clipboard
+pub fn items_to_markdown<'a>(items: impl IntoIterator<Item = &'a TodoItem>) -> String {
+    let mut out = String::new();
+    for item in items {
+        write_markdown_item(&mut out, item, 0);
+    }
+    out
+}
+
+// Items as plain text for emails and chat, one per line with subtasks indented below them
+pub fn items_to_text<'a>(items: impl IntoIterator<Item = &'a TodoItem>) -> String {
+    let mut out = String::new();
+    for item in items {
+        write_text_item(&mut out, item, 0);
+    }
+    out
+}
+
+fn write_text_item(out: &mut String, item: &TodoItem, depth: usize) {
+    let indent = "  ".repeat(depth);
+    let continuation = format!("\n{}  ", indent);
+    let _ = write!(out, "{}{}", indent, item.description.lines().collect::<Vec<_>>().join(&continuation));
+    if item.completed {
+        out.push_str(" (done)");
+    } else if let Some(due_date) = item.due_date {
+        let _ = write!(out, " (due {})", due_date);
+    }
+    out.push('\n');
+    for child in &item.children {
+        write_text_item(out, child, depth + 1);
+    }
+}
+
+fn write_markdown_item(out: &mut String, item: &TodoItem, depth: usize) {
+    let indent = "  ".repeat(depth);
+    let checkbox = if item.completed { "[x]" } else { "[ ]" };
+    // Continuation lines are indented to stay part of the same item
+    let continuation = format!("\n{}  ", indent);
+    let description = item.description.lines().collect::<Vec<_>>().join(&continuation);
+
+    let _ = write!(out, "{}- {} {}", indent, checkbox, description);
+    for tag in &item.tags {
+        let _ = write!(out, " #{}", tag);
+    }
+    if let Some(due_date) = item.due_date {
+        let _ = write!(out, " (due {})", due_date);
+    }
+    let tracked = item.tracked_seconds(Local::now());
+    if tracked > 0 {
+        let _ = write!(out, " (time {})", format_duration(tracked));
+    }
+    out.push('\n');
+
+    // Notes become a quote nested under the item
+    for line in item.notes.lines() {
+        let _ = writeln!(out, "{}  > {}", indent, line);
+    }
+
+    for child in &item.children {
+        write_markdown_item(out, child, depth + 1);
+    }
+}
@@ -0,0 +1,87 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+// What a custom field holds. Dropdowns pick one of their options.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub enum FieldKind {
+    #[default]
+    Text,
+    Number,
+    Date,
+    Dropdown(Vec<String>),
+}
+
+impl FieldKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            FieldKind::Text => "Text",
+            FieldKind::Number => "Number",
+            FieldKind::Date => "Date",
+            FieldKind::Dropdown(_) => "Dropdown",
+        }
+    }
+}
+
+// A field the user added to a list, e.g. "Story points" or "Customer". Items keep their
+// values by the field's name.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct FieldDef {
+    pub name: String,
+    #[serde(default)]
+    pub kind: FieldKind,
+}
+
+// The value of a custom field on one item, dropdown choices are kept as text
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    Text(String),
+    Number(f64),
+    Date(NaiveDate),
+}
+
+impl FieldValue {
+    // As written to CSV, dates as 2024-03-13
+    pub fn display(&self) -> String {
+        match self {
+            FieldValue::Text(text) => text.clone(),
+            FieldValue::Number(number) => number.to_string(),
+            FieldValue::Date(date) => date.format("%Y-%m-%d").to_string(),
+        }
+    }
+
+    // A value read back for a field of `kind`, None if it doesn't fit
+    pub fn parse(kind: &FieldKind, text: &str) -> Option<Self> {
+        let text = text.trim();
+        if text.is_empty() {
+            return None;
+        }
+        match kind {
+            FieldKind::Text => Some(FieldValue::Text(text.to_string())),
+            FieldKind::Number => text.parse().ok().map(FieldValue::Number),
+            FieldKind::Date => crate::csv_io::parse_date(text).map(FieldValue::Date),
+            FieldKind::Dropdown(options) => options.iter().find(|option| *option == text).map(|option| FieldValue::Text(option.clone())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_read_back_as_written() {
+        let sizes = FieldKind::Dropdown(vec!["S".to_string(), "M".to_string(), "L".to_string()]);
+        let date = NaiveDate::from_ymd_opt(2024, 3, 13).unwrap();
+        for (kind, value) in [
+            (FieldKind::Text, FieldValue::Text("Acme".to_string())),
+            (FieldKind::Number, FieldValue::Number(2.5)),
+            (FieldKind::Date, FieldValue::Date(date)),
+            (sizes.clone(), FieldValue::Text("M".to_string())),
+        ] {
+            assert_eq!(FieldValue::parse(&kind, &value.display()), Some(value));
+        }
+        assert_eq!(FieldValue::parse(&FieldKind::Number, "lots"), None);
+        assert_eq!(FieldValue::parse(&sizes, "XL"), None);
+        assert_eq!(FieldValue::parse(&FieldKind::Text, "  "), None);
+    }
+}
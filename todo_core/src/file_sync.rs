@@ -0,0 +1,150 @@
+use crate::storage;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+// A save file shared with other devices, either on a WebDAV server or in a folder
+// that a cloud client such as Dropbox keeps in sync
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct SharedFile {
+    pub enabled: bool,
+    // An http(s) URL of the file on a WebDAV server, or the path of the file in a synced folder
+    pub location: String,
+    // Only used for WebDAV
+    pub username: String,
+    pub password: String,
+    pub interval_minutes: u32,
+}
+
+impl Default for SharedFile {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            location: String::new(),
+            username: String::new(),
+            password: String::new(),
+            interval_minutes: 5,
+        }
+    }
+}
+
+impl SharedFile {
+    pub fn is_configured(&self) -> bool {
+        self.enabled && !self.location.trim().is_empty()
+    }
+
+    pub fn is_webdav(&self) -> bool {
+        let location = self.location.trim();
+        location.starts_with("http://") || location.starts_with("https://")
+    }
+
+    // Basic auth over plain HTTP hands the password to anyone on the way, so such addresses are refused
+    pub fn sends_password_in_cleartext(&self) -> bool {
+        self.location.trim().starts_with("http://") && !self.password.is_empty()
+    }
+
+    fn authorization(&self) -> String {
+        let credentials = format!("{}:{}", self.username, self.password);
+        format!("Basic {}", base64::engine::general_purpose::STANDARD.encode(credentials))
+    }
+}
+
+#[derive(Debug)]
+pub enum FileSyncError {
+    Http(Box<ureq::Error>),
+    Io(std::io::Error),
+    // Someone else wrote the file since it was fetched, so it has to be merged again
+    Changed,
+    // The address is http:// and a password is set
+    Cleartext,
+}
+
+impl fmt::Display for FileSyncError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FileSyncError::Http(e) => write!(f, "{}", e),
+            FileSyncError::Io(e) => write!(f, "{}", e),
+            FileSyncError::Changed => write!(f, "The file was changed by another device meanwhile"),
+            FileSyncError::Cleartext => write!(f, "The password would be sent unencrypted, use an https:// address"),
+        }
+    }
+}
+
+impl From<ureq::Error> for FileSyncError {
+    fn from(e: ureq::Error) -> Self {
+        FileSyncError::Http(Box::new(e))
+    }
+}
+
+impl From<std::io::Error> for FileSyncError {
+    fn from(e: std::io::Error) -> Self {
+        FileSyncError::Io(e)
+    }
+}
+
+// Contents of the shared file as fetched, along with what identifies this version of it:
+// the ETag on a WebDAV server, the modification time in a folder.
+// Both are None if the file doesn't exist yet.
+#[derive(Debug, Clone, Default)]
+pub struct Fetched {
+    pub json: Option<String>,
+    pub version: Option<String>,
+}
+
+pub fn fetch(shared: &SharedFile) -> Result<Fetched, FileSyncError> {
+    if !shared.is_webdav() {
+        let path = Path::new(shared.location.trim());
+        if !path.exists() {
+            return Ok(Fetched::default());
+        }
+        let json = std::fs::read_to_string(path)?;
+        return Ok(Fetched { json: Some(json), version: file_version(path)? });
+    }
+    if shared.sends_password_in_cleartext() {
+        return Err(FileSyncError::Cleartext);
+    }
+    match ureq::get(shared.location.trim()).set("Authorization", &shared.authorization()).call() {
+        Ok(response) => {
+            let version = response.header("ETag").map(str::to_string);
+            Ok(Fetched { json: Some(response.into_string()?), version })
+        }
+        Err(ureq::Error::Status(404, _)) => Ok(Fetched::default()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+// Replace the shared file with `json`, unless it changed since `version` was fetched
+pub fn upload(shared: &SharedFile, json: &str, version: Option<&str>) -> Result<(), FileSyncError> {
+    if !shared.is_webdav() {
+        let path = Path::new(shared.location.trim());
+        let current = if path.exists() { file_version(path)? } else { None };
+        if current.as_deref() != version {
+            return Err(FileSyncError::Changed);
+        }
+        return Ok(storage::write_atomically(path, json.as_bytes())?);
+    }
+    if shared.sends_password_in_cleartext() {
+        return Err(FileSyncError::Cleartext);
+    }
+    let request = ureq::put(shared.location.trim())
+        .set("Authorization", &shared.authorization())
+        .set("Content-Type", "application/json");
+    // The server refuses the write if the file was changed or created meanwhile
+    let request = match version {
+        Some(etag) => request.set("If-Match", etag),
+        None => request.set("If-None-Match", "*"),
+    };
+    match request.send_string(json) {
+        Ok(_) => Ok(()),
+        Err(ureq::Error::Status(412, _)) => Err(FileSyncError::Changed),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn file_version(path: &Path) -> std::io::Result<Option<String>> {
+    let modified = std::fs::metadata(path)?.modified()?;
+    Ok(modified.duration_since(UNIX_EPOCH).ok().map(|since| since.as_nanos().to_string()))
+}
@@ -0,0 +1,532 @@
+use crate::query::{self, Query, QueryCache};
+use crate::search::SearchIndex;
+use crate::{ColorLabel, Priority, TodoItem};
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+// How the list is ordered when displayed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Manual,
+    Created,
+    Oldest,
+    Alphabetical,
+    DueDate,
+    Priority,
+}
+
+impl SortOrder {
+    pub const ALL: [SortOrder; 6] = [
+        SortOrder::Manual,
+        SortOrder::Created,
+        SortOrder::Oldest,
+        SortOrder::Alphabetical,
+        SortOrder::DueDate,
+        SortOrder::Priority,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortOrder::Manual => "Manual",
+            SortOrder::Created => "Newest first",
+            SortOrder::Oldest => "Oldest first",
+            SortOrder::Alphabetical => "Alphabetical",
+            SortOrder::DueDate => "Due date",
+            SortOrder::Priority => "Priority",
+        }
+    }
+}
+
+// Smart view buckets for grouping items by when they are due
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DueBucket {
+    Overdue,
+    Today,
+    ThisWeek,
+    Later,
+    NoDueDate,
+}
+
+impl DueBucket {
+    pub const ALL: [DueBucket; 5] = [
+        DueBucket::Overdue,
+        DueBucket::Today,
+        DueBucket::ThisWeek,
+        DueBucket::Later,
+        DueBucket::NoDueDate,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DueBucket::Overdue => "Overdue",
+            DueBucket::Today => "Today",
+            DueBucket::ThisWeek => "This Week",
+            DueBucket::Later => "Later",
+            DueBucket::NoDueDate => "No Due Date",
+        }
+    }
+
+    // Which bucket an item falls into, weeks end on Sunday
+    pub fn of(item: &TodoItem, today: NaiveDate) -> DueBucket {
+        let Some(due) = item.due_date else {
+            return DueBucket::NoDueDate;
+        };
+        let days_left_in_week = 6 - today.weekday().num_days_from_monday() as i64;
+        let days_until_due = (due - today).num_days();
+        if item.is_overdue(today) {
+            DueBucket::Overdue
+        } else if days_until_due <= 0 {
+            // Completed items due in the past are shown with today's
+            DueBucket::Today
+        } else if days_until_due <= days_left_in_week {
+            DueBucket::ThisWeek
+        } else {
+            DueBucket::Later
+        }
+    }
+}
+
+// Whether items are done, for filtering by status
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Completion {
+    Open,
+    Done,
+}
+
+impl Completion {
+    pub const ALL: [Completion; 2] = [Completion::Open, Completion::Done];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Completion::Open => "Not done",
+            Completion::Done => "Done",
+        }
+    }
+
+    fn matches(self, item: &TodoItem) -> bool {
+        item.completed == (self == Completion::Done)
+    }
+}
+
+// Narrows down which items of a list are shown
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    // Tags selected in the filter chips, only items with all of them are shown
+    pub active_tags: BTreeSet<String>,
+    // Text typed into the search box, matched against descriptions, or a query like `tag:work !done`
+    pub search: String,
+    // Smart view picked in the view menu, None shows every item
+    pub due: Option<DueBucket>,
+    // Only items with this color label, None shows every item
+    pub color: Option<ColorLabel>,
+    // Also show items whose start date hasn't come yet
+    pub show_deferred: bool,
+    // Only items assigned to this person, None shows every item
+    pub assignee: Option<String>,
+    // Only items of at least this priority, None shows every item
+    pub priority: Option<Priority>,
+    // Only open or only done items, None shows both
+    pub completion: Option<Completion>,
+    pub index: SearchIndex,
+    pub query: QueryCache,
+}
+
+impl Filter {
+    pub fn matches(&self, item: &TodoItem, today: NaiveDate) -> bool {
+        self.active_tags.iter().all(|tag| item.tags.contains(tag))
+            && self.query.get(&self.search).matches(item, &self.index, today)
+            && self.due.is_none_or(|bucket| DueBucket::of(item, today) == bucket)
+            && self.color.is_none_or(|color| item.color == Some(color))
+            && (self.show_deferred || !item.is_deferred(today))
+            && self.assignee.as_ref().is_none_or(|assignee| item.assignee.as_ref() == Some(assignee))
+            && self.priority.is_none_or(|priority| item.priority >= priority)
+            && self.completion.is_none_or(|completion| completion.matches(item))
+    }
+}
+
+// A named combination of search text, tags, priority and status, shown in the sidebar.
+// The items it shows are worked out again every time, so it follows along as they change.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct SmartList {
+    pub name: String,
+    pub search: String,
+    pub tags: BTreeSet<String>,
+    pub priority: Option<Priority>,
+    pub completion: Option<Completion>,
+}
+
+impl SmartList {
+    // The parts of `filter` a smart list keeps
+    pub fn from_filter(name: String, filter: &Filter) -> Self {
+        Self {
+            name,
+            search: filter.search.clone(),
+            tags: filter.active_tags.clone(),
+            priority: filter.priority,
+            completion: filter.completion,
+        }
+    }
+
+    // Due date, color and assignee filters are left as they are
+    pub fn apply(&self, filter: &mut Filter) {
+        filter.search = self.search.clone();
+        filter.active_tags = self.tags.clone();
+        filter.priority = self.priority;
+        filter.completion = self.completion;
+    }
+
+    pub fn is_applied(&self, filter: &Filter) -> bool {
+        filter.search == self.search
+            && filter.active_tags == self.tags
+            && filter.priority == self.priority
+            && filter.completion == self.completion
+    }
+
+    // The search text as a query, parsed once for matching many items
+    pub fn query(&self) -> Query {
+        query::parse_search(&self.search).unwrap_or_else(|_| Query::Text(self.search.clone()))
+    }
+
+    // `query` is from `query()`, and `index` the one of the filter in use so counting
+    // matches doesn't redo its entries
+    pub fn matches(&self, item: &TodoItem, query: &Query, index: &SearchIndex, today: NaiveDate) -> bool {
+        self.tags.iter().all(|tag| item.tags.contains(tag))
+            && query.matches(item, index, today)
+            && self.priority.is_none_or(|priority| item.priority >= priority)
+            && self.completion.is_none_or(|completion| completion.matches(item))
+    }
+}
+
+// Every context used in the items, sorted and without duplicates
+pub fn all_contexts<'a>(items: impl IntoIterator<Item = &'a TodoItem>) -> BTreeSet<String> {
+    items.into_iter().filter_map(|item| item.context.clone()).collect()
+}
+
+// Everyone items are assigned to, sorted and without duplicates
+pub fn all_assignees<'a>(items: impl IntoIterator<Item = &'a TodoItem>) -> BTreeSet<String> {
+    items.into_iter().filter_map(|item| item.assignee.clone()).collect()
+}
+
+// Every tag used in the items, sorted and without duplicates
+pub fn all_tags(items: &[TodoItem]) -> BTreeSet<String> {
+    items.iter().flat_map(|item| item.tags.iter().cloned()).collect()
+}
+
+// Indices into `items` in the order they should be displayed
+pub fn display_order(items: &[TodoItem], filter: &Filter, sort_order: SortOrder, today: NaiveDate) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..items.len())
+        .filter(|&index| filter.matches(&items[index], today))
+        .collect();
+    // Only the returned indices are sorted, the items keep their manual order.
+    // Stable sorts keep the manual order among items with equal keys.
+    match sort_order {
+        SortOrder::Manual => {}
+        // Items from saves without creation times count as oldest, in the order of their IDs,
+        // which are handed out in increasing order
+        SortOrder::Created => order.sort_by_key(|&index| std::cmp::Reverse((items[index].created_at, items[index].id))),
+        SortOrder::Oldest => order.sort_by_key(|&index| (items[index].created_at, items[index].id)),
+        SortOrder::Alphabetical => order.sort_by_cached_key(|&index| items[index].description.to_lowercase()),
+        // Items without a due date go last
+        SortOrder::DueDate => order.sort_by_key(|&index| (items[index].due_date.is_none(), items[index].due_date)),
+        SortOrder::Priority => order.sort_by_key(|&index| std::cmp::Reverse(items[index].priority)),
+    }
+    order
+}
+
+// How the open items of the list view are split under headings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Grouping {
+    #[default]
+    None,
+    DueDate,
+    Context,
+}
+
+impl Grouping {
+    pub const ALL: [Grouping; 3] = [Grouping::None, Grouping::DueDate, Grouping::Context];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Grouping::None => "Nothing",
+            Grouping::DueDate => "Due date",
+            Grouping::Context => "Context",
+        }
+    }
+}
+
+// Heading of a group of rows
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Group {
+    Due(DueBucket),
+    // Items without a context are grouped under None
+    Context(Option<String>),
+}
+
+impl Group {
+    fn contains(&self, item: &TodoItem, today: NaiveDate) -> bool {
+        match self {
+            Group::Due(bucket) => DueBucket::of(item, today) == *bucket,
+            Group::Context(context) => item.context == *context,
+        }
+    }
+}
+
+// The rows of the list view in the order from `display_order`: open pinned items at the top,
+// other open items grouped if asked for, and completed items at the bottom
+#[derive(Debug, Clone, Default)]
+pub struct Sections {
+    pub pinned: Vec<usize>,
+    // A single group without a heading when not grouped
+    pub groups: Vec<(Option<Group>, Vec<usize>)>,
+    pub completed: Vec<usize>,
+}
+
+impl Sections {
+    // Buckets are worked out from `today` so items move along as days pass.
+    // Contexts are sorted by name, items without one come last.
+    pub fn new(items: &[TodoItem], order: Vec<usize>, grouping: Grouping, today: NaiveDate) -> Self {
+        let (active, completed): (Vec<usize>, Vec<usize>) = order.into_iter().partition(|&index| !items[index].completed);
+        let (pinned, active): (Vec<usize>, Vec<usize>) = active.into_iter().partition(|&index| items[index].pinned);
+        let headings: Vec<Group> = match grouping {
+            Grouping::None => return Self { pinned, groups: vec![(None, active)], completed },
+            Grouping::DueDate => DueBucket::ALL.into_iter().map(Group::Due).collect(),
+            Grouping::Context => {
+                let contexts: BTreeSet<&String> = active.iter().filter_map(|&index| items[index].context.as_ref()).collect();
+                contexts.into_iter().map(|context| Group::Context(Some(context.clone()))).chain([Group::Context(None)]).collect()
+            }
+        };
+        let groups = headings
+            .into_iter()
+            .map(|group| {
+                let rows: Vec<usize> = active.iter().copied().filter(|&index| group.contains(&items[index], today)).collect();
+                (Some(group), rows)
+            })
+            .filter(|(_, rows)| !rows.is_empty())
+            .collect();
+        Self { pinned, groups, completed }
+    }
+
+    // Every row from top to bottom
+    pub fn rows(&self) -> impl Iterator<Item = usize> + '_ {
+        self.pinned.iter().chain(self.groups.iter().flat_map(|(_, rows)| rows)).chain(&self.completed).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Priority;
+
+    // A Wednesday
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, 13).unwrap()
+    }
+
+    fn item(description: &str) -> TodoItem {
+        TodoItem::new(description.to_string())
+    }
+
+    fn due(description: &str, day: u32) -> TodoItem {
+        TodoItem { due_date: NaiveDate::from_ymd_opt(2024, 3, day), ..item(description) }
+    }
+
+    fn shown(items: &[TodoItem], filter: &Filter, sort_order: SortOrder) -> Vec<String> {
+        display_order(items, filter, sort_order, today()).into_iter().map(|index| items[index].description.clone()).collect()
+    }
+
+    #[test]
+    fn buckets_follow_the_due_date() {
+        assert_eq!(DueBucket::of(&due("a", 12), today()), DueBucket::Overdue);
+        assert_eq!(DueBucket::of(&due("a", 13), today()), DueBucket::Today);
+        // The week ends on Sunday the 17th
+        assert_eq!(DueBucket::of(&due("a", 17), today()), DueBucket::ThisWeek);
+        assert_eq!(DueBucket::of(&due("a", 18), today()), DueBucket::Later);
+        assert_eq!(DueBucket::of(&item("a"), today()), DueBucket::NoDueDate);
+    }
+
+    #[test]
+    fn completed_items_are_never_overdue() {
+        let done = TodoItem { completed: true, ..due("a", 1) };
+        assert_eq!(DueBucket::of(&done, today()), DueBucket::Today);
+    }
+
+    #[test]
+    fn search_matches_descriptions_notes_and_subtasks() {
+        let with_note = TodoItem { notes: "ask about the Invoice".to_string(), ..item("call bank") };
+        let with_child = TodoItem { children: vec![item("send invoice")], ..item("taxes") };
+        let items = [item("buy milk"), with_note, with_child];
+        let filter = Filter { search: "invoice".to_string(), ..Filter::default() };
+        assert_eq!(shown(&items, &filter, SortOrder::Manual), ["call bank", "taxes"]);
+    }
+
+    #[test]
+    fn search_follows_edits() {
+        let mut items = [item("buy milk"), item("call bank")];
+        let filter = Filter { search: "Milk".to_string(), ..Filter::default() };
+        assert_eq!(shown(&items, &filter, SortOrder::Manual), ["buy milk"]);
+        items[1].description = "call bank about milk".to_string();
+        items[1].modified = Some(chrono::Utc::now() + chrono::Duration::seconds(1));
+        assert_eq!(shown(&items, &filter, SortOrder::Manual), ["buy milk", "call bank about milk"]);
+    }
+
+    #[test]
+    fn every_active_tag_must_be_present() {
+        let mut both = item("both");
+        both.add_tag("work");
+        both.add_tag("urgent");
+        let mut one = item("one");
+        one.add_tag("work");
+        let items = [both, one, item("none")];
+
+        let mut filter = Filter::default();
+        filter.active_tags.insert("work".to_string());
+        assert_eq!(shown(&items, &filter, SortOrder::Manual), ["both", "one"]);
+        filter.active_tags.insert("urgent".to_string());
+        assert_eq!(shown(&items, &filter, SortOrder::Manual), ["both"]);
+    }
+
+    #[test]
+    fn filters_combine() {
+        let red_today = TodoItem { color: Some(ColorLabel::Red), ..due("red today", 13) };
+        let red_later = TodoItem { color: Some(ColorLabel::Red), ..due("red later", 20) };
+        let blue_today = TodoItem { color: Some(ColorLabel::Blue), ..due("blue today", 13) };
+        let items = [red_today, red_later, blue_today];
+        let filter = Filter { color: Some(ColorLabel::Red), due: Some(DueBucket::Today), ..Filter::default() };
+        assert_eq!(shown(&items, &filter, SortOrder::Manual), ["red today"]);
+    }
+
+    #[test]
+    fn items_starting_later_are_hidden_until_revealed() {
+        let items = [
+            TodoItem { start_date: Some(today() + chrono::Duration::days(2)), ..item("later") },
+            TodoItem { start_date: Some(today()), ..item("started") },
+        ];
+        let mut filter = Filter::default();
+        assert_eq!(shown(&items, &filter, SortOrder::Manual), ["started"]);
+        filter.show_deferred = true;
+        assert_eq!(shown(&items, &filter, SortOrder::Manual), ["later", "started"]);
+    }
+
+    #[test]
+    fn assignee_filter_keeps_their_items() {
+        let items = [
+            TodoItem { assignee: Some("Sam".to_string()), ..item("dishes") },
+            item("bins"),
+            TodoItem { assignee: Some("Alex".to_string()), ..item("shopping") },
+        ];
+        let filter = Filter { assignee: Some("Sam".to_string()), ..Filter::default() };
+        assert_eq!(shown(&items, &filter, SortOrder::Manual), ["dishes"]);
+        assert_eq!(all_assignees(&items).into_iter().collect::<Vec<String>>(), ["Alex", "Sam"]);
+    }
+
+    #[test]
+    fn sorting_keeps_manual_order_for_ties() {
+        let items = [
+            TodoItem { priority: Priority::Low, ..due("b", 20) },
+            TodoItem { priority: Priority::High, ..item("a") },
+            TodoItem { priority: Priority::High, ..due("C", 14) },
+        ];
+        let filter = Filter::default();
+        assert_eq!(shown(&items, &filter, SortOrder::Manual), ["b", "a", "C"]);
+        assert_eq!(shown(&items, &filter, SortOrder::Created), ["C", "a", "b"]);
+        assert_eq!(shown(&items, &filter, SortOrder::Oldest), ["b", "a", "C"]);
+        assert_eq!(shown(&items, &filter, SortOrder::Alphabetical), ["a", "b", "C"]);
+        // Items without a due date go last
+        assert_eq!(shown(&items, &filter, SortOrder::DueDate), ["C", "b", "a"]);
+        assert_eq!(shown(&items, &filter, SortOrder::Priority), ["a", "C", "b"]);
+    }
+
+    #[test]
+    fn age_sorting_goes_by_creation_time() {
+        let now = chrono::Utc::now();
+        let items = [
+            TodoItem { created_at: None, ..item("unknown") },
+            TodoItem { created_at: Some(now), ..item("new") },
+            // Merged in from another save, so its ID is newer than its age
+            TodoItem { created_at: Some(now - chrono::Duration::days(3)), ..item("old") },
+        ];
+        let filter = Filter::default();
+        assert_eq!(shown(&items, &filter, SortOrder::Created), ["new", "old", "unknown"]);
+        assert_eq!(shown(&items, &filter, SortOrder::Oldest), ["unknown", "old", "new"]);
+    }
+
+    #[test]
+    fn all_tags_are_sorted_and_unique() {
+        let mut first = item("first");
+        first.add_tag("work");
+        first.add_tag("home");
+        let mut second = item("second");
+        second.add_tag("work");
+        let tags: Vec<String> = all_tags(&[first, second]).into_iter().collect();
+        assert_eq!(tags, ["home", "work"]);
+    }
+
+    #[test]
+    fn sections_split_pinned_groups_and_completed() {
+        let items = [
+            TodoItem { completed: true, ..due("done", 13) },
+            TodoItem { pinned: true, ..item("pinned") },
+            due("later", 30),
+            due("today", 13),
+            item("someday"),
+        ];
+        let order = (0..items.len()).collect();
+        let sections = Sections::new(&items, order, Grouping::DueDate, today());
+        assert_eq!(sections.pinned, [1]);
+        let buckets: Vec<Option<Group>> = sections.groups.iter().map(|(group, _)| group.clone()).collect();
+        assert_eq!(
+            buckets,
+            [Some(Group::Due(DueBucket::Today)), Some(Group::Due(DueBucket::Later)), Some(Group::Due(DueBucket::NoDueDate))]
+        );
+        assert_eq!(sections.completed, [0]);
+        assert_eq!(sections.rows().collect::<Vec<usize>>(), [1, 3, 2, 4, 0]);
+    }
+
+    #[test]
+    fn contexts_are_grouped_by_name_with_the_rest_last() {
+        let items = [
+            TodoItem { context: Some("home".to_string()), ..item("vacuum") },
+            item("think"),
+            TodoItem { context: Some("errands".to_string()), ..item("post office") },
+            TodoItem { context: Some("home".to_string()), ..item("laundry") },
+        ];
+        let order = (0..items.len()).collect();
+        let sections = Sections::new(&items, order, Grouping::Context, today());
+        let groups: Vec<(Option<Group>, Vec<usize>)> = sections.groups;
+        assert_eq!(
+            groups,
+            [
+                (Some(Group::Context(Some("errands".to_string()))), vec![2]),
+                (Some(Group::Context(Some("home".to_string()))), vec![0, 3]),
+                (Some(Group::Context(None)), vec![1]),
+            ]
+        );
+    }
+
+    #[test]
+    fn smart_lists_keep_search_tags_priority_and_status() {
+        let mut filter = Filter {
+            search: "report".to_string(),
+            priority: Some(Priority::High),
+            completion: Some(Completion::Open),
+            ..Filter::default()
+        };
+        filter.active_tags.insert("work".to_string());
+        let smart = SmartList::from_filter("Urgent work".to_string(), &filter);
+
+        let mut other = Filter { color: Some(ColorLabel::Red), ..Filter::default() };
+        smart.apply(&mut other);
+        assert!(smart.is_applied(&other));
+        assert_eq!(other.color, Some(ColorLabel::Red));
+
+        let work = TodoItem { tags: vec!["work".to_string()], priority: Priority::Urgent, ..item("Quarterly report") };
+        let query = smart.query();
+        let matches = |item: &TodoItem| smart.matches(item, &query, &filter.index, today());
+        assert!(matches(&work));
+        assert!(!matches(&TodoItem { completed: true, ..work.clone() }));
+        assert!(!matches(&TodoItem { priority: Priority::Medium, ..work.clone() }));
+        assert!(!matches(&TodoItem { tags: Vec::new(), ..work }));
+    }
+}
@@ -0,0 +1,293 @@
+use crate::{TodoItem, TodoList};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+
+const AUTH_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
+const TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+const API_URL: &str = "https://tasks.googleapis.com/tasks/v1";
+const SCOPE: &str = "https://www.googleapis.com/auth/tasks";
+
+// OAuth client of type "Desktop app" created in the Google Cloud console, and the
+// refresh token handed out once the user allowed access
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct GoogleAccount {
+    pub client_id: String,
+    pub client_secret: String,
+    pub refresh_token: Option<String>,
+}
+
+impl GoogleAccount {
+    pub fn is_connected(&self) -> bool {
+        self.refresh_token.is_some()
+    }
+}
+
+#[derive(Debug)]
+pub enum GoogleError {
+    Http(Box<ureq::Error>),
+    Io(std::io::Error),
+    // The browser came back without an authorization code, e.g. because access was denied
+    Denied(String),
+}
+
+impl From<ureq::Error> for GoogleError {
+    fn from(e: ureq::Error) -> Self {
+        GoogleError::Http(Box::new(e))
+    }
+}
+
+impl From<std::io::Error> for GoogleError {
+    fn from(e: std::io::Error) -> Self {
+        GoogleError::Io(e)
+    }
+}
+
+// Sign-in started in the browser, Google redirects back to a local port when done
+pub struct Authorization {
+    listener: TcpListener,
+    redirect_uri: String,
+    pub url: String,
+}
+
+impl Authorization {
+    pub fn start(account: &GoogleAccount) -> Result<Self, GoogleError> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let redirect_uri = format!("http://127.0.0.1:{}", listener.local_addr()?.port());
+        let url = format!(
+            "{}?client_id={}&redirect_uri={}&response_type=code&scope={}&access_type=offline&prompt=consent",
+            AUTH_URL,
+            encode(&account.client_id),
+            encode(&redirect_uri),
+            encode(SCOPE)
+        );
+        Ok(Self { listener, redirect_uri, url })
+    }
+
+    // Blocks until the browser is redirected back, then trades the code for a refresh token
+    pub fn finish(self, account: &GoogleAccount) -> Result<String, GoogleError> {
+        let (mut stream, _) = self.listener.accept()?;
+        let mut request_line = String::new();
+        BufReader::new(&stream).read_line(&mut request_line)?;
+        let _ = stream.write_all(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nConnection: close\r\n\r\n\
+              <p>You can close this window and return to the Todo List App.</p>",
+        );
+
+        // e.g. "GET /?code=4%2F0Ab...&scope=... HTTP/1.1"
+        let query = request_line.split_whitespace().nth(1).and_then(|path| path.split_once('?')).map_or("", |(_, query)| query);
+        let parameters: HashMap<&str, String> = query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .map(|(key, value)| (key, decode(value)))
+            .collect();
+        let Some(code) = parameters.get("code") else {
+            return Err(GoogleError::Denied(parameters.get("error").cloned().unwrap_or_default()));
+        };
+
+        let response: TokenResponse = ureq::post(TOKEN_URL)
+            .send_form(&[
+                ("code", code.as_str()),
+                ("client_id", &account.client_id),
+                ("client_secret", &account.client_secret),
+                ("redirect_uri", &self.redirect_uri),
+                ("grant_type", "authorization_code"),
+            ])?
+            .into_json()?;
+        response.refresh_token.ok_or_else(|| GoogleError::Denied("no refresh token".to_string()))
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    refresh_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Page<T> {
+    #[serde(default = "Vec::new")]
+    items: Vec<T>,
+    #[serde(default, rename = "nextPageToken")]
+    next_page_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TaskList {
+    id: String,
+    title: String,
+}
+
+#[derive(Deserialize)]
+struct Task {
+    id: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    notes: String,
+    #[serde(default)]
+    status: String,
+    // RFC 3339 timestamp, only the date part is used by Google Tasks
+    #[serde(default)]
+    due: Option<String>,
+    #[serde(default)]
+    parent: Option<String>,
+}
+
+// Short lived token for API calls
+fn access_token(account: &GoogleAccount) -> Result<String, GoogleError> {
+    let refresh_token = account.refresh_token.as_deref().unwrap_or_default();
+    let response: TokenResponse = ureq::post(TOKEN_URL)
+        .send_form(&[
+            ("client_id", account.client_id.as_str()),
+            ("client_secret", &account.client_secret),
+            ("refresh_token", refresh_token),
+            ("grant_type", "refresh_token"),
+        ])?
+        .into_json()?;
+    Ok(response.access_token)
+}
+
+// Every page of a list endpoint
+fn get_all<T: serde::de::DeserializeOwned>(url: &str, token: &str) -> Result<Vec<T>, GoogleError> {
+    let mut items = Vec::new();
+    let mut page_token: Option<String> = None;
+    loop {
+        let mut request = ureq::get(url).set("Authorization", &format!("Bearer {}", token));
+        if let Some(page_token) = &page_token {
+            request = request.query("pageToken", page_token);
+        }
+        let page: Page<T> = request.call()?.into_json()?;
+        items.extend(page.items);
+        match page.next_page_token {
+            Some(next) => page_token = Some(next),
+            None => return Ok(items),
+        }
+    }
+}
+
+// One list per Google task list, subtasks nested under their parents
+pub fn import(account: &GoogleAccount) -> Result<Vec<TodoList>, GoogleError> {
+    let token = access_token(account)?;
+    let mut lists = Vec::new();
+    for task_list in get_all::<TaskList>(&format!("{}/users/@me/lists", API_URL), &token)? {
+        let url = format!("{}/lists/{}/tasks?showCompleted=true&showHidden=true&maxResults=100", API_URL, task_list.id);
+        let tasks = get_all::<Task>(&url, &token)?;
+
+        let mut children: HashMap<String, Vec<TodoItem>> = HashMap::new();
+        let mut top_level: Vec<(String, TodoItem)> = Vec::new();
+        for task in tasks {
+            let mut item = TodoItem::new(task.title);
+            item.notes = task.notes;
+            item.completed = task.status == "completed";
+            item.due_date = task.due.and_then(|due| NaiveDate::parse_from_str(due.get(..10)?, "%Y-%m-%d").ok());
+            match task.parent {
+                Some(parent) => children.entry(parent).or_default().push(item),
+                None => top_level.push((task.id, item)),
+            }
+        }
+
+        // Google Tasks only nests one level deep
+        let mut list = TodoList::new(task_list.title);
+        for (id, mut item) in top_level {
+            item.children = children.remove(&id).unwrap_or_default();
+            list.items.push(item);
+        }
+        lists.push(list);
+    }
+    Ok(lists)
+}
+
+// Push a list to the Google task list of the same name, creating it if needed.
+// Tasks already there are matched by title and updated, the others are added.
+pub fn export(account: &GoogleAccount, list: &TodoList) -> Result<(), GoogleError> {
+    let token = access_token(account)?;
+    let authorization = format!("Bearer {}", token);
+
+    let task_lists = get_all::<TaskList>(&format!("{}/users/@me/lists", API_URL), &token)?;
+    let list_id = match task_lists.into_iter().find(|task_list| task_list.title == list.name) {
+        Some(task_list) => task_list.id,
+        None => {
+            let created: TaskList = ureq::post(&format!("{}/users/@me/lists", API_URL))
+                .set("Authorization", &authorization)
+                .send_json(json!({ "title": list.name }))?
+                .into_json()?;
+            created.id
+        }
+    };
+
+    let url = format!("{}/lists/{}/tasks?showCompleted=true&showHidden=true&maxResults=100", API_URL, list_id);
+    let existing = get_all::<Task>(&url, &token)?;
+    for item in &list.items {
+        let id = upsert(&list_id, &existing, item, None, &authorization)?;
+        for child in &item.children {
+            upsert(&list_id, &existing, child, Some(&id), &authorization)?;
+        }
+    }
+    Ok(())
+}
+
+// Returns the Google ID of the task
+fn upsert(list_id: &str, existing: &[Task], item: &TodoItem, parent: Option<&str>, authorization: &str) -> Result<String, GoogleError> {
+    let body = json!({
+        "title": item.description,
+        "notes": item.notes,
+        "status": if item.completed { "completed" } else { "needsAction" },
+        "due": item.due_date.map(|due| format!("{}T00:00:00.000Z", due)),
+    });
+    let matching = existing.iter().find(|task| task.title == item.description && task.parent.as_deref() == parent);
+    let task: Task = match matching {
+        Some(task) => ureq::patch(&format!("{}/lists/{}/tasks/{}", API_URL, list_id, task.id))
+            .set("Authorization", authorization)
+            .send_json(body)?
+            .into_json()?,
+        None => {
+            let mut request = ureq::post(&format!("{}/lists/{}/tasks", API_URL, list_id)).set("Authorization", authorization);
+            if let Some(parent) = parent {
+                request = request.query("parent", parent);
+            }
+            request.send_json(body)?.into_json()?
+        }
+    };
+    Ok(task.id)
+}
+
+// Percent-encode everything but unreserved characters
+fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+fn decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let hex = bytes.get(index + 1..index + 3).and_then(|hex| std::str::from_utf8(hex).ok());
+        match (bytes[index], hex.and_then(|hex| u8::from_str_radix(hex, 16).ok())) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                index += 3;
+            }
+            (b'+', _) => {
+                out.push(b' ');
+                index += 1;
+            }
+            (byte, _) => {
+                out.push(byte);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
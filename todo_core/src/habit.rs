@@ -0,0 +1,68 @@
+use crate::recurrence::Recurrence;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+// A task done again and again to build a routine, like exercising. How often it should be
+// done is the item's recurrence, which also reopens it after each completion.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct Habit {
+    // Days it was done on, oldest first and at most once each
+    #[serde(default)]
+    pub done_on: Vec<NaiveDate>,
+}
+
+impl Habit {
+    pub fn mark_done(&mut self, day: NaiveDate) {
+        if let Err(position) = self.done_on.binary_search(&day) {
+            self.done_on.insert(position, day);
+        }
+    }
+
+    pub fn is_done_on(&self, day: NaiveDate) -> bool {
+        self.done_on.binary_search(&day).is_ok()
+    }
+
+    // Completions in a row, each within one `target` interval of the one before. The streak is
+    // broken once today is past the interval after the latest completion.
+    pub fn streak(&self, target: Recurrence, today: NaiveDate) -> usize {
+        let Some(&latest) = self.done_on.last() else {
+            return 0;
+        };
+        if target.advance(latest) < today {
+            return 0;
+        }
+        let in_time = self.done_on.windows(2).rev().take_while(|pair| target.advance(pair[0]) >= pair[1]).count();
+        in_time + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, day).unwrap()
+    }
+
+    #[test]
+    fn days_are_kept_sorted_and_once() {
+        let mut habit = Habit::default();
+        habit.mark_done(day(12));
+        habit.mark_done(day(10));
+        habit.mark_done(day(12));
+        assert_eq!(habit.done_on, [day(10), day(12)]);
+        assert!(habit.is_done_on(day(10)));
+        assert!(!habit.is_done_on(day(11)));
+    }
+
+    #[test]
+    fn streak_counts_completions_in_time() {
+        let habit = Habit { done_on: vec![day(1), day(3), day(4), day(5)] };
+        assert_eq!(habit.streak(Recurrence::Daily, day(5)), 3);
+        // Not done yet today, still going
+        assert_eq!(habit.streak(Recurrence::Daily, day(6)), 3);
+        assert_eq!(habit.streak(Recurrence::Daily, day(7)), 0);
+        assert_eq!(habit.streak(Recurrence::EveryNDays(2), day(6)), 4);
+        assert_eq!(Habit::default().streak(Recurrence::Weekly, day(6)), 0);
+    }
+}
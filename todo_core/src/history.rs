@@ -0,0 +1,223 @@
+use crate::{ArchivedItem, TodoItem, TrashedItem};
+use chrono::Local;
+
+// Upper bound on remembered commands so long sessions don't grow without limit
+const MAX_HISTORY: usize = 100;
+
+// A reversible change to the list
+#[derive(Debug, Clone)]
+pub enum Command {
+    Add { index: usize, item: TodoItem },
+    // Moves the item into the trash rather than dropping it
+    Delete { index: usize, item: TodoItem },
+    // Moves a completed item into the archive
+    Archive { index: usize, item: TodoItem },
+    // Replaces the item with the same ID, used for any change to an existing item
+    Edit { before: Box<TodoItem>, after: Box<TodoItem> },
+    ToggleComplete { id: u32 },
+    // Manual reordering, the item at `from` ends up at `to`
+    Move { from: usize, to: usize },
+    // Several commands that are undone and redone together
+    Batch(Vec<Command>),
+}
+
+impl Command {
+    fn apply(&self, items: &mut Vec<TodoItem>, trash: &mut Vec<TrashedItem>, archive: &mut Vec<ArchivedItem>) {
+        match self {
+            Command::Add { index, item } => items.insert((*index).min(items.len()), item.clone()),
+            Command::Delete { item, .. } => {
+                items.retain(|i| i.id != item.id);
+                trash.push(TrashedItem::new(item.clone(), Local::now().date_naive()));
+            }
+            Command::Archive { item, .. } => {
+                items.retain(|i| i.id != item.id);
+                archive.push(ArchivedItem::new(item.clone(), Local::now().date_naive()));
+            }
+            Command::Edit { after, .. } => replace_item(items, after),
+            Command::ToggleComplete { id } => toggle_item(items, *id),
+            Command::Move { from, to } => move_item(items, *from, *to),
+            Command::Batch(commands) => commands.iter().for_each(|command| command.apply(items, trash, archive)),
+        }
+    }
+
+    fn revert(&self, items: &mut Vec<TodoItem>, trash: &mut Vec<TrashedItem>, archive: &mut Vec<ArchivedItem>) {
+        match self {
+            Command::Add { item, .. } => items.retain(|i| i.id != item.id),
+            Command::Delete { index, item } => {
+                trash.retain(|trashed| trashed.item.id != item.id);
+                items.insert((*index).min(items.len()), item.clone());
+            }
+            Command::Archive { index, item } => {
+                archive.retain(|archived| archived.item.id != item.id);
+                items.insert((*index).min(items.len()), item.clone());
+            }
+            Command::Edit { before, .. } => replace_item(items, before),
+            Command::ToggleComplete { id } => toggle_item(items, *id),
+            Command::Move { from, to } => move_item(items, *to, *from),
+            // Undo in reverse so indices line up with when each command ran
+            Command::Batch(commands) => commands.iter().rev().for_each(|command| command.revert(items, trash, archive)),
+        }
+    }
+}
+
+fn replace_item(items: &mut [TodoItem], replacement: &TodoItem) {
+    if let Some(item) = items.iter_mut().find(|i| i.id == replacement.id) {
+        *item = replacement.clone();
+    }
+}
+
+pub fn move_item(items: &mut Vec<TodoItem>, from: usize, to: usize) {
+    if from < items.len() && to < items.len() {
+        let item = items.remove(from);
+        items.insert(to, item);
+    }
+}
+
+fn toggle_item(items: &mut [TodoItem], id: u32) {
+    if let Some(item) = items.iter_mut().find(|i| i.id == id) {
+        item.completed = !item.completed;
+    }
+}
+
+// Undo and redo stacks of commands that have already been applied
+#[derive(Default, Debug)]
+pub struct History {
+    undo_stack: Vec<Command>,
+    redo_stack: Vec<Command>,
+}
+
+impl History {
+    // Record a command the UI already performed, this invalidates the redo stack
+    pub fn push(&mut self, command: Command) {
+        self.undo_stack.push(command);
+        if self.undo_stack.len() > MAX_HISTORY {
+            self.undo_stack.remove(0);
+        }
+        self.redo_stack.clear();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    pub fn undo(&mut self, items: &mut Vec<TodoItem>, trash: &mut Vec<TrashedItem>, archive: &mut Vec<ArchivedItem>) {
+        if let Some(command) = self.undo_stack.pop() {
+            command.revert(items, trash, archive);
+            self.redo_stack.push(command);
+        }
+    }
+
+    pub fn redo(&mut self, items: &mut Vec<TodoItem>, trash: &mut Vec<TrashedItem>, archive: &mut Vec<ArchivedItem>) {
+        if let Some(command) = self.redo_stack.pop() {
+            command.apply(items, trash, archive);
+            self.undo_stack.push(command);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Command;
+    use crate::{TodoItem, TodoList};
+
+    fn list_with(descriptions: &[&str]) -> TodoList {
+        let mut list = TodoList::new("Test".to_string());
+        list.items = descriptions.iter().map(|description| TodoItem::new(description.to_string())).collect();
+        list
+    }
+
+    fn descriptions(list: &TodoList) -> Vec<&str> {
+        list.items.iter().map(|item| item.description.as_str()).collect()
+    }
+
+    #[test]
+    fn add_is_undone_and_redone() {
+        let mut list = list_with(&["a"]);
+        let item = TodoItem::new("b".to_string());
+        list.items.push(item.clone());
+        list.history.push(Command::Add { index: 1, item });
+
+        list.undo();
+        assert_eq!(descriptions(&list), ["a"]);
+        list.redo();
+        assert_eq!(descriptions(&list), ["a", "b"]);
+    }
+
+    #[test]
+    fn delete_goes_to_the_trash_and_comes_back_in_place() {
+        let mut list = list_with(&["a", "b", "c"]);
+        let item = list.items.remove(1);
+        list.history.push(Command::Delete { index: 1, item });
+        // Redo is what performs a delete through the history
+        list.undo();
+        list.redo();
+        assert_eq!(descriptions(&list), ["a", "c"]);
+        assert_eq!(list.trash.len(), 1);
+
+        list.undo();
+        assert_eq!(descriptions(&list), ["a", "b", "c"]);
+        assert!(list.trash.is_empty());
+    }
+
+    #[test]
+    fn edit_restores_the_previous_version() {
+        let mut list = list_with(&["draft"]);
+        let before = list.items[0].clone();
+        list.items[0].description = "final".to_string();
+        list.history.push(Command::Edit { before: Box::new(before), after: Box::new(list.items[0].clone()) });
+
+        list.undo();
+        assert_eq!(descriptions(&list), ["draft"]);
+        list.redo();
+        assert_eq!(descriptions(&list), ["final"]);
+    }
+
+    #[test]
+    fn completing_is_undone() {
+        let mut list = list_with(&["a"]);
+        list.items[0].completed = true;
+        list.history.push(Command::ToggleComplete { id: list.items[0].id });
+
+        list.undo();
+        assert!(!list.items[0].completed);
+        list.redo();
+        assert!(list.items[0].completed);
+    }
+
+    #[test]
+    fn a_new_change_clears_redo() {
+        let mut list = list_with(&["a", "b"]);
+        list.items.swap(0, 1);
+        list.history.push(Command::Move { from: 0, to: 1 });
+        list.undo();
+        assert!(list.history.can_redo());
+
+        list.history.push(Command::ToggleComplete { id: list.items[0].id });
+        assert!(!list.history.can_redo());
+    }
+
+    #[test]
+    fn a_batch_is_undone_in_one_step() {
+        let mut list = list_with(&["a", "b", "c"]);
+        let today = chrono::Local::now().date_naive();
+        for item in list.items.iter_mut().take(2) {
+            item.completed = true;
+        }
+        list.archive_completed(today);
+        assert_eq!(descriptions(&list), ["c"]);
+        assert_eq!(list.archive.len(), 2);
+
+        list.undo();
+        assert_eq!(descriptions(&list), ["a", "b", "c"]);
+        assert!(list.archive.is_empty());
+    }
+}
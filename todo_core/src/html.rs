@@ -0,0 +1,151 @@
+use crate::export::Section;
+use crate::theme::Theme;
+use crate::{Priority, TodoItem};
+use chrono::NaiveDate;
+use std::fmt::Write;
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn css_color([r, g, b]: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+// Colors come from the theme, the text colors from whether it is dark
+fn style(theme: &Theme) -> String {
+    let (text, muted, card) = if theme.dark {
+        ("#e6e6e6", "#a0a0a0", "rgba(255, 255, 255, 0.05)")
+    } else {
+        ("#1e1e1e", "#6b6b6b", "rgba(0, 0, 0, 0.04)")
+    };
+    format!(
+        "body {{ background: {background}; color: {text}; font-family: system-ui, sans-serif; max-width: 46em; margin: 2em auto; padding: 0 1em; line-height: 1.45; }}
+h1 {{ margin-bottom: 0.1em; }}
+h2 {{ font-size: 1.1em; margin-top: 1.6em; border-bottom: 2px solid {accent}; padding-bottom: 0.2em; }}
+time.exported {{ color: {muted}; }}
+ul {{ list-style: none; padding-left: 0; margin: 0; }}
+ul ul {{ padding-left: 1.6em; }}
+li {{ margin: 0.3em 0; }}
+.task {{ background: {card}; border-left: 3px solid transparent; border-radius: 4px; padding: 0.3em 0.6em; }}
+.priority-high {{ border-left-color: {accent}; }}
+.priority-urgent {{ border-left-color: {overdue}; }}
+.box {{ margin-right: 0.4em; }}
+.done > .task {{ color: {completed}; }}
+.done > .task .description {{ text-decoration: line-through; }}
+.tag {{ color: {muted}; margin-left: 0.4em; }}
+.due {{ color: {muted}; margin-left: 0.4em; white-space: nowrap; }}
+.overdue {{ color: {overdue}; }}
+.notes {{ color: {muted}; font-size: 0.9em; margin: 0.2em 0 0 1.6em; white-space: pre-wrap; }}
+",
+        background = css_color(theme.background),
+        accent = css_color(theme.accent),
+        completed = css_color(theme.completed_text),
+        overdue = css_color(theme.overdue),
+    )
+}
+
+fn write_item(out: &mut String, item: &TodoItem, today: NaiveDate) {
+    let _ = write!(out, "<li{}>", if item.completed { " class=\"done\"" } else { "" });
+    let priority = match item.priority {
+        Priority::High => " priority-high",
+        Priority::Urgent => " priority-urgent",
+        Priority::Low | Priority::Medium => "",
+    };
+    let _ = write!(out, "<div class=\"task{}\">", priority);
+    let _ = write!(out, "<span class=\"box\">{}</span>", if item.completed { "☑" } else { "☐" });
+    let description: Vec<String> = item.description.lines().map(escape).collect();
+    let _ = write!(out, "<span class=\"description\">{}</span>", description.join("<br>"));
+    for tag in &item.tags {
+        let _ = write!(out, "<span class=\"tag\">#{}</span>", escape(tag));
+    }
+    if let (false, Some(due_date)) = (item.completed, item.due_date) {
+        let class = if item.is_overdue(today) { "due overdue" } else { "due" };
+        let _ = write!(out, "<time class=\"{}\" datetime=\"{}\">{}</time>", class, due_date, due_date);
+    }
+    if !item.notes.is_empty() {
+        let _ = write!(out, "<div class=\"notes\">{}</div>", escape(&item.notes));
+    }
+    out.push_str("</div>");
+    if !item.children.is_empty() {
+        out.push_str("<ul>");
+        for child in &item.children {
+            write_item(out, child, today);
+        }
+        out.push_str("</ul>");
+    }
+    out.push_str("</li>\n");
+}
+
+// A standalone page for people without the app, styled after `theme` and with the styles
+// inline so the file can be mailed or uploaded as it is. Empty sections are left out.
+pub fn to_html(title: &str, sections: &[Section], theme: &Theme, today: NaiveDate) -> String {
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>{title}</title>\n<style>\n{style}</style>\n</head>\n<body>\n<h1>{title}</h1>\n<time class=\"exported\" datetime=\"{today}\">{today}</time>\n",
+        title = escape(title),
+        style = style(theme),
+        today = today,
+    );
+    for section in sections.iter().filter(|section| !section.items.is_empty()) {
+        if let Some(heading) = &section.heading {
+            let _ = writeln!(out, "<h2>{}</h2>", escape(heading));
+        }
+        out.push_str("<ul>\n");
+        for item in &section.items {
+            write_item(&mut out, item, today);
+        }
+        out.push_str("</ul>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, 13).unwrap()
+    }
+
+    #[test]
+    fn text_is_escaped() {
+        let item = TodoItem::new("fix <b> & \"quotes\"".to_string());
+        let sections = [Section { heading: Some("Q&A".to_string()), items: vec![&item] }];
+        let html = to_html("Mine <3", &sections, &Theme::light("Light".to_string()), today());
+        assert!(html.contains("fix &lt;b&gt; &amp; &quot;quotes&quot;"));
+        assert!(html.contains("<h2>Q&amp;A</h2>"));
+        assert!(html.contains("<title>Mine &lt;3</title>"));
+    }
+
+    #[test]
+    fn the_theme_colors_the_page() {
+        let theme = Theme::dark("Dark".to_string());
+        let html = to_html("Todo", &[], &theme, today());
+        assert!(html.contains("background: #1b1b1b"));
+        assert!(html.contains("color: #e6e6e6"));
+    }
+
+    #[test]
+    fn completed_and_overdue_tasks_are_marked() {
+        let done = TodoItem { completed: true, ..TodoItem::new("done".to_string()) };
+        let late = TodoItem { due_date: NaiveDate::from_ymd_opt(2024, 3, 1), ..TodoItem::new("late".to_string()) };
+        let sections = [Section { heading: None, items: vec![&done, &late] }];
+        let html = to_html("Todo", &sections, &Theme::light("Light".to_string()), today());
+        assert!(html.contains("<li class=\"done\"><div class=\"task\"><span class=\"box\">☑</span><span class=\"description\">done</span>"));
+        assert!(html.contains("<time class=\"due overdue\" datetime=\"2024-03-01\">2024-03-01</time>"));
+    }
+}
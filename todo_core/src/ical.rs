@@ -0,0 +1,242 @@
+use crate::{Priority, Status, TodoItem, TodoList};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use std::fmt::Write;
+
+const TIMESTAMP_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+// Calendar apps show VTODOs by due date, so only dated items and their dated subtasks are exported
+pub fn to_ical(list: &TodoList) -> String {
+    let stamp = Utc::now().format(TIMESTAMP_FORMAT).to_string();
+    let mut out = String::new();
+    write_calendar_start(&mut out);
+    write_line(&mut out, &format!("X-WR-CALNAME:{}", escape(&list.name)));
+    for item in &list.items {
+        write_dated_vtodos(&mut out, item, None, &stamp);
+    }
+    write_line(&mut out, "END:VCALENDAR");
+    out
+}
+
+// A calendar holding just this item, as stored on a CalDAV server
+pub fn to_single_ical(item: &TodoItem) -> String {
+    let stamp = Utc::now().format(TIMESTAMP_FORMAT).to_string();
+    let mut out = String::new();
+    write_calendar_start(&mut out);
+    write_vtodo(&mut out, item, None, &stamp);
+    write_line(&mut out, "END:VCALENDAR");
+    out
+}
+
+// Items keep the UID they were synced with, others get one made from their ID
+pub fn uid(item: &TodoItem) -> String {
+    item.sync_uid.clone().unwrap_or_else(|| format!("{}@rust_todo_list", item.id))
+}
+
+fn write_calendar_start(out: &mut String) {
+    write_line(out, "BEGIN:VCALENDAR");
+    write_line(out, "VERSION:2.0");
+    write_line(out, "PRODID:-//rust_todo_list//Todo List App//EN");
+}
+
+fn write_dated_vtodos(out: &mut String, item: &TodoItem, parent: Option<&TodoItem>, stamp: &str) {
+    if item.due_date.is_some() {
+        // Subtasks only point at their parent if it is in the file too
+        write_vtodo(out, item, parent.filter(|parent| parent.due_date.is_some()), stamp);
+    }
+    for child in &item.children {
+        write_dated_vtodos(out, child, Some(item), stamp);
+    }
+}
+
+fn write_vtodo(out: &mut String, item: &TodoItem, parent: Option<&TodoItem>, stamp: &str) {
+    write_line(out, "BEGIN:VTODO");
+    write_line(out, &format!("UID:{}", uid(item)));
+    write_line(out, &format!("DTSTAMP:{}", stamp));
+    if let Some(modified) = item.modified {
+        write_line(out, &format!("LAST-MODIFIED:{}", modified.format(TIMESTAMP_FORMAT)));
+    }
+    let summary = item.description.lines().next().unwrap_or_default();
+    write_line(out, &format!("SUMMARY:{}", escape(summary)));
+    // The rest of a multi-line description goes in front of the notes
+    let rest: Vec<&str> = item.description.lines().skip(1).chain(item.notes.lines()).collect();
+    if !rest.is_empty() {
+        write_line(out, &format!("DESCRIPTION:{}", escape(&rest.join("\n"))));
+    }
+    if let Some(due_date) = item.due_date {
+        write_line(out, &format!("DUE;VALUE=DATE:{}", due_date.format("%Y%m%d")));
+    }
+    write_line(out, &format!("PRIORITY:{}", priority_value(item.priority)));
+    write_line(out, &format!("STATUS:{}", status_value(item.status())));
+    if !item.tags.is_empty() {
+        let tags: Vec<String> = item.tags.iter().map(|tag| escape(tag)).collect();
+        write_line(out, &format!("CATEGORIES:{}", tags.join(",")));
+    }
+    if let Some(parent) = parent {
+        write_line(out, &format!("RELATED-TO:{}", uid(parent)));
+    }
+    write_line(out, "END:VTODO");
+}
+
+// 1 is the highest and 9 the lowest priority in iCalendar
+pub fn priority_value(priority: Priority) -> u8 {
+    match priority {
+        Priority::Urgent => 1,
+        Priority::High => 3,
+        Priority::Medium => 5,
+        Priority::Low => 9,
+    }
+}
+
+// 0 means no priority was set
+fn priority_from_value(value: u8) -> Priority {
+    match value {
+        1..=2 => Priority::Urgent,
+        3..=4 => Priority::High,
+        6..=9 => Priority::Low,
+        _ => Priority::Medium,
+    }
+}
+
+pub fn status_value(status: Status) -> &'static str {
+    match status {
+        Status::Todo => "NEEDS-ACTION",
+        Status::InProgress => "IN-PROCESS",
+        Status::Done => "COMPLETED",
+    }
+}
+
+fn status_from_value(value: &str) -> Status {
+    match value {
+        "IN-PROCESS" => Status::InProgress,
+        "COMPLETED" => Status::Done,
+        _ => Status::Todo,
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') | Some('N') => out.push('\n'),
+                Some(other) => out.push(other),
+                None => {}
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+// Split a comma separated value, leaving escaped commas inside their part
+fn split_list(value: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+    for (index, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            ',' => {
+                parts.push(&value[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&value[start..]);
+    parts
+}
+
+// Content lines end in CRLF and are folded so no line is longer than 75 bytes
+fn write_line(out: &mut String, line: &str) {
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            // The leading space of the continuation counts towards its length
+            length = 1;
+        }
+        out.push(c);
+        length += c.len_utf8();
+    }
+    let _ = write!(out, "\r\n");
+}
+
+// The fields of a VTODO read from another application
+#[derive(Debug, Clone, Default)]
+pub struct VTodo {
+    pub uid: String,
+    pub summary: String,
+    pub description: String,
+    pub due_date: Option<NaiveDate>,
+    pub priority: u8,
+    pub status: String,
+    pub categories: Vec<String>,
+    pub last_modified: Option<DateTime<Utc>>,
+}
+
+impl VTodo {
+    // Copy the synced fields onto an item, leaving local only fields alone
+    pub fn apply_to(&self, item: &mut TodoItem) {
+        item.description = self.summary.clone();
+        item.notes = self.description.clone();
+        item.due_date = self.due_date;
+        item.priority = priority_from_value(self.priority);
+        item.set_status(status_from_value(&self.status));
+        item.tags = self.categories.clone();
+        item.sync_uid = Some(self.uid.clone());
+        item.modified = self.last_modified;
+    }
+}
+
+// Every VTODO in an iCalendar file, other components are skipped
+pub fn parse_vtodos(text: &str) -> Vec<VTodo> {
+    // Unfold continuation lines first
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        match (line.strip_prefix(' ').or_else(|| line.strip_prefix('\t')), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    let mut todos = Vec::new();
+    let mut current: Option<VTodo> = None;
+    for line in &lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        // Parameters such as ";VALUE=DATE" don't matter for the fields read here
+        let name = name.split(';').next().unwrap_or_default().to_ascii_uppercase();
+        match (name.as_str(), current.as_mut()) {
+            ("BEGIN", None) if value.eq_ignore_ascii_case("VTODO") => current = Some(VTodo::default()),
+            ("END", Some(_)) if value.eq_ignore_ascii_case("VTODO") => todos.extend(current.take()),
+            ("UID", Some(todo)) => todo.uid = value.to_string(),
+            ("SUMMARY", Some(todo)) => todo.summary = unescape(value),
+            ("DESCRIPTION", Some(todo)) => todo.description = unescape(value),
+            ("DUE", Some(todo)) => todo.due_date = value.get(..8).and_then(|date| NaiveDate::parse_from_str(date, "%Y%m%d").ok()),
+            ("PRIORITY", Some(todo)) => todo.priority = value.trim().parse().unwrap_or(0),
+            ("STATUS", Some(todo)) => todo.status = value.trim().to_ascii_uppercase(),
+            ("CATEGORIES", Some(todo)) => {
+                todo.categories = split_list(value).iter().map(|tag| unescape(tag)).filter(|tag| !tag.is_empty()).collect()
+            }
+            ("LAST-MODIFIED", Some(todo)) => {
+                todo.last_modified = NaiveDateTime::parse_from_str(value.trim(), TIMESTAMP_FORMAT)
+                    .ok()
+                    .map(|time| time.and_utc())
+            }
+            _ => {}
+        }
+    }
+    todos
+}
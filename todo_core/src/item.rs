@@ -0,0 +1,836 @@
+use crate::attachment::Attachment;
+use crate::fields::FieldValue;
+use crate::habit::Habit;
+use crate::recurrence::{self, Recurrence};
+use crate::search;
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::collections::BTreeMap;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+// Generate unique IDs for each todo item to ensure each item's uniqueness
+static NEXT_ID: AtomicU32 = AtomicU32::new(1);
+
+// Make sure IDs handed out from now on are above every ID already in use
+pub(crate) fn reserve_ids_up_to(max_id: u32) {
+    NEXT_ID.fetch_max(max_id.saturating_add(1), Ordering::SeqCst);
+}
+
+// Never wraps around to IDs that may be in use. Loading renumbers saves that use the upper half
+// of the range, so this would take billions of new items.
+pub(crate) fn next_id() -> u32 {
+    NEXT_ID
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |id| id.checked_add(1))
+        .expect("ran out of item IDs")
+}
+
+// Random ID that tells items apart across machines, where the counter above starts over
+fn new_uid(created: DateTime<Utc>) -> String {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_i64(created.timestamp_nanos_opt().unwrap_or_default());
+    format!("{:016x}", hasher.finish())
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Priority {
+    Low,
+    #[default]
+    Medium,
+    High,
+    Urgent,
+}
+
+impl Priority {
+    pub const ALL: [Priority; 4] = [Priority::Low, Priority::Medium, Priority::High, Priority::Urgent];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Priority::Low => "Low",
+            Priority::Medium => "Medium",
+            Priority::High => "High",
+            Priority::Urgent => "Urgent",
+        }
+    }
+
+    // Cycle through the levels when the badge is clicked
+    pub fn next(self) -> Self {
+        match self {
+            Priority::Low => Priority::Medium,
+            Priority::Medium => Priority::High,
+            Priority::High => Priority::Urgent,
+            Priority::Urgent => Priority::Low,
+        }
+    }
+}
+
+// Color label of an item, shown as a stripe next to its row
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorLabel {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Blue,
+    Purple,
+}
+
+impl ColorLabel {
+    pub const ALL: [ColorLabel; 6] = [
+        ColorLabel::Red,
+        ColorLabel::Orange,
+        ColorLabel::Yellow,
+        ColorLabel::Green,
+        ColorLabel::Blue,
+        ColorLabel::Purple,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ColorLabel::Red => "Red",
+            ColorLabel::Orange => "Orange",
+            ColorLabel::Yellow => "Yellow",
+            ColorLabel::Green => "Green",
+            ColorLabel::Blue => "Blue",
+            ColorLabel::Purple => "Purple",
+        }
+    }
+}
+
+// Square of an item in the urgent/important matrix, Eisenhower style
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quadrant {
+    DoFirst,
+    Schedule,
+    Delegate,
+    Eliminate,
+}
+
+impl Quadrant {
+    // Reading order of the matrix, important ones on top and urgent ones on the left
+    pub const ALL: [Quadrant; 4] = [Quadrant::DoFirst, Quadrant::Schedule, Quadrant::Delegate, Quadrant::Eliminate];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Quadrant::DoFirst => "Do first",
+            Quadrant::Schedule => "Schedule",
+            Quadrant::Delegate => "Delegate",
+            Quadrant::Eliminate => "Eliminate",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Quadrant::DoFirst => "Urgent and important",
+            Quadrant::Schedule => "Important, not urgent",
+            Quadrant::Delegate => "Urgent, not important",
+            Quadrant::Eliminate => "Neither urgent nor important",
+        }
+    }
+
+    // The priority a quadrant stands for, so sorting and badges agree with the matrix
+    pub fn priority(self) -> Priority {
+        match self {
+            Quadrant::DoFirst => Priority::Urgent,
+            Quadrant::Schedule => Priority::High,
+            Quadrant::Delegate => Priority::Medium,
+            Quadrant::Eliminate => Priority::Low,
+        }
+    }
+
+    pub fn from_priority(priority: Priority) -> Self {
+        match priority {
+            Priority::Urgent => Quadrant::DoFirst,
+            Priority::High => Quadrant::Schedule,
+            Priority::Medium => Quadrant::Delegate,
+            Priority::Low => Quadrant::Eliminate,
+        }
+    }
+}
+
+// Column of an item on the board view
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Status {
+    #[default]
+    Todo,
+    InProgress,
+    Done,
+}
+
+impl Status {
+    pub const ALL: [Status; 3] = [Status::Todo, Status::InProgress, Status::Done];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Status::Todo => "To Do",
+            Status::InProgress => "In Progress",
+            Status::Done => "Done",
+        }
+    }
+}
+
+// Something that happened to an item, kept in its activity log
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Activity {
+    Created,
+    Edited,
+    Completed,
+    Reopened,
+}
+
+impl Activity {
+    pub fn label(self) -> &'static str {
+        match self {
+            Activity::Created => "Created",
+            Activity::Edited => "Edited",
+            Activity::Completed => "Completed",
+            Activity::Reopened => "Reopened",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LogEntry {
+    pub at: DateTime<Utc>,
+    pub activity: Activity,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TodoItem {
+    pub id: u32,
+    // Same on every copy of the save, used to match items when merging them. Filled in on load for older saves.
+    #[serde(default)]
+    pub uid: String,
+    pub description: String,
+    pub completed: bool,
+    pub edit: bool,
+    // Optional so that saves from before due dates existed still load
+    #[serde(default)]
+    pub due_date: Option<NaiveDate>,
+    // First day of work on the item, drawn as the start of its bar on the timeline
+    #[serde(default)]
+    pub start_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub priority: Priority,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    // Text typed into the tag field while editing, not saved
+    #[serde(skip)]
+    pub tag_input: String,
+    #[serde(default)]
+    pub children: Vec<TodoItem>,
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
+    // Day of the month a monthly recurrence comes back on, while short months move it earlier
+    #[serde(default)]
+    pub recurrence_day: Option<u32>,
+    // Longer free text kept apart from the one line description
+    #[serde(default)]
+    pub notes: String,
+    // Board column of an open item, `completed` still decides whether it is done
+    #[serde(default)]
+    pub status: Status,
+    // Number of focus sessions finished on this item
+    #[serde(default)]
+    pub pomodoros: u32,
+    // Tracked time in seconds, not counting a timer that is still running
+    #[serde(default)]
+    pub time_spent: u64,
+    // Start of the running time tracker, if any
+    #[serde(default)]
+    pub tracking_since: Option<DateTime<Local>>,
+    // Last time the item was changed, the newer side wins when syncing
+    #[serde(default)]
+    pub modified: Option<DateTime<Utc>>,
+    // iCalendar UID of the item once it is known to be on a CalDAV server
+    #[serde(default)]
+    pub sync_uid: Option<String>,
+    #[serde(default)]
+    pub color: Option<ColorLabel>,
+    // When to pop up a reminder, separate from the due date. Cleared once dismissed.
+    #[serde(default)]
+    pub reminder: Option<DateTime<Local>>,
+    // Pinned items are shown above the rest of the list whatever the sort order
+    #[serde(default)]
+    pub pinned: bool,
+    // When the item was added, unknown for items from older saves
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    // When the item was last checked off, cleared when it is reopened
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Utc>>,
+    // What happened to the item and when, oldest first. Only ever added to.
+    #[serde(default)]
+    pub log: Vec<LogEntry>,
+    // Emoji shown before the description, to find the item at a glance
+    #[serde(default)]
+    pub icon: Option<String>,
+    // Day the item was picked for My Day. Open items from earlier days are carried over.
+    #[serde(default)]
+    pub my_day: Option<NaiveDate>,
+    // Set for items tracked as habits, with the days they were done on
+    #[serde(default)]
+    pub habit: Option<Habit>,
+    // Square of the matrix view the item was dragged to, placed by its priority until then
+    #[serde(default)]
+    pub quadrant: Option<Quadrant>,
+    // Expected effort in minutes
+    #[serde(default)]
+    pub estimate: Option<u32>,
+    // Where or with what the item can be done, like "home" for @home. Kept apart from tags
+    // for grouping Getting Things Done style.
+    #[serde(default)]
+    pub context: Option<String>,
+    // Who the item is up to on a list shared by several people
+    #[serde(default)]
+    pub assignee: Option<String>,
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+    // Values of the list's custom fields by field name, unset fields are left out
+    #[serde(default)]
+    pub fields: BTreeMap<String, FieldValue>,
+}
+
+impl TodoItem {
+    pub fn new(description: String) -> Self {
+        let now = Utc::now();
+        Self {
+            id: next_id(),
+            uid: new_uid(now),
+            description,
+            completed: false,
+            edit: false,
+            due_date: None,
+            start_date: None,
+            priority: Priority::default(),
+            tags: Vec::new(),
+            tag_input: String::new(),
+            children: Vec::new(),
+            recurrence: None,
+            recurrence_day: None,
+            notes: String::new(),
+            status: Status::default(),
+            pomodoros: 0,
+            time_spent: 0,
+            tracking_since: None,
+            modified: Some(now),
+            sync_uid: None,
+            color: None,
+            pinned: false,
+            reminder: None,
+            created_at: Some(now),
+            completed_at: None,
+            log: vec![LogEntry { at: now, activity: Activity::Created }],
+            icon: None,
+            my_day: None,
+            habit: None,
+            quadrant: None,
+            estimate: None,
+            context: None,
+            assignee: None,
+            attachments: Vec::new(),
+            fields: BTreeMap::new(),
+        }
+    }
+
+    // Copy of the item and its subtasks under new IDs, open again and without its tracked history.
+    // Due dates can be moved one interval of `shift` later, e.g. to set up next week's copy.
+    pub fn duplicate(&self, shift: Option<Recurrence>) -> Self {
+        TodoItem {
+            due_date: self.due_date.map(|due| shift.map_or(due, |shift| shift.advance(due))),
+            start_date: self.start_date.map(|start| shift.map_or(start, |shift| shift.advance(start))),
+            priority: self.priority,
+            tags: self.tags.clone(),
+            children: self.children.iter().map(|child| child.duplicate(shift)).collect(),
+            recurrence: self.recurrence,
+            notes: self.notes.clone(),
+            color: self.color,
+            icon: self.icon.clone(),
+            estimate: self.estimate,
+            context: self.context.clone(),
+            assignee: self.assignee.clone(),
+            fields: self.fields.clone(),
+            ..TodoItem::new(self.description.clone())
+        }
+    }
+
+    // Highest ID used by this item or any of its subtasks
+    pub fn max_id(&self) -> u32 {
+        self.children.iter().map(TodoItem::max_id).fold(self.id, u32::max)
+    }
+
+    // Items from saves older than `uid` get one made from their ID and creation time,
+    // so copies of the same save agree on it
+    pub(crate) fn fill_missing_uid(&mut self) {
+        if self.uid.is_empty() {
+            let created = self.created_at.map_or(0, |created| created.timestamp_millis());
+            self.uid = format!("{}-{}", self.id, created);
+        }
+        self.children.iter_mut().for_each(TodoItem::fill_missing_uid);
+    }
+
+    // The same item and subtasks under IDs that are new to this session, e.g. when they come from another machine
+    pub(crate) fn renumbered(self) -> Self {
+        TodoItem {
+            id: next_id(),
+            edit: false,
+            children: self.children.into_iter().map(TodoItem::renumbered).collect(),
+            ..self
+        }
+    }
+
+    // True if this item or any subtask is in edit mode
+    pub fn is_editing(&self) -> bool {
+        self.edit || self.children.iter().any(TodoItem::is_editing)
+    }
+
+    // True if the description or notes of this item or any subtask contain the query
+    pub fn matches_search(&self, query: &str) -> bool {
+        search::contains_ignore_case(&self.description, query)
+            || search::contains_ignore_case(&self.notes, query)
+            || self.children.iter().any(|child| child.matches_search(query))
+    }
+
+    // Completed items are always Done, so ticking the checkbox moves them on the board too
+    pub fn status(&self) -> Status {
+        match (self.completed, self.status) {
+            (true, _) => Status::Done,
+            (false, Status::Done) => Status::Todo,
+            (false, status) => status,
+        }
+    }
+
+    pub fn set_status(&mut self, status: Status) {
+        self.completed = status == Status::Done;
+        self.status = status;
+    }
+
+    pub fn quadrant(&self) -> Quadrant {
+        self.quadrant.unwrap_or_else(|| Quadrant::from_priority(self.priority))
+    }
+
+    // Dragging an item to a quadrant sets the matching priority as well
+    pub fn set_quadrant(&mut self, quadrant: Quadrant) {
+        self.quadrant = Some(quadrant);
+        self.priority = quadrant.priority();
+    }
+
+    // Move the start and due dates by whole days, as when a timeline bar or one of its ends
+    // is dragged. The start never ends up after the due date, and stretching an item that only
+    // has a due date gives it a start date.
+    pub fn shift_dates(&mut self, start_days: i64, due_days: i64) {
+        let shift = |date: NaiveDate, days: i64| date.checked_add_signed(chrono::Duration::days(days)).unwrap_or(date);
+        let start = if start_days != due_days { self.start_date.or(self.due_date) } else { self.start_date };
+        self.start_date = start.map(|start| shift(start, start_days));
+        self.due_date = self.due_date.map(|due| shift(due, due_days));
+        if let (Some(start), Some(due)) = (self.start_date, self.due_date) {
+            self.start_date = Some(start.min(due));
+        }
+    }
+
+    pub fn all_children_completed(&self) -> bool {
+        !self.children.is_empty() && self.children.iter().all(|child| child.completed)
+    }
+
+    // Add a tag unless it is blank or already present
+    pub fn add_tag(&mut self, tag: &str) {
+        let tag = tag.trim();
+        if !tag.is_empty() && !self.tags.iter().any(|t| t == tag) {
+            self.tags.push(tag.to_string());
+        }
+    }
+
+    // Keep `completed_at` in line with the checkbox, an existing stamp is left alone
+    pub fn stamp_completion(&mut self, now: DateTime<Utc>) {
+        self.completed_at = if self.completed { Some(self.completed_at.unwrap_or(now)) } else { None };
+    }
+
+    // Completing a recurring item reopens it with its next due date instead.
+    // It leaves My Day, this occurrence is done. Habits note the day they were done.
+    // A start date keeps its distance to the due date.
+    pub fn reschedule_if_recurring(&mut self, today: NaiveDate) {
+        if let (true, Some(habit)) = (self.completed, self.habit.as_mut()) {
+            habit.mark_done(today);
+        }
+        if let (true, Some(recurrence)) = (self.completed, self.recurrence) {
+            let next = recurrence.next_due_date(self.due_date, self.recurrence_day, today);
+            if recurrence == Recurrence::Monthly {
+                self.recurrence_day = Some(recurrence::monthly_day(self.due_date.unwrap_or(today), self.recurrence_day));
+            }
+            if let (Some(start), Some(due)) = (self.start_date, self.due_date) {
+                self.start_date = Some(start + (next - due));
+            }
+            self.due_date = Some(next);
+            self.completed = false;
+            self.my_day = None;
+            self.log.push(LogEntry { at: Utc::now(), activity: Activity::Completed });
+        }
+    }
+
+    // Two items that read the same, whatever their IDs, times and log. Every field is named,
+    // so a new one has to be sorted in here before this compiles.
+    pub fn same_content(&self, other: &TodoItem) -> bool {
+        let TodoItem {
+            id: _,
+            uid: _,
+            description,
+            completed,
+            edit: _,
+            due_date,
+            start_date,
+            priority,
+            tags,
+            tag_input: _,
+            children,
+            recurrence,
+            recurrence_day,
+            notes,
+            status,
+            pomodoros,
+            time_spent,
+            tracking_since,
+            modified: _,
+            sync_uid,
+            color,
+            reminder,
+            pinned,
+            created_at: _,
+            completed_at,
+            log: _,
+            icon,
+            my_day,
+            habit,
+            quadrant,
+            estimate,
+            context,
+            assignee,
+            attachments,
+            fields,
+        } = self;
+        *description == other.description
+            && *completed == other.completed
+            && *due_date == other.due_date
+            && *start_date == other.start_date
+            && *priority == other.priority
+            && *tags == other.tags
+            && *recurrence == other.recurrence
+            && *recurrence_day == other.recurrence_day
+            && *notes == other.notes
+            && *status == other.status
+            && *pomodoros == other.pomodoros
+            && *time_spent == other.time_spent
+            && *tracking_since == other.tracking_since
+            && *sync_uid == other.sync_uid
+            && *color == other.color
+            && *reminder == other.reminder
+            && *pinned == other.pinned
+            && *completed_at == other.completed_at
+            && *icon == other.icon
+            && *my_day == other.my_day
+            && *habit == other.habit
+            && *quadrant == other.quadrant
+            && *estimate == other.estimate
+            && *context == other.context
+            && *assignee == other.assignee
+            && *attachments == other.attachments
+            && *fields == other.fields
+            && children.len() == other.children.len()
+            && children.iter().zip(&other.children).all(|(child, other)| child.same_content(other))
+    }
+
+    // Log how the item changed since `before`. Entries logged in between, like a recurring
+    // item being completed, are taken as covering it. Undoing a change doesn't take back its entry.
+    pub fn log_changes(&mut self, before: &TodoItem, now: DateTime<Utc>) {
+        if self.log.len() > before.log.len() {
+            return;
+        }
+        if self.log.len() < before.log.len() {
+            self.log = before.log.clone();
+        }
+        let activity = match (before.completed, self.completed) {
+            (false, true) => Activity::Completed,
+            (true, false) => Activity::Reopened,
+            _ if !self.same_content(before) => Activity::Edited,
+            _ => return,
+        };
+        self.log.push(LogEntry { at: now, activity });
+    }
+
+    // Items planned for today, open ones left over from earlier days and ones from earlier days
+    // that were checked off today, so they don't vanish from the plan the moment they are done
+    pub fn is_in_my_day(&self, today: NaiveDate) -> bool {
+        match self.my_day {
+            Some(day) if day == today => true,
+            Some(day) if day < today => {
+                !self.completed || self.completed_at.is_some_and(|at| at.with_timezone(&Local).date_naive() == today)
+            }
+            _ => false,
+        }
+    }
+
+    // Planned on an earlier day and still in My Day
+    pub fn is_carried_over(&self, today: NaiveDate) -> bool {
+        self.my_day.is_some_and(|day| day < today) && self.is_in_my_day(today)
+    }
+
+    // Current streak of a habit, which is done daily unless it repeats on another schedule
+    pub fn habit_streak(&self, today: NaiveDate) -> Option<usize> {
+        let habit = self.habit.as_ref()?;
+        Some(habit.streak(self.recurrence.unwrap_or(Recurrence::Daily), today))
+    }
+
+    // Minutes of work left: the item's own estimate, or else what its open subtasks add up to
+    pub fn remaining_estimate(&self) -> u32 {
+        if self.completed {
+            return 0;
+        }
+        self.estimate.unwrap_or_else(|| self.children.iter().map(TodoItem::remaining_estimate).sum())
+    }
+
+    // Open items whose start date is still to come aren't actionable yet
+    pub fn is_deferred(&self, today: NaiveDate) -> bool {
+        !self.completed && self.start_date.is_some_and(|start| start > today)
+    }
+
+    // An item is overdue if it is still open and its due date has passed
+    pub fn is_overdue(&self, today: NaiveDate) -> bool {
+        !self.completed && self.due_date.is_some_and(|due| due < today)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, day).unwrap()
+    }
+
+    #[test]
+    fn new_items_get_unique_ids() {
+        let first = TodoItem::new("first".to_string());
+        let second = TodoItem::new("second".to_string());
+        assert!(second.id > first.id);
+    }
+
+    #[test]
+    fn ids_are_reserved_above_loaded_ones() {
+        reserve_ids_up_to(1_000_000);
+        assert!(TodoItem::new("after load".to_string()).id > 1_000_000);
+    }
+
+    #[test]
+    fn new_items_get_different_uids() {
+        let first = TodoItem::new("first".to_string());
+        let second = TodoItem::new("second".to_string());
+        assert_ne!(first.uid, second.uid);
+        assert_ne!(first.duplicate(None).uid, first.uid);
+    }
+
+    #[test]
+    fn copies_of_an_old_save_agree_on_uids() {
+        let mut item = TodoItem { uid: String::new(), ..TodoItem::new("from an old save".to_string()) };
+        let mut copy = item.clone();
+        item.fill_missing_uid();
+        copy.fill_missing_uid();
+        assert!(!item.uid.is_empty());
+        assert_eq!(item.uid, copy.uid);
+    }
+
+    #[test]
+    fn completing_a_recurring_item_reopens_it() {
+        let mut item = TodoItem::new("water plants".to_string());
+        item.due_date = Some(day(13));
+        item.recurrence = Some(Recurrence::Weekly);
+        item.completed = true;
+        item.reschedule_if_recurring(day(13));
+        assert!(!item.completed);
+        assert_eq!(item.due_date, Some(day(20)));
+    }
+
+    #[test]
+    fn completing_a_habit_logs_the_day() {
+        let mut item = TodoItem::new("stretch".to_string());
+        item.habit = Some(Habit::default());
+        item.recurrence = Some(Recurrence::Daily);
+        item.completed = true;
+        item.reschedule_if_recurring(day(13));
+        assert!(!item.completed);
+        assert_eq!(item.habit_streak(day(13)), Some(1));
+        assert_eq!(item.habit.as_ref().map(|habit| habit.done_on.clone()), Some(vec![day(13)]));
+    }
+
+    #[test]
+    fn quadrant_follows_priority_until_placed() {
+        let mut item = TodoItem::new("report".to_string());
+        item.priority = Priority::High;
+        assert_eq!(item.quadrant(), Quadrant::Schedule);
+        item.set_quadrant(Quadrant::Delegate);
+        assert_eq!(item.quadrant(), Quadrant::Delegate);
+        assert_eq!(item.priority, Priority::Medium);
+    }
+
+    #[test]
+    fn shifting_keeps_the_start_before_the_due_date() {
+        let mut item = TodoItem::new("report".to_string());
+        item.start_date = Some(day(10));
+        item.due_date = Some(day(12));
+        item.shift_dates(3, 3);
+        assert_eq!((item.start_date, item.due_date), (Some(day(13)), Some(day(15))));
+        item.shift_dates(5, 0);
+        assert_eq!((item.start_date, item.due_date), (Some(day(15)), Some(day(15))));
+
+        let mut item = TodoItem::new("call".to_string());
+        item.due_date = Some(day(12));
+        item.shift_dates(0, 0);
+        assert_eq!(item.start_date, None);
+        item.shift_dates(-2, 0);
+        assert_eq!((item.start_date, item.due_date), (Some(day(10)), Some(day(12))));
+    }
+
+    #[test]
+    fn estimates_fall_back_to_open_subtasks() {
+        let mut item = TodoItem::new("move house".to_string());
+        item.children = vec![
+            TodoItem { estimate: Some(30), ..TodoItem::new("pack".to_string()) },
+            TodoItem { estimate: Some(60), completed: true, ..TodoItem::new("book van".to_string()) },
+            TodoItem { estimate: Some(45), ..TodoItem::new("clean".to_string()) },
+        ];
+        assert_eq!(item.remaining_estimate(), 75);
+        item.estimate = Some(240);
+        assert_eq!(item.remaining_estimate(), 240);
+        item.completed = true;
+        assert_eq!(item.remaining_estimate(), 0);
+    }
+
+    #[test]
+    fn only_open_items_are_overdue() {
+        let mut item = TodoItem::new("report".to_string());
+        item.due_date = Some(day(12));
+        assert!(item.is_overdue(day(13)));
+        assert!(!item.is_overdue(day(12)));
+        item.completed = true;
+        assert!(!item.is_overdue(day(13)));
+    }
+
+    #[test]
+    fn my_day_carries_over_open_items() {
+        let mut item = TodoItem::new("report".to_string());
+        assert!(!item.is_in_my_day(day(13)));
+        item.my_day = Some(day(13));
+        assert!(item.is_in_my_day(day(13)));
+        assert!(!item.is_carried_over(day(13)));
+        assert!(item.is_in_my_day(day(14)));
+        assert!(item.is_carried_over(day(14)));
+        // Done on an earlier day, so it drops out
+        item.completed = true;
+        item.completed_at = Some(Utc::now() - chrono::Duration::days(2));
+        assert!(!item.is_in_my_day(Local::now().date_naive()));
+        item.completed_at = Some(Utc::now());
+        item.my_day = Some(Local::now().date_naive() - chrono::Duration::days(1));
+        assert!(item.is_in_my_day(Local::now().date_naive()));
+    }
+
+    #[test]
+    fn blank_and_repeated_tags_are_ignored() {
+        let mut item = TodoItem::new("report".to_string());
+        item.add_tag(" work ");
+        item.add_tag("work");
+        item.add_tag("  ");
+        assert_eq!(item.tags, ["work"]);
+    }
+
+    #[test]
+    fn completion_stamp_follows_the_checkbox() {
+        let now = Utc::now();
+        let mut item = TodoItem::new("report".to_string());
+        item.completed = true;
+        item.stamp_completion(now);
+        assert_eq!(item.completed_at, Some(now));
+        // An existing stamp is kept
+        item.stamp_completion(now + chrono::Duration::hours(1));
+        assert_eq!(item.completed_at, Some(now));
+        item.completed = false;
+        item.stamp_completion(now);
+        assert_eq!(item.completed_at, None);
+    }
+
+    fn activities(item: &TodoItem) -> Vec<Activity> {
+        item.log.iter().map(|entry| entry.activity).collect()
+    }
+
+    #[test]
+    fn changes_are_logged() {
+        let now = Utc::now();
+        let mut item = TodoItem::new("report".to_string());
+        let before = item.clone();
+        item.completed = true;
+        item.log_changes(&before, now);
+        let before = item.clone();
+        item.description = "final report".to_string();
+        item.log_changes(&before, now);
+        let before = item.clone();
+        item.completed = false;
+        item.log_changes(&before, now);
+        // Nothing that is shown changed
+        let before = item.clone();
+        item.modified = Some(now);
+        item.log_changes(&before, now);
+        assert_eq!(activities(&item), [Activity::Created, Activity::Completed, Activity::Edited, Activity::Reopened]);
+    }
+
+    #[test]
+    fn field_color_and_attachment_edits_are_logged() {
+        let now = Utc::now();
+        let mut item = TodoItem::new("report".to_string());
+        let before = item.clone();
+        item.fields.insert("Cost".to_string(), FieldValue::Number(12.0));
+        item.log_changes(&before, now);
+        let before = item.clone();
+        item.color = Some(ColorLabel::Red);
+        item.log_changes(&before, now);
+        let before = item.clone();
+        item.attachments.push(Attachment::link("plan.pdf".into()));
+        item.log_changes(&before, now);
+        assert_eq!(activities(&item), [Activity::Created, Activity::Edited, Activity::Edited, Activity::Edited]);
+    }
+
+    #[test]
+    fn undone_changes_stay_logged() {
+        let now = Utc::now();
+        let original = TodoItem::new("report".to_string());
+        let mut item = original.clone();
+        item.description = "final report".to_string();
+        item.log_changes(&original, now);
+        let edited = item.clone();
+        let mut undone = original.clone();
+        undone.log_changes(&edited, now);
+        assert_eq!(activities(&undone), [Activity::Created, Activity::Edited, Activity::Edited]);
+    }
+
+    #[test]
+    fn completing_a_recurring_item_is_logged_once() {
+        let mut item = TodoItem::new("water plants".to_string());
+        item.recurrence = Some(Recurrence::Weekly);
+        let before = item.clone();
+        item.completed = true;
+        item.reschedule_if_recurring(day(13));
+        item.log_changes(&before, Utc::now());
+        assert_eq!(activities(&item), [Activity::Created, Activity::Completed]);
+    }
+
+    #[test]
+    fn duplicates_are_open_with_new_ids() {
+        let mut item = TodoItem::new("report".to_string());
+        item.completed = true;
+        item.due_date = Some(day(13));
+        item.children.push(TodoItem::new("draft".to_string()));
+        let copy = item.duplicate(Some(Recurrence::Weekly));
+        assert!(!copy.completed);
+        assert_eq!(copy.due_date, Some(day(20)));
+        assert_ne!(copy.id, item.id);
+        assert_ne!(copy.children[0].id, item.children[0].id);
+        assert_eq!(copy.children[0].description, "draft");
+    }
+}
@@ -0,0 +1,206 @@
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::io::{self, Read, Write};
+
+type HmacSha256 = Hmac<Sha256>;
+
+const CHALLENGE_LENGTH: usize = 32;
+const PROOF_LENGTH: usize = 32;
+// Anything longer from a peer is refused before it is read
+pub const MAX_MESSAGE_LENGTH: u64 = 64 * 1024 * 1024;
+
+// Which side of the connection sealed a message, so a message can't be sent back to its sender
+#[derive(Clone, Copy, PartialEq)]
+enum Side {
+    Client = 0,
+    Server = 1,
+}
+
+// A connection where both computers proved they know the shared key without sending it.
+// Messages are encrypted and authenticated with a key that only this connection uses.
+pub struct Session {
+    cipher: Aes256Gcm,
+    side: Side,
+    sent: u64,
+    received: u64,
+}
+
+fn mac(key: &str, label: &[u8], first: &[u8], second: &[u8]) -> HmacSha256 {
+    let mut mac = <HmacSha256 as Mac>::new_from_slice(key.as_bytes()).expect("HMAC takes keys of any length");
+    mac.update(label);
+    mac.update(first);
+    mac.update(second);
+    mac
+}
+
+fn challenge() -> [u8; CHALLENGE_LENGTH] {
+    let mut challenge = [0; CHALLENGE_LENGTH];
+    OsRng.fill_bytes(&mut challenge);
+    challenge
+}
+
+// Answer a computer that connected to us. None if it doesn't know the key or hung up.
+pub fn accept<S: Read + Write>(stream: &mut S, key: &str) -> io::Result<Option<Session>> {
+    let ours = challenge();
+    stream.write_all(&ours)?;
+    stream.flush()?;
+    let mut theirs = [0; CHALLENGE_LENGTH];
+    let mut proof = [0; PROOF_LENGTH];
+    if stream.read_exact(&mut theirs).and_then(|()| stream.read_exact(&mut proof)).is_err() {
+        return Ok(None);
+    }
+    // Checked in constant time
+    if mac(key, b"client", &ours, &theirs).verify_slice(&proof).is_err() {
+        return Ok(None);
+    }
+    stream.write_all(&mac(key, b"server", &theirs, &ours).finalize().into_bytes())?;
+    stream.flush()?;
+    Ok(Some(Session::new(key, &ours, &theirs, Side::Server)))
+}
+
+// Prove we know the key to the computer we connected to, and check that it does too before
+// anything else is sent. None if it hung up, which it does when another key is set over there.
+pub fn connect<S: Read + Write>(stream: &mut S, key: &str) -> io::Result<Option<Session>> {
+    let mut theirs = [0; CHALLENGE_LENGTH];
+    stream.read_exact(&mut theirs)?;
+    let ours = challenge();
+    stream.write_all(&ours)?;
+    stream.write_all(&mac(key, b"client", &theirs, &ours).finalize().into_bytes())?;
+    stream.flush()?;
+    let mut proof = [0; PROOF_LENGTH];
+    match stream.read_exact(&mut proof) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    if mac(key, b"server", &ours, &theirs).verify_slice(&proof).is_err() {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "the other computer doesn't know the key"));
+    }
+    Ok(Some(Session::new(key, &theirs, &ours, Side::Client)))
+}
+
+impl Session {
+    fn new(key: &str, server_challenge: &[u8], client_challenge: &[u8], side: Side) -> Self {
+        let session_key = mac(key, b"session", server_challenge, client_challenge).finalize().into_bytes();
+        Self {
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&session_key)),
+            side,
+            sent: 0,
+            received: 0,
+        }
+    }
+
+    // The session key is new for each connection, so the sender and a counter make unique nonces
+    fn nonce(side: Side, counter: u64) -> [u8; 12] {
+        let mut nonce = [0; 12];
+        nonce[0] = side as u8;
+        nonce[4..].copy_from_slice(&counter.to_be_bytes());
+        nonce
+    }
+
+    // The length of the ciphertext and then the ciphertext
+    pub fn send(&mut self, stream: &mut impl Write, message: &[u8]) -> io::Result<()> {
+        let nonce = Self::nonce(self.side, self.sent);
+        self.sent += 1;
+        let ciphertext = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), message)
+            .map_err(|_| io::Error::other("failed to encrypt the message"))?;
+        stream.write_all(&(ciphertext.len() as u64).to_be_bytes())?;
+        stream.write_all(&ciphertext)?;
+        stream.flush()
+    }
+
+    pub fn receive(&mut self, stream: &mut impl Read) -> io::Result<Vec<u8>> {
+        let mut length = [0; 8];
+        stream.read_exact(&mut length)?;
+        let length = u64::from_be_bytes(length);
+        if length > MAX_MESSAGE_LENGTH {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "the other computer sent too much data"));
+        }
+        let mut ciphertext = Vec::new();
+        stream.take(length).read_to_end(&mut ciphertext)?;
+        if ciphertext.len() as u64 != length {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let their_side = if self.side == Side::Client { Side::Server } else { Side::Client };
+        let nonce = Self::nonce(their_side, self.received);
+        self.received += 1;
+        self.cipher
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "the message was changed on the way"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{Shutdown, TcpListener, TcpStream};
+    use std::thread;
+
+    // Runs `server` on one end of a local connection and returns what `client` made of the other
+    fn talk<T: Send + 'static>(server: impl FnOnce(TcpStream) + Send + 'static, client: impl FnOnce(TcpStream) -> T) -> T {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let handle = thread::spawn(move || server(listener.accept().unwrap().0));
+        let result = client(TcpStream::connect(address).unwrap());
+        handle.join().unwrap();
+        result
+    }
+
+    #[test]
+    fn same_key_exchanges_messages() {
+        let reply = talk(
+            |mut stream| {
+                let mut session = accept(&mut stream, "secret").unwrap().unwrap();
+                let message = session.receive(&mut stream).unwrap();
+                assert_eq!(message, b"ours");
+                session.send(&mut stream, b"theirs").unwrap();
+            },
+            |mut stream| {
+                let mut session = connect(&mut stream, "secret").unwrap().unwrap();
+                session.send(&mut stream, b"ours").unwrap();
+                session.receive(&mut stream).unwrap()
+            },
+        );
+        assert_eq!(reply, b"theirs");
+    }
+
+    #[test]
+    fn other_key_is_hung_up_on_before_anything_is_sent() {
+        let session = talk(
+            |mut stream| {
+                assert!(accept(&mut stream, "secret").unwrap().is_none());
+                let _ = stream.shutdown(Shutdown::Both);
+            },
+            |mut stream| connect(&mut stream, "guess").unwrap(),
+        );
+        assert!(session.is_none());
+    }
+
+    #[test]
+    fn peer_without_the_key_is_not_trusted() {
+        let result = talk(
+            |mut stream| {
+                // Pretends to be a peer, answering with a proof made up without the key
+                stream.write_all(&[0; CHALLENGE_LENGTH]).unwrap();
+                let mut hello = [0; CHALLENGE_LENGTH + PROOF_LENGTH];
+                stream.read_exact(&mut hello).unwrap();
+                stream.write_all(&[0; PROOF_LENGTH]).unwrap();
+            },
+            |mut stream| connect(&mut stream, "secret"),
+        );
+        assert_eq!(result.err().map(|e| e.kind()), Some(io::ErrorKind::InvalidData));
+    }
+
+    #[test]
+    fn oversized_message_is_refused_unread() {
+        let mut session = Session::new("secret", &[0; CHALLENGE_LENGTH], &[1; CHALLENGE_LENGTH], Side::Server);
+        let mut stream = io::Cursor::new((MAX_MESSAGE_LENGTH + 1).to_be_bytes().to_vec());
+        let error = session.receive(&mut stream).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
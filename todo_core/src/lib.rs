@@ -0,0 +1,43 @@
+// Todo list model, persistence and filtering, shared by the GUI and any other front end
+
+pub mod attachment;
+pub mod autosave;
+#[cfg(target_arch = "wasm32")]
+pub mod browser;
+pub mod caldav;
+pub mod checklist;
+pub mod csv_io;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod database;
+pub mod encryption;
+pub mod export;
+pub mod fields;
+pub mod file_sync;
+pub mod filter;
+pub mod google_tasks;
+pub mod habit;
+pub mod history;
+pub mod html;
+pub mod ical;
+pub mod item;
+pub mod lan;
+pub mod list;
+pub mod merge;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod pdf;
+pub mod query;
+pub mod quick_add;
+pub mod recurrence;
+pub mod reminder;
+pub mod search;
+pub mod settings;
+pub mod stats;
+pub mod storage;
+pub mod theme;
+pub mod todoist;
+pub mod tracking;
+
+pub use filter::{Filter, SortOrder};
+pub use item::{Activity, ColorLabel, LogEntry, Priority, Quadrant, Status, TodoItem};
+pub use list::{ArchivedItem, DeletedItem, TodoList, TrashedItem, TRASH_RETENTION_DAYS};
+pub use storage::{load_lists, read_lists, save_lists, write_lists, Storage, StorageError};
@@ -0,0 +1,302 @@
+use crate::fields::FieldDef;
+use crate::history::{Command, History};
+use crate::TodoItem;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+// Deleted items are kept this long before being purged from the trash
+pub const TRASH_RETENTION_DAYS: u64 = 30;
+// Items deleted for good are remembered this long for syncing
+const DELETION_RETENTION_DAYS: u64 = 365;
+
+// A deleted item waiting in the trash to be restored or purged
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TrashedItem {
+    pub item: TodoItem,
+    pub deleted_on: NaiveDate,
+}
+
+impl TrashedItem {
+    pub fn new(item: TodoItem, deleted_on: NaiveDate) -> Self {
+        Self { item, deleted_on }
+    }
+}
+
+// A completed item moved out of the list by "Clear completed"
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ArchivedItem {
+    pub item: TodoItem,
+    pub archived_on: NaiveDate,
+}
+
+impl ArchivedItem {
+    pub fn new(item: TodoItem, archived_on: NaiveDate) -> Self {
+        Self { item, archived_on }
+    }
+}
+
+// An item removed from the trash for good, remembered so syncing doesn't bring it back
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DeletedItem {
+    pub uid: String,
+    pub deleted_at: DateTime<Utc>,
+}
+
+impl DeletedItem {
+    fn of(item: &TodoItem, deleted_at: DateTime<Utc>) -> Self {
+        Self { uid: item.uid.clone(), deleted_at }
+    }
+}
+
+// A named list of items, shown as a tab
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct TodoList {
+    pub name: String,
+    // Emoji shown before the name in the list tabs
+    #[serde(default)]
+    pub icon: Option<String>,
+    pub items: Vec<TodoItem>,
+    #[serde(default)]
+    pub trash: Vec<TrashedItem>,
+    #[serde(default)]
+    pub archive: Vec<ArchivedItem>,
+    // Custom fields every item of the list can fill in
+    #[serde(default)]
+    pub fields: Vec<FieldDef>,
+    #[serde(default)]
+    pub deleted: Vec<DeletedItem>,
+    // Each list keeps its own undo history for the session
+    #[serde(skip)]
+    pub history: History,
+}
+
+impl TodoList {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            ..Default::default()
+        }
+    }
+
+    // Everything that is saved, leaving out the undo history, e.g. for a save written in the background
+    pub fn saved_copy(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            icon: self.icon.clone(),
+            items: self.items.clone(),
+            trash: self.trash.clone(),
+            archive: self.archive.clone(),
+            fields: self.fields.clone(),
+            deleted: self.deleted.clone(),
+            history: History::default(),
+        }
+    }
+
+    pub fn purge_old_trash(&mut self, today: NaiveDate) {
+        let retention = chrono::Days::new(TRASH_RETENTION_DAYS);
+        let now = Utc::now();
+        let (kept, expired): (Vec<_>, Vec<_>) = std::mem::take(&mut self.trash)
+            .into_iter()
+            .partition(|trashed| trashed.deleted_on.checked_add_days(retention).is_none_or(|expires| expires > today));
+        self.trash = kept;
+        self.deleted.extend(expired.iter().map(|trashed| DeletedItem::of(&trashed.item, now)));
+        // By now every copy has seen the deletion, or is so out of date that it won't matter
+        let forgotten = chrono::Days::new(DELETION_RETENTION_DAYS);
+        self.deleted
+            .retain(|deleted| deleted.deleted_at.date_naive().checked_add_days(forgotten).is_none_or(|expires| expires > today));
+    }
+
+    // Remove an item from the trash for good
+    pub fn purge(&mut self, trash_index: usize) {
+        let trashed = self.trash.remove(trash_index);
+        self.deleted.push(DeletedItem::of(&trashed.item, Utc::now()));
+    }
+
+    pub fn empty_trash(&mut self) {
+        let now = Utc::now();
+        let trash = std::mem::take(&mut self.trash);
+        self.deleted.extend(trash.iter().map(|trashed| DeletedItem::of(&trashed.item, now)));
+    }
+
+    pub fn undo(&mut self) {
+        self.history.undo(&mut self.items, &mut self.trash, &mut self.archive);
+    }
+
+    pub fn redo(&mut self) {
+        self.history.redo(&mut self.items, &mut self.trash, &mut self.archive);
+    }
+
+    // Move every completed item to the archive, undoable as one step
+    pub fn archive_completed(&mut self, today: NaiveDate) {
+        let mut commands = Vec::new();
+        // Reverse iterate so the recorded indices stay valid when undone in reverse
+        for index in (0..self.items.len()).rev() {
+            if self.items[index].completed {
+                let item = self.items.remove(index);
+                self.archive.push(ArchivedItem::new(item.clone(), today));
+                commands.push(Command::Archive { index, item });
+            }
+        }
+        if !commands.is_empty() {
+            self.history.push(Command::Batch(commands));
+        }
+    }
+
+    // Move items that were completed more than `after_days` ago to the archive. Not undoable,
+    // it happens on its own. Completed items without a stamp, e.g. from older saves, start
+    // counting now. Returns true if anything changed.
+    pub fn archive_stale_completed(&mut self, now: DateTime<Utc>, after_days: u32) -> bool {
+        let mut changed = false;
+        for item in self.items.iter_mut().filter(|item| item.completed && item.completed_at.is_none()) {
+            item.completed_at = Some(now);
+            changed = true;
+        }
+        let cutoff = now - chrono::Duration::days(i64::from(after_days));
+        let today = now.date_naive();
+        let (stale, kept): (Vec<TodoItem>, Vec<TodoItem>) = std::mem::take(&mut self.items)
+            .into_iter()
+            .partition(|item| item.completed && item.completed_at.is_some_and(|at| at <= cutoff));
+        self.items = kept;
+        changed |= !stale.is_empty();
+        self.archive.extend(stale.into_iter().map(|item| ArchivedItem::new(item, today)));
+        changed
+    }
+
+    // Number of completed items and the total, not counting subtasks
+    pub fn progress(&self) -> (usize, usize) {
+        let done = self.items.iter().filter(|item| item.completed).count();
+        (done, self.items.len())
+    }
+
+    // Put a trashed item back at the end of the list
+    pub fn restore(&mut self, trash_index: usize) {
+        let trashed = self.trash.remove(trash_index);
+        self.items.push(trashed.item);
+    }
+
+    // Put an archived item back at the end of the list, still completed
+    pub fn unarchive(&mut self, archive_index: usize) {
+        let archived = self.archive.remove(archive_index);
+        self.items.push(archived.item);
+    }
+
+    pub(crate) fn fill_missing_uids(&mut self) {
+        self.items
+            .iter_mut()
+            .chain(self.trash.iter_mut().map(|trashed| &mut trashed.item))
+            .chain(self.archive.iter_mut().map(|archived| &mut archived.item))
+            .for_each(TodoItem::fill_missing_uid);
+    }
+
+    // Give every item a new ID, including subtasks and items in the trash and archive
+    pub(crate) fn renumber(&mut self) {
+        self.items = std::mem::take(&mut self.items).into_iter().map(TodoItem::renumbered).collect();
+        for trashed in &mut self.trash {
+            trashed.item = trashed.item.clone().renumbered();
+        }
+        for archived in &mut self.archive {
+            archived.item = archived.item.clone().renumbered();
+        }
+    }
+
+    // Highest ID used by any item in the list, its trash or its archive
+    pub fn max_id(&self) -> u32 {
+        self.items
+            .iter()
+            .chain(self.trash.iter().map(|trashed| &trashed.item))
+            .chain(self.archive.iter().map(|archived| &archived.item))
+            .map(TodoItem::max_id)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, day).unwrap()
+    }
+
+    #[test]
+    fn trash_is_purged_after_the_retention_period() {
+        let mut list = TodoList::new("Test".to_string());
+        list.trash.push(TrashedItem::new(TodoItem::new("old".to_string()), day(1)));
+        list.trash.push(TrashedItem::new(TodoItem::new("recent".to_string()), day(20)));
+        list.purge_old_trash(day(31));
+        assert_eq!(list.trash.len(), 1);
+        assert_eq!(list.trash[0].item.description, "recent");
+    }
+
+    #[test]
+    fn purged_items_are_remembered_as_deleted() {
+        let mut list = TodoList::new("Test".to_string());
+        list.trash.push(TrashedItem::new(TodoItem::new("old".to_string()), day(1)));
+        list.trash.push(TrashedItem::new(TodoItem::new("picked".to_string()), day(20)));
+        list.trash.push(TrashedItem::new(TodoItem::new("emptied".to_string()), day(20)));
+        let uids: Vec<String> = list.trash.iter().map(|trashed| trashed.item.uid.clone()).collect();
+        list.purge_old_trash(day(31));
+        list.purge(0);
+        list.empty_trash();
+        assert!(list.trash.is_empty());
+        assert_eq!(list.deleted.iter().map(|deleted| deleted.uid.clone()).collect::<Vec<_>>(), uids);
+    }
+
+    #[test]
+    fn restored_items_go_to_the_end() {
+        let mut list = TodoList::new("Test".to_string());
+        list.items.push(TodoItem::new("kept".to_string()));
+        list.trash.push(TrashedItem::new(TodoItem::new("deleted".to_string()), day(1)));
+        list.restore(0);
+        assert!(list.trash.is_empty());
+        assert_eq!(list.items[1].description, "deleted");
+    }
+
+    #[test]
+    fn stale_completed_items_are_archived() {
+        let now = Utc.with_ymd_and_hms(2024, 3, 20, 12, 0, 0).unwrap();
+        let mut list = TodoList::new("Test".to_string());
+        let mut old = TodoItem::new("old".to_string());
+        old.completed = true;
+        old.completed_at = Some(now - chrono::Duration::days(15));
+        let mut recent = TodoItem::new("recent".to_string());
+        recent.completed = true;
+        recent.completed_at = Some(now - chrono::Duration::days(3));
+        list.items = vec![old, recent, TodoItem::new("open".to_string())];
+
+        assert!(list.archive_stale_completed(now, 14));
+        assert_eq!(list.archive.len(), 1);
+        assert_eq!(list.archive[0].item.description, "old");
+        assert_eq!(list.progress(), (1, 2));
+        assert!(!list.archive_stale_completed(now, 14));
+    }
+
+    #[test]
+    fn completed_items_without_a_stamp_start_counting_now() {
+        let now = Utc.with_ymd_and_hms(2024, 3, 20, 12, 0, 0).unwrap();
+        let mut list = TodoList::new("Test".to_string());
+        let mut unstamped = TodoItem::new("unstamped".to_string());
+        unstamped.completed = true;
+        list.items.push(unstamped);
+
+        assert!(list.archive_stale_completed(now, 14));
+        assert!(list.archive.is_empty());
+        assert_eq!(list.items[0].completed_at, Some(now));
+    }
+
+    #[test]
+    fn max_id_covers_subtasks_trash_and_archive() {
+        let mut list = TodoList::new("Test".to_string());
+        let mut parent = TodoItem::new("parent".to_string());
+        parent.children.push(TodoItem::new("child".to_string()));
+        let trashed = TodoItem::new("trashed".to_string());
+        let archived = TodoItem::new("archived".to_string());
+        let highest = archived.id;
+        list.items.push(parent);
+        list.trash.push(TrashedItem::new(trashed, day(1)));
+        list.archive.push(ArchivedItem::new(archived, day(1)));
+        assert_eq!(list.max_id(), highest);
+    }
+}
@@ -0,0 +1,392 @@
+use crate::{ArchivedItem, DeletedItem, TodoItem, TodoList, TrashedItem};
+use std::collections::HashSet;
+
+// Where both copies changed an item, the one changed last wins. An unknown time counts as oldest.
+fn is_newer(item: &TodoItem, than: &TodoItem) -> bool {
+    item.modified > than.modified
+}
+
+// Deleted for good on either side and not changed since
+fn is_deleted(deleted: &[DeletedItem], item: &TodoItem) -> bool {
+    deleted
+        .iter()
+        .any(|deleted| deleted.uid == item.uid && item.modified.is_none_or(|modified| modified <= deleted.deleted_at))
+}
+
+fn position(items: &[TodoItem], uid: &str) -> Option<usize> {
+    items.iter().position(|item| item.uid == uid)
+}
+
+// Merge the lists of another copy of the save into these, matching lists by name and items by `uid`.
+// Lists only the other copy has are added. Returns true if anything changed.
+pub fn merge_lists(lists: &mut Vec<TodoList>, other: Vec<TodoList>) -> bool {
+    let mut changed = false;
+    for theirs in other {
+        match lists.iter_mut().find(|list| list.name == theirs.name) {
+            Some(list) => changed |= merge_list(list, theirs),
+            None => {
+                lists.push(TodoList {
+                    items: theirs.items.into_iter().map(TodoItem::renumbered).collect(),
+                    trash: theirs
+                        .trash
+                        .into_iter()
+                        .map(|trashed| TrashedItem::new(trashed.item.renumbered(), trashed.deleted_on))
+                        .collect(),
+                    archive: theirs
+                        .archive
+                        .into_iter()
+                        .map(|archived| ArchivedItem::new(archived.item.renumbered(), archived.archived_on))
+                        .collect(),
+                    icon: theirs.icon,
+                    fields: theirs.fields,
+                    deleted: theirs.deleted,
+                    ..TodoList::new(theirs.name)
+                });
+                changed = true;
+            }
+        }
+    }
+    changed
+}
+
+// Merge another copy of the list into this one. Items deleted or archived on one side are
+// deleted or archived on the other too, unless they were changed there since, and items
+// purged from the trash on one side don't come back from the other. Items being edited
+// here are left alone until the edit is done. Returns true if anything changed.
+pub fn merge_list(list: &mut TodoList, other: TodoList) -> bool {
+    let mut changed = false;
+
+    for deleted in other.deleted {
+        if !list.deleted.iter().any(|ours| ours.uid == deleted.uid) {
+            list.deleted.push(deleted);
+            changed = true;
+        }
+    }
+    let deleted = std::mem::take(&mut list.deleted);
+    let (items, trash, archive) = (list.items.len(), list.trash.len(), list.archive.len());
+    list.items.retain(|item| item.is_editing() || !is_deleted(&deleted, item));
+    list.trash.retain(|trashed| !is_deleted(&deleted, &trashed.item));
+    list.archive.retain(|archived| !is_deleted(&deleted, &archived.item));
+    changed |= (items, trash, archive) != (list.items.len(), list.trash.len(), list.archive.len());
+    list.deleted = deleted;
+
+    // Custom fields added on either side are kept
+    for field in other.fields {
+        if !list.fields.iter().any(|ours| ours.name == field.name) {
+            list.fields.push(field);
+            changed = true;
+        }
+    }
+
+    for theirs in other.items {
+        if is_deleted(&list.deleted, &theirs) {
+            continue;
+        }
+        if let Some(ours) = list.items.iter_mut().find(|item| item.uid == theirs.uid) {
+            if is_newer(&theirs, ours) && !ours.is_editing() {
+                *ours = TodoItem { id: ours.id, ..theirs.renumbered() };
+                changed = true;
+            }
+            continue;
+        }
+        // Gone from here, it only comes back if it was changed over there since
+        let trashed = list.trash.iter().position(|trashed| trashed.item.uid == theirs.uid);
+        let archived = list.archive.iter().position(|archived| archived.item.uid == theirs.uid);
+        let restored = match (trashed, archived) {
+            (Some(index), _) if is_newer(&theirs, &list.trash[index].item) => Some(list.trash.remove(index).item),
+            (_, Some(index)) if is_newer(&theirs, &list.archive[index].item) => Some(list.archive.remove(index).item),
+            (None, None) => None,
+            _ => continue,
+        };
+        let item = theirs.renumbered();
+        list.items.push(match restored {
+            Some(ours) => TodoItem { id: ours.id, ..item },
+            None => item,
+        });
+        changed = true;
+    }
+
+    for trashed in other.trash {
+        match position(&list.items, &trashed.item.uid) {
+            Some(index) if !is_newer(&list.items[index], &trashed.item) && !list.items[index].is_editing() => {
+                let item = list.items.remove(index);
+                list.trash.push(TrashedItem::new(item, trashed.deleted_on));
+                changed = true;
+            }
+            // Their trash isn't brought over for items this copy never had
+            _ => {}
+        }
+    }
+
+    for archived in other.archive {
+        let known = list.archive.iter().any(|ours| ours.item.uid == archived.item.uid)
+            || list.trash.iter().any(|ours| ours.item.uid == archived.item.uid);
+        match position(&list.items, &archived.item.uid) {
+            Some(index) if !is_newer(&list.items[index], &archived.item) && !list.items[index].is_editing() => {
+                let item = list.items.remove(index);
+                list.archive.push(ArchivedItem::new(item, archived.archived_on));
+                changed = true;
+            }
+            None if !known && !is_deleted(&list.deleted, &archived.item) => {
+                list.archive.push(ArchivedItem::new(archived.item.renumbered(), archived.archived_on));
+                changed = true;
+            }
+            _ => {}
+        }
+    }
+    changed
+}
+
+// Merge a list from another save file into this one, e.g. a conflicted copy left by a sync client.
+// Items are matched by `uid` as in `merge_list`, then items new to this list that read the same
+// as one already in it are dropped, since copies of one task made on different devices or
+// saves from before `uid` don't share it. Returns how many items were added.
+pub fn merge_from_file(list: &mut TodoList, other: TodoList) -> usize {
+    let known: HashSet<String> = list.items.iter().map(|item| item.uid.clone()).collect();
+    merge_list(list, other);
+    let existing: Vec<TodoItem> = list.items.iter().filter(|item| known.contains(&item.uid)).cloned().collect();
+    let mut added = 0;
+    list.items.retain(|item| {
+        let keep = known.contains(&item.uid) || !existing.iter().any(|ours| ours.same_content(item));
+        if keep && !known.contains(&item.uid) {
+            added += 1;
+        }
+        keep
+    });
+    added
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, NaiveDate, Utc};
+
+    fn day() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, 13).unwrap()
+    }
+
+    // The other machine's copy of a list, as it arrives over the network
+    fn copy(list: &TodoList) -> TodoList {
+        TodoList {
+            items: list.items.clone(),
+            trash: list.trash.clone(),
+            archive: list.archive.clone(),
+            deleted: list.deleted.clone(),
+            ..TodoList::new(list.name.clone())
+        }
+    }
+
+    fn list_with(descriptions: &[&str]) -> TodoList {
+        TodoList {
+            items: descriptions.iter().map(|description| TodoItem::new(description.to_string())).collect(),
+            ..TodoList::new("Todo".to_string())
+        }
+    }
+
+    fn touch(item: &mut TodoItem, minutes: i64) {
+        item.modified = Some(Utc::now() + Duration::minutes(minutes));
+    }
+
+    #[test]
+    fn identical_copies_change_nothing() {
+        let mut list = list_with(&["a", "b"]);
+        let other = copy(&list);
+        assert!(!merge_list(&mut list, other));
+        assert_eq!(list.items.len(), 2);
+    }
+
+    #[test]
+    fn items_added_over_there_are_added_under_new_ids() {
+        let mut list = list_with(&["a"]);
+        let mut other = copy(&list);
+        other.items.push(TodoItem::new("b".to_string()));
+        let added = other.items[1].clone();
+
+        assert!(merge_list(&mut list, other));
+        assert_eq!(list.items[1].uid, added.uid);
+        assert_eq!(list.items[1].description, "b");
+        assert_ne!(list.items[1].id, added.id);
+    }
+
+    #[test]
+    fn the_newer_edit_wins() {
+        let mut list = list_with(&["a", "b"]);
+        let mut other = copy(&list);
+        other.items[0].description = "a, changed there".to_string();
+        touch(&mut other.items[0], 5);
+        list.items[1].description = "b, changed here".to_string();
+        touch(&mut list.items[1], 5);
+        other.items[1].description = "b, changed there first".to_string();
+        touch(&mut other.items[1], 1);
+        let id = list.items[0].id;
+
+        assert!(merge_list(&mut list, other));
+        assert_eq!(list.items[0].description, "a, changed there");
+        assert_eq!(list.items[0].id, id);
+        assert_eq!(list.items[1].description, "b, changed here");
+    }
+
+    #[test]
+    fn items_being_edited_are_left_alone() {
+        let mut list = list_with(&["a"]);
+        let mut other = copy(&list);
+        other.items[0].description = "changed there".to_string();
+        touch(&mut other.items[0], 5);
+        list.items[0].edit = true;
+
+        assert!(!merge_list(&mut list, other));
+        assert_eq!(list.items[0].description, "a");
+    }
+
+    #[test]
+    fn deletions_carry_over_unless_changed_since() {
+        let mut list = list_with(&["a", "b"]);
+        let mut other = copy(&list);
+        let deleted = other.items.drain(..).map(|item| TrashedItem::new(item, day())).collect();
+        other.trash = deleted;
+        touch(&mut list.items[1], 5);
+
+        assert!(merge_list(&mut list, other));
+        assert_eq!(list.items.len(), 1);
+        assert_eq!(list.items[0].description, "b");
+        assert_eq!(list.trash.len(), 1);
+        assert_eq!(list.trash[0].item.description, "a");
+    }
+
+    #[test]
+    fn deleted_items_stay_deleted() {
+        let mut list = list_with(&["a"]);
+        let other = copy(&list);
+        let item = list.items.remove(0);
+        list.trash.push(TrashedItem::new(item, day()));
+
+        assert!(!merge_list(&mut list, other));
+        assert!(list.items.is_empty());
+    }
+
+    #[test]
+    fn deleted_items_come_back_if_changed_over_there() {
+        let mut list = list_with(&["a"]);
+        let mut other = copy(&list);
+        let item = list.items.remove(0);
+        let id = item.id;
+        list.trash.push(TrashedItem::new(item, day()));
+        touch(&mut other.items[0], 5);
+
+        assert!(merge_list(&mut list, other));
+        assert!(list.trash.is_empty());
+        assert_eq!(list.items[0].id, id);
+    }
+
+    #[test]
+    fn items_purged_here_are_not_brought_back() {
+        let mut list = list_with(&["a", "b"]);
+        let other = copy(&list);
+        let item = list.items.remove(0);
+        list.trash.push(TrashedItem::new(item, day()));
+        list.empty_trash();
+
+        assert!(!merge_list(&mut list, other));
+        assert_eq!(list.items.len(), 1);
+        assert_eq!(list.items[0].description, "b");
+    }
+
+    #[test]
+    fn items_purged_over_there_are_removed_here() {
+        let mut list = list_with(&["a", "b"]);
+        let mut other = copy(&list);
+        let trashed = list.items.remove(1);
+        list.trash.push(TrashedItem::new(trashed, day()));
+        other.trash = other.items.drain(..).map(|item| TrashedItem::new(item, day())).collect();
+        other.empty_trash();
+
+        assert!(merge_list(&mut list, other));
+        assert!(list.items.is_empty());
+        assert!(list.trash.is_empty());
+        assert_eq!(list.deleted.len(), 2);
+    }
+
+    #[test]
+    fn purged_items_come_back_if_changed_over_there_since() {
+        let mut list = list_with(&["a"]);
+        let mut other = copy(&list);
+        let item = list.items.remove(0);
+        list.trash.push(TrashedItem::new(item, day()));
+        list.empty_trash();
+        touch(&mut other.items[0], 5);
+
+        assert!(merge_list(&mut list, other));
+        assert_eq!(list.items.len(), 1);
+    }
+
+    #[test]
+    fn archived_items_are_brought_over() {
+        let mut list = list_with(&["a", "b"]);
+        let mut other = copy(&list);
+        let done = other.items.remove(0);
+        other.archive.push(ArchivedItem::new(done, day()));
+        other.archive.push(ArchivedItem::new(TodoItem::new("only there".to_string()), day()));
+
+        assert!(merge_list(&mut list, other));
+        assert_eq!(list.items.len(), 1);
+        let archived: Vec<&str> = list.archive.iter().map(|archived| archived.item.description.as_str()).collect();
+        assert_eq!(archived, ["a", "only there"]);
+    }
+
+    #[test]
+    fn merging_twice_changes_nothing_more() {
+        let mut list = list_with(&["a"]);
+        let mut other = list_with(&["b"]);
+        other.archive.push(ArchivedItem::new(TodoItem::new("c".to_string()), day()));
+        let again = copy(&other);
+
+        assert!(merge_list(&mut list, other));
+        assert!(!merge_list(&mut list, again));
+        assert_eq!(list.items.len(), 2);
+        assert_eq!(list.archive.len(), 1);
+    }
+
+    #[test]
+    fn merging_a_file_drops_copies_with_other_uids() {
+        let mut list = list_with(&["a", "b"]);
+        let mut other = list_with(&["a", "c"]);
+        other.items.push(list.items[1].clone());
+
+        assert_eq!(merge_from_file(&mut list, other), 1);
+        let descriptions: Vec<&str> = list.items.iter().map(|item| item.description.as_str()).collect();
+        assert_eq!(descriptions, ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn merging_a_file_keeps_items_that_differ() {
+        let mut list = list_with(&["a"]);
+        let mut other = list_with(&["a"]);
+        other.items[0].completed = true;
+
+        assert_eq!(merge_from_file(&mut list, other), 1);
+        assert_eq!(list.items.len(), 2);
+    }
+
+    #[test]
+    fn merging_a_file_keeps_items_that_differ_in_attachments_or_fields() {
+        let mut list = list_with(&["a", "b"]);
+        let mut other = list_with(&["a", "b"]);
+        other.items[0].attachments.push(crate::attachment::Attachment::link("plan.pdf".into()));
+        other.items[1].fields.insert("Cost".to_string(), crate::fields::FieldValue::Number(12.0));
+
+        assert_eq!(merge_from_file(&mut list, other), 2);
+        assert_eq!(list.items.len(), 4);
+    }
+
+    #[test]
+    fn lists_only_there_are_added() {
+        let mut lists = vec![list_with(&["a"])];
+        let mut other = vec![copy(&lists[0]), list_with(&["b"])];
+        other[1].name = "Work".to_string();
+
+        assert!(merge_lists(&mut lists, other));
+        assert_eq!(lists.len(), 2);
+        assert_eq!(lists[1].name, "Work");
+        assert_eq!(lists[1].items[0].description, "b");
+    }
+}
@@ -0,0 +1,188 @@
+use crate::export::Section;
+use crate::TodoItem;
+use printpdf::{BuiltinFont, Color, IndirectFontRef, Line, Mm, PdfDocument, PdfDocumentReference, PdfLayerReference, Point, Rgb};
+
+// A4 portrait
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const MARGIN: f32 = 20.0;
+const FONT_SIZE: f32 = 11.0;
+const LINE_HEIGHT: f32 = 6.0;
+const SUBTASK_INDENT: f32 = 6.0;
+const MM_PER_PT: f32 = 25.4 / 72.0;
+
+// The built-in fonts come without metrics, so text is measured by Helvetica's average glyph
+// width of about half the font size. Good enough for wrapping and the strikethrough.
+fn text_width(text: &str, font_size: f32) -> f32 {
+    text.chars().count() as f32 * font_size * 0.5 * MM_PER_PT
+}
+
+// Break `text` into lines no wider than `width`, long words are left whole
+fn wrap(text: &str, width: f32) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            if !line.is_empty() && text_width(&format!("{} {}", line, word), FONT_SIZE) > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+fn grey() -> Color {
+    Color::Rgb(Rgb::new(0.45, 0.45, 0.45, None))
+}
+
+fn black() -> Color {
+    Color::Rgb(Rgb::new(0.0, 0.0, 0.0, None))
+}
+
+fn line(layer: &PdfLayerReference, points: &[(f32, f32)], is_closed: bool) {
+    layer.add_line(Line {
+        points: points.iter().map(|&(x, y)| (Point::new(Mm(x), Mm(y)), false)).collect(),
+        is_closed,
+    });
+}
+
+// Writes lines top to bottom, starting a new page when one is full
+struct Writer {
+    doc: PdfDocumentReference,
+    font: IndirectFontRef,
+    bold: IndirectFontRef,
+    layers: Vec<PdfLayerReference>,
+    y: f32,
+}
+
+impl Writer {
+    fn layer(&self) -> &PdfLayerReference {
+        self.layers.last().expect("the document starts with a page")
+    }
+
+    // Room for `height` below the current line, on a new page if this one is full
+    fn make_room(&mut self, height: f32) {
+        if self.y - height < MARGIN {
+            let (page, layer) = self.doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Tasks");
+            self.layers.push(self.doc.get_page(page).get_layer(layer));
+            self.y = PAGE_HEIGHT - MARGIN;
+        }
+    }
+
+    fn heading(&mut self, text: &str, font_size: f32) {
+        // Keep a heading on the page of the first task below it
+        self.make_room(LINE_HEIGHT * 3.0);
+        self.y -= LINE_HEIGHT * 1.5;
+        self.layer().set_fill_color(black());
+        self.layer().use_text(text, font_size, Mm(MARGIN), Mm(self.y), &self.bold);
+        self.y -= LINE_HEIGHT * 0.5;
+    }
+
+    fn item(&mut self, item: &TodoItem, depth: usize) {
+        let x = MARGIN + SUBTASK_INDENT * depth as f32;
+        let text_x = x + 6.0;
+        let mut text = item.description.clone();
+        for tag in &item.tags {
+            text.push_str(&format!(" #{}", tag));
+        }
+        if let (false, Some(due_date)) = (item.completed, item.due_date) {
+            text.push_str(&format!(" (due {})", due_date));
+        }
+        let lines = wrap(&text, PAGE_WIDTH - MARGIN - text_x);
+        self.make_room(LINE_HEIGHT);
+        self.y -= LINE_HEIGHT;
+
+        // The checkbox, with a tick when done
+        let layer = self.layer().clone();
+        let (box_y, size) = (self.y - 0.5, 3.2);
+        layer.set_outline_color(if item.completed { grey() } else { black() });
+        layer.set_outline_thickness(0.6);
+        line(&layer, &[(x, box_y), (x + size, box_y), (x + size, box_y + size), (x, box_y + size)], true);
+        if item.completed {
+            line(&layer, &[(x + 0.6, box_y + 1.6), (x + 1.4, box_y + 0.6), (x + 2.8, box_y + 2.8)], false);
+        }
+
+        for (index, text) in lines.iter().enumerate() {
+            if index > 0 {
+                self.make_room(LINE_HEIGHT);
+                self.y -= LINE_HEIGHT;
+            }
+            let layer = self.layer().clone();
+            layer.set_fill_color(if item.completed { grey() } else { black() });
+            layer.use_text(text.as_str(), FONT_SIZE, Mm(text_x), Mm(self.y), &self.font);
+            if item.completed {
+                let strike_y = self.y + FONT_SIZE * 0.3 * MM_PER_PT;
+                line(&layer, &[(text_x, strike_y), (text_x + text_width(text, FONT_SIZE), strike_y)], false);
+            }
+        }
+        for child in &item.children {
+            self.item(child, depth + 1);
+        }
+    }
+}
+
+fn layout(title: &str, sections: &[Section]) -> Result<Writer, printpdf::Error> {
+    let (doc, page, layer) = PdfDocument::new(title, Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Tasks");
+    let font = doc.add_builtin_font(BuiltinFont::Helvetica)?;
+    let bold = doc.add_builtin_font(BuiltinFont::HelveticaBold)?;
+    let first = doc.get_page(page).get_layer(layer);
+    let mut writer = Writer {
+        doc,
+        font,
+        bold,
+        layers: vec![first],
+        y: PAGE_HEIGHT - MARGIN + LINE_HEIGHT * 1.5,
+    };
+
+    writer.heading(title, FONT_SIZE * 1.6);
+    writer.y -= LINE_HEIGHT * 0.5;
+    for section in sections.iter().filter(|section| !section.items.is_empty()) {
+        if let Some(heading) = &section.heading {
+            writer.heading(heading, FONT_SIZE * 1.2);
+        }
+        for item in &section.items {
+            writer.item(item, 0);
+        }
+    }
+    Ok(writer)
+}
+
+// The sections of a list as a paginated A4 PDF under `title`. Completed tasks are greyed out
+// and struck through, subtasks are indented below their task and pages are numbered.
+pub fn to_pdf(title: &str, sections: &[Section]) -> Result<Vec<u8>, printpdf::Error> {
+    let writer = layout(title, sections)?;
+    let pages = writer.layers.len();
+    for (index, layer) in writer.layers.iter().enumerate() {
+        layer.set_fill_color(grey());
+        let number = format!("{} / {}", index + 1, pages);
+        let x = (PAGE_WIDTH - text_width(&number, FONT_SIZE * 0.8)) / 2.0;
+        layer.use_text(number, FONT_SIZE * 0.8, Mm(x), Mm(MARGIN / 2.0), &writer.font);
+    }
+    writer.doc.save_to_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_text_is_wrapped_at_words() {
+        // Wide enough for "three four" but not for "one two three"
+        let width = text_width("three four", FONT_SIZE) + 0.1;
+        assert_eq!(wrap("one two three four", width), ["one two", "three four"]);
+        assert_eq!(wrap("a extraordinarily b", width), ["a", "extraordinarily", "b"]);
+    }
+
+    #[test]
+    fn long_lists_run_over_several_pages() {
+        let items: Vec<TodoItem> = (0..100).map(|n| TodoItem::new(format!("task {}", n))).collect();
+        let sections = [Section { heading: None, items: items.iter().collect() }];
+        assert_eq!(layout("Todo", &sections).unwrap().layers.len(), 3);
+        assert!(to_pdf("Todo", &sections).unwrap().starts_with(b"%PDF"));
+    }
+}
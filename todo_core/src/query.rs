@@ -0,0 +1,477 @@
+use crate::search::SearchIndex;
+use crate::{csv_io, quick_add, Priority, Status, TodoItem};
+use chrono::{Duration, NaiveDate};
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::fmt;
+use std::rc::Rc;
+
+// How a value is compared with the item's, `:` and `=` both mean equal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            Comparison::Equal => ordering == Ordering::Equal,
+            Comparison::Less => ordering == Ordering::Less,
+            Comparison::LessOrEqual => ordering != Ordering::Greater,
+            Comparison::Greater => ordering == Ordering::Greater,
+            Comparison::GreaterOrEqual => ordering != Ordering::Less,
+        }
+    }
+}
+
+// A date in a query, "today" and the like are worked out when items are matched
+// so a saved query moves along with the days
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Day {
+    Date(NaiveDate),
+    // Days from today, "yesterday" is -1
+    FromToday(i64),
+}
+
+impl Day {
+    fn resolve(self, today: NaiveDate) -> NaiveDate {
+        match self {
+            Day::Date(date) => date,
+            Day::FromToday(days) => today + Duration::days(days),
+        }
+    }
+}
+
+// Expression tree of a query like `tag:work AND due<2025-01-01 AND !done`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    Not(Box<Query>),
+    // Words in the description, notes or subtasks
+    Text(String),
+    Tag(String),
+    Context(String),
+    Assignee(String),
+    Priority(Comparison, Priority),
+    Status(Status),
+    // None is `due:none`, items without a due date
+    Due(Comparison, Option<Day>),
+    Start(Comparison, Option<Day>),
+    Done,
+    Overdue,
+    Pinned,
+}
+
+impl Query {
+    // `index` finds the words of text terms, as in a plain search
+    pub fn matches(&self, item: &TodoItem, index: &SearchIndex, today: NaiveDate) -> bool {
+        let date_matches = |date: Option<NaiveDate>, comparison: Comparison, day: Option<Day>| match (date, day) {
+            (Some(date), Some(day)) => comparison.holds(date.cmp(&day.resolve(today))),
+            (None, None) => true,
+            _ => false,
+        };
+        match self {
+            Query::And(left, right) => left.matches(item, index, today) && right.matches(item, index, today),
+            Query::Or(left, right) => left.matches(item, index, today) || right.matches(item, index, today),
+            Query::Not(query) => !query.matches(item, index, today),
+            Query::Text(text) => index.matches(item, text),
+            Query::Tag(tag) => item.tags.iter().any(|item_tag| item_tag.eq_ignore_ascii_case(tag)),
+            Query::Context(context) => item.context.as_ref().is_some_and(|item_context| item_context.eq_ignore_ascii_case(context)),
+            Query::Assignee(name) => item.assignee.as_ref().is_some_and(|assignee| assignee.eq_ignore_ascii_case(name)),
+            Query::Priority(comparison, priority) => comparison.holds(item.priority.cmp(priority)),
+            Query::Status(status) => item.status() == *status,
+            Query::Due(comparison, day) => date_matches(item.due_date, *comparison, *day),
+            Query::Start(comparison, day) => date_matches(item.start_date, *comparison, *day),
+            Query::Done => item.completed,
+            Query::Overdue => item.is_overdue(today),
+            Query::Pinned => item.pinned,
+        }
+    }
+}
+
+// Why a search couldn't be read as a query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    UnknownField(String),
+    BadValue(String, String),
+    MissingValue(String),
+    // AND, OR or NOT with nothing to apply it to
+    MissingTerm,
+    UnclosedParenthesis,
+    UnopenedParenthesis,
+    UnclosedQuote,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::UnknownField(field) => write!(f, "unknown field \"{}\"", field),
+            ParseError::BadValue(field, value) => write!(f, "\"{}\" is not a value for {}", value, field),
+            ParseError::MissingValue(field) => write!(f, "{} needs a value", field),
+            ParseError::MissingTerm => write!(f, "AND, OR and NOT need something on both sides"),
+            ParseError::UnclosedParenthesis => write!(f, "a \"(\" is not closed"),
+            ParseError::UnopenedParenthesis => write!(f, "a \")\" has no \"(\""),
+            ParseError::UnclosedQuote => write!(f, "a quote is not closed"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Term(Query),
+}
+
+// Searches without any of these are plain text, so "meeting: budget" or "salt and pepper"
+// are looked for as typed
+fn looks_like_query(text: &str) -> bool {
+    text.contains([':', '<', '>', '=', '!', '(', ')', '"'])
+        || text.split_whitespace().any(|word| {
+            matches!(word, "AND" | "OR" | "NOT") || (word.len() > 1 && (word.starts_with('#') || word.starts_with('@')))
+        })
+}
+
+// What's typed in a search box. Plain text is matched as a whole like before, anything with
+// query syntax is parsed. An empty search is an empty text term, which matches everything.
+pub fn parse_search(text: &str) -> Result<Query, ParseError> {
+    let text = text.trim();
+    if looks_like_query(text) {
+        parse(text)
+    } else {
+        Ok(Query::Text(text.to_string()))
+    }
+}
+
+// Terms next to each other are joined with AND, which binds tighter than OR.
+// NOT and `!` apply to the term or parenthesis right after them.
+pub fn parse(text: &str) -> Result<Query, ParseError> {
+    let tokens = tokenize(text)?;
+    let mut position = 0;
+    let query = parse_or(&tokens, &mut position)?;
+    match tokens.get(position) {
+        None => Ok(query.unwrap_or(Query::Text(String::new()))),
+        Some(Token::Close) => Err(ParseError::UnopenedParenthesis),
+        Some(_) => Err(ParseError::MissingTerm),
+    }
+}
+
+fn parse_or(tokens: &[Token], position: &mut usize) -> Result<Option<Query>, ParseError> {
+    let Some(mut query) = parse_and(tokens, position)? else {
+        return match tokens.get(*position) {
+            Some(Token::Or) => Err(ParseError::MissingTerm),
+            _ => Ok(None),
+        };
+    };
+    while tokens.get(*position) == Some(&Token::Or) {
+        *position += 1;
+        let right = parse_and(tokens, position)?.ok_or(ParseError::MissingTerm)?;
+        query = Query::Or(Box::new(query), Box::new(right));
+    }
+    Ok(Some(query))
+}
+
+fn parse_and(tokens: &[Token], position: &mut usize) -> Result<Option<Query>, ParseError> {
+    let Some(mut query) = parse_not(tokens, position)? else {
+        return match tokens.get(*position) {
+            Some(Token::And) => Err(ParseError::MissingTerm),
+            _ => Ok(None),
+        };
+    };
+    loop {
+        if tokens.get(*position) == Some(&Token::And) {
+            *position += 1;
+            let right = parse_not(tokens, position)?.ok_or(ParseError::MissingTerm)?;
+            query = Query::And(Box::new(query), Box::new(right));
+        } else if let Some(right) = parse_not(tokens, position)? {
+            query = Query::And(Box::new(query), Box::new(right));
+        } else {
+            return Ok(Some(query));
+        }
+    }
+}
+
+// None if the next token doesn't start a term, e.g. OR or the end
+fn parse_not(tokens: &[Token], position: &mut usize) -> Result<Option<Query>, ParseError> {
+    match tokens.get(*position) {
+        Some(Token::Not) => {
+            *position += 1;
+            let query = parse_not(tokens, position)?.ok_or(ParseError::MissingTerm)?;
+            Ok(Some(Query::Not(Box::new(query))))
+        }
+        Some(Token::Open) => {
+            *position += 1;
+            let query = parse_or(tokens, position)?;
+            if tokens.get(*position) != Some(&Token::Close) {
+                return Err(ParseError::UnclosedParenthesis);
+            }
+            *position += 1;
+            // "()" matches everything, like an empty search
+            Ok(Some(query.unwrap_or(Query::Text(String::new()))))
+        }
+        Some(Token::Term(query)) => {
+            *position += 1;
+            Ok(Some(query.clone()))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            '!' => {
+                chars.next();
+                tokens.push(Token::Not);
+            }
+            '"' => {
+                chars.next();
+                tokens.push(Token::Term(Query::Text(read_quoted(&mut chars)?)));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' {
+                        break;
+                    }
+                    chars.next();
+                    // A quoted value keeps its spaces, as in assignee:"Sam Lee"
+                    if c == '"' {
+                        word.push_str(&read_quoted(&mut chars)?);
+                    } else {
+                        word.push(c);
+                    }
+                }
+                tokens.push(match word.as_str() {
+                    "AND" | "and" | "&&" => Token::And,
+                    "OR" | "or" | "||" => Token::Or,
+                    "NOT" | "not" => Token::Not,
+                    _ => Token::Term(term(&word)?),
+                });
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+// The text up to the closing quote, which is dropped
+fn read_quoted(chars: &mut impl Iterator<Item = char>) -> Result<String, ParseError> {
+    let mut text = String::new();
+    for c in chars {
+        if c == '"' {
+            return Ok(text);
+        }
+        text.push(c);
+    }
+    Err(ParseError::UnclosedQuote)
+}
+
+// One word such as `tag:work`, `due<=today`, `#work`, `@home`, `done` or `milk`
+fn term(word: &str) -> Result<Query, ParseError> {
+    if let Some(tag) = word.strip_prefix('#').filter(|tag| !tag.is_empty()) {
+        return Ok(Query::Tag(tag.to_string()));
+    }
+    if let Some(context) = word.strip_prefix('@').filter(|context| !context.is_empty()) {
+        return Ok(Query::Context(context.to_string()));
+    }
+    let Some(split) = word.find([':', '<', '>', '=']) else {
+        return Ok(match word.to_lowercase().as_str() {
+            "done" => Query::Done,
+            "overdue" => Query::Overdue,
+            "pinned" => Query::Pinned,
+            _ => Query::Text(word.to_string()),
+        });
+    };
+    let field = word[..split].to_lowercase();
+    let rest = &word[split..];
+    let (comparison, value) = [
+        ("<=", Comparison::LessOrEqual),
+        (">=", Comparison::GreaterOrEqual),
+        ("<", Comparison::Less),
+        (">", Comparison::Greater),
+        (":", Comparison::Equal),
+        ("=", Comparison::Equal),
+    ]
+    .into_iter()
+    .find_map(|(operator, comparison)| rest.strip_prefix(operator).map(|value| (comparison, value)))
+    .expect("the word was split at an operator");
+    if value.is_empty() {
+        return Err(ParseError::MissingValue(field));
+    }
+    let bad_value = || ParseError::BadValue(field.clone(), value.to_string());
+    // Names only compare as equal
+    let name = |query: fn(String) -> Query| match comparison {
+        Comparison::Equal => Ok(query(value.to_string())),
+        _ => Err(bad_value()),
+    };
+    match field.as_str() {
+        "tag" => name(Query::Tag),
+        "context" => name(Query::Context),
+        "assignee" => name(Query::Assignee),
+        "text" => name(Query::Text),
+        "priority" => quick_add::parse_priority(&value.to_lowercase())
+            .map(|priority| Query::Priority(comparison, priority))
+            .ok_or_else(bad_value),
+        "status" => match (comparison, value.to_lowercase().as_str()) {
+            (Comparison::Equal, "todo" | "open") => Ok(Query::Status(Status::Todo)),
+            (Comparison::Equal, "inprogress" | "in-progress" | "doing") => Ok(Query::Status(Status::InProgress)),
+            (Comparison::Equal, "done") => Ok(Query::Status(Status::Done)),
+            _ => Err(bad_value()),
+        },
+        "due" => day(comparison, value).map(|day| Query::Due(comparison, day)).ok_or_else(bad_value),
+        "start" => day(comparison, value).map(|day| Query::Start(comparison, day)).ok_or_else(bad_value),
+        _ => Err(ParseError::UnknownField(field.clone())),
+    }
+}
+
+// Some(None) for `none`, which only makes sense as equal
+fn day(comparison: Comparison, value: &str) -> Option<Option<Day>> {
+    let day = match value.to_lowercase().as_str() {
+        "none" if comparison == Comparison::Equal => return Some(None),
+        "today" => Day::FromToday(0),
+        "tomorrow" => Day::FromToday(1),
+        "yesterday" => Day::FromToday(-1),
+        _ => Day::Date(csv_io::parse_date(value)?),
+    };
+    Some(Some(day))
+}
+
+// The search text parsed once and kept until it changes, as filtering asks for it per item.
+// Text that isn't a valid query is searched for as typed.
+#[derive(Debug, Clone, Default)]
+pub struct QueryCache {
+    parsed: RefCell<Option<(String, Rc<Query>)>>,
+}
+
+impl QueryCache {
+    pub fn get(&self, text: &str) -> Rc<Query> {
+        let mut parsed = self.parsed.borrow_mut();
+        match parsed.as_ref() {
+            Some((cached, query)) if cached == text => query.clone(),
+            _ => {
+                let query = Rc::new(parse_search(text).unwrap_or_else(|_| Query::Text(text.to_string())));
+                *parsed = Some((text.to_string(), query.clone()));
+                query
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, 13).unwrap()
+    }
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, month, day).unwrap()
+    }
+
+    fn and(left: Query, right: Query) -> Query {
+        Query::And(Box::new(left), Box::new(right))
+    }
+
+    fn or(left: Query, right: Query) -> Query {
+        Query::Or(Box::new(left), Box::new(right))
+    }
+
+    fn not(query: Query) -> Query {
+        Query::Not(Box::new(query))
+    }
+
+    fn matches(query: &str, item: &TodoItem) -> bool {
+        parse(query).unwrap().matches(item, &SearchIndex::default(), today())
+    }
+
+    #[test]
+    fn terms_are_joined_with_and_before_or() {
+        assert_eq!(
+            parse("tag:work AND due<2025-01-01 AND !done").unwrap(),
+            and(
+                and(
+                    Query::Tag("work".to_string()),
+                    Query::Due(Comparison::Less, Some(Day::Date(NaiveDate::from_ymd_opt(2025, 1, 1).unwrap())))
+                ),
+                not(Query::Done)
+            )
+        );
+        assert_eq!(
+            parse("#home @phone OR priority>=high").unwrap(),
+            or(
+                and(Query::Tag("home".to_string()), Query::Context("phone".to_string())),
+                Query::Priority(Comparison::GreaterOrEqual, Priority::High)
+            )
+        );
+        assert_eq!(
+            parse("NOT (overdue OR pinned) milk").unwrap(),
+            and(not(or(Query::Overdue, Query::Pinned)), Query::Text("milk".to_string()))
+        );
+        assert_eq!(parse("assignee:\"Sam Lee\"").unwrap(), Query::Assignee("Sam Lee".to_string()));
+    }
+
+    #[test]
+    fn mistakes_are_reported() {
+        assert_eq!(parse("size:large"), Err(ParseError::UnknownField("size".to_string())));
+        assert_eq!(parse("due<soon"), Err(ParseError::BadValue("due".to_string(), "soon".to_string())));
+        assert_eq!(parse("tag<work"), Err(ParseError::BadValue("tag".to_string(), "work".to_string())));
+        assert_eq!(parse("tag:"), Err(ParseError::MissingValue("tag".to_string())));
+        assert_eq!(parse("milk AND"), Err(ParseError::MissingTerm));
+        assert_eq!(parse("OR milk"), Err(ParseError::MissingTerm));
+        assert_eq!(parse("(milk"), Err(ParseError::UnclosedParenthesis));
+        assert_eq!(parse("milk)"), Err(ParseError::UnopenedParenthesis));
+        assert_eq!(parse("\"milk"), Err(ParseError::UnclosedQuote));
+    }
+
+    #[test]
+    fn plain_searches_are_taken_as_typed() {
+        assert_eq!(parse_search("salt and pepper"), Ok(Query::Text("salt and pepper".to_string())));
+        assert_eq!(parse_search("get it done"), Ok(Query::Text("get it done".to_string())));
+        assert_eq!(parse_search(""), Ok(Query::Text(String::new())));
+        assert_eq!(parse_search("#work"), Ok(Query::Tag("work".to_string())));
+        // Not a query after all, so the cache falls back to the text
+        assert_eq!(*QueryCache::default().get("meeting: budget"), Query::Text("meeting: budget".to_string()));
+    }
+
+    #[test]
+    fn items_are_matched() {
+        let item = TodoItem {
+            tags: vec!["Work".to_string()],
+            due_date: Some(date(3, 12)),
+            priority: Priority::High,
+            ..TodoItem::new("Send the invoice".to_string())
+        };
+        assert!(matches("tag:work AND due<2025-01-01 AND !done", &item));
+        assert!(matches("overdue due<today due:yesterday", &item));
+        assert!(matches("priority>medium invoice", &item));
+        assert!(!matches("priority:urgent", &item));
+        assert!(!matches("due:none", &item));
+        assert!(matches("due:none", &TodoItem::new("Someday".to_string())));
+        assert!(!matches("start<=today", &item));
+        assert!(matches("status:todo OR done", &item));
+        assert!(!matches("status:done", &item));
+        assert!(matches("status:done", &TodoItem { completed: true, ..item }));
+    }
+}
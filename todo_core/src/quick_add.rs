@@ -0,0 +1,290 @@
+use crate::{reminder, Priority, TodoItem};
+use chrono::{Datelike, Duration, NaiveDate, NaiveTime, Timelike, Weekday};
+
+// What was picked out of a quick-add line like "call mom tomorrow 5pm #family !high @phone"
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct QuickAdd {
+    pub description: String,
+    pub due_date: Option<NaiveDate>,
+    // Items only keep the date, a time becomes a reminder on that day
+    pub due_time: Option<NaiveTime>,
+    pub tags: Vec<String>,
+    pub priority: Option<Priority>,
+    pub context: Option<String>,
+}
+
+impl QuickAdd {
+    pub fn into_item(self) -> TodoItem {
+        let mut item = TodoItem::new(self.description);
+        item.due_date = self.due_date;
+        if let (Some(date), Some(time)) = (self.due_date, self.due_time) {
+            item.reminder = reminder::at(date, time.hour(), time.minute());
+        }
+        item.priority = self.priority.unwrap_or_default();
+        item.context = self.context;
+        for tag in &self.tags {
+            item.add_tag(tag);
+        }
+        item
+    }
+}
+
+// Pull dates, times, #tags, !priorities and an @context out of the first line, the remaining words
+// are the description. Lines below the first are kept as they are. If nothing but
+// tokens is left, the text is taken literally so "today" can still be added as a task.
+pub fn parse(input: &str, today: NaiveDate) -> QuickAdd {
+    let (first_line, rest) = match input.split_once('\n') {
+        Some((first_line, rest)) => (first_line, Some(rest)),
+        None => (input, None),
+    };
+    let words: Vec<&str> = first_line.split_whitespace().collect();
+    let mut parsed = QuickAdd::default();
+    let mut description: Vec<&str> = Vec::new();
+
+    let mut index = 0;
+    while index < words.len() {
+        let word = words[index];
+        let lower = word.to_lowercase();
+        let next = words.get(index + 1).map(|next| next.to_lowercase());
+
+        if let Some(tag) = word.strip_prefix('#').filter(|tag| !tag.is_empty()) {
+            parsed.tags.push(tag.to_string());
+        } else if let Some(priority) = lower.strip_prefix('!').and_then(parse_priority) {
+            parsed.priority = Some(priority);
+        } else if let Some(context) = word.strip_prefix('@').filter(|context| !context.is_empty()) {
+            parsed.context = Some(context.to_string());
+        } else if let Some((date, used)) = parse_date(&lower, next.as_deref(), today) {
+            parsed.due_date = Some(date);
+            index += used - 1;
+        } else if let Some(time) = parse_time(&lower) {
+            parsed.due_time = Some(time);
+        } else if let Some(time) = next.as_deref().filter(|_| lower == "at").and_then(parse_time) {
+            parsed.due_time = Some(time);
+            index += 1;
+        } else {
+            description.push(word);
+        }
+        index += 1;
+    }
+
+    // A time alone means today
+    if parsed.due_time.is_some() && parsed.due_date.is_none() {
+        parsed.due_date = Some(today);
+    }
+    if description.is_empty() {
+        return QuickAdd { description: input.to_string(), ..Default::default() };
+    }
+    parsed.description = description.join(" ");
+    if let Some(rest) = rest {
+        parsed.description = format!("{}\n{}", parsed.description, rest);
+    }
+    parsed
+}
+
+pub(crate) fn parse_priority(word: &str) -> Option<Priority> {
+    match word {
+        "low" => Some(Priority::Low),
+        "medium" | "med" => Some(Priority::Medium),
+        "high" => Some(Priority::High),
+        "urgent" => Some(Priority::Urgent),
+        _ => None,
+    }
+}
+
+fn parse_weekday(word: &str) -> Option<Weekday> {
+    match word {
+        "monday" => Some(Weekday::Mon),
+        "tuesday" => Some(Weekday::Tue),
+        "wednesday" => Some(Weekday::Wed),
+        "thursday" => Some(Weekday::Thu),
+        "friday" => Some(Weekday::Fri),
+        "saturday" => Some(Weekday::Sat),
+        "sunday" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+// Also takes abbreviations, which are ordinary words too ("sun cream", "sat down"),
+// so they only count after "on" or "next"
+fn parse_weekday_or_abbreviation(word: &str) -> Option<Weekday> {
+    parse_weekday(word).or(match word {
+        "mon" => Some(Weekday::Mon),
+        "tue" | "tues" => Some(Weekday::Tue),
+        "wed" => Some(Weekday::Wed),
+        "thu" | "thurs" => Some(Weekday::Thu),
+        "fri" => Some(Weekday::Fri),
+        "sat" => Some(Weekday::Sat),
+        "sun" => Some(Weekday::Sun),
+        _ => None,
+    })
+}
+
+// The date a word (and maybe the one after it) stands for, with how many words it took
+fn parse_date(word: &str, next: Option<&str>, today: NaiveDate) -> Option<(NaiveDate, usize)> {
+    // The first such weekday after today, a week ahead if it is today
+    let upcoming = |weekday: Weekday| {
+        let days = (weekday.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+        today + Duration::days(if days == 0 { 7 } else { i64::from(days) })
+    };
+    match (word, next) {
+        ("today", _) => Some((today, 1)),
+        ("tomorrow" | "tmr", _) => Some((today + Duration::days(1), 1)),
+        ("next", Some("week")) => Some((upcoming(Weekday::Mon), 2)),
+        ("next" | "on", Some(next)) => parse_weekday_or_abbreviation(next).map(|weekday| (upcoming(weekday), 2)),
+        ("in", Some(amount)) => {
+            // "in 3d", "in 2w"
+            let (count, unit) = amount.split_at(amount.find(|c: char| !c.is_ascii_digit())?);
+            let count: i64 = count.parse().ok()?;
+            // Counts too large for a date are not a date
+            let offset = match unit {
+                "d" => Duration::try_days(count)?,
+                "w" => Duration::try_weeks(count)?,
+                _ => return None,
+            };
+            today.checked_add_signed(offset).map(|date| (date, 2))
+        }
+        _ => parse_weekday(word)
+            .map(|weekday| (upcoming(weekday), 1))
+            .or_else(|| NaiveDate::parse_from_str(word, "%Y-%m-%d").ok().map(|date| (date, 1))),
+    }
+}
+
+// "5pm", "5:30pm", "17:00"
+fn parse_time(word: &str) -> Option<NaiveTime> {
+    let (clock, offset) = if let Some(clock) = word.strip_suffix("am") {
+        (clock, Some(0))
+    } else if let Some(clock) = word.strip_suffix("pm") {
+        (clock, Some(12))
+    } else {
+        (word, None)
+    };
+    let (hour, minute) = match clock.split_once(':') {
+        Some((hour, minute)) if minute.len() == 2 => (hour.parse::<u32>().ok()?, minute.parse::<u32>().ok()?),
+        // A bare number is only a time with am/pm after it
+        None if offset.is_some() => (clock.parse::<u32>().ok()?, 0),
+        _ => return None,
+    };
+    let hour = match offset {
+        Some(offset) if (1..=12).contains(&hour) => hour % 12 + offset,
+        Some(_) => return None,
+        None => hour,
+    };
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A Wednesday
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, 13).unwrap()
+    }
+
+    fn date(month: u32, day: u32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(2024, month, day)
+    }
+
+    #[test]
+    fn plain_text_is_left_alone() {
+        let parsed = parse("buy milk", today());
+        assert_eq!(parsed.description, "buy milk");
+        assert_eq!(parsed.due_date, None);
+        assert!(parsed.tags.is_empty());
+        assert_eq!(parsed.priority, None);
+    }
+
+    #[test]
+    fn tokens_are_stripped_from_the_description() {
+        let parsed = parse("call mom tomorrow 5pm #family !high @phone", today());
+        assert_eq!(parsed.description, "call mom");
+        assert_eq!(parsed.due_date, date(3, 14));
+        assert_eq!(parsed.due_time, NaiveTime::from_hms_opt(17, 0, 0));
+        assert_eq!(parsed.tags, vec!["family"]);
+        assert_eq!(parsed.priority, Some(Priority::High));
+        assert_eq!(parsed.context.as_deref(), Some("phone"));
+    }
+
+    #[test]
+    fn weekdays_are_the_next_one_after_today() {
+        assert_eq!(parse("report friday", today()).due_date, date(3, 15));
+        assert_eq!(parse("report on wed", today()).due_date, date(3, 20));
+        assert_eq!(parse("report next monday", today()).due_date, date(3, 18));
+        assert_eq!(parse("report next week", today()).due_date, date(3, 18));
+    }
+
+    #[test]
+    fn weekday_abbreviations_alone_are_words() {
+        let parsed = parse("buy sun cream", today());
+        assert_eq!(parsed.description, "buy sun cream");
+        assert_eq!(parsed.due_date, None);
+        let parsed = parse("buy sun cream on sat", today());
+        assert_eq!(parsed.description, "buy sun cream");
+        assert_eq!(parsed.due_date, date(3, 16));
+    }
+
+    #[test]
+    fn huge_counts_are_not_dates() {
+        for text in ["x in 100000000d", "x in 999999999999999w", "x in 99999999999999999999d"] {
+            let parsed = parse(text, today());
+            assert_eq!(parsed.due_date, None);
+            assert_eq!(parsed.description, text);
+        }
+    }
+
+    #[test]
+    fn relative_and_absolute_dates() {
+        assert_eq!(parse("water plants in 3d", today()).due_date, date(3, 16));
+        assert_eq!(parse("water plants in 2w", today()).due_date, date(3, 27));
+        assert_eq!(parse("taxes 2024-04-15", today()).due_date, date(4, 15));
+    }
+
+    #[test]
+    fn times() {
+        assert_eq!(parse_time("5:30pm"), NaiveTime::from_hms_opt(17, 30, 0));
+        assert_eq!(parse_time("12am"), NaiveTime::from_hms_opt(0, 0, 0));
+        assert_eq!(parse_time("17:00"), NaiveTime::from_hms_opt(17, 0, 0));
+        assert_eq!(parse_time("13pm"), None);
+        assert_eq!(parse_time("5"), None);
+        let parsed = parse("standup at 9am", today());
+        assert_eq!(parsed.description, "standup");
+        assert_eq!(parsed.due_date, Some(today()));
+    }
+
+    #[test]
+    fn only_tokens_is_taken_literally() {
+        let parsed = parse("today", today());
+        assert_eq!(parsed.description, "today");
+        assert_eq!(parsed.due_date, None);
+    }
+
+    #[test]
+    fn unknown_priorities_and_lone_hashes_stay_in_the_text() {
+        let parsed = parse("fix bug # 12 !later", today());
+        assert_eq!(parsed.description, "fix bug # 12 !later");
+    }
+
+    #[test]
+    fn later_lines_are_kept() {
+        let parsed = parse("plan trip #travel\nbook hotel\nrent car", today());
+        assert_eq!(parsed.description, "plan trip\nbook hotel\nrent car");
+        assert_eq!(parsed.tags, vec!["travel"]);
+    }
+
+    #[test]
+    fn into_item_fills_the_fields() {
+        let item = parse("pay rent tomorrow !urgent #home", today()).into_item();
+        assert_eq!(item.description, "pay rent");
+        assert_eq!(item.due_date, date(3, 14));
+        assert_eq!(item.priority, Priority::Urgent);
+        assert_eq!(item.tags, vec!["home"]);
+        assert_eq!(item.reminder, None);
+    }
+
+    #[test]
+    fn a_time_becomes_a_reminder() {
+        let item = parse("dentist friday 2:30pm", today()).into_item();
+        assert_eq!(item.due_date, date(3, 15));
+        assert_eq!(item.reminder, reminder::at(date(3, 15).unwrap(), 14, 30));
+    }
+}
@@ -0,0 +1,134 @@
+use chrono::{Datelike, Days, Months, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+// How often a recurring item comes back after being completed
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recurrence {
+    Daily,
+    Weekly,
+    Monthly,
+    EveryNDays(u32),
+}
+
+impl Recurrence {
+    // Choices offered in the repeat menu, the custom interval starts at 2 days
+    pub const ALL: [Recurrence; 4] = [
+        Recurrence::Daily,
+        Recurrence::Weekly,
+        Recurrence::Monthly,
+        Recurrence::EveryNDays(2),
+    ];
+
+    pub fn label(self) -> String {
+        match self {
+            Recurrence::Daily => "Daily".to_string(),
+            Recurrence::Weekly => "Weekly".to_string(),
+            Recurrence::Monthly => "Monthly".to_string(),
+            Recurrence::EveryNDays(days) => format!("Every {} days", days),
+        }
+    }
+
+    // Used by the repeat menu to treat every custom interval as the same choice
+    pub fn same_kind(self, other: Recurrence) -> bool {
+        std::mem::discriminant(&self) == std::mem::discriminant(&other)
+    }
+
+    // The date one interval after `date`. A month later is the last day of the next month when
+    // that is shorter, so repeating it drifts to earlier days, `next_due_date` doesn't.
+    pub fn advance(self, date: NaiveDate) -> NaiveDate {
+        let next = match self {
+            Recurrence::Daily => date.checked_add_days(Days::new(1)),
+            Recurrence::Weekly => date.checked_add_days(Days::new(7)),
+            Recurrence::Monthly => date.checked_add_months(Months::new(1)),
+            Recurrence::EveryNDays(days) => date.checked_add_days(Days::new(days.max(1) as u64)),
+        };
+        next.unwrap_or(date)
+    }
+
+    // The first occurrence after today, counting from the current due date. Monthly items come
+    // back on the day of the month from `monthly_day`, or the last day of shorter months.
+    pub fn next_due_date(self, due_date: Option<NaiveDate>, day: Option<u32>, today: NaiveDate) -> NaiveDate {
+        let due = due_date.unwrap_or(today);
+        if self == Recurrence::Monthly {
+            let day = monthly_day(due, day);
+            let first = due.with_day(1).unwrap_or(due);
+            return (1..)
+                .map_while(|months| first.checked_add_months(Months::new(months)))
+                .map(|month| on_day(month, day))
+                .find(|&next| next > today)
+                .unwrap_or(due);
+        }
+        let mut next = self.advance(due);
+        while next <= today {
+            let advanced = self.advance(next);
+            if advanced == next {
+                break;
+            }
+            next = advanced;
+        }
+        next
+    }
+}
+
+// The day of the month a monthly item comes back on. `day` is the one it was first due on,
+// kept while the due date falls on it or on the end of a month too short for it. Once the
+// due date was moved to another day, that day is used.
+pub fn monthly_day(due: NaiveDate, day: Option<u32>) -> u32 {
+    day.filter(|&day| on_day(due, day) == due).unwrap_or(due.day())
+}
+
+// `day` of the month `date` is in, or its last day if the month is shorter
+fn on_day(date: NaiveDate, day: u32) -> NaiveDate {
+    (1..=day).rev().find_map(|day| date.with_day(day)).unwrap_or(date)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    // Completes a monthly item due on `due` the day it is due, `times` times
+    fn monthly_dates(due: NaiveDate, times: usize) -> Vec<NaiveDate> {
+        let mut dates = Vec::new();
+        let (mut due, mut day) = (due, None);
+        for _ in 0..times {
+            day = Some(monthly_day(due, day));
+            due = Recurrence::Monthly.next_due_date(Some(due), day, due);
+            dates.push(due);
+        }
+        dates
+    }
+
+    #[test]
+    fn month_end_dates_come_back_after_short_months() {
+        assert_eq!(
+            monthly_dates(date(2023, 12, 31), 4),
+            [date(2024, 1, 31), date(2024, 2, 29), date(2024, 3, 31), date(2024, 4, 30)]
+        );
+        assert_eq!(monthly_dates(date(2023, 1, 30), 2), [date(2023, 2, 28), date(2023, 3, 30)]);
+    }
+
+    #[test]
+    fn leap_day_falls_back_in_other_years() {
+        assert_eq!(Recurrence::Monthly.next_due_date(Some(date(2024, 1, 29)), None, date(2024, 1, 29)), date(2024, 2, 29));
+        assert_eq!(Recurrence::Monthly.next_due_date(Some(date(2025, 1, 29)), None, date(2025, 1, 29)), date(2025, 2, 28));
+        assert_eq!(Recurrence::Monthly.next_due_date(Some(date(2025, 2, 28)), Some(29), date(2025, 2, 28)), date(2025, 3, 29));
+    }
+
+    #[test]
+    fn moving_the_due_date_moves_the_day() {
+        // The 31st no longer applies once the item was moved to the 15th
+        assert_eq!(monthly_day(date(2024, 2, 15), Some(31)), 15);
+        assert_eq!(monthly_day(date(2024, 2, 29), Some(31)), 31);
+        assert_eq!(monthly_day(date(2024, 3, 31), None), 31);
+    }
+
+    #[test]
+    fn overdue_items_skip_to_the_next_month_after_today() {
+        let next = Recurrence::Monthly.next_due_date(Some(date(2024, 1, 31)), Some(31), date(2024, 5, 10));
+        assert_eq!(next, date(2024, 5, 31));
+    }
+}
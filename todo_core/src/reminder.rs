@@ -0,0 +1,61 @@
+use crate::{TodoItem, TodoList};
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveTime, TimeZone};
+
+// Snoozed "until tomorrow" reminders come back at this time
+const MORNING: (u32, u32) = (9, 0);
+
+// Ways to push a reminder back from its popup
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Snooze {
+    TenMinutes,
+    OneHour,
+    Tomorrow,
+}
+
+impl Snooze {
+    pub const ALL: [Snooze; 3] = [Snooze::TenMinutes, Snooze::OneHour, Snooze::Tomorrow];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Snooze::TenMinutes => "10 min",
+            Snooze::OneHour => "1 hour",
+            Snooze::Tomorrow => "Tomorrow",
+        }
+    }
+
+    pub fn until(self, now: DateTime<Local>) -> DateTime<Local> {
+        match self {
+            Snooze::TenMinutes => now + Duration::minutes(10),
+            Snooze::OneHour => now + Duration::hours(1),
+            Snooze::Tomorrow => at(now.date_naive() + Duration::days(1), MORNING.0, MORNING.1).unwrap_or(now + Duration::days(1)),
+        }
+    }
+}
+
+// Local time on a day, None if it doesn't exist, e.g. when clocks go forward
+pub fn at(day: NaiveDate, hour: u32, minute: u32) -> Option<DateTime<Local>> {
+    let time = NaiveTime::from_hms_opt(hour, minute, 0)?;
+    Local.from_local_datetime(&day.and_time(time)).earliest()
+}
+
+// Open items of every list whose reminder has come up, as (list index, item ID)
+pub fn due(lists: &[TodoList], now: DateTime<Local>) -> Vec<(usize, u32)> {
+    lists
+        .iter()
+        .enumerate()
+        .flat_map(|(index, list)| list.items.iter().map(move |item| (index, item)))
+        .filter(|(_, item)| !item.completed && item.reminder.is_some_and(|reminder| reminder <= now))
+        .map(|(index, item)| (index, item.id))
+        .collect()
+}
+
+// The soonest reminder still to come, to know when to look again
+pub fn next(lists: &[TodoList], now: DateTime<Local>) -> Option<DateTime<Local>> {
+    lists
+        .iter()
+        .flat_map(|list| &list.items)
+        .filter(|item| !item.completed)
+        .filter_map(|item: &TodoItem| item.reminder)
+        .filter(|reminder| *reminder > now)
+        .min()
+}
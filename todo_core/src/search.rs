@@ -0,0 +1,83 @@
+use crate::TodoItem;
+use chrono::{DateTime, Utc};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ops::Range;
+
+// Byte ranges in `text` where `query` occurs, ignoring case
+pub fn match_ranges(text: &str, query: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    if query.is_empty() {
+        return ranges;
+    }
+    let query_len = query.chars().count();
+    let mut search_from = 0;
+    for (start, _) in text.char_indices() {
+        // Skip positions inside a previous match so matches don't overlap
+        if start < search_from {
+            continue;
+        }
+        let rest = &text[start..];
+        let matched = rest.chars().count() >= query_len
+            && rest.chars().zip(query.chars()).all(|(a, b)| a.to_lowercase().eq(b.to_lowercase()));
+        if matched {
+            let end = start + rest.char_indices().nth(query_len).map_or(rest.len(), |(offset, _)| offset);
+            ranges.push(start..end);
+            search_from = end;
+        }
+    }
+    ranges
+}
+
+pub fn contains_ignore_case(text: &str, query: &str) -> bool {
+    query.is_empty() || !match_ranges(text, query).is_empty()
+}
+
+// Lowercased description, notes and subtasks of each item, kept between frames so typing in the
+// search box doesn't go through every item's text again. Every edit stamps `modified`, so an
+// item's entry is redone when that changes. Entries are looked up from `&self` while filtering.
+#[derive(Debug, Clone, Default)]
+pub struct SearchIndex {
+    entries: RefCell<HashMap<u32, Entry>>,
+}
+
+// Searchable text of an item as of its `modified` stamp
+#[derive(Debug, Clone)]
+struct Entry {
+    modified: Option<DateTime<Utc>>,
+    text: String,
+}
+
+impl Entry {
+    fn of(item: &TodoItem) -> Self {
+        Self { modified: item.modified, text: searchable_text(item) }
+    }
+}
+
+impl SearchIndex {
+    pub fn matches(&self, item: &TodoItem, query: &str) -> bool {
+        if query.is_empty() {
+            return true;
+        }
+        let mut entries = self.entries.borrow_mut();
+        let entry = entries.entry(item.id).or_insert_with(|| Entry::of(item));
+        if entry.modified != item.modified {
+            *entry = Entry::of(item);
+        }
+        entry.text.contains(&query.to_lowercase())
+    }
+
+    // Forget every entry, e.g. when another file is opened and IDs may be reused
+    pub fn clear(&self) {
+        self.entries.borrow_mut().clear();
+    }
+}
+
+fn searchable_text(item: &TodoItem) -> String {
+    let mut text = format!("{}\n{}", item.description, item.notes).to_lowercase();
+    for child in &item.children {
+        text.push('\n');
+        text.push_str(&searchable_text(child));
+    }
+    text
+}
@@ -0,0 +1,330 @@
+#[cfg(not(target_arch = "wasm32"))]
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use crate::caldav::CaldavAccount;
+use crate::file_sync::SharedFile;
+use crate::filter::SmartList;
+use crate::google_tasks::GoogleAccount;
+use crate::theme::Theme;
+use crate::TodoItem;
+use std::path::PathBuf;
+#[cfg(not(target_arch = "wasm32"))]
+use std::{fs::File, io::BufReader};
+
+// When clicking ❌ asks for confirmation before an item goes to the trash
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DeleteConfirmation {
+    Never,
+    // Only items that would take more than one click to recreate
+    #[default]
+    #[serde(alias = "WithSubtasks")]
+    WithSubtasksOrNotes,
+    Always,
+}
+
+impl DeleteConfirmation {
+    pub const ALL: [DeleteConfirmation; 3] = [
+        DeleteConfirmation::Never,
+        DeleteConfirmation::WithSubtasksOrNotes,
+        DeleteConfirmation::Always,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DeleteConfirmation::Never => "Never",
+            DeleteConfirmation::WithSubtasksOrNotes => "Items with subtasks or notes",
+            DeleteConfirmation::Always => "Always",
+        }
+    }
+
+    pub fn applies_to(self, item: &TodoItem) -> bool {
+        match self {
+            DeleteConfirmation::Never => false,
+            DeleteConfirmation::WithSubtasksOrNotes => !item.children.is_empty() || !item.notes.trim().is_empty(),
+            DeleteConfirmation::Always => true,
+        }
+    }
+}
+
+// Language of the interface. Translations live in the GUI, this is only the choice.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Language {
+    #[default]
+    English,
+    German,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::German];
+
+    // Each language is listed under its own name
+    pub fn label(self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::German => "Deutsch",
+        }
+    }
+
+    pub fn from_index(index: u8) -> Self {
+        Language::ALL.get(index as usize).copied().unwrap_or_default()
+    }
+}
+
+// Font used for the interface instead of egui's built-in one
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub enum FontChoice {
+    #[default]
+    Default,
+    // Family name of an installed font
+    System(String),
+    // A TTF or OTF file loaded directly
+    File(PathBuf),
+}
+
+impl FontChoice {
+    pub fn label(&self) -> String {
+        match self {
+            FontChoice::Default => "Default".to_string(),
+            FontChoice::System(family) => family.clone(),
+            FontChoice::File(path) => path.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+        }
+    }
+}
+
+// How descriptions longer than the row is wide are shown
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Wrapping {
+    // Every line, however tall the row gets
+    #[default]
+    Wrap,
+    // Up to `Settings::clamp_lines` lines, with a button to show the rest
+    Clamp,
+    // One line ending in an ellipsis, the rest shows on hover
+    Truncate,
+}
+
+impl Wrapping {
+    pub const ALL: [Wrapping; 3] = [Wrapping::Wrap, Wrapping::Clamp, Wrapping::Truncate];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Wrapping::Wrap => "Wrap",
+            Wrapping::Clamp => "Limit lines",
+            Wrapping::Truncate => "One line",
+        }
+    }
+}
+
+// Local storage key of the settings in the web build
+#[cfg(target_arch = "wasm32")]
+const BROWSER_KEY: &str = "rust_todo_list.settings";
+
+// Size and place of the main window when it was last closed, in points
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(default)]
+pub struct WindowGeometry {
+    pub size: Option<[f32; 2]>,
+    pub position: Option<[f32; 2]>,
+    pub maximized: bool,
+}
+
+// Embedded HTTP server that builds with the `api` feature run for scripts and phones
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct ApiSettings {
+    pub enabled: bool,
+    pub port: u16,
+    // Listen on every network interface instead of only this computer. Plain HTTP, so the token
+    // can be read by anyone on the network.
+    pub lan: bool,
+    // Clients send it as a bearer token, generated when the server is first enabled
+    pub token: String,
+}
+
+impl Default for ApiSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 8787,
+            lan: false,
+            token: String::new(),
+        }
+    }
+}
+
+// Syncing with other computers running the app on the same network
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct LanSync {
+    pub enabled: bool,
+    // Entered on each computer, only computers with the same key sync with each other
+    pub key: String,
+    pub interval_minutes: u32,
+}
+
+impl Default for LanSync {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            key: String::new(),
+            interval_minutes: 5,
+        }
+    }
+}
+
+impl LanSync {
+    pub fn is_configured(&self) -> bool {
+        self.enabled && !self.key.is_empty()
+    }
+}
+
+// User preferences that are kept between launches
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    pub text_size: f32,
+    // Tighter rows with descriptions cut to one line, to fit more tasks on screen
+    pub compact: bool,
+    // Fade and strike through tasks as they are checked off instead of at once
+    pub completion_animation: bool,
+    // Play a short chime when a task is checked off
+    pub completion_sound: bool,
+    pub wrapping: Wrapping,
+    pub clamp_lines: u8,
+    pub language: Language,
+    pub font: FontChoice,
+    pub dark_mode: bool,
+    // Use the dark or light look the operating system is set to, instead of `dark_mode`
+    pub follow_system_theme: bool,
+    // Themes made in the theme editor, and the name of the one in use instead of the plain dark or light look
+    pub themes: Vec<Theme>,
+    pub theme: Option<String>,
+    // Mark an item done automatically once all of its subtasks are done
+    pub auto_complete_parents: bool,
+    // Show desktop notifications for tasks that are due or overdue
+    pub notifications: bool,
+    // Open the quick-add window with Ctrl+Alt+T from anywhere
+    pub quick_add_hotkey: bool,
+    pub confirm_delete: DeleteConfirmation,
+    // Move tasks to the archive once they have been completed for `auto_archive_days`
+    pub auto_archive: bool,
+    pub auto_archive_days: u32,
+    // File most recently opened or saved, autosaved to and reopened on launch
+    pub last_file: Option<PathBuf>,
+    pub caldav: CaldavAccount,
+    pub shared_file: SharedFile,
+    pub google: GoogleAccount,
+    pub window: WindowGeometry,
+    pub api: ApiSettings,
+    pub lan_sync: LanSync,
+    // Saved filters shown in the sidebar
+    pub smart_lists: Vec<SmartList>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            text_size: 14.0,
+            compact: false,
+            completion_animation: true,
+            completion_sound: false,
+            wrapping: Wrapping::default(),
+            clamp_lines: 3,
+            language: Language::default(),
+            font: FontChoice::default(),
+            dark_mode: false,
+            follow_system_theme: false,
+            themes: Vec::new(),
+            theme: None,
+            auto_complete_parents: true,
+            notifications: true,
+            quick_add_hotkey: true,
+            confirm_delete: DeleteConfirmation::default(),
+            auto_archive: true,
+            auto_archive_days: 14,
+            last_file: None,
+            caldav: CaldavAccount::default(),
+            shared_file: SharedFile::default(),
+            google: GoogleAccount::default(),
+            window: WindowGeometry::default(),
+            api: ApiSettings::default(),
+            lan_sync: LanSync::default(),
+            smart_lists: Vec::new(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn active_theme(&self) -> Option<&Theme> {
+        let name = self.theme.as_ref()?;
+        self.themes.iter().find(|theme| &theme.name == name)
+    }
+
+    // In the web build the settings are kept in the browser's local storage
+    #[cfg(target_arch = "wasm32")]
+    pub fn load() -> Self {
+        let Some(json) = crate::browser::read(BROWSER_KEY) else {
+            return Self::default();
+        };
+        serde_json::from_str(&json).unwrap_or_else(|e| {
+            eprintln!("Failed to deserialize settings: {:?}", e);
+            Self::default()
+        })
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn save(&self) {
+        match serde_json::to_string(self) {
+            Ok(json) => {
+                crate::browser::write(BROWSER_KEY, &json);
+            }
+            Err(e) => eprintln!("Failed to serialize settings: {:?}", e),
+        }
+    }
+
+    // Platform config directory, e.g. ~/.config/rust_todo_list on Linux
+    #[cfg(not(target_arch = "wasm32"))]
+    fn path() -> Option<PathBuf> {
+        ProjectDirs::from("", "", "rust_todo_list").map(|dirs| dirs.config_dir().join("settings.json"))
+    }
+
+    // Fall back to defaults if the file is missing or unreadable
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+        match File::open(&path) {
+            Ok(file) => match serde_json::from_reader(BufReader::new(file)) {
+                Ok(settings) => settings,
+                Err(e) => {
+                    eprintln!("Failed to deserialize settings: {:?}", e);
+                    Self::default()
+                }
+            },
+            Err(_) => Self::default(),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self) {
+        let Some(path) = Self::path() else {
+            return;
+        };
+        if let Some(dir) = path.parent() {
+            if let Err(e) = std::fs::create_dir_all(dir) {
+                eprintln!("Failed to create config directory: {:?}", e);
+                return;
+            }
+        }
+        // Saved on every change, so a crash mid-write must not lose the accounts and tokens in it
+        match serde_json::to_vec_pretty(self) {
+            Ok(json) => {
+                if let Err(e) = crate::storage::write_privately(&path, &json) {
+                    eprintln!("Failed to write settings to file: {:?}", e);
+                }
+            }
+            Err(e) => eprintln!("Failed to serialize settings: {:?}", e),
+        }
+    }
+}
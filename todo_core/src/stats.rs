@@ -0,0 +1,103 @@
+use crate::{TodoItem, TodoList};
+use chrono::{Datelike, Duration, Local, NaiveDate, Utc};
+use std::collections::{BTreeMap, HashMap};
+
+// Numbers behind the statistics view, worked out over every list and its archive.
+// Only the latest completion of an item is known, so reopened or recurring items
+// count once at most.
+pub struct Stats {
+    // Completions on each local day, oldest first
+    pub per_day: Vec<(NaiveDate, usize)>,
+    // Completions in each week, keyed by the Monday it starts on, oldest first
+    pub per_week: Vec<(NaiveDate, usize)>,
+    // Days in a row with at least one completion, up to today or yesterday
+    pub streak: u32,
+    // Mean time from creation to completion, None if no item has both
+    pub average_time_to_complete: Option<Duration>,
+    // How long the oldest open item has been waiting, None if no open item has a creation time
+    pub oldest_open: Option<Duration>,
+    // Items per tag, most used first
+    pub tag_counts: Vec<(String, usize)>,
+}
+
+impl Stats {
+    pub fn collect(lists: &[TodoList], today: NaiveDate, days: u32, weeks: u32) -> Self {
+        let items: Vec<&TodoItem> = lists
+            .iter()
+            .flat_map(|list| list.items.iter().chain(list.archive.iter().map(|archived| &archived.item)))
+            .collect();
+
+        let mut completions: BTreeMap<NaiveDate, usize> = BTreeMap::new();
+        for at in items.iter().filter(|item| item.completed).filter_map(|item| item.completed_at) {
+            *completions.entry(at.with_timezone(&Local).date_naive()).or_default() += 1;
+        }
+        let count_on = |day: NaiveDate| completions.get(&day).copied().unwrap_or(0);
+
+        let per_day = (0..days)
+            .rev()
+            .map(|offset| today - Duration::days(i64::from(offset)))
+            .map(|day| (day, count_on(day)))
+            .collect();
+
+        let this_week = today - Duration::days(i64::from(today.weekday().num_days_from_monday()));
+        let per_week = (0..weeks)
+            .rev()
+            .map(|offset| this_week - Duration::weeks(i64::from(offset)))
+            .map(|monday| (monday, (0..7).map(|day| count_on(monday + Duration::days(day))).sum()))
+            .collect();
+
+        // A streak isn't broken until today is over
+        let mut day = if count_on(today) > 0 { today } else { today - Duration::days(1) };
+        let mut streak = 0;
+        while count_on(day) > 0 {
+            streak += 1;
+            day -= Duration::days(1);
+        }
+
+        let durations: Vec<Duration> = items
+            .iter()
+            .filter(|item| item.completed)
+            .filter_map(|item| Some(item.completed_at? - item.created_at?))
+            .filter(|duration| *duration >= Duration::zero())
+            .collect();
+        let average_time_to_complete = (!durations.is_empty())
+            .then(|| durations.iter().fold(Duration::zero(), |sum, duration| sum + *duration) / durations.len() as i32);
+
+        let now = Utc::now();
+        let oldest_open = lists
+            .iter()
+            .flat_map(|list| &list.items)
+            .filter(|item| !item.completed)
+            .filter_map(|item| item.created_at)
+            .min()
+            .map(|created| now - created);
+
+        let mut tags: HashMap<&str, usize> = HashMap::new();
+        for tag in items.iter().flat_map(|item| &item.tags) {
+            *tags.entry(tag).or_default() += 1;
+        }
+        let mut tag_counts: Vec<(String, usize)> = tags.into_iter().map(|(tag, count)| (tag.to_string(), count)).collect();
+        tag_counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        Self {
+            per_day,
+            per_week,
+            streak,
+            average_time_to_complete,
+            oldest_open,
+            tag_counts,
+        }
+    }
+}
+
+// Rough length for humans, e.g. "3 days" or "5 hours"
+pub fn format_age(duration: Duration) -> String {
+    let plural = |count: i64, unit: &str| format!("{} {}{}", count, unit, if count == 1 { "" } else { "s" });
+    if duration.num_days() > 0 {
+        plural(duration.num_days(), "day")
+    } else if duration.num_hours() > 0 {
+        plural(duration.num_hours(), "hour")
+    } else {
+        plural(duration.num_minutes(), "minute")
+    }
+}
@@ -0,0 +1,382 @@
+#[cfg(not(target_arch = "wasm32"))]
+use crate::database::{self, DatabaseError};
+use crate::encryption::EncryptionError;
+use crate::{encryption, item, TodoItem, TodoList};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+// Layout of a save file, every list is stored together
+#[derive(Deserialize)]
+struct SaveData {
+    lists: Vec<TodoList>,
+}
+
+// Borrowing counterpart of `SaveData` used when writing
+#[derive(Serialize)]
+pub(crate) struct SaveDataRef<'a> {
+    pub lists: &'a [TodoList],
+}
+
+// Saves from before multiple lists existed are a plain array of items
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SaveFormat {
+    Lists(SaveData),
+    Items(Vec<TodoItem>),
+}
+
+// Why a save file couldn't be read or written, shown to the user
+#[derive(Debug)]
+pub enum StorageError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    #[cfg(not(target_arch = "wasm32"))]
+    Database(DatabaseError),
+    Encryption(EncryptionError),
+    // The file is encrypted and no passphrase was entered for it
+    PassphraseNeeded,
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StorageError::Io(e) => write!(f, "{}", e),
+            StorageError::Json(e) => write!(f, "The file is not a valid save: {}", e),
+            #[cfg(not(target_arch = "wasm32"))]
+            StorageError::Database(e) => write!(f, "Database error: {:?}", e),
+            StorageError::Encryption(EncryptionError::Cipher) => write!(f, "Wrong passphrase, or the file is damaged"),
+            StorageError::Encryption(e) => write!(f, "Encryption failed: {:?}", e),
+            StorageError::PassphraseNeeded => write!(f, "The file is encrypted and needs its passphrase"),
+        }
+    }
+}
+
+impl From<std::io::Error> for StorageError {
+    fn from(e: std::io::Error) -> Self {
+        StorageError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for StorageError {
+    fn from(e: serde_json::Error) -> Self {
+        StorageError::Json(e)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<DatabaseError> for StorageError {
+    fn from(e: DatabaseError) -> Self {
+        StorageError::Database(e)
+    }
+}
+
+impl From<EncryptionError> for StorageError {
+    fn from(e: EncryptionError) -> Self {
+        StorageError::Encryption(e)
+    }
+}
+
+// Where a set of lists is kept. The app, the command line tool and autosave go through this,
+// so the same code works on a JSON file, a database, the browser's storage or memory.
+pub trait Storage {
+    // Read the lists, with a tab to show and IDs reserved so new items don't collide
+    fn load(&self) -> Result<Vec<TodoList>, StorageError>;
+    fn save(&mut self, lists: &[TodoList]) -> Result<(), StorageError>;
+}
+
+// The backend for a save file, picked by its extension
+pub fn open(path: &Path) -> Box<dyn Storage> {
+    #[cfg(not(target_arch = "wasm32"))]
+    if database::is_database(path) {
+        return Box::new(SqliteFile { path: path.to_path_buf() });
+    }
+    Box::new(JsonFile { path: path.to_path_buf() })
+}
+
+// Indented JSON, encrypted if a passphrase was given for the file
+pub struct JsonFile {
+    pub path: PathBuf,
+}
+
+impl Storage for JsonFile {
+    fn load(&self) -> Result<Vec<TodoList>, StorageError> {
+        let mut data = std::fs::read(&self.path)?;
+        if encryption::is_encrypted_data(&data) {
+            let passphrase = encryption::passphrase_for(&self.path).ok_or(StorageError::PassphraseNeeded)?;
+            data = encryption::decrypt(&data, &passphrase)?;
+        }
+        Ok(prepare(parse_lists(&data)?))
+    }
+
+    fn save(&mut self, lists: &[TodoList]) -> Result<(), StorageError> {
+        // Use to_vec_pretty for indented JSON
+        let json = serde_json::to_vec_pretty(&SaveDataRef { lists })?;
+        let data = match encryption::passphrase_for(&self.path) {
+            Some(passphrase) => encryption::encrypt(&json, &passphrase)?,
+            // Never replace an encrypted file with plain text just because the passphrase isn't known
+            None if encryption::is_encrypted(&self.path) => return Err(StorageError::PassphraseNeeded),
+            None => json,
+        };
+        Ok(write_atomically(&self.path, &data)?)
+    }
+}
+
+// A SQLite database, only changed tasks are written on each save
+#[cfg(not(target_arch = "wasm32"))]
+pub struct SqliteFile {
+    pub path: PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Storage for SqliteFile {
+    fn load(&self) -> Result<Vec<TodoList>, StorageError> {
+        Ok(prepare(database::read(&self.path)?))
+    }
+
+    fn save(&mut self, lists: &[TodoList]) -> Result<(), StorageError> {
+        Ok(database::write(&self.path, lists)?)
+    }
+}
+
+// Lists that only live as long as the value, e.g. for tests. They are kept as the JSON
+// a save file would hold, so what doesn't survive a file doesn't survive here either.
+#[derive(Default)]
+pub struct Memory {
+    json: Option<Vec<u8>>,
+}
+
+impl Storage for Memory {
+    fn load(&self) -> Result<Vec<TodoList>, StorageError> {
+        let lists = match &self.json {
+            Some(json) => parse_lists(json)?,
+            None => Vec::new(),
+        };
+        Ok(prepare(lists))
+    }
+
+    fn save(&mut self, lists: &[TodoList]) -> Result<(), StorageError> {
+        self.json = Some(serde_json::to_vec(&SaveDataRef { lists })?);
+        Ok(())
+    }
+}
+
+// Write the lists, returns false and logs on failure
+pub fn write_lists(path: &Path, lists: &[TodoList]) -> bool {
+    match save_lists(path, lists) {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("Failed to write {}: {}", path.display(), e);
+            false
+        }
+    }
+}
+
+// Read the lists, returns None and logs on failure
+pub fn read_lists(path: &Path) -> Option<Vec<TodoList>> {
+    match load_lists(path) {
+        Ok(lists) => Some(lists),
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+// Write the lists to a JSON file or database
+pub fn save_lists(path: &Path, lists: &[TodoList]) -> Result<(), StorageError> {
+    open(path).save(lists)
+}
+
+// Replace an encrypted JSON save with plain JSON. The encrypted file is only replaced once the
+// new one is written, so it is still there if writing fails.
+pub fn save_unencrypted(path: &Path, lists: &[TodoList]) -> Result<(), StorageError> {
+    let json = serde_json::to_vec_pretty(&SaveDataRef { lists })?;
+    write_atomically(path, &json)?;
+    encryption::forget_passphrase(path);
+    Ok(())
+}
+
+// `todo.json` becomes `todo.json.<suffix>` next to it
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+// Write to a temporary file and rename it over the target, so a crash mid-write
+// leaves either the old or the new file. The previous version is kept as `.bak`,
+// unless that would leave plain text next to an encrypted file.
+pub(crate) fn write_atomically(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    write_through_temp(path, data, false)
+}
+
+// Like `write_atomically`, for files with passwords and tokens. Only the user can read the
+// file, and no backup is kept since an older one may have been readable by others.
+pub(crate) fn write_privately(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    write_through_temp(path, data, true)
+}
+
+fn write_through_temp(path: &Path, data: &[u8], private: bool) -> std::io::Result<()> {
+    let temp_path = sibling_path(path, "tmp");
+    let result = (|| {
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        if private {
+            std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        }
+        let mut file = options.open(&temp_path)?;
+        // A temporary file left by a crash keeps the permissions it was created with
+        #[cfg(unix)]
+        if private {
+            file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+        }
+        file.write_all(data)?;
+        file.sync_all()?;
+        if !private {
+            let backup = sibling_path(path, "bak");
+            if encryption::is_encrypted_data(data) && !encryption::is_encrypted(path) {
+                if backup.exists() {
+                    std::fs::remove_file(&backup)?;
+                }
+            } else if path.exists() {
+                std::fs::copy(path, &backup)?;
+            }
+        }
+        std::fs::rename(&temp_path, path)
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result
+}
+
+// Read lists from a JSON file or database
+pub fn load_lists(path: &Path) -> Result<Vec<TodoList>, StorageError> {
+    open(path).load()
+}
+
+// There is always at least one tab to show, and new IDs must not collide with loaded ones
+pub(crate) fn prepare(mut lists: Vec<TodoList>) -> Vec<TodoList> {
+    if lists.is_empty() {
+        lists.push(TodoList::new("Todo".to_string()));
+    }
+    let today = Local::now().date_naive();
+    for list in lists.iter_mut() {
+        list.purge_old_trash(today);
+        list.fill_missing_uids();
+    }
+    let max_id = lists.iter().map(TodoList::max_id).max().unwrap_or(0);
+    if max_id > u32::MAX / 2 {
+        // Too close to the end for new IDs to stay above it, IDs are only used while the app runs
+        lists.iter_mut().for_each(TodoList::renumber);
+    } else {
+        item::reserve_ids_up_to(max_id);
+    }
+    lists
+}
+
+// Lists as one line of JSON, e.g. to send them to another machine
+pub fn to_json(lists: &[TodoList]) -> Result<String, StorageError> {
+    Ok(serde_json::to_string(&SaveDataRef { lists })?)
+}
+
+// Lists written by `to_json`, with the IDs they had on the other side
+pub fn from_json(json: &str) -> Result<Vec<TodoList>, StorageError> {
+    let mut lists = parse_lists(json.as_bytes())?;
+    lists.iter_mut().for_each(TodoList::fill_missing_uids);
+    Ok(lists)
+}
+
+pub(crate) fn parse_lists(data: &[u8]) -> Result<Vec<TodoList>, StorageError> {
+    Ok(match serde_json::from_slice::<SaveFormat>(data)? {
+        SaveFormat::Lists(data) => data.lists,
+        SaveFormat::Items(items) => vec![TodoList { items, ..TodoList::new("Todo".to_string()) }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Lists don't compare directly because of their undo history
+    fn assert_same(loaded: &[TodoList], saved: &[TodoList]) {
+        assert_eq!(loaded.len(), saved.len());
+        for (loaded, saved) in loaded.iter().zip(saved) {
+            assert_eq!(loaded.name, saved.name);
+            assert_eq!(loaded.items, saved.items);
+            assert_eq!(loaded.trash, saved.trash);
+            assert_eq!(loaded.archive, saved.archive);
+        }
+    }
+
+    fn sample() -> Vec<TodoList> {
+        let mut work = TodoList::new("Work".to_string());
+        work.items.push(TodoItem::new("write report".to_string()));
+        let mut home = TodoList::new("Home".to_string());
+        home.items.push(TodoItem::new("water plants".to_string()));
+        vec![work, home]
+    }
+
+    #[test]
+    fn memory_returns_what_was_saved() {
+        let lists = sample();
+        let mut storage = Memory::default();
+        storage.save(&lists).unwrap();
+        assert_same(&storage.load().unwrap(), &lists);
+    }
+
+    #[test]
+    fn empty_storage_loads_one_list() {
+        let lists = Memory::default().load().unwrap();
+        assert_eq!(lists.len(), 1);
+        assert_eq!(lists[0].name, "Todo");
+    }
+
+    #[test]
+    fn json_file_round_trip() {
+        let path = std::env::temp_dir().join(format!("rust_todo_list_{}.json", std::process::id()));
+        let lists = sample();
+        let mut storage = open(&path);
+        storage.save(&lists).unwrap();
+        let loaded = storage.load();
+        let _ = std::fs::remove_file(&path);
+        assert_same(&loaded.unwrap(), &lists);
+    }
+
+    #[test]
+    fn ids_near_the_end_of_the_range_are_renumbered() {
+        let mut lists = sample();
+        lists[0].items[0].id = u32::MAX;
+        let mut storage = Memory::default();
+        storage.save(&lists).unwrap();
+        let loaded = storage.load().unwrap();
+
+        let mut ids: Vec<u32> = loaded.iter().flat_map(|list| &list.items).map(|item| item.id).collect();
+        ids.extend((0..3).map(|_| TodoItem::new("new".to_string()).id));
+        assert!(!ids.contains(&u32::MAX));
+        let count = ids.len();
+        ids.sort_unstable();
+        ids.dedup();
+        assert_eq!(ids.len(), count);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn private_files_are_only_readable_by_the_user() {
+        use std::os::unix::fs::PermissionsExt;
+        let path = std::env::temp_dir().join(format!("rust_todo_list_{}_settings.json", std::process::id()));
+        std::fs::write(&path, "{}").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        write_privately(&path, b"{\"password\": \"secret\"}").unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        let backup = sibling_path(&path, "bak").exists();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(mode & 0o777, 0o600);
+        assert!(!backup);
+    }
+}
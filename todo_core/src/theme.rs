@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+// Colors picked in the theme editor. They are plain sRGB triples so
+// the settings don't depend on the GUI toolkit.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Theme {
+    pub name: String,
+    // Dark or light widgets underneath the custom colors
+    pub dark: bool,
+    // Selections, links and highlighted buttons
+    pub accent: [u8; 3],
+    pub background: [u8; 3],
+    pub completed_text: [u8; 3],
+    pub overdue: [u8; 3],
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::light("Custom".to_string())
+    }
+}
+
+impl Theme {
+    // Starting points close to the built-in looks
+    pub fn light(name: String) -> Self {
+        Self {
+            name,
+            dark: false,
+            accent: [144, 209, 255],
+            background: [248, 248, 248],
+            completed_text: [140, 140, 140],
+            overdue: [255, 0, 0],
+        }
+    }
+
+    pub fn dark(name: String) -> Self {
+        Self {
+            name,
+            dark: true,
+            accent: [0, 92, 128],
+            background: [27, 27, 27],
+            completed_text: [120, 120, 120],
+            overdue: [255, 80, 80],
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ThemeError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for ThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ThemeError::Io(e) => write!(f, "{}", e),
+            ThemeError::Json(e) => write!(f, "The file is not a valid theme: {}", e),
+        }
+    }
+}
+
+impl From<std::io::Error> for ThemeError {
+    fn from(e: std::io::Error) -> Self {
+        ThemeError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for ThemeError {
+    fn from(e: serde_json::Error) -> Self {
+        ThemeError::Json(e)
+    }
+}
+
+// Themes are shared as small indented JSON files
+pub fn export(path: &Path, theme: &Theme) -> Result<(), ThemeError> {
+    std::fs::write(path, serde_json::to_vec_pretty(theme)?)?;
+    Ok(())
+}
+
+pub fn import(path: &Path) -> Result<Theme, ThemeError> {
+    Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+}
@@ -0,0 +1,213 @@
+use crate::csv_io::parse_date;
+use crate::{Priority, TodoItem, TodoList};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+const API_URL: &str = "https://api.todoist.com/rest/v2";
+
+#[derive(Debug)]
+pub enum TodoistError {
+    Csv(csv::Error),
+    Json(serde_json::Error),
+    Http(Box<ureq::Error>),
+    Io(std::io::Error),
+}
+
+impl From<csv::Error> for TodoistError {
+    fn from(e: csv::Error) -> Self {
+        TodoistError::Csv(e)
+    }
+}
+
+impl From<serde_json::Error> for TodoistError {
+    fn from(e: serde_json::Error) -> Self {
+        TodoistError::Json(e)
+    }
+}
+
+impl From<ureq::Error> for TodoistError {
+    fn from(e: ureq::Error) -> Self {
+        TodoistError::Http(Box::new(e))
+    }
+}
+
+impl From<std::io::Error> for TodoistError {
+    fn from(e: std::io::Error) -> Self {
+        TodoistError::Io(e)
+    }
+}
+
+#[derive(Deserialize)]
+struct Project {
+    id: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct Due {
+    date: String,
+}
+
+// A task as returned by the REST API, backups of the sync API use a few other names
+#[derive(Deserialize)]
+struct Task {
+    id: String,
+    project_id: String,
+    content: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default, alias = "checked")]
+    is_completed: bool,
+    #[serde(default)]
+    labels: Vec<String>,
+    // 4 is the most urgent (p1) and 1 is a normal task (p4)
+    #[serde(default = "normal_priority")]
+    priority: u8,
+    #[serde(default)]
+    due: Option<Due>,
+    #[serde(default)]
+    parent_id: Option<String>,
+}
+
+fn normal_priority() -> u8 {
+    1
+}
+
+#[derive(Deserialize)]
+struct Export {
+    projects: Vec<Project>,
+    #[serde(alias = "tasks")]
+    items: Vec<Task>,
+}
+
+// One list per project, read from a JSON backup with "projects" and "items" (or "tasks")
+pub fn read_json(path: &Path) -> Result<Vec<TodoList>, TodoistError> {
+    let export: Export = serde_json::from_reader(std::io::BufReader::new(std::fs::File::open(path)?))?;
+    Ok(to_lists(export.projects, export.items))
+}
+
+// One list per project, fetched with a personal API token from the Todoist settings
+pub fn fetch(token: &str) -> Result<Vec<TodoList>, TodoistError> {
+    let authorization = format!("Bearer {}", token.trim());
+    let projects: Vec<Project> = ureq::get(&format!("{}/projects", API_URL))
+        .set("Authorization", &authorization)
+        .call()?
+        .into_json()?;
+    let tasks: Vec<Task> = ureq::get(&format!("{}/tasks", API_URL))
+        .set("Authorization", &authorization)
+        .call()?
+        .into_json()?;
+    Ok(to_lists(projects, tasks))
+}
+
+fn to_lists(projects: Vec<Project>, tasks: Vec<Task>) -> Vec<TodoList> {
+    let known: HashSet<String> = tasks.iter().map(|task| task.id.clone()).collect();
+    // Subtask IDs of each task and the top level tasks with their project, in Todoist's order
+    let mut children: HashMap<String, Vec<String>> = HashMap::new();
+    let mut top_level: Vec<(String, String)> = Vec::new();
+    let mut items: HashMap<String, TodoItem> = HashMap::new();
+    for task in tasks {
+        match task.parent_id.clone().filter(|parent| known.contains(parent)) {
+            Some(parent) => children.entry(parent).or_default().push(task.id.clone()),
+            None => top_level.push((task.id.clone(), task.project_id.clone())),
+        }
+        items.insert(task.id.clone(), to_item(task));
+    }
+
+    projects
+        .into_iter()
+        .map(|project| {
+            let mut list = TodoList::new(project.name);
+            for (id, project_id) in &top_level {
+                if *project_id == project.id {
+                    list.items.extend(build_tree(id, &children, &mut items));
+                }
+            }
+            list
+        })
+        .filter(|list| !list.items.is_empty())
+        .collect()
+}
+
+fn to_item(task: Task) -> TodoItem {
+    let mut item = TodoItem::new(task.content);
+    item.notes = task.description;
+    item.completed = task.is_completed;
+    item.priority = match task.priority {
+        4 => Priority::Urgent,
+        3 => Priority::High,
+        2 => Priority::Medium,
+        _ => Priority::Low,
+    };
+    for label in &task.labels {
+        item.add_tag(label);
+    }
+    // Dates may come with a time, e.g. "2024-05-01T12:00:00"
+    item.due_date = task.due.and_then(|due| parse_date(due.date.get(..10).unwrap_or(&due.date)));
+    item
+}
+
+// Removing each item from the map as it is placed also guards against cycles
+fn build_tree(id: &str, children: &HashMap<String, Vec<String>>, items: &mut HashMap<String, TodoItem>) -> Option<TodoItem> {
+    let mut item = items.remove(id)?;
+    for child in children.get(id).into_iter().flatten() {
+        item.children.extend(build_tree(child, children, items));
+    }
+    Some(item)
+}
+
+// A CSV export of a single project, the list is named after the file.
+// Labels are written into the content as "@label", subtasks are marked by INDENT.
+pub fn read_csv(path: &Path) -> Result<TodoList, TodoistError> {
+    let name = path.file_stem().map_or("Todoist".to_string(), |stem| stem.to_string_lossy().into_owned());
+    let mut list = TodoList::new(name);
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_path(path)?;
+    let headers = reader.headers()?.clone();
+    let column = |name: &str| headers.iter().position(|header| header.eq_ignore_ascii_case(name));
+    let (kind, content, description, priority, indent, date) =
+        (column("TYPE"), column("CONTENT"), column("DESCRIPTION"), column("PRIORITY"), column("INDENT"), column("DATE"));
+
+    // Path of child indices from the top level item to the last item read
+    let mut path_to_last: Vec<usize> = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let cell = |column: Option<usize>| column.and_then(|column| record.get(column)).unwrap_or("").trim();
+        if !cell(kind).eq_ignore_ascii_case("task") || cell(content).is_empty() {
+            continue;
+        }
+
+        let mut words = Vec::new();
+        let mut labels = Vec::new();
+        for word in cell(content).split_whitespace() {
+            match word.strip_prefix('@') {
+                Some(label) if !label.is_empty() => labels.push(label),
+                _ => words.push(word),
+            }
+        }
+        let mut item = TodoItem::new(words.join(" "));
+        for label in labels {
+            item.add_tag(label);
+        }
+        item.notes = cell(description).to_string();
+        // Unlike the API, the CSV counts 1 as the most urgent
+        item.priority = match cell(priority) {
+            "1" => Priority::Urgent,
+            "2" => Priority::High,
+            "3" => Priority::Medium,
+            _ => Priority::Low,
+        };
+        // Recurring dates such as "every day" are written in words and can't be read back
+        item.due_date = parse_date(cell(date));
+
+        let depth = cell(indent).parse::<usize>().unwrap_or(1).max(1) - 1;
+        path_to_last.truncate(depth);
+        let mut siblings = &mut list.items;
+        for &index in &path_to_last {
+            siblings = &mut siblings[index].children;
+        }
+        path_to_last.push(siblings.len());
+        siblings.push(item);
+    }
+    Ok(list)
+}
@@ -0,0 +1,54 @@
+use crate::TodoItem;
+use chrono::{DateTime, Local};
+
+// Start and stop the time tracker of an item, running timers are saved with
+// their start time so they keep counting while the app is closed
+impl TodoItem {
+    pub fn is_tracking(&self) -> bool {
+        self.tracking_since.is_some()
+    }
+
+    pub fn start_tracking(&mut self, now: DateTime<Local>) {
+        if self.tracking_since.is_none() {
+            self.tracking_since = Some(now);
+        }
+    }
+
+    pub fn stop_tracking(&mut self, now: DateTime<Local>) {
+        if let Some(since) = self.tracking_since.take() {
+            self.time_spent += elapsed_seconds(since, now);
+        }
+    }
+
+    // Total tracked time in seconds, including the running timer
+    pub fn tracked_seconds(&self, now: DateTime<Local>) -> u64 {
+        self.time_spent + self.tracking_since.map_or(0, |since| elapsed_seconds(since, now))
+    }
+}
+
+fn elapsed_seconds(since: DateTime<Local>, now: DateTime<Local>) -> u64 {
+    // A clock that went backwards counts as no time
+    (now - since).num_seconds().max(0) as u64
+}
+
+// Short human readable duration, e.g. "1h 05m" or "42s"
+pub fn format_duration(seconds: u64) -> String {
+    let (hours, minutes) = (seconds / 3600, seconds / 60 % 60);
+    if hours > 0 {
+        format!("{}h {:02}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m", minutes)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+// Seconds until `format_duration` shows something else for a running timer, so the
+// window only has to be redrawn then
+pub fn seconds_until_next_change(seconds: u64) -> u64 {
+    if seconds < 60 {
+        1
+    } else {
+        60 - seconds % 60
+    }
+}
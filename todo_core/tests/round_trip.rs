@@ -0,0 +1,321 @@
+// Property tests: arbitrary lists must come back unchanged from every save format,
+// and the export formats must keep the fields they claim to carry
+
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use proptest::collection::{btree_map, vec};
+use proptest::option;
+use proptest::prelude::*;
+use std::path::PathBuf;
+use todo_core::attachment::Attachment;
+use todo_core::csv_io::{self, CsvImport};
+use todo_core::fields::{FieldDef, FieldKind, FieldValue};
+use todo_core::habit::Habit;
+use todo_core::ical;
+use todo_core::recurrence::Recurrence;
+use todo_core::storage::{Memory, Storage};
+use todo_core::{load_lists, save_lists, ArchivedItem, ColorLabel, Priority, Quadrant, Status, TodoItem, TodoList, TrashedItem};
+
+// A file in the temp directory that is removed again when the case ends
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn new(name: &str) -> Self {
+        Self(std::env::temp_dir().join(format!("rust_todo_list_proptest_{}_{}", std::process::id(), name)))
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        for suffix in ["", ".bak", ".tmp"] {
+            let mut path = self.0.clone().into_os_string();
+            path.push(suffix);
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+fn date() -> impl Strategy<Value = NaiveDate> {
+    (730_000i32..740_000).prop_map(|days| NaiveDate::from_num_days_from_ce_opt(days).unwrap())
+}
+
+fn timestamp() -> impl Strategy<Value = DateTime<Utc>> {
+    (0i64..4_000_000_000, 0u32..1_000_000_000).prop_map(|(seconds, nanos)| DateTime::from_timestamp(seconds, nanos).unwrap())
+}
+
+fn local_timestamp() -> impl Strategy<Value = DateTime<Local>> {
+    timestamp().prop_map(|time| time.with_timezone(&Local))
+}
+
+fn priority() -> impl Strategy<Value = Priority> {
+    prop::sample::select(Priority::ALL.to_vec())
+}
+
+fn status() -> impl Strategy<Value = Status> {
+    prop::sample::select(Status::ALL.to_vec())
+}
+
+fn quadrant() -> impl Strategy<Value = Quadrant> {
+    prop::sample::select(Quadrant::ALL.to_vec())
+}
+
+fn color() -> impl Strategy<Value = ColorLabel> {
+    prop::sample::select(ColorLabel::ALL.to_vec())
+}
+
+fn recurrence() -> impl Strategy<Value = Recurrence> {
+    prop_oneof![
+        Just(Recurrence::Daily),
+        Just(Recurrence::Weekly),
+        Just(Recurrence::Monthly),
+        (1u32..400).prop_map(Recurrence::EveryNDays),
+    ]
+}
+
+// Quarters stay exact through the JSON number format
+fn field_value() -> impl Strategy<Value = FieldValue> {
+    prop_oneof![
+        "\\PC{0,12}".prop_map(FieldValue::Text),
+        (-100_000i32..100_000).prop_map(|quarters| FieldValue::Number(f64::from(quarters) / 4.0)),
+        date().prop_map(FieldValue::Date),
+    ]
+}
+
+fn field_def() -> impl Strategy<Value = FieldDef> {
+    let kind = prop_oneof![
+        Just(FieldKind::Text),
+        Just(FieldKind::Number),
+        Just(FieldKind::Date),
+        vec("\\PC{1,8}", 1..4).prop_map(FieldKind::Dropdown),
+    ];
+    ("\\PC{1,12}", kind).prop_map(|(name, kind)| FieldDef { name, kind })
+}
+
+// Tags include the separators of the CSV and iCalendar formats
+fn tag() -> impl Strategy<Value = String> {
+    "[a-zA-Z0-9 ,;\\\\äß]{1,10}"
+}
+
+prop_compose! {
+    fn details()(
+        priority in priority(),
+        tags in vec(tag(), 0..4),
+        recurrence in option::of(recurrence()),
+        notes in "\\PC{0,40}",
+        status in status(),
+        pomodoros in 0u32..100,
+        time_spent in 0u64..1_000_000,
+        sync_uid in option::of("[a-z0-9@.-]{1,20}"),
+        color in option::of(color()),
+        pinned in any::<bool>(),
+        icon in option::of("\\PC{1,2}"),
+        habit in option::of(vec(date(), 0..5)),
+    ) -> TodoItem {
+        TodoItem {
+            priority,
+            tags,
+            recurrence,
+            notes,
+            status,
+            pomodoros,
+            time_spent,
+            sync_uid,
+            color,
+            pinned,
+            icon,
+            habit: habit.map(|days| {
+                let mut habit = Habit::default();
+                days.into_iter().for_each(|day| habit.mark_done(day));
+                habit
+            }),
+            ..TodoItem::new(String::new())
+        }
+    }
+}
+
+// Fields for planning ahead, split off because a strategy takes at most 12 parts
+prop_compose! {
+    fn planning()(
+        details in details(),
+        start_date in option::of(date()),
+        my_day in option::of(date()),
+        quadrant in option::of(quadrant()),
+        estimate in option::of(1u32..10_000),
+        context in option::of("[a-z]{1,10}"),
+        assignee in option::of("\\PC{1,12}"),
+        attachments in vec(("[a-z/]{1,20}\\.[a-z]{3}", any::<bool>()), 0..3),
+        fields in btree_map("\\PC{1,12}", field_value(), 0..3),
+    ) -> TodoItem {
+        TodoItem {
+            start_date,
+            my_day,
+            quadrant,
+            estimate,
+            context,
+            assignee,
+            attachments: attachments.into_iter().map(|(path, copied)| Attachment { path: path.into(), copied }).collect(),
+            fields,
+            ..details
+        }
+    }
+}
+
+prop_compose! {
+    fn leaf()(
+        planning in planning(),
+        description in "\\PC{0,40}",
+        completed in any::<bool>(),
+        due_date in option::of(date()),
+        tracking_since in option::of(local_timestamp()),
+        modified in option::of(timestamp()),
+        reminder in option::of(local_timestamp()),
+        created_at in option::of(timestamp()),
+        completed_at in option::of(timestamp()),
+    ) -> TodoItem {
+        TodoItem {
+            description,
+            completed,
+            due_date,
+            tracking_since,
+            modified,
+            reminder,
+            created_at,
+            completed_at,
+            ..planning
+        }
+    }
+}
+
+// Items nest two levels deep at most, which is plenty to cover subtasks
+fn item() -> impl Strategy<Value = TodoItem> {
+    (leaf(), vec(leaf(), 0..3)).prop_map(|(mut item, children)| {
+        item.children = children;
+        item
+    })
+}
+
+prop_compose! {
+    fn list()(
+        name in "\\PC{1,20}",
+        icon in option::of("\\PC{1,2}"),
+        items in vec(item(), 0..5),
+        trash in vec((leaf(), date()), 0..3),
+        archive in vec((leaf(), date()), 0..3),
+        fields in vec(field_def(), 0..3),
+    ) -> TodoList {
+        TodoList {
+            icon,
+            fields,
+            items,
+            trash: trash.into_iter().map(|(item, day)| TrashedItem::new(item, day)).collect(),
+            archive: archive.into_iter().map(|(item, day)| ArchivedItem::new(item, day)).collect(),
+            ..TodoList::new(name)
+        }
+    }
+}
+
+// Trash older than the retention period is purged on load, so only recent trash is generated
+fn lists() -> impl Strategy<Value = Vec<TodoList>> {
+    vec(list(), 1..4).prop_map(|mut lists| {
+        let today = Local::now().date_naive();
+        for trashed in lists.iter_mut().flat_map(|list| list.trash.iter_mut()) {
+            trashed.deleted_on = today;
+        }
+        lists
+    })
+}
+
+// Lists don't compare directly because of their undo history
+fn assert_same(loaded: &[TodoList], saved: &[TodoList]) -> Result<(), TestCaseError> {
+    prop_assert_eq!(loaded.len(), saved.len());
+    for (loaded, saved) in loaded.iter().zip(saved) {
+        prop_assert_eq!(&loaded.name, &saved.name);
+        prop_assert_eq!(&loaded.icon, &saved.icon);
+        prop_assert_eq!(&loaded.fields, &saved.fields);
+        prop_assert_eq!(&loaded.items, &saved.items);
+        prop_assert_eq!(&loaded.trash, &saved.trash);
+        prop_assert_eq!(&loaded.archive, &saved.archive);
+    }
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn json_keeps_every_field(lists in lists()) {
+        let mut storage = Memory::default();
+        storage.save(&lists).unwrap();
+        assert_same(&storage.load().unwrap(), &lists)?;
+    }
+}
+
+// Each case writes a file, so fewer of them
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn json_files_keep_every_field(lists in lists()) {
+        let file = TempFile::new("round_trip.json");
+        save_lists(&file.0, &lists).unwrap();
+        assert_same(&load_lists(&file.0).unwrap(), &lists)?;
+    }
+
+    #[test]
+    fn sqlite_keeps_every_field(lists in lists()) {
+        let file = TempFile::new("round_trip.db");
+        save_lists(&file.0, &lists).unwrap();
+        assert_same(&load_lists(&file.0).unwrap(), &lists)?;
+    }
+
+    // CSV carries the description, completion, due date and notes of top level items.
+    // Cells are trimmed on import and blank descriptions skipped.
+    #[test]
+    fn csv_keeps_its_columns(items in vec(item(), 0..8)) {
+        let items: Vec<TodoItem> = items
+            .into_iter()
+            .map(|item| TodoItem {
+                description: item.description.trim().to_string(),
+                notes: item.notes.trim().to_string(),
+                ..item
+            })
+            .filter(|item| !item.description.is_empty())
+            .collect();
+        let list = TodoList { items, ..TodoList::new("CSV".to_string()) };
+        let file = TempFile::new("round_trip.csv");
+        csv_io::write_csv(&file.0, &list).unwrap();
+
+        let imported = CsvImport::read(&file.0).unwrap().to_items();
+        prop_assert_eq!(imported.len(), list.items.len());
+        for (imported, item) in imported.iter().zip(&list.items) {
+            prop_assert_eq!(&imported.description, &item.description);
+            prop_assert_eq!(imported.completed, item.completed);
+            prop_assert_eq!(imported.due_date, item.due_date);
+            prop_assert_eq!(&imported.notes, &item.notes);
+        }
+    }
+}
+
+proptest! {
+    // iCalendar carries the fields CalDAV sync relies on. The first line of the description
+    // is the summary, so single line descriptions and notes without a trailing new line are used.
+    #[test]
+    fn ical_keeps_synced_fields(
+        item in leaf(),
+        description in "[^\\p{C}\n]{0,40}",
+        notes in vec("[^\\p{C}]{0,20}", 0..3)
+            .prop_map(|lines| lines.join("\n"))
+            .prop_filter("a trailing new line is dropped", |notes| !notes.ends_with('\n')),
+    ) {
+        let item = TodoItem { description, notes, ..item };
+        let todos = ical::parse_vtodos(&ical::to_single_ical(&item));
+        prop_assert_eq!(todos.len(), 1);
+
+        let mut parsed = TodoItem::new(String::new());
+        todos[0].apply_to(&mut parsed);
+        prop_assert_eq!(&parsed.description, &item.description);
+        prop_assert_eq!(&parsed.notes, &item.notes);
+        prop_assert_eq!(parsed.due_date, item.due_date);
+        prop_assert_eq!(parsed.priority, item.priority);
+        prop_assert_eq!(parsed.status(), item.status());
+        prop_assert_eq!(&parsed.tags, &item.tags);
+        prop_assert_eq!(parsed.sync_uid, Some(ical::uid(&item)));
+    }
+}
@@ -0,0 +1,138 @@
+// Saving and loading through the public API, the way the GUI and the command line tool use it
+
+use std::path::PathBuf;
+use todo_core::{encryption, load_lists, save_lists, storage, StorageError, TodoItem, TodoList};
+
+// A file in the temp directory that is removed again when the test ends
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn new(name: &str) -> Self {
+        Self(std::env::temp_dir().join(format!("rust_todo_list_{}_{}", std::process::id(), name)))
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        for suffix in ["", ".bak", ".tmp"] {
+            let mut path = self.0.clone().into_os_string();
+            path.push(suffix);
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+fn sample() -> Vec<TodoList> {
+    let mut item = TodoItem::new("write report".to_string());
+    item.add_tag("work");
+    item.notes = "due at the end of the quarter".to_string();
+    item.children.push(TodoItem::new("collect numbers".to_string()));
+    let mut work = TodoList::new("Work".to_string());
+    work.items.push(item);
+    vec![work, TodoList::new("Home".to_string())]
+}
+
+fn assert_same(loaded: &[TodoList], saved: &[TodoList]) {
+    assert_eq!(loaded.len(), saved.len());
+    for (loaded, saved) in loaded.iter().zip(saved) {
+        assert_eq!(loaded.name, saved.name);
+        assert_eq!(loaded.items, saved.items);
+    }
+}
+
+#[test]
+fn json_saves_round_trip() {
+    let file = TempFile::new("round_trip.json");
+    let lists = sample();
+    save_lists(&file.0, &lists).unwrap();
+    assert_same(&load_lists(&file.0).unwrap(), &lists);
+}
+
+#[test]
+fn saving_twice_keeps_a_backup() {
+    let file = TempFile::new("backup.json");
+    save_lists(&file.0, &sample()).unwrap();
+    save_lists(&file.0, &sample()).unwrap();
+    let mut backup = file.0.clone().into_os_string();
+    backup.push(".bak");
+    assert!(PathBuf::from(backup).exists());
+}
+
+#[test]
+fn new_items_never_reuse_loaded_ids() {
+    let file = TempFile::new("ids.json");
+    std::fs::write(
+        &file.0,
+        r#"{"lists": [{"name": "Todo", "items": [{"id": 4000000, "description": "old", "completed": false, "edit": false}]}]}"#,
+    )
+    .unwrap();
+    let lists = load_lists(&file.0).unwrap();
+    assert_eq!(lists[0].items[0].id, 4000000);
+    assert!(TodoItem::new("new".to_string()).id > 4000000);
+}
+
+#[test]
+fn saves_from_before_multiple_lists_load_as_one_list() {
+    let file = TempFile::new("legacy.json");
+    std::fs::write(&file.0, r#"[{"id": 1, "description": "old", "completed": true, "edit": false}]"#).unwrap();
+    let lists = load_lists(&file.0).unwrap();
+    assert_eq!(lists.len(), 1);
+    assert_eq!(lists[0].name, "Todo");
+    assert_eq!(lists[0].items[0].description, "old");
+    assert!(lists[0].items[0].completed);
+}
+
+#[test]
+fn invalid_files_are_reported() {
+    let file = TempFile::new("invalid.json");
+    std::fs::write(&file.0, "not a save").unwrap();
+    assert!(matches!(load_lists(&file.0), Err(StorageError::Json(_))));
+}
+
+#[test]
+fn encrypted_saves_need_their_passphrase() {
+    let file = TempFile::new("encrypted.json");
+    let lists = sample();
+    encryption::remember_passphrase(&file.0, "correct horse");
+    save_lists(&file.0, &lists).unwrap();
+    assert!(encryption::is_encrypted(&file.0));
+    assert_same(&load_lists(&file.0).unwrap(), &lists);
+
+    encryption::forget_passphrase(&file.0);
+    assert!(matches!(load_lists(&file.0), Err(StorageError::PassphraseNeeded)));
+    // Nor is it overwritten with plain text
+    assert!(matches!(save_lists(&file.0, &lists), Err(StorageError::PassphraseNeeded)));
+}
+
+#[test]
+fn encrypting_a_plain_save_keeps_no_plain_backup() {
+    let file = TempFile::new("encrypted_backup.json");
+    save_lists(&file.0, &sample()).unwrap();
+    save_lists(&file.0, &sample()).unwrap();
+    encryption::remember_passphrase(&file.0, "correct horse");
+    save_lists(&file.0, &sample()).unwrap();
+    encryption::forget_passphrase(&file.0);
+    let mut backup = file.0.clone().into_os_string();
+    backup.push(".bak");
+    assert!(!PathBuf::from(backup).exists());
+}
+
+#[test]
+fn removing_encryption_replaces_the_file_with_plain_json() {
+    let file = TempFile::new("decrypted.json");
+    let lists = sample();
+    encryption::remember_passphrase(&file.0, "correct horse");
+    save_lists(&file.0, &lists).unwrap();
+    storage::save_unencrypted(&file.0, &lists).unwrap();
+    assert!(!encryption::is_encrypted(&file.0));
+    assert!(encryption::passphrase_for(&file.0).is_none());
+    assert_same(&load_lists(&file.0).unwrap(), &lists);
+}
+
+#[test]
+fn sqlite_saves_round_trip() {
+    let file = TempFile::new("round_trip.db");
+    let lists = sample();
+    save_lists(&file.0, &lists).unwrap();
+    assert_same(&load_lists(&file.0).unwrap(), &lists);
+}